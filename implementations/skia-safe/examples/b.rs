@@ -13,7 +13,7 @@ fn draw(painter: &mut impl Painter<NativeColor = Color>) {
     let mut paint = Paint::default();
 
     paint.ink = Color::new(0.0, 0.0, 0.0, 1.0).into();
-    //paint.anti_alias = true;
+    //paint.antialias_mode = AntialiasMode::Exact;
     let mut pen = Pen::default();
     pen.paint = paint.clone();
     pen.cap = PenCap::Round;
@@ -52,7 +52,9 @@ impl<'canvas> Canvas for SkiaCanvas<'canvas> {
 }
 
 mod conversions {
-    use repaint::{base::{defs::colors::default_color_types::RgbaFColor, paint::{Paint, Ink}, blending::BlendMode, pen::{StrokeWidth, PenCap}, shapes::path::PathCommand}, methods::PaintStyle};
+    use repaint::{base::{defs::{colors::default_color_types::RgbaFColor, linalg::Vec2f64, vertex_mode::VertexMode}, paint::{Paint, Ink}, blending::BlendMode, pen::{StrokeWidth, PenCap}, shapes::{path::PathCommand, BasicShape, Shape}}, methods::PaintStyle};
+
+    use crate::IntoSkiaCorrespondingType;
 
     pub fn color_to_skia_color(color: RgbaFColor) -> skia_safe::Color4f {
         skia_safe::Color4f {
@@ -99,6 +101,15 @@ mod conversions {
         }
     }
 
+    pub fn vertex_mode_to_skia(mode: VertexMode) -> skia_safe::vertices::VertexMode {
+        use skia_safe::vertices::VertexMode as SkiaVertexMode;
+        match mode {
+            VertexMode::Triangles => SkiaVertexMode::Triangles,
+            VertexMode::TriangleStrip => SkiaVertexMode::TriangleStrip,
+            VertexMode::TriangleFan => SkiaVertexMode::TriangleFan,
+        }
+    }
+
     pub fn paint_style_to_skia_paint(style: &PaintStyle<RgbaFColor>) -> skia_safe::Paint {
         use skia_safe::{
             Paint as SkiaPaint,
@@ -144,7 +155,7 @@ mod conversions {
 
         sk_paint.set_blend_mode(blend_mode_to_skia(paint.blend_mode));
 
-        sk_paint.set_anti_alias(paint.anti_alias);
+        sk_paint.set_anti_alias(paint.antialias_mode.is_antialiased());
     }
 
     // TODO move and remove pub
@@ -198,8 +209,32 @@ mod conversions {
                 ),
                 PathCommand::SmoothQuadraticBezierCurveTo(_) => todo!("SmoothQuadraticBezierCurveTo"),
                 PathCommand::SmoothQuadraticBezierCurveToOffset(_) => todo!("SmoothQuadraticBezierCurveToOffset"),
-                PathCommand::EllipticalArcTo { .. } => todo!("EllipticalArcTo"),
-                PathCommand::EllipticalArcToOffset { .. } => todo!("EllipticalArcToOffset"),
+                PathCommand::EllipticalArcTo {
+                    radii,
+                    x_axis_rotation,
+                    large_arc_flag,
+                    sweep_flag,
+                    end_pt
+                } => sk_path.arc_to_rotated(
+                    (radii.x as f32, radii.y as f32),
+                    x_axis_rotation.to_degrees() as f32,
+                    arc_size_to_skia(large_arc_flag),
+                    sweep_to_skia(sweep_flag),
+                    (end_pt.x as f32, end_pt.y as f32),
+                ),
+                PathCommand::EllipticalArcToOffset {
+                    radii,
+                    x_axis_rotation,
+                    large_arc_flag,
+                    sweep_flag,
+                    end_pt_offset
+                } => sk_path.r_arc_to_rotated(
+                    (radii.x as f32, radii.y as f32),
+                    x_axis_rotation.to_degrees() as f32,
+                    arc_size_to_skia(large_arc_flag),
+                    sweep_to_skia(sweep_flag),
+                    (end_pt_offset.x as f32, end_pt_offset.y as f32),
+                ),
             }; // TODO following skia, *Offset -> Relative* or something like that
             // TODO add functions to convert from relative to absolute given the current position,
             // also handle the smooth case
@@ -208,6 +243,102 @@ mod conversions {
 
         sk_path
     }
+
+    fn arc_size_to_skia(large_arc_flag: bool) -> skia_safe::path::ArcSize {
+        if large_arc_flag {
+            skia_safe::path::ArcSize::Large
+        } else {
+            skia_safe::path::ArcSize::Small
+        }
+    }
+
+    fn sweep_to_skia(sweep_flag: bool) -> skia_safe::PathDirection {
+        // the positive angle direction is clockwise with the y axis pointing down
+        if sweep_flag {
+            skia_safe::PathDirection::CW
+        } else {
+            skia_safe::PathDirection::CCW
+        }
+    }
+
+    /// The bounds of an axis-aligned ellipse as a Skia oval.
+    pub fn oval_to_skia(center: Vec2f64, radii: Vec2f64) -> skia_safe::Rect {
+        skia_safe::Rect::new(
+            (center.x - radii.x.abs()) as f32,
+            (center.y - radii.y.abs()) as f32,
+            (center.x + radii.x.abs()) as f32,
+            (center.y + radii.y.abs()) as f32,
+        )
+    }
+
+    /// Converts a rounded rectangle, Skia scales the radii that do not fit like we do.
+    pub fn rounded_rect_to_skia(shape: &BasicShape) -> Option<skia_safe::RRect> {
+        if let BasicShape::RoundedRect { rect, radii } = shape {
+            let radius = |r: &Vec2f64| skia_safe::Vector::new(r.x as f32, r.y as f32);
+            // Skia order: upper left, upper right, lower right, lower left
+            Some(skia_safe::RRect::new_rect_radii(
+                rect.fix().into_skia(),
+                &[
+                    radius(&radii.top_left),
+                    radius(&radii.top_right),
+                    radius(&radii.bottom_right),
+                    radius(&radii.bottom_left),
+                ],
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Creates a Skia path from a basic shape, using the native Skia shapes when possible.
+    pub fn create_skia_path_from_basic_shape(shape: &BasicShape) -> skia_safe::Path {
+        let mut sk_path = skia_safe::Path::new();
+        match shape {
+            BasicShape::Rect(rect) => {
+                sk_path.add_rect(rect.into_skia(), None);
+            }
+            BasicShape::Path(_) | BasicShape::Polyline(_) => {
+                return create_skia_path(shape.to_path_iter());
+            }
+            BasicShape::Circle { center, radius } => {
+                sk_path.add_circle((center.x as f32, center.y as f32), *radius as f32, None);
+            }
+            BasicShape::Ellipse { center, radii } => {
+                sk_path.add_oval(oval_to_skia(*center, *radii), None);
+            }
+            BasicShape::RoundedRect { .. } => {
+                if let Some(rrect) = rounded_rect_to_skia(shape) {
+                    sk_path.add_rrect(rrect, None);
+                }
+            }
+            BasicShape::Arc { center, radii, start_angle, sweep_angle } => {
+                sk_path.add_arc(
+                    oval_to_skia(*center, *radii),
+                    start_angle.to_degrees() as f32,
+                    sweep_angle.to_degrees() as f32,
+                );
+            }
+            BasicShape::Pie { center, radii, start_angle, sweep_angle } => {
+                sk_path.move_to((center.x as f32, center.y as f32));
+                sk_path.arc_to(
+                    oval_to_skia(*center, *radii),
+                    start_angle.to_degrees() as f32,
+                    sweep_angle.to_degrees() as f32,
+                    false,
+                );
+                sk_path.close();
+            }
+            BasicShape::Chord { center, radii, start_angle, sweep_angle } => {
+                sk_path.add_arc(
+                    oval_to_skia(*center, *radii),
+                    start_angle.to_degrees() as f32,
+                    sweep_angle.to_degrees() as f32,
+                );
+                sk_path.close();
+            }
+        }
+        sk_path
+    }
 }
 
 
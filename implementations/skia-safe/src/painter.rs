@@ -1,8 +1,8 @@
 
-use repaint::{BasicPainter, base::{shapes::{path::PathCommand, Shape, BasicShape}, defs::{colors::default_color_types::RgbaFColor, linalg::Vec2f64}, paint::{Paint, Ink}, pen::Pen, blending::BlendMode, transform::Transform2d}, nalgebra::Matrix4, SaveLayerRec, methods::{TransformError, ClipError, PaintStyle}, ClipOperation, WithPathResource, WithText, FontStyle, FontWeight, FontWidth, FontSlant, PointMode, Canvas, RasterPainter, LocalizedString, FontVariationParameter, FontVariationAxisTag, FontVariationAxis};
+use repaint::{BasicPainter, base::{shapes::{path::PathCommand, Shape, BasicShape}, defs::{colors::default_color_types::RgbaFColor, linalg::Vec2f64, rect::{F64Rect, TlTrBlBr}, vertex_mode::VertexMode}, paint::{Paint, Ink}, pen::Pen, blending::BlendMode, transform::Transform2d}, nalgebra::Matrix4, SaveLayerRec, methods::{TransformError, ClipError, PaintStyle}, ClipOperation, WithPathResource, WithText, FontStyle, FontWeight, FontWidth, FontSlant, PointMode, Canvas, RasterPainter, LocalizedString, FontVariationParameter, FontVariationAxisTag, FontVariationAxis};
 use skia_safe::{font_arguments::VariationPosition, FourByteTag};
 
-use crate::{SkiaCanvas, conversions::{create_skia_path, paint_style_to_skia_paint, color_to_skia_color}, IntoSkiaCorrespondingType};
+use crate::{SkiaCanvas, conversions::{create_skia_path, create_skia_path_from_basic_shape, oval_to_skia, rounded_rect_to_skia, paint_style_to_skia_paint, color_to_skia_color, vertex_mode_to_skia}, IntoSkiaCorrespondingType};
 
 struct Buffers {
     points: Vec<skia_safe::Point>,
@@ -41,6 +41,21 @@ impl<'c, 'canvas> SkiaPainter<'c, 'canvas> {
     fn skia_canvas_mut_priv(&mut self) -> &mut skia_safe::Canvas {
         self.canvas.skia_canvas
     }
+
+    fn draw_polygon(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2f64>,
+        closed: bool,
+        style: PaintStyle<RgbaFColor>,
+    ) {
+        self.buffers.points.clear();
+        for point in points {
+            self.buffers.points.push(skia_safe::Point::new(point.x as f32, point.y as f32));
+        }
+        let path = skia_safe::Path::polygon(&self.buffers.points, closed, None, None);
+        self.canvas.skia_canvas.draw_path(&path, &paint_style_to_skia_paint(&style));
+        self.buffers.points.clear();
+    }
 }
 
 impl<'c, 'canvas> BasicPainter for SkiaPainter<'c, 'canvas> {
@@ -151,8 +166,18 @@ impl<'c, 'canvas> BasicPainter for SkiaPainter<'c, 'canvas> {
                     );
                     return Ok(());
                 }
-                // TODO other cases
-                _ => {}
+                BasicShape::RoundedRect { .. } => {
+                    if let Some(rrect) = rounded_rect_to_skia(&basic) {
+                        self.skia_canvas_mut_priv().clip_rrect(rrect, op, true);
+                        return Ok(());
+                    }
+                }
+                BasicShape::Path(_) | BasicShape::Polyline(_) => {}
+                _ => {
+                    let path = create_skia_path_from_basic_shape(&basic);
+                    self.skia_canvas_mut_priv().clip_path(&path, op, true);
+                    return Ok(());
+                }
             }
         }
         let path = create_skia_path(shape.to_path_iter());
@@ -208,6 +233,143 @@ impl<'c, 'canvas> BasicPainter for SkiaPainter<'c, 'canvas> {
         );
     }
 
+    fn circle(
+        &mut self,
+        center: Vec2f64,
+        radius: f64,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.canvas.skia_canvas.draw_circle(
+            (center.x as f32, center.y as f32),
+            radius as f32,
+            &paint_style_to_skia_paint(&style),
+        );
+    }
+
+    fn ellipse(
+        &mut self,
+        center: Vec2f64,
+        radii: Vec2f64,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.canvas.skia_canvas.draw_oval(
+            oval_to_skia(center, radii),
+            &paint_style_to_skia_paint(&style),
+        );
+    }
+
+    fn rounded_rect(
+        &mut self,
+        rect: F64Rect,
+        radii: TlTrBlBr<Vec2f64>,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        if let Some(rrect) = rounded_rect_to_skia(&BasicShape::RoundedRect { rect, radii }) {
+            self.canvas.skia_canvas.draw_rrect(
+                rrect,
+                &paint_style_to_skia_paint(&style),
+            );
+        }
+    }
+
+    fn arc(
+        &mut self,
+        center: Vec2f64,
+        radii: Vec2f64,
+        start_angle: f64,
+        sweep_angle: f64,
+        use_center: bool,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        // both are clockwise on screen, Skia uses degrees
+        self.canvas.skia_canvas.draw_arc(
+            oval_to_skia(center, radii),
+            start_angle.to_degrees() as f32,
+            sweep_angle.to_degrees() as f32,
+            use_center,
+            &paint_style_to_skia_paint(&style),
+        );
+    }
+
+    fn polygon(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2f64>,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.draw_polygon(points, true, style);
+    }
+
+    fn polyline(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2f64>,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.draw_polygon(points, false, style);
+    }
+
+    fn vertices(
+        &mut self,
+        positions: &[Vec2f64],
+        colors: Option<&[Self::NativeColor]>,
+        uvs: Option<&[Vec2f64]>,
+        indices: Option<&[u16]>,
+        mode: VertexMode,
+        paint: &Paint<Self::NativeColor>,
+    ) {
+        use skia_safe::vertices::{Builder, BuilderFlags, VertexMode as SkiaVertexMode};
+
+        let colors = colors.filter(|colors| colors.len() == positions.len());
+        let uvs = uvs.filter(|uvs| uvs.len() == positions.len());
+
+        // Skia doesn't check the indices: the triangles with an index out of the positions are
+        // removed by listing the others
+        let valid_indices: Vec<u16>;
+        let (sk_mode, indices) = match indices {
+            Some(indices) if indices.iter().any(|i| *i as usize >= positions.len()) => {
+                valid_indices = mode
+                    .triangles(positions.len(), Some(indices))
+                    .filter(|triangle| triangle.iter().all(|i| *i < positions.len()))
+                    .flatten()
+                    .map(|i| i as u16)
+                    .collect();
+                (SkiaVertexMode::Triangles, Some(valid_indices.as_slice()))
+            }
+            _ => (vertex_mode_to_skia(mode), indices),
+        };
+
+        let mut flags = BuilderFlags::empty();
+        if colors.is_some() {
+            flags |= BuilderFlags::HAS_COLORS;
+        }
+        if uvs.is_some() {
+            flags |= BuilderFlags::HAS_TEX_COORDS;
+        }
+        let mut builder = Builder::new(sk_mode, positions.len(), indices.map_or(0, |indices| indices.len()), flags);
+        for (sk_pos, pos) in builder.positions().iter_mut().zip(positions) {
+            *sk_pos = skia_safe::Point::new(pos.x as f32, pos.y as f32);
+        }
+        if let (Some(sk_uvs), Some(uvs)) = (builder.tex_coords(), uvs) {
+            for (sk_uv, uv) in sk_uvs.iter_mut().zip(uvs) {
+                *sk_uv = skia_safe::Point::new(uv.x as f32, uv.y as f32);
+            }
+        }
+        if let (Some(sk_colors), Some(colors)) = (builder.colors(), colors) {
+            for (sk_color, color) in sk_colors.iter_mut().zip(colors) {
+                *sk_color = color_to_skia_color(*color).to_color();
+            }
+        }
+        if let (Some(sk_indices), Some(indices)) = (builder.indices(), indices) {
+            sk_indices.copy_from_slice(indices);
+        }
+
+        // the vertex colors (the destination of the blend mode) replace the ink of the paint
+        self.canvas.skia_canvas.draw_vertices(
+            &builder.detach(),
+            skia_safe::BlendMode::Dst,
+            &paint_style_to_skia_paint(&PaintStyle::Fill(paint.clone())),
+        );
+    }
+
     fn draw_path_iter<'s, 'a>(
         &'s mut self,
         path_iter: &mut dyn Iterator<Item = PathCommand>,
@@ -0,0 +1,102 @@
+//! Compares the curve flattening methods: number of points and speed.
+//!
+//! Run with `cargo bench --bench flattening`, the error bounds are checked in `tests/flattening.rs`.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::time::Instant;
+
+use common::Lcg;
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::path::discretization::{
+    curves::{ParametricCubic2dBezier, ParametricQuadratic2dBezier},
+    DiscretizationParams, FlatteningMethod, PathDiscretizer,
+};
+use repaint::base::shapes::path::PathCommand;
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::base::transform::Transform2d;
+
+const CURVES: usize = 2000;
+const BENCH_ROUNDS: usize = 20;
+
+fn point(rng: &mut Lcg, scale: f64) -> Vec2f64 {
+    Vec2f64::new(rng.next() * scale, rng.next() * scale)
+}
+
+enum Curve {
+    Quadratic(ParametricQuadratic2dBezier),
+    Cubic(ParametricCubic2dBezier),
+}
+
+impl Curve {
+    fn to_path(&self) -> [PathCommand; 2] {
+        match self {
+            Curve::Quadratic(c) => [
+                PathCommand::MoveTo(c.p0),
+                PathCommand::QuadraticBezierCurveTo { control_pt: c.p1, end_pt: c.p2 },
+            ],
+            Curve::Cubic(c) => [
+                PathCommand::MoveTo(c.p0),
+                PathCommand::CubicBezierTo { control_pt_1: c.p1, control_pt_2: c.p2, end_pt: c.p3 },
+            ],
+        }
+    }
+}
+
+fn main() {
+    let mut rng = Lcg(42);
+    let curves: Vec<Curve> = (0..CURVES)
+        .map(|i| {
+            let scale = [10.0, 100.0, 1000.0][i % 3];
+            if i % 2 == 0 {
+                Curve::Quadratic(ParametricQuadratic2dBezier::new(
+                    point(&mut rng, scale),
+                    point(&mut rng, scale),
+                    point(&mut rng, scale),
+                ))
+            } else {
+                Curve::Cubic(ParametricCubic2dBezier::new(
+                    point(&mut rng, scale),
+                    point(&mut rng, scale),
+                    point(&mut rng, scale),
+                    point(&mut rng, scale),
+                ))
+            }
+        })
+        .collect();
+
+    for tolerance in [1.0, 0.25, 0.05] {
+        println!("tolerance {}", tolerance);
+        println!("  {:<20} {:>10} {:>12}", "method", "points", "time (ms)");
+        let methods = [
+            ("Adaptive", FlatteningMethod::Adaptive, DiscretizationParams::default().max_angle),
+            // the adaptive method with the tolerance criterion only
+            ("Adaptive (no angle)", FlatteningMethod::Adaptive, std::f64::consts::PI),
+            ("Wang", FlatteningMethod::Wang, 0.0),
+            ("ParabolaIntegral", FlatteningMethod::ParabolaIntegral, 0.0),
+        ];
+        for (name, method, max_angle) in methods {
+            let params = DiscretizationParams::default().with_tolerance(tolerance).with_max_angle(max_angle).with_flattening(method);
+            let discretizer = PathDiscretizer::new(params, &Transform2d::Identity);
+
+            let points: usize = curves.iter().map(|curve| discretizer.discretize_iter(curve.to_path()).count()).sum();
+
+            let start = Instant::now();
+            let mut checksum = 0.0;
+            for _ in 0..BENCH_ROUNDS {
+                for curve in &curves {
+                    for command in discretizer.discretize_iter(curve.to_path()) {
+                        if let BrokenPolylineCommand::LineTo(pt) = command {
+                            checksum += pt.x;
+                        }
+                    }
+                }
+            }
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0 / BENCH_ROUNDS as f64;
+            std::hint::black_box(checksum);
+
+            println!("  {:<20} {:>10} {:>12.3}", name, points, elapsed);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Measures the speed of the flood fill.
+//!
+//! Run with `cargo run --release --example flood_fill`, the properties of the flood fill and the
+//! boundary fill are checked by the `flood_fill` tests.
+
+use std::time::Instant;
+
+use repaint::base::defs::colors::default_color_types::RgbaFColor;
+use repaint::base::rasterization::algorithms::flood_fill::*;
+use repaint::nalgebra::Vector2;
+
+/// A small deterministic pseudo-random generator, so that runs are comparable.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Fills a large maze-like image, with many spans per row.
+fn measure() {
+    let (width, height) = (2000, 2000);
+    let (white, black) = (RgbaFColor::new(1.0, 1.0, 1.0, 1.0), RgbaFColor::new(0.0, 0.0, 0.0, 1.0));
+    let mut rng = Lcg(3);
+    let mut image = vec![white; width * height];
+    for (idx, pixel) in image.iter_mut().enumerate() {
+        if (idx % width) % 8 == 7 && rng.next() < 0.9 || (idx / width) % 8 == 7 && rng.next() < 0.5 {
+            *pixel = black;
+        }
+    }
+    for connectivity in [Connectivity::Four, Connectivity::Eight] {
+        let params = FloodFillParams { connectivity, antialias: true, ..FloodFillParams::default() };
+        let mut filled = image.clone();
+        let start = Instant::now();
+        flood_fill(&mut PixelSlice::new(&mut filled, width), Vector2::new(0, 0), RgbaFColor::new(1.0, 0.0, 0.0, 1.0), &params);
+        let count = filled.iter().filter(|pixel| **pixel != image[0] && **pixel != black).count();
+        println!("{:?}-connected fill of {} pixels: {:.2?}", connectivity, count, start.elapsed());
+    }
+}
+
+fn main() {
+    measure();
+}
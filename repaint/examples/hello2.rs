@@ -78,7 +78,7 @@ fn main() {
     params.max_angle = 1.1;
     let discretizer = pp::discretization::PathDiscretizer::new(
         params,
-        Transform2d::Identity,
+        &Transform2d::Identity,
     );
     let mut it = path.iter();
     let discr_commands_iter = discretizer.discretize(&mut it);
@@ -0,0 +1,76 @@
+//! Compares the speed of the sparse strip filler and the accumulation filler.
+//!
+//! Run with `cargo run --release --example sparse_strips`, the properties of the filler are checked by
+//! the `sparse_strips` tests.
+
+use std::time::Instant;
+
+use repaint::base::defs::{fill_rule::FillRule, linalg::*, rect::Rect};
+use repaint::base::rasterization::algorithms::polygon_filling::{accumulation, scanline::LineSpanConsumer, sparse_strips::SparseStripFiller};
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::nalgebra::Vector2;
+
+/// A small deterministic pseudo-random generator, so that runs are comparable.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+}
+
+/// Fills glyph-like paths with both fillers, the pixels are only counted.
+fn measure() {
+    struct Counter(usize);
+    impl LineSpanConsumer for Counter {
+        fn start_line(&mut self, _idx: usize) {}
+        fn put_weighted(&mut self, _idx: usize, _weight: f32) {
+            self.0 += 1;
+        }
+        fn put_solid_span(&mut self, start_idx: usize, end_idx: usize) {
+            self.0 += end_idx - start_idx;
+        }
+    }
+
+    let mut rng = Lcg(17);
+    let clip = Rect::new(Vector2::new(0, 0), Vector2::new(4000, 4000));
+    for (name, count, size) in [("small paths", 20000, 12.0), ("large paths", 200, 1500.0)] {
+        let paths: Vec<Vec<BrokenPolylineCommand>> = (0..count)
+            .map(|_| {
+                let center = Vec2f64::new(rng.range(0.0, 4000.0), rng.range(0.0, 4000.0));
+                (0..24)
+                    .map(|i| {
+                        let angle = i as f64 / 24.0 * std::f64::consts::TAU;
+                        let pt = center + Vec2f64::new(angle.cos(), angle.sin()) * size * rng.range(0.5, 1.0);
+                        if i == 0 { BrokenPolylineCommand::MoveTo(pt) } else { BrokenPolylineCommand::LineTo(pt) }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut counter = Counter(0);
+        let start = Instant::now();
+        for path in &paths {
+            accumulation::fill(path, &clip, FillRule::NonZero, &mut counter);
+        }
+        let accumulation_time = start.elapsed();
+
+        let mut sparse_counter = Counter(0);
+        let mut filler = SparseStripFiller::new();
+        let start = Instant::now();
+        for path in &paths {
+            filler.fill(path, &clip, FillRule::NonZero, &mut sparse_counter);
+        }
+        let sparse_time = start.elapsed();
+        println!("{}: accumulation {:.2?}, sparse strips {:.2?} ({} and {} pixels)", name, accumulation_time, sparse_time, counter.0, sparse_counter.0);
+    }
+}
+
+fn main() {
+    measure();
+}
@@ -0,0 +1,116 @@
+//! Measures the speedup of the tiled rasterizer on a 4K image.
+//!
+//! Run with `cargo run --release --features tiled --example tiled_rendering`, the properties of the
+//! rasterizer are checked by the `tiled_rendering` tests.
+
+use std::time::Instant;
+
+use repaint::base::blending::BlendMode;
+use repaint::base::defs::{antialiasing::AntialiasMode, colors::default_color_types::RgbaFColor, fill_rule::FillRule, linalg::*, rect::Rect};
+use repaint::base::paint::{Ink, Paint};
+use repaint::base::rasterization::algorithms::ellipse::antialiased;
+use repaint::base::rasterization::tiled::TiledRasterizer;
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::nalgebra::Vector2;
+
+/// A small deterministic pseudo-random generator, so that runs are comparable.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+
+    fn color(&mut self) -> RgbaFColor {
+        RgbaFColor::new(self.next() as f32, self.next() as f32, self.next() as f32, self.range(0.2, 1.0) as f32)
+    }
+
+    /// A star polygon around a random center, partly outside the image.
+    fn star(&mut self, width: f64, height: f64, size: f64) -> Vec<BrokenPolylineCommand> {
+        let center = Vec2f64::new(self.range(-0.1, 1.1) * width, self.range(-0.1, 1.1) * height);
+        let count = 3 + (self.next() * 12.0) as usize;
+        (0..count)
+            .map(|i| {
+                let angle = i as f64 / count as f64 * std::f64::consts::TAU * 2.0;
+                let radius = self.range(0.2, 1.0) * size;
+                let pt = center + Vec2f64::new(angle.cos(), angle.sin()) * radius;
+                if i == 0 { BrokenPolylineCommand::MoveTo(pt) } else { BrokenPolylineCommand::LineTo(pt) }
+            })
+            .collect()
+    }
+
+    fn paint(&mut self) -> Paint<RgbaFColor> {
+        let modes = [AntialiasMode::None, AntialiasMode::Exact, AntialiasMode::sub_pixel(4)];
+        let blend_modes = [BlendMode::SrcOver, BlendMode::SrcOver, BlendMode::Src, BlendMode::Clear];
+        Paint {
+            ink: Ink::Color(self.color()),
+            blend_mode: blend_modes[(self.next() * 4.0) as usize],
+            antialias_mode: modes[(self.next() * 3.0) as usize],
+        }
+    }
+}
+
+/// Records random stars and ellipses.
+fn record_scene(rasterizer: &mut TiledRasterizer, seed: u64, count: usize, size: f64) {
+    let mut rng = Lcg(seed);
+    let (width, height) = (rasterizer.width() as f64, rasterizer.height() as f64);
+    for i in 0..count {
+        let paint = rng.paint();
+        if i % 5 == 4 {
+            let center = Vec2f32::new(rng.range(0.0, width) as f32, rng.range(0.0, height) as f32);
+            let radii = Vec2f32::new(rng.range(1.0, size) as f32, rng.range(1.0, size) as f32);
+            let bounds = Rect::new(
+                Vector2::new((center.x - radii.x) as i32 - 2, (center.y - radii.y) as i32 - 2),
+                Vector2::new((center.x + radii.x) as i32 + 2, (center.y + radii.y) as i32 + 2),
+            );
+            rasterizer.record(bounds, &paint, move |clip_rect, coverage| {
+                antialiased::fill_ellipse(&center, &radii, clip_rect, coverage);
+            });
+        } else {
+            let fill_rule = if rng.next() < 0.5 { FillRule::NonZero } else { FillRule::EvenOdd };
+            rasterizer.fill_polyline(&rng.star(width, height, size), fill_rule, &paint);
+        }
+    }
+}
+
+fn same_bits(a: &[RgbaFColor], b: &[RgbaFColor]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.to_slice().iter().zip(b.to_slice()).all(|(a, b)| a.to_bits() == b.to_bits()))
+}
+
+fn measure() {
+    let (width, height, count) = (3840, 2160, 3000);
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut rasterizer = TiledRasterizer::new(width, height);
+    record_scene(&mut rasterizer, 7, count, 300.0);
+
+    let mut single = vec![RgbaFColor::TRANSPARENT; width * height];
+    let start = Instant::now();
+    rasterizer.render_with_threads(&mut single, 1);
+    let single_time = start.elapsed();
+
+    let mut parallel = vec![RgbaFColor::TRANSPARENT; width * height];
+    let start = Instant::now();
+    rasterizer.render_with_threads(&mut parallel, threads);
+    let parallel_time = start.elapsed();
+
+    assert!(same_bits(&single, &parallel), "the parallel rendering differs");
+    println!(
+        "{}x{}, {} draws: {:.2?} on 1 thread, {:.2?} on {} thread(s) ({:.1}x)",
+        width,
+        height,
+        count,
+        single_time,
+        parallel_time,
+        threads,
+        single_time.as_secs_f64() / parallel_time.as_secs_f64()
+    );
+}
+
+fn main() {
+    measure();
+}
@@ -89,6 +89,8 @@ All components are real numbers in the range <i-math>[0, 1]</i-math>.
 
 use strum::{EnumIter, IntoEnumIterator};
 
+use crate::base::defs::colors::default_color_types::RgbaFColor;
+
 /// A blend mode is a function that combines a *source* color with a *destination* color to produce a new color.
 /// 
 /// The **source** is the color that is being drawn on the canvas, while the **destination** is the color that is already present on the canvas (same as **backdrop** in this context).
@@ -315,6 +317,18 @@ impl BlendMode {
     pub fn enumerate_all() -> impl Iterator<Item = BlendMode> {
         BlendMode::iter()
     }
+
+    /// Returns the function blending a source color into a destination color, if the blend mode
+    /// is implemented in [`modes_impl`].
+    pub fn implementation(&self) -> Option<fn(&RgbaFColor, &mut RgbaFColor)> {
+        match self {
+            BlendMode::Clear => Some(modes_impl::clear),
+            BlendMode::Src => Some(modes_impl::src),
+            BlendMode::Dst => Some(modes_impl::dst),
+            BlendMode::SrcOver => Some(modes_impl::src_over),
+            _ => None,
+        }
+    }
 }
 
 impl Default for BlendMode {
@@ -0,0 +1,621 @@
+/*!
+Boolean operations (union, intersection, difference and xor) between filled polygons.
+
+The algorithm works on flattened contours (see [`BrokenPolylineCommand`]) and follows the same
+idea as the *Martinez-Rueda* algorithm:
+ 1. all the edges of both operands are collected, every contour is implicitly closed;
+ 2. the edges are split at their mutual intersections, so that two edges can only meet at their endpoints
+    or overlap completely;
+ 3. each edge is classified by computing the winding numbers of both operands on its two sides: an edge
+    is part of the result if the operation gives a different result on the two sides;
+ 4. the selected edges are oriented so that the inside of the result lies on their left and they are
+    chained back into closed contours.
+
+The resulting contours never overlap, the outer contours have a positive signed area while holes have a
+negative one, so the result is the same for both [`FillRule::NonZero`] and [`FillRule::EvenOdd`].
+
+## Example
+```rust
+use repaint::base::clipping::boolean::{boolean_operation, BooleanOperation};
+use repaint::base::defs::{fill_rule::FillRule, linalg::Vec2f64};
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+
+let square = |x: f64, y: f64| [
+    BrokenPolylineCommand::MoveTo(Vec2f64::new(x, y)),
+    BrokenPolylineCommand::LineTo(Vec2f64::new(x + 2.0, y)),
+    BrokenPolylineCommand::LineTo(Vec2f64::new(x + 2.0, y + 2.0)),
+    BrokenPolylineCommand::LineTo(Vec2f64::new(x, y + 2.0)),
+];
+let path = boolean_operation(
+    &square(0.0, 0.0), FillRule::NonZero,
+    &square(1.0, 1.0), FillRule::NonZero,
+    BooleanOperation::Union,
+);
+assert!(!path.is_empty());
+```
+*/
+
+use std::collections::HashMap;
+
+use crate::base::defs::{fill_rule::FillRule, linalg::Vec2f64};
+use crate::base::shapes::{
+    path::{discretization::{DiscretizationParams, PathDiscretizer}, PathCommand},
+    polyline::BrokenPolylineCommand,
+};
+use crate::base::transform::Transform2d;
+use crate::painter::ClipOperation;
+
+/// A boolean operation between two filled shapes, the *subject* and the *clip*.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BooleanOperation {
+    /// The points inside the subject or the clip.
+    Union,
+
+    /// The points inside both the subject and the clip.
+    Intersection,
+
+    /// The points inside the subject but not inside the clip.
+    Difference,
+
+    /// The points inside exactly one of the subject and the clip.
+    Xor,
+}
+
+impl BooleanOperation {
+    /// Tells if a point is inside the result given whether it is inside the subject and the clip.
+    pub fn apply(&self, in_subject: bool, in_clip: bool) -> bool {
+        match self {
+            BooleanOperation::Union => in_subject || in_clip,
+            BooleanOperation::Intersection => in_subject && in_clip,
+            BooleanOperation::Difference => in_subject && !in_clip,
+            BooleanOperation::Xor => in_subject != in_clip,
+        }
+    }
+}
+
+impl From<ClipOperation> for BooleanOperation {
+    fn from(operation: ClipOperation) -> Self {
+        match operation {
+            ClipOperation::Difference => BooleanOperation::Difference,
+            ClipOperation::Intersect => BooleanOperation::Intersection,
+        }
+    }
+}
+
+/// Computes a boolean operation between two sets of closed contours.
+///
+/// Every contour starts with a [`BrokenPolylineCommand::MoveTo`] and is implicitly closed.
+/// Each operand is interpreted using its own fill rule. The result is a list of closed
+/// polygons made of [`PathCommand::MoveTo`], [`PathCommand::LineTo`] and [`PathCommand::ClosePath`].
+///
+/// ## Notes
+///  - non-finite points are ignored
+///  - collinear consecutive points are merged in the result
+pub fn boolean_operation(
+    subject: &[BrokenPolylineCommand],
+    subject_fill_rule: FillRule,
+    clip: &[BrokenPolylineCommand],
+    clip_fill_rule: FillRule,
+    operation: BooleanOperation,
+) -> Vec<PathCommand> {
+    let contours = boolean_operation_contours(
+        subject,
+        subject_fill_rule,
+        clip,
+        clip_fill_rule,
+        operation,
+    );
+    contours_to_path(&contours)
+}
+
+/// Same as [`boolean_operation`], but the operands are paths that are flattened
+/// using the given discretization parameters first.
+pub fn path_boolean_operation(
+    subject: &[PathCommand],
+    subject_fill_rule: FillRule,
+    clip: &[PathCommand],
+    clip_fill_rule: FillRule,
+    operation: BooleanOperation,
+    params: DiscretizationParams,
+) -> Vec<PathCommand> {
+    let discretizer = PathDiscretizer::new(params, &Transform2d::Identity);
+    let subject: Vec<BrokenPolylineCommand> = discretizer.discretize(&mut subject.iter()).collect();
+    let clip: Vec<BrokenPolylineCommand> = discretizer.discretize(&mut clip.iter()).collect();
+    boolean_operation(&subject, subject_fill_rule, &clip, clip_fill_rule, operation)
+}
+
+/// Same as [`boolean_operation`], but the result is returned as a list of contours.
+///
+/// Each contour is a list of points, the closing edge from the last to the first point is implicit.
+pub fn boolean_operation_contours(
+    subject: &[BrokenPolylineCommand],
+    subject_fill_rule: FillRule,
+    clip: &[BrokenPolylineCommand],
+    clip_fill_rule: FillRule,
+    operation: BooleanOperation,
+) -> Vec<Vec<Vec2f64>> {
+    let mut edges = Vec::new();
+    collect_edges(subject, Operand::Subject, &mut edges);
+    collect_edges(clip, Operand::Clip, &mut edges);
+
+    let edges = split_edges(&edges);
+    let fill_rules = [subject_fill_rule, clip_fill_rule];
+    let result_edges = select_edges(&edges, |winding: [i32; 2]| {
+        operation.apply(
+            fill_rules[0].is_inside(winding[0]),
+            fill_rules[1].is_inside(winding[1]),
+        )
+    });
+
+    link_edges(&result_edges)
+}
+
+/// Resolves the self-intersections of a set of closed contours.
+///
+/// The result contains the regions whose winding number satisfies `is_inside`, as
+/// non-overlapping contours with the same orientation rules of [`boolean_operation`].
+/// For example, `|winding| winding > 0` keeps only the positively wound regions.
+pub fn simplify_contours(
+    commands: &[BrokenPolylineCommand],
+    is_inside: impl Fn(i32) -> bool,
+) -> Vec<Vec<Vec2f64>> {
+    let mut edges = Vec::new();
+    collect_edges(commands, Operand::Subject, &mut edges);
+
+    let edges = split_edges(&edges);
+    let result_edges = select_edges(&edges, |winding: [i32; 2]| is_inside(winding[0]));
+
+    link_edges(&result_edges)
+}
+
+/// Converts a list of closed contours to a path.
+pub fn contours_to_path(contours: &[Vec<Vec2f64>]) -> Vec<PathCommand> {
+    let mut path = Vec::with_capacity(contours.iter().map(|c| c.len() + 1).sum());
+    for contour in contours {
+        let mut points = contour.iter();
+        if let Some(first) = points.next() {
+            path.push(PathCommand::MoveTo(*first));
+            path.extend(points.map(|pt| PathCommand::LineTo(*pt)));
+            path.push(PathCommand::ClosePath);
+        }
+    }
+    path
+}
+
+/// Converts a list of closed contours to polyline commands.
+///
+/// The contours are not explicitly closed, as expected by [`boolean_operation`].
+pub fn contours_to_polyline(contours: &[Vec<Vec2f64>]) -> Vec<BrokenPolylineCommand> {
+    let mut commands = Vec::with_capacity(contours.iter().map(|c| c.len()).sum());
+    for contour in contours {
+        let mut points = contour.iter();
+        if let Some(first) = points.next() {
+            commands.push(BrokenPolylineCommand::MoveTo(*first));
+            commands.extend(points.map(|pt| BrokenPolylineCommand::LineTo(*pt)));
+        }
+    }
+    commands
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operand {
+    Subject = 0,
+    Clip = 1,
+}
+
+/// An oriented edge of one of the operands.
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    a: Vec2f64,
+    b: Vec2f64,
+    operand: Operand,
+}
+
+/// Collects the edges of the given contours, closing them.
+fn collect_edges(commands: &[BrokenPolylineCommand], operand: Operand, edges: &mut Vec<Edge>) {
+    let mut push = |a: Vec2f64, b: Vec2f64| {
+        if a != b {
+            edges.push(Edge { a, b, operand });
+        }
+    };
+
+    let mut start = Vec2f64::zeros();
+    let mut pos = Vec2f64::zeros();
+    for command in commands {
+        match command {
+            BrokenPolylineCommand::MoveTo(pt) => {
+                if !is_finite(pt) {
+                    continue;
+                }
+                push(pos, start);
+                start = *pt;
+                pos = *pt;
+            }
+            BrokenPolylineCommand::LineTo(pt) => {
+                if !is_finite(pt) {
+                    continue;
+                }
+                push(pos, *pt);
+                pos = *pt;
+            }
+        }
+    }
+    push(pos, start);
+}
+
+/// The maximum number of passes of [`split_edges`], the pieces are usually stable after two.
+const MAX_SPLIT_PASSES: usize = 16;
+
+/// Splits the edges at their mutual intersections.
+///
+/// Intersection points are computed once and shared between the two edges, so the resulting
+/// edges meet exactly at their endpoints and overlapping parts become identical edges. The
+/// intersection points are rounded, so the pieces ending there can cross other pieces: the
+/// pieces are split again until no edge is split anymore.
+fn split_edges(edges: &[Edge]) -> Vec<Edge> {
+    let mut edges = split_pass(edges);
+    for _ in 1..MAX_SPLIT_PASSES {
+        let pieces = split_pass(&edges);
+        if pieces.len() == edges.len() {
+            break;
+        }
+        edges = pieces;
+    }
+    edges
+}
+
+/// Splits the edges at the intersections found between them once.
+fn split_pass(edges: &[Edge]) -> Vec<Edge> {
+    let mut splits: Vec<Vec<Vec2f64>> = vec![Vec::new(); edges.len()];
+
+    // sort and sweep along x to only test edges with overlapping bounding boxes
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    order.sort_by(|&i, &j| edges[i].a.x.min(edges[i].b.x).total_cmp(&edges[j].a.x.min(edges[j].b.x)));
+    let mut active: Vec<usize> = Vec::new();
+    for &i in &order {
+        let e = &edges[i];
+        let min_x = e.a.x.min(e.b.x);
+        active.retain(|&j| edges[j].a.x.max(edges[j].b.x) >= min_x);
+        for &j in &active {
+            let f = &edges[j];
+            if e.a.y.max(e.b.y) < f.a.y.min(f.b.y) || f.a.y.max(f.b.y) < e.a.y.min(e.b.y) {
+                continue;
+            }
+            intersect_edges(e, f, |on_e, pt| {
+                if on_e {
+                    splits[i].push(pt);
+                } else {
+                    splits[j].push(pt);
+                }
+            });
+        }
+        active.push(i);
+    }
+
+    let mut result = Vec::with_capacity(edges.len());
+    for (edge, points) in edges.iter().zip(splits.iter_mut()) {
+        let d = edge.b - edge.a;
+        points.sort_by(|p, q| (p - edge.a).dot(&d).total_cmp(&(q - edge.a).dot(&d)));
+        let mut a = edge.a;
+        for pt in points.iter().chain(std::iter::once(&edge.b)) {
+            if *pt != a {
+                result.push(Edge { a, b: *pt, operand: edge.operand });
+                a = *pt;
+            }
+        }
+    }
+    result
+}
+
+/// Finds the points where the two edges should be split.
+///
+/// `split` is called with `true` for points on `e` and with `false` for points on `f`.
+fn intersect_edges(e: &Edge, f: &Edge, mut split: impl FnMut(bool, Vec2f64)) {
+    let d1 = orient(&f.a, &f.b, &e.a);
+    let d2 = orient(&f.a, &f.b, &e.b);
+    let d3 = orient(&e.a, &e.b, &f.a);
+    let d4 = orient(&e.a, &e.b, &f.b);
+
+    if d1 == 0.0 && d2 == 0.0 {
+        // collinear edges, split each one at the endpoints of the other one
+        for pt in [f.a, f.b] {
+            if is_strictly_inside_collinear(e, &pt) {
+                split(true, pt);
+            }
+        }
+        for pt in [e.a, e.b] {
+            if is_strictly_inside_collinear(f, &pt) {
+                split(false, pt);
+            }
+        }
+        return;
+    }
+
+    if (d1 > 0.0 && d2 > 0.0) || (d1 < 0.0 && d2 < 0.0) || (d3 > 0.0 && d4 > 0.0) || (d3 < 0.0 && d4 < 0.0) {
+        return;
+    }
+
+    if d1 == 0.0 || d2 == 0.0 || d3 == 0.0 || d4 == 0.0 {
+        // an endpoint lies on the other edge
+        for (d, pt) in [(d1, e.a), (d2, e.b)] {
+            if d == 0.0 && is_strictly_inside_collinear(f, &pt) {
+                split(false, pt);
+            }
+        }
+        for (d, pt) in [(d3, f.a), (d4, f.b)] {
+            if d == 0.0 && is_strictly_inside_collinear(e, &pt) {
+                split(true, pt);
+            }
+        }
+        return;
+    }
+
+    // proper intersection
+    let t = d1 / (d1 - d2);
+    let pt = e.a + (e.b - e.a) * t;
+    if !is_finite(&pt) {
+        return;
+    }
+    if pt != e.a && pt != e.b {
+        split(true, pt);
+    }
+    if pt != f.a && pt != f.b {
+        split(false, pt);
+    }
+}
+
+/// An edge of the result, the inside of the result is on its left.
+#[derive(Clone, Copy, Debug)]
+struct ResultEdge {
+    from: Vec2f64,
+    to: Vec2f64,
+}
+
+/// Selects the edges that separate the inside from the outside of the result.
+///
+/// `is_inside` tells if a point with the given subject and clip winding numbers is inside the result.
+fn select_edges(edges: &[Edge], is_inside: impl Fn([i32; 2]) -> bool) -> Vec<ResultEdge> {
+    // group coincident edges, they have to be classified together
+    let mut groups: HashMap<[u64; 4], Vec<usize>> = HashMap::new();
+    for (idx, edge) in edges.iter().enumerate() {
+        let (lo, hi) = canonical(edge);
+        groups.entry([key(lo.x), key(lo.y), key(hi.x), key(hi.y)]).or_default().push(idx);
+    }
+
+    let x_buckets = AxisBuckets::new(edges, |pt| pt.x);
+    let y_buckets = AxisBuckets::new(edges, |pt| pt.y);
+
+    let mut result = Vec::new();
+    for group in groups.values() {
+        let (a, b) = canonical(&edges[group[0]]);
+        let d = b - a;
+        let m = (a + b) * 0.5;
+
+        // The winding numbers are computed by casting a ray from the midpoint of the edge
+        // (up for mostly horizontal edges, right for mostly vertical ones) and ignoring the
+        // edges in the group: this gives the winding numbers on the side the ray goes to.
+        let steep = d.y.abs() > d.x.abs();
+        let (ray, candidates) = if steep {
+            (Vec2f64::new(1.0, 0.0), y_buckets.query(m.y))
+        } else {
+            (Vec2f64::new(0.0, -1.0), x_buckets.query(m.x))
+        };
+
+        let mut ahead = [0i32; 2];
+        for &idx in candidates {
+            let edge = &edges[idx];
+            if group.contains(&idx) {
+                continue;
+            }
+            ahead[edge.operand as usize] += ray_crossing(edge, &m, steep);
+        }
+
+        // on the other side, the ray also crosses the edges in the group
+        let mut behind = ahead;
+        for &idx in group {
+            let edge = &edges[idx];
+            let dir = if steep { edge.b.y - edge.a.y } else { edge.b.x - edge.a.x };
+            behind[edge.operand as usize] += if dir > 0.0 { 1 } else { -1 };
+        }
+
+        let inside_ahead = is_inside(ahead);
+        if inside_ahead == is_inside(behind) {
+            continue;
+        }
+
+        // orient the edge so that the inside is on its left
+        let to_inside = if inside_ahead { ray } else { -ray };
+        if cross(&d, &to_inside) > 0.0 {
+            result.push(ResultEdge { from: a, to: b });
+        } else {
+            result.push(ResultEdge { from: b, to: a });
+        }
+    }
+    result
+}
+
+/// Returns the contribution of the edge to the winding number of `pt`
+/// computed with a ray going up (or right if `horizontal_ray`).
+fn ray_crossing(edge: &Edge, pt: &Vec2f64, horizontal_ray: bool) -> i32 {
+    if horizontal_ray {
+        if (edge.a.y <= pt.y) != (edge.b.y <= pt.y) {
+            let x = edge.a.x + (pt.y - edge.a.y) * (edge.b.x - edge.a.x) / (edge.b.y - edge.a.y);
+            if x > pt.x {
+                return if edge.b.y > edge.a.y { 1 } else { -1 };
+            }
+        }
+    } else if (edge.a.x <= pt.x) != (edge.b.x <= pt.x) {
+        let y = edge.a.y + (pt.x - edge.a.x) * (edge.b.y - edge.a.y) / (edge.b.x - edge.a.x);
+        if y < pt.y {
+            return if edge.b.x > edge.a.x { 1 } else { -1 };
+        }
+    }
+    0
+}
+
+/// Chains the result edges into closed contours.
+fn link_edges(edges: &[ResultEdge]) -> Vec<Vec<Vec2f64>> {
+    let mut outgoing: HashMap<[u64; 2], Vec<usize>> = HashMap::new();
+    for (idx, edge) in edges.iter().enumerate() {
+        outgoing.entry(point_key(&edge.from)).or_default().push(idx);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut contours = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+
+        let start = edges[first].from;
+        let mut contour = vec![start];
+        let mut current = first;
+        while edges[current].to != start {
+            let edge = &edges[current];
+            contour.push(edge.to);
+
+            // When more contours touch at a vertex, turning as much as possible to the left
+            // keeps the inside on the left and produces contours that do not cross themselves.
+            let dir = edge.to - edge.from;
+            let next = outgoing
+                .get(&point_key(&edge.to))
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|&idx| !used[idx])
+                .max_by(|&i, &j| {
+                    let turn = |idx: usize| {
+                        let out = edges[idx].to - edges[idx].from;
+                        cross(&dir, &out).atan2(dir.dot(&out))
+                    };
+                    turn(i).total_cmp(&turn(j))
+                });
+
+            match next {
+                Some(next) => {
+                    used[next] = true;
+                    current = next;
+                }
+                // this should not happen, but a broken contour is better than an infinite loop
+                None => break,
+            }
+        }
+
+        let contour = remove_collinear_points(contour);
+        if contour.len() >= 3 {
+            contours.push(contour);
+        }
+    }
+    contours
+}
+
+/// Removes the points of a closed contour that lie on the line between their neighbors.
+fn remove_collinear_points(mut contour: Vec<Vec2f64>) -> Vec<Vec2f64> {
+    let mut changed = true;
+    while changed && contour.len() >= 3 {
+        changed = false;
+        let mut idx = 0;
+        while idx < contour.len() && contour.len() >= 3 {
+            let n = contour.len();
+            let prev = contour[(idx + n - 1) % n];
+            let next = contour[(idx + 1) % n];
+            let u = contour[idx] - prev;
+            let v = next - contour[idx];
+            if cross(&u, &v).abs() <= 1e-12 * u.norm() * v.norm() && u.dot(&v) > 0.0 {
+                contour.remove(idx);
+                changed = true;
+            } else {
+                idx += 1;
+            }
+        }
+    }
+    contour
+}
+
+/// A simple uniform grid along one axis used to find the edges that can cross a ray.
+struct AxisBuckets {
+    origin: f64,
+    cell_size: f64,
+    cells: Vec<Vec<usize>>,
+}
+
+impl AxisBuckets {
+    fn new(edges: &[Edge], axis: impl Fn(&Vec2f64) -> f64) -> Self {
+        let (min, max) = edges.iter().fold((f64::MAX, f64::MIN), |(min, max), edge| {
+            (min.min(axis(&edge.a)).min(axis(&edge.b)), max.max(axis(&edge.a)).max(axis(&edge.b)))
+        });
+
+        let count = ((edges.len() as f64).sqrt() as usize).clamp(1, 4096);
+        let cell_size = (max - min) / count as f64;
+        let mut buckets = Self {
+            origin: min,
+            cell_size,
+            cells: vec![Vec::new(); count],
+        };
+
+        for (idx, edge) in edges.iter().enumerate() {
+            let lo = buckets.cell(axis(&edge.a).min(axis(&edge.b)));
+            let hi = buckets.cell(axis(&edge.a).max(axis(&edge.b)));
+            for cell in &mut buckets.cells[lo..=hi] {
+                cell.push(idx);
+            }
+        }
+        buckets
+    }
+
+    fn cell(&self, value: f64) -> usize {
+        if self.cell_size.is_nan() || self.cell_size <= 0.0 {
+            return 0;
+        }
+        let cell = ((value - self.origin) / self.cell_size).floor();
+        (cell.max(0.0) as usize).min(self.cells.len() - 1)
+    }
+
+    fn query(&self, value: f64) -> &[usize] {
+        &self.cells[self.cell(value)]
+    }
+}
+
+/// The endpoints of the edge, sorted lexicographically.
+fn canonical(edge: &Edge) -> (Vec2f64, Vec2f64) {
+    if (edge.a.x, edge.a.y) < (edge.b.x, edge.b.y) {
+        (edge.a, edge.b)
+    } else {
+        (edge.b, edge.a)
+    }
+}
+
+/// A hashable key for a coordinate, `-0.0` and `0.0` are considered equal.
+fn key(value: f64) -> u64 {
+    (value + 0.0).to_bits()
+}
+
+fn point_key(pt: &Vec2f64) -> [u64; 2] {
+    [key(pt.x), key(pt.y)]
+}
+
+fn is_finite(pt: &Vec2f64) -> bool {
+    pt.x.is_finite() && pt.y.is_finite()
+}
+
+#[inline]
+fn cross(u: &Vec2f64, v: &Vec2f64) -> f64 {
+    u.x * v.y - u.y * v.x
+}
+
+/// Positive if `c` is on the left of the line `a -> b`, zero if the points are collinear.
+#[inline]
+fn orient(a: &Vec2f64, b: &Vec2f64, c: &Vec2f64) -> f64 {
+    cross(&(b - a), &(c - a))
+}
+
+/// Tells if `pt`, known to be collinear with the edge, lies strictly between its endpoints.
+fn is_strictly_inside_collinear(edge: &Edge, pt: &Vec2f64) -> bool {
+    if *pt == edge.a || *pt == edge.b {
+        return false;
+    }
+    let d = edge.b - edge.a;
+    let t = (pt - edge.a).dot(&d);
+    t > 0.0 && t < d.dot(&d)
+}
@@ -0,0 +1,248 @@
+/*!
+Parametric line clipping.
+
+A line is given by two points, `start` and `end`, and its points are `start + t * (end - start)`.
+The clipping functions return the range `t0..t1` of the visible part along with its endpoints,
+`t0 <= t1`, so the orientation of the line is kept and the parameters can be used to clip dashes
+or gradients defined along the original line.
+
+Two classic algorithms are provided for rectangles:
+ - [*Liang–Barsky*](https://en.wikipedia.org/wiki/Liang%E2%80%93Barsky_algorithm), that works with
+   segments, rays and infinite lines, see [`LineExtent`];
+ - [*Cohen–Sutherland*](https://en.wikipedia.org/wiki/Cohen%E2%80%93Sutherland_algorithm), for segments
+   only, that quickly accepts or rejects segments that are completely inside or on one side of the rectangle.
+
+Convex polygons are handled with the *Cyrus–Beck* generalization of Liang–Barsky.
+*/
+
+use crate::base::defs::{linalg::*, rect::F64Rect};
+use crate::base::transform::Transform2d;
+
+/// The part of the line through `start` and `end` that is considered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum LineExtent {
+    /// The segment between `start` and `end`, i.e. `0 <= t <= 1`.
+    #[default]
+    Segment,
+
+    /// The half-infinite line starting at `start` and going through `end`, i.e. `t >= 0`.
+    Ray,
+
+    /// The infinite line through `start` and `end`.
+    Line,
+}
+
+impl LineExtent {
+    /// The range of the parameter.
+    pub fn range(&self) -> (f64, f64) {
+        match self {
+            LineExtent::Segment => (0.0, 1.0),
+            LineExtent::Ray => (0.0, f64::INFINITY),
+            LineExtent::Line => (f64::NEG_INFINITY, f64::INFINITY),
+        }
+    }
+}
+
+/// The visible part of a clipped line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClippedLine {
+    /// The point at `t0`.
+    pub start: Vec2f64,
+    /// The point at `t1`.
+    pub end: Vec2f64,
+    /// The parameter of the first visible point.
+    pub t0: f64,
+    /// The parameter of the last visible point, not smaller than `t0`.
+    pub t1: f64,
+}
+
+/// Clips a line to a rectangle with the Liang–Barsky algorithm.
+///
+/// Returns `None` if the line is outside the rectangle or if a coordinate is not finite. If
+/// `start == end` the line is a point, visible if it is inside the rectangle.
+pub fn liang_barsky(start: &Vec2f64, end: &Vec2f64, extent: LineExtent, rect: &F64Rect) -> Option<ClippedLine> {
+    let rect = rect.fix();
+    let delta = end - start;
+    let clipped = clip_parametric(
+        start,
+        end,
+        extent,
+        [
+            (-delta.x, start.x - rect.min.x),
+            (delta.x, rect.max.x - start.x),
+            (-delta.y, start.y - rect.min.y),
+            (delta.y, rect.max.y - start.y),
+        ],
+    )?;
+    Some(snap_to_rect(clipped, &rect))
+}
+
+/// Clips a segment to a rectangle with the Cohen–Sutherland algorithm.
+///
+/// Returns `None` if the segment is outside the rectangle or if a coordinate is not finite.
+pub fn cohen_sutherland(start: &Vec2f64, end: &Vec2f64, rect: &F64Rect) -> Option<ClippedLine> {
+    if !is_finite(start) || !is_finite(end) {
+        return None;
+    }
+    let rect = rect.fix();
+    let delta = end - start;
+    let outcode = |pt: &Vec2f64| {
+        let mut code = 0;
+        if pt.x < rect.min.x {
+            code |= LEFT;
+        } else if pt.x > rect.max.x {
+            code |= RIGHT;
+        }
+        if pt.y < rect.min.y {
+            code |= TOP;
+        } else if pt.y > rect.max.y {
+            code |= BOTTOM;
+        }
+        code
+    };
+
+    let (mut t0, mut t1) = (0.0, 1.0);
+    let (mut p0, mut p1) = (*start, *end);
+    let (mut code0, mut code1) = (outcode(&p0), outcode(&p1));
+    loop {
+        if code0 | code1 == 0 {
+            return Some(ClippedLine { start: p0, end: p1, t0, t1 });
+        }
+        if code0 & code1 != 0 {
+            return None;
+        }
+
+        // move the endpoint outside the rectangle to the boundary it crosses
+        let code = if code0 != 0 { code0 } else { code1 };
+        let t = if code & LEFT != 0 {
+            (rect.min.x - start.x) / delta.x
+        } else if code & RIGHT != 0 {
+            (rect.max.x - start.x) / delta.x
+        } else if code & TOP != 0 {
+            (rect.min.y - start.y) / delta.y
+        } else {
+            (rect.max.y - start.y) / delta.y
+        };
+        let mut pt = start + delta * t;
+        // the coordinate of the crossed boundary is exact
+        if code & (LEFT | RIGHT) != 0 {
+            pt.x = if code & LEFT != 0 { rect.min.x } else { rect.max.x };
+        } else {
+            pt.y = if code & TOP != 0 { rect.min.y } else { rect.max.y };
+        }
+
+        if code == code0 {
+            (p0, t0, code0) = (pt, t, outcode(&pt));
+        } else {
+            (p1, t1, code1) = (pt, t, outcode(&pt));
+        }
+    }
+}
+
+/// Clips a line to a rectangle transformed by an affine transform, e.g. a clip rectangle
+/// in local coordinates while the line is in device coordinates.
+///
+/// The parameters refer to the original line. Returns `None` if the transform is not affine
+/// or not invertible, see [`liang_barsky`] for the other cases.
+pub fn clip_line_to_transformed_rect(
+    start: &Vec2f64,
+    end: &Vec2f64,
+    extent: LineExtent,
+    rect: &F64Rect,
+    transform: &Transform2d,
+) -> Option<ClippedLine> {
+    let (linear, translation) = transform.to_affine()?;
+    let inverse = linear.try_inverse()?;
+    // affine transforms keep the parameterization of the line
+    let to_local = |pt: &Vec2f64| inverse * (pt - translation);
+    let clipped = liang_barsky(&to_local(start), &to_local(end), extent, rect)?;
+    let delta = end - start;
+    Some(ClippedLine {
+        start: start + delta * clipped.t0,
+        end: start + delta * clipped.t1,
+        ..clipped
+    })
+}
+
+/// Clips a line to a convex polygon with the Cyrus–Beck algorithm.
+///
+/// The polygon can have any orientation. Returns `None` if the polygon is degenerate,
+/// see [`liang_barsky`] for the other cases.
+pub fn clip_line_to_convex_polygon(
+    start: &Vec2f64,
+    end: &Vec2f64,
+    extent: LineExtent,
+    polygon: &[Vec2f64],
+) -> Option<ClippedLine> {
+    let area = super::polygon::signed_area(polygon);
+    if polygon.len() < 3 || area == 0.0 || !area.is_finite() {
+        return None;
+    }
+    let orientation = area.signum();
+
+    // positive on the inner side of each edge
+    let distance = |a: &Vec2f64, edge: &Vec2f64, pt: &Vec2f64| {
+        let d = pt - a;
+        (edge.x * d.y - edge.y * d.x) * orientation
+    };
+    let half_planes = polygon.iter().enumerate().map(|(i, a)| {
+        let edge = polygon[(i + 1) % polygon.len()] - a;
+        let start_distance = distance(a, &edge, start);
+        (start_distance - distance(a, &edge, end), start_distance)
+    });
+    clip_parametric(start, end, extent, half_planes)
+}
+
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const TOP: u8 = 4;
+const BOTTOM: u8 = 8;
+
+/// The Liang–Barsky clipping: the visible points satisfy `p * t <= q` for every `(p, q)`.
+fn clip_parametric(
+    start: &Vec2f64,
+    end: &Vec2f64,
+    extent: LineExtent,
+    half_planes: impl IntoIterator<Item = (f64, f64)>,
+) -> Option<ClippedLine> {
+    if !is_finite(start) || !is_finite(end) {
+        return None;
+    }
+
+    let (mut t0, mut t1) = extent.range();
+    if start == end {
+        // the line is a point
+        (t0, t1) = (0.0, 0.0);
+    }
+    for (p, q) in half_planes {
+        if p == 0.0 {
+            // parallel to the boundary
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+        if t0 > t1 {
+            return None;
+        }
+    }
+
+    let delta = end - start;
+    Some(ClippedLine { start: start + delta * t0, end: start + delta * t1, t0, t1 })
+}
+
+/// Removes the rounding errors of the intersections.
+fn snap_to_rect(clipped: ClippedLine, rect: &F64Rect) -> ClippedLine {
+    ClippedLine {
+        start: clipped.start.sup(&rect.min).inf(&rect.max),
+        end: clipped.end.sup(&rect.min).inf(&rect.max),
+        ..clipped
+    }
+}
+
+fn is_finite(pt: &Vec2f64) -> bool {
+    pt.x.is_finite() && pt.y.is_finite()
+}
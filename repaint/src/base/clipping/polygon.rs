@@ -0,0 +1,173 @@
+/*!
+Polygon clipping against convex regions.
+
+The contours are clipped with the *Sutherland–Hodgman* algorithm: each contour, implicitly closed,
+is clipped by every edge of the convex region in turn. The parts of a contour outside the region are
+replaced by pieces of the region boundary, so the winding number of every point inside the region is
+unchanged and the result can be filled with the same [`FillRule`](crate::base::defs::fill_rule::FillRule).
+
+The resulting contours can have degenerate edges along the boundary of the region, this doesn't
+change the filled area.
+
+## Example
+```rust
+use repaint::base::clipping::polygon::clip_contours_to_rect;
+use repaint::base::defs::{linalg::Vec2f64, rect::F64Rect};
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+
+let triangle = [
+    BrokenPolylineCommand::MoveTo(Vec2f64::new(-10.0, -10.0)),
+    BrokenPolylineCommand::LineTo(Vec2f64::new(1e12, 5.0)),
+    BrokenPolylineCommand::LineTo(Vec2f64::new(5.0, 1e12)),
+];
+let rect = F64Rect::new(Vec2f64::new(0.0, 0.0), Vec2f64::new(100.0, 100.0));
+let clipped = clip_contours_to_rect(&triangle, &rect);
+assert!(clipped.iter().all(|command| rect.contains(&command.point())));
+```
+*/
+
+use crate::base::defs::{linalg::*, rect::F64Rect};
+use crate::base::shapes::polyline::{split_polylines, BrokenPolylineCommand};
+
+/// Clips the contours of a broken polyline to a rectangle.
+///
+/// Every contour is considered closed. A contour ending on its first point is still
+/// explicitly closed after clipping, contours that are completely outside are removed.
+/// Points with non-finite coordinates are ignored.
+pub fn clip_contours_to_rect(commands: &[BrokenPolylineCommand], rect: &F64Rect) -> Vec<BrokenPolylineCommand> {
+    clip_contours(commands, |points| clip_polygon_to_rect(points, rect))
+}
+
+/// Clips the contours of a broken polyline to a convex polygon, see [`clip_contours_to_rect`].
+///
+/// The clip polygon can have any orientation, the result is empty if it is degenerate.
+pub fn clip_contours_to_convex_polygon(
+    commands: &[BrokenPolylineCommand],
+    clip: &[Vec2f64],
+) -> Vec<BrokenPolylineCommand> {
+    clip_contours(commands, |points| clip_polygon_to_convex_polygon(points, clip))
+}
+
+/// Clips an implicitly closed polygon to a rectangle.
+///
+/// The result is empty if the polygon is completely outside the rectangle.
+pub fn clip_polygon_to_rect(points: &[Vec2f64], rect: &F64Rect) -> Vec<Vec2f64> {
+    let rect = rect.fix();
+    let mut polygon = finite_points(points);
+    let mut buffer = Vec::with_capacity(polygon.len() + 4);
+    // the intersections are placed exactly on the sides, so that the pieces of the sides in the
+    // result are exactly aligned
+    clip_by_half_plane(&polygon, &mut buffer, |pt| pt.x - rect.min.x, |pt| Vec2f64::new(rect.min.x, pt.y));
+    clip_by_half_plane(&buffer, &mut polygon, |pt| rect.max.x - pt.x, |pt| Vec2f64::new(rect.max.x, pt.y));
+    clip_by_half_plane(&polygon, &mut buffer, |pt| pt.y - rect.min.y, |pt| Vec2f64::new(pt.x, rect.min.y));
+    clip_by_half_plane(&buffer, &mut polygon, |pt| rect.max.y - pt.y, |pt| Vec2f64::new(pt.x, rect.max.y));
+
+    // the other coordinate of the intersections is computed with a small error, snap it to the rectangle
+    for pt in polygon.iter_mut() {
+        *pt = pt.sup(&rect.min).inf(&rect.max);
+    }
+    polygon
+}
+
+/// Clips an implicitly closed polygon to a convex polygon.
+///
+/// The clip polygon can have any orientation, the result is empty if it is degenerate
+/// or if the polygon is completely outside.
+pub fn clip_polygon_to_convex_polygon(points: &[Vec2f64], clip: &[Vec2f64]) -> Vec<Vec2f64> {
+    let clip = finite_points(clip);
+    let area = signed_area(&clip);
+    if clip.len() < 3 || area == 0.0 || area.is_nan() {
+        return Vec::new();
+    }
+    let orientation = area.signum();
+
+    let mut polygon = finite_points(points);
+    let mut buffer = Vec::with_capacity(polygon.len() + clip.len());
+    for (i, a) in clip.iter().enumerate() {
+        let b = clip[(i + 1) % clip.len()];
+        let edge = b - a;
+        // positive on the inner side of the edge
+        clip_by_half_plane(
+            &polygon,
+            &mut buffer,
+            |pt| {
+                let d = pt - a;
+                (edge.x * d.y - edge.y * d.x) * orientation
+            },
+            |pt| pt,
+        );
+        std::mem::swap(&mut polygon, &mut buffer);
+    }
+    polygon
+}
+
+/// The signed area of an implicitly closed polygon, positive if it is clockwise on screen
+/// (when the y axis points down).
+pub fn signed_area(points: &[Vec2f64]) -> f64 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+    area / 2.0
+}
+
+/// Clips each contour with the given function, keeping the explicit closures.
+fn clip_contours(
+    commands: &[BrokenPolylineCommand],
+    mut clip: impl FnMut(&[Vec2f64]) -> Vec<Vec2f64>,
+) -> Vec<BrokenPolylineCommand> {
+    let mut result = Vec::new();
+    for mut contour in split_polylines(commands) {
+        let closed = contour.len() > 1 && contour.first() == contour.last();
+        if closed {
+            contour.pop();
+        }
+
+        let clipped = clip(&contour);
+        if clipped.is_empty() {
+            continue;
+        }
+        result.push(BrokenPolylineCommand::MoveTo(clipped[0]));
+        result.extend(clipped[1..].iter().map(|pt| BrokenPolylineCommand::LineTo(*pt)));
+        if closed {
+            result.push(BrokenPolylineCommand::LineTo(clipped[0]));
+        }
+    }
+    result
+}
+
+/// A single Sutherland–Hodgman step: keeps the part of the polygon where `distance` is not negative.
+///
+/// `distance` must be an affine function of the point, e.g. the signed distance to a line.
+/// `on_line` moves the computed intersections exactly onto the line, when it can be represented.
+fn clip_by_half_plane(
+    input: &[Vec2f64],
+    output: &mut Vec<Vec2f64>,
+    distance: impl Fn(&Vec2f64) -> f64,
+    on_line: impl Fn(Vec2f64) -> Vec2f64,
+) {
+    output.clear();
+    let Some(last) = input.last() else {
+        return;
+    };
+
+    let mut prev = *last;
+    let mut prev_distance = distance(&prev);
+    for pt in input {
+        let pt_distance = distance(pt);
+        if (prev_distance >= 0.0) != (pt_distance >= 0.0) {
+            let t = prev_distance / (prev_distance - pt_distance);
+            output.push(on_line(prev + (pt - prev) * t));
+        }
+        if pt_distance >= 0.0 {
+            output.push(*pt);
+        }
+        prev = *pt;
+        prev_distance = pt_distance;
+    }
+}
+
+fn finite_points(points: &[Vec2f64]) -> Vec<Vec2f64> {
+    points.iter().filter(|pt| pt.x.is_finite() && pt.y.is_finite()).copied().collect()
+}
@@ -0,0 +1,244 @@
+/*!
+Clip state for painters that do not have native clipping.
+
+A [`ClipStack`] follows the `save`/`restore` nesting of a painter (both
+[`with_save`](crate::BasicPainter::with_save) and [`with_save_layer`](crate::BasicPainter::with_save_layer)
+save the clip). While every clip is an axis-aligned rectangle intersected with the previous ones,
+the clip is kept as an exact device-space rectangle, otherwise it falls back to an anti-aliased
+coverage mask ([`AlphaMask`]) of the size of the device.
+*/
+
+use std::sync::Arc;
+
+use nalgebra::Vector2;
+
+use crate::base::defs::{fill_rule::FillRule, linalg::*, rect::{F64Rect, Rect}};
+use crate::base::rasterization::mask::AlphaMask;
+use crate::base::shapes::{
+    path::discretization::{DiscretizationParams, PathDiscretizer},
+    polyline::BrokenPolylineCommand,
+    BasicShape, Shape,
+};
+use crate::base::transform::Transform2d;
+use crate::painter::{methods::ClipError, ClipOperation};
+
+/// The clip of a painter, with `save`/`restore` support.
+///
+/// ## Example
+/// ```rust
+/// use repaint::ClipOperation;
+/// use repaint::base::clipping::ClipStack;
+/// use repaint::base::defs::rect::F64Rect;
+/// use repaint::base::shapes::BasicShape;
+/// use repaint::base::transform::Transform2d;
+///
+/// let mut clip = ClipStack::new(100, 100);
+/// clip.save();
+/// let rect = BasicShape::Rect(F64Rect::from_xywh(10.0, 10.0, 20.0, 20.0));
+/// clip.clip(&rect, &Transform2d::Translate([5.0, 0.0].into()), ClipOperation::Intersect).unwrap();
+/// assert_eq!(clip.as_rect(), Some(F64Rect::from_xywh(15.0, 10.0, 20.0, 20.0)));
+/// clip.restore();
+/// assert_eq!(clip.as_rect(), Some(F64Rect::from_xywh(0.0, 0.0, 100.0, 100.0)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClipStack {
+    width: usize,
+    height: usize,
+    current: ClipState,
+    saved: Vec<ClipState>,
+}
+
+/// The state of a [`ClipStack`].
+#[derive(Clone, Debug)]
+pub enum ClipState {
+    /// The visible area is exactly this device-space rectangle, possibly empty.
+    Rect(F64Rect),
+
+    /// The visible area is given by a coverage mask of the size of the device.
+    Mask {
+        mask: Arc<AlphaMask>,
+        /// The covered pixels, see [`AlphaMask::coverage_bounds`].
+        bounds: Option<Rect<i32>>,
+    },
+}
+
+impl ClipStack {
+    /// Creates a clip stack for a device of the given size, everything is visible.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            current: ClipState::Rect(Self::device_rect(width, height)),
+            saved: Vec::new(),
+        }
+    }
+
+    /// The current state.
+    pub fn state(&self) -> &ClipState {
+        &self.current
+    }
+
+    /// The number of saved states.
+    pub fn save_count(&self) -> usize {
+        self.saved.len()
+    }
+
+    /// Saves the current clip, it will be restored by [`ClipStack::restore`].
+    pub fn save(&mut self) {
+        // masks are shared until they are modified
+        self.saved.push(self.current.clone());
+    }
+
+    /// Restores the last saved clip, returns false if there is nothing to restore.
+    pub fn restore(&mut self) -> bool {
+        match self.saved.pop() {
+            Some(state) => {
+                self.current = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Calls `f` between a [`ClipStack::save`] and a [`ClipStack::restore`].
+    pub fn with_save<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.save();
+        let result = f(self);
+        self.restore();
+        result
+    }
+
+    /// Clips with a shape, `transform` maps the shape to the device.
+    ///
+    /// The shape is filled with [`FillRule::NonZero`].
+    pub fn clip(
+        &mut self,
+        shape: &impl Shape,
+        transform: &Transform2d,
+        operation: ClipOperation,
+    ) -> Result<(), ClipError> {
+        if let Some(rect) = shape.to_basic_shape().and_then(|shape| device_rect(&shape, transform)) {
+            if rect.min.x.is_nan() || rect.min.y.is_nan() || rect.max.x.is_nan() || rect.max.y.is_nan() {
+                return Err(ClipError::InvalidShape);
+            }
+            if let ClipState::Rect(current) = self.current {
+                match operation {
+                    ClipOperation::Intersect => {
+                        self.current = ClipState::Rect(intersect_or_empty(&current, &rect));
+                        return Ok(());
+                    }
+                    ClipOperation::Difference => {
+                        let overlap = intersect_or_empty(&current, &rect);
+                        if is_empty_rect(&overlap) {
+                            return Ok(());
+                        }
+                        if overlap == current {
+                            self.current = ClipState::Rect(empty_rect(&current));
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+
+        // the curves are flattened in device space, so that the tolerance is in pixels, the affine
+        // form of the transform keeps the lines unsplit
+        let affine = transform.to_affine().map(|(linear, translation)| Transform2d::Affine { linear, translation });
+        let discretizer = PathDiscretizer::new(DiscretizationParams::default(), affine.as_ref().unwrap_or(transform));
+        let polyline: Vec<BrokenPolylineCommand> = discretizer.discretize_iter(shape.to_path_iter()).collect();
+        let mut mask = AlphaMask::new(self.device_bounds());
+        mask.fill_polyline(&polyline, FillRule::NonZero);
+        self.combine(mask, operation);
+        Ok(())
+    }
+
+    /// The device-space bounds of the visible area, it could be larger than the visible area
+    /// when the clip is a mask.
+    pub fn bounds(&self) -> F64Rect {
+        match &self.current {
+            ClipState::Rect(rect) => *rect,
+            ClipState::Mask { bounds: Some(bounds), .. } => F64Rect::new(bounds.min.cast(), bounds.max.cast()),
+            ClipState::Mask { bounds: None, .. } => F64Rect::new(Vec2f64::zeros(), Vec2f64::zeros()),
+        }
+    }
+
+    /// Returns true if nothing is visible.
+    pub fn is_empty(&self) -> bool {
+        is_empty_rect(&self.bounds())
+    }
+
+    /// The clip rectangle, if the clip is an exact rectangle.
+    pub fn as_rect(&self) -> Option<F64Rect> {
+        match &self.current {
+            ClipState::Rect(rect) => Some(*rect),
+            ClipState::Mask { .. } => None,
+        }
+    }
+
+    /// The coverage mask, if the clip is not an exact rectangle.
+    pub fn as_mask(&self) -> Option<&AlphaMask> {
+        match &self.current {
+            ClipState::Rect(_) => None,
+            ClipState::Mask { mask, .. } => Some(mask),
+        }
+    }
+
+    /// The visible part of a pixel, between `0.0` and `1.0`.
+    pub fn coverage(&self, x: i32, y: i32) -> f32 {
+        match &self.current {
+            ClipState::Rect(rect) => {
+                let pixel = F64Rect::new(Vec2f64::new(x as f64, y as f64), Vec2f64::new(x as f64 + 1.0, y as f64 + 1.0));
+                pixel.intersect(rect).map_or(0.0, |overlap| (overlap.width() * overlap.height()) as f32)
+            }
+            ClipState::Mask { mask, .. } => mask.get(x, y),
+        }
+    }
+
+    fn combine(&mut self, other: AlphaMask, operation: ClipOperation) {
+        let device = self.device_bounds();
+        let mut mask = match std::mem::replace(&mut self.current, ClipState::Rect(F64Rect::new(Vec2f64::zeros(), Vec2f64::zeros()))) {
+            ClipState::Mask { mask, .. } => mask,
+            ClipState::Rect(rect) => Arc::new(AlphaMask::from_rect(device, &rect)),
+        };
+        match operation {
+            ClipOperation::Intersect => Arc::make_mut(&mut mask).intersect(&other),
+            ClipOperation::Difference => Arc::make_mut(&mut mask).subtract(&other),
+        }
+        let bounds = mask.coverage_bounds();
+        self.current = ClipState::Mask { mask, bounds };
+    }
+
+    fn device_bounds(&self) -> Rect<i32> {
+        Rect::new(Vector2::zeros(), Vector2::new(self.width as i32, self.height as i32))
+    }
+
+    fn device_rect(width: usize, height: usize) -> F64Rect {
+        F64Rect::new(Vec2f64::zeros(), Vec2f64::new(width as f64, height as f64))
+    }
+}
+
+/// The device-space rectangle of a shape, if it is a rectangle that stays axis-aligned.
+fn device_rect(shape: &BasicShape, transform: &Transform2d) -> Option<F64Rect> {
+    let BasicShape::Rect(rect) = shape else {
+        return None;
+    };
+    let (linear, translation) = transform.to_affine()?;
+    if linear[(0, 1)] != 0.0 || linear[(1, 0)] != 0.0 {
+        return None;
+    }
+    let min = linear * rect.min + translation;
+    let max = linear * rect.max + translation;
+    Some(F64Rect::new(min.inf(&max), min.sup(&max)))
+}
+
+fn intersect_or_empty(a: &F64Rect, b: &F64Rect) -> F64Rect {
+    a.intersect(b).unwrap_or_else(|| empty_rect(a))
+}
+
+fn empty_rect(rect: &F64Rect) -> F64Rect {
+    F64Rect::new(rect.min, rect.min)
+}
+
+fn is_empty_rect(rect: &F64Rect) -> bool {
+    !(rect.min.x < rect.max.x && rect.min.y < rect.max.y)
+}
@@ -1,4 +1,6 @@
 pub mod colors;
 pub mod rect;
 pub mod antialiasing;
-pub mod linalg;
\ No newline at end of file
+pub mod linalg;
+pub mod fill_rule;
+pub mod vertex_mode;
@@ -1,14 +1,30 @@
-
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 /// The antialias mode.
 pub enum AntialiasMode {
-    /// No antialiasing.
+    /// No antialiasing, a pixel is covered if its center is inside the shape.
     None,
 
-    /// Exact antialiasing.
+    /// Exact antialiasing, the coverage of a pixel is the area of the shape inside it.
+    #[default]
     Exact,
 
-    /// Antialiasing using a `ny x nx` grid.
+    /// Antialiasing using a `ny x nx` grid, the coverage of a pixel is the proportion of
+    /// the samples inside the shape.
     SubPixel{nx: u16, ny: u16},
-}
\ No newline at end of file
+}
+
+impl AntialiasMode {
+    /// A `n x n` sub-pixel grid.
+    pub fn sub_pixel(n: u16) -> Self {
+        AntialiasMode::SubPixel { nx: n, ny: n }
+    }
+
+    /// Tells if the edges are antialiased, i.e. if the pixels can be partially covered.
+    pub fn is_antialiased(&self) -> bool {
+        match self {
+            AntialiasMode::None => false,
+            AntialiasMode::Exact => true,
+            AntialiasMode::SubPixel { nx, ny } => *nx > 1 || *ny > 1,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+/// The rule used to decide which points are inside a filled shape.
+///
+/// See <https://developer.mozilla.org/en-US/docs/Web/SVG/Attribute/fill-rule>
+pub enum FillRule {
+    /// A point is inside if its winding number is not zero.
+    #[default]
+    NonZero,
+
+    /// A point is inside if its winding number is odd.
+    EvenOdd,
+}
+
+impl FillRule {
+    /// Tells if a point with the given winding number is inside the shape.
+    pub fn is_inside(&self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
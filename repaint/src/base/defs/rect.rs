@@ -6,6 +6,7 @@ use nalgebra as na;
 pub type F32Rect = Rect<f32>;
 pub type F64Rect = Rect<f64>;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TlTrBlBr<T>
 {
     pub top_left: T,
@@ -0,0 +1,41 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+/// How the vertices of a mesh make triangles, see [`BasicPainter::vertices`](crate::BasicPainter::vertices).
+pub enum VertexMode {
+    /// Each group of three vertices is a triangle, the remaining vertices are ignored.
+    #[default]
+    Triangles,
+
+    /// Each vertex after the second one makes a triangle with the two previous ones.
+    TriangleStrip,
+
+    /// Each vertex after the second one makes a triangle with the previous one and the first one.
+    TriangleFan,
+}
+
+impl VertexMode {
+    /// The triangles made by the vertices, as indices of the vertices.
+    ///
+    /// The sequence of vertices is given by `indices`, or is the `vertex_count` vertices in order without
+    /// indices. The triangles of a strip alternate their vertices, so that they all have the winding of
+    /// the first one. The indices are not checked against `vertex_count`.
+    pub fn triangles<'a>(&self, vertex_count: usize, indices: Option<&'a [u16]>) -> impl Iterator<Item = [usize; 3]> + 'a {
+        let mode = *self;
+        let count = indices.map_or(vertex_count, |indices| indices.len());
+        let triangle_count = match mode {
+            VertexMode::Triangles => count / 3,
+            VertexMode::TriangleStrip | VertexMode::TriangleFan => count.saturating_sub(2),
+        };
+        (0..triangle_count).map(move |i| {
+            let triangle = match mode {
+                VertexMode::Triangles => [3 * i, 3 * i + 1, 3 * i + 2],
+                VertexMode::TriangleStrip if i % 2 == 0 => [i, i + 1, i + 2],
+                VertexMode::TriangleStrip => [i + 1, i, i + 2],
+                VertexMode::TriangleFan => [0, i + 1, i + 2],
+            };
+            match indices {
+                Some(indices) => triangle.map(|i| indices[i] as usize),
+                None => triangle,
+            }
+        })
+    }
+}
@@ -1,14 +1,15 @@
 
-use crate::base::defs::colors::default_color_types::RgbaFColor;
+use crate::base::defs::{antialiasing::AntialiasMode, colors::default_color_types::RgbaFColor};
 
 use super::{blending::BlendMode};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Paint<Color> {
     pub ink: Ink<Color>,
-    // ??? pub antialias_mode: AntialiasMode,
     pub blend_mode: BlendMode,
-    pub anti_alias: bool,
+    /// How the edges are antialiased, the painters that can't honor a mode use the closest one
+    /// they support (e.g. any antialiasing for Skia).
+    pub antialias_mode: AntialiasMode,
     // TODO filters, etc...
     // see https://skia.org/docs/user/api/skpaint_overview/
 }
@@ -18,7 +19,7 @@ impl<Color> Default for Paint<Color> {
         Paint {
             ink: Ink::None,
             blend_mode: BlendMode::default(),
-            anti_alias: true,
+            antialias_mode: AntialiasMode::default(),
         }
     }
 }
@@ -50,6 +51,9 @@ pub enum Ink<Color> {
     Color(Color),
 
     /// The ink is a shader.
+    ///
+    /// It doesn't carry the shader yet, so the CPU painters can't evaluate it: they ignore the texture
+    /// coordinates of the meshes until it does.
     Shader, // TODO
 
     // TODO gradient, image, 1d image, etc...
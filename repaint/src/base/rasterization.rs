@@ -1 +1,4 @@
 pub mod algorithms;
+pub mod mask;
+#[cfg(feature = "tiled")]
+pub mod tiled;
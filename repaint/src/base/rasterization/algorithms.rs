@@ -1,2 +1,5 @@
+pub mod ellipse;
+pub mod flood_fill;
 pub mod line;
 pub mod polygon_filling;
+pub mod triangle;
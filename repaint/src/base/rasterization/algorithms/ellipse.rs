@@ -0,0 +1,55 @@
+/*!
+Circle and ellipse rasterization.
+
+The algorithms draw axis-aligned ellipses without flattening them into polygons:
+ - [`midpoint`]: integer outlines and filled spans, with the center on a pixel and integer radii;
+ - [`antialiased`]: antialiased outlines with the Xiaolin Wu method and antialiased filled spans, with
+   any center and radii.
+
+They follow the conventions of the [`line`](super::line) algorithms: the pixel `(x, y)` is the square
+`[x, x + 1) x [y, y + 1)`, only the pixels inside the clip rectangle (`max` is exclusive) are plotted.
+The outlines are plotted through a callback, the filled ellipses are passed to a [`LineSpanConsumer`]
+line by line from top to bottom, with the device coordinates of the pixels, like the
+[`polygon_filling`](super::polygon_filling) algorithms.
+
+The arcs take the angles of [`BasicShape::Arc`](crate::base::shapes::BasicShape::Arc): in radians,
+the point at the angle `t` is `center + (radii.x * cos(t), radii.y * sin(t))`, and a positive sweep
+goes from the positive x axis towards the positive y axis.
+
+[`LineSpanConsumer`]: super::polygon_filling::scanline::LineSpanConsumer
+*/
+
+pub mod antialiased;
+pub mod midpoint;
+
+use std::f64::consts::TAU;
+
+/// The angles of an elliptical arc.
+struct ArcRange {
+    start: f64,
+    sweep: f64,
+}
+
+impl ArcRange {
+    /// Returns `None` if the arc is empty.
+    fn new(start_angle: f64, sweep_angle: f64) -> Option<Self> {
+        if !start_angle.is_finite() || sweep_angle.is_nan() || sweep_angle == 0.0 {
+            return None;
+        }
+        Some(Self { start: start_angle, sweep: sweep_angle.clamp(-TAU, TAU) })
+    }
+
+    /// Tells if the point of the ellipse at the offset `(dx, dy)` from the center is on the arc.
+    fn contains(&self, dx: f64, dy: f64, radii: (f64, f64)) -> bool {
+        if self.sweep.abs() >= TAU {
+            return true;
+        }
+        // the parametric angle of the point
+        let angle = (dy * radii.0).atan2(dx * radii.1);
+        if self.sweep > 0.0 {
+            (angle - self.start).rem_euclid(TAU) <= self.sweep
+        } else {
+            (self.start - angle).rem_euclid(TAU) <= -self.sweep
+        }
+    }
+}
@@ -0,0 +1,258 @@
+/*!
+Antialiased circles and ellipses.
+
+The center and the radii are in device coordinates, the ellipse is `center + (radii.x * cos(t),
+radii.y * sin(t))`.
+
+The outlines are drawn with the method of Xiaolin Wu: where the slope of the ellipse is below one, the
+center line of each column crosses it at two points, and the two pixels whose centers are the nearest
+to each crossing get an intensity proportional to their closeness, like in
+[`xiaolin_wu`](super::super::line::antialiased::xiaolin_wu). Where the slope is above one, the rows
+are used instead. The pixels near the points where the slope is one, and the pixels between two
+crossings less than two pixels apart, like at the ends of flat ellipses, can be plotted more than once:
+their intensities add up.
+
+The filled ellipses are passed to the consumer line by line, the pixels at the ends of the lines are
+weighted by an estimate of their coverage and the pixels in between are passed as solid spans.
+*/
+
+use crate::base::defs::{linalg::*, rect::Rect};
+use crate::base::rasterization::algorithms::polygon_filling::scanline::LineSpanConsumer;
+
+use super::ArcRange;
+
+/// Draws the antialiased outline of a circle, nothing is drawn if the radius is not positive.
+///
+/// ## Arguments
+/// * `center` - the center of the circle
+/// * `radius` - the radius of the circle
+/// * `plot` - a function that is called with each pixel of the outline and its intensity
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn circle<F: FnMut(i32, i32, f32)>(center: &Vec2f32, radius: f32, plot: &mut F, rect: &Rect<i32>) {
+    ellipse(center, &Vec2f32::new(radius, radius), plot, rect);
+}
+
+/// Draws the antialiased outline of an ellipse, nothing is drawn if a radius is negative or if both
+/// are zero.
+///
+/// ## Arguments
+/// * `center` - the center of the ellipse
+/// * `radii` - the radii along the x and y axes
+/// * `plot` - a function that is called with each pixel of the outline and its intensity
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn ellipse<F: FnMut(i32, i32, f32)>(center: &Vec2f32, radii: &Vec2f32, plot: &mut F, rect: &Rect<i32>) {
+    outline(center, radii, None, plot, rect);
+}
+
+/// Draws the part of the antialiased outline of an ellipse that is on an arc.
+///
+/// The crossings of the centers lines of the columns and rows with the ellipse are on the arc if their
+/// parametric angle is, so that an arc and the rest of the ellipse draw the whole outline.
+///
+/// ## Arguments
+/// * `center` - the center of the ellipse
+/// * `radii` - the radii along the x and y axes
+/// * `start_angle` - the angle of the start of the arc, see the [conventions](super)
+/// * `sweep_angle` - the angle from the start to the end of the arc, the whole ellipse is drawn if it
+///   is larger than a full turn
+/// * `plot` - a function that is called with each pixel of the arc and its intensity
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn arc<F: FnMut(i32, i32, f32)>(
+    center: &Vec2f32,
+    radii: &Vec2f32,
+    start_angle: f64,
+    sweep_angle: f64,
+    plot: &mut F,
+    rect: &Rect<i32>,
+) {
+    if let Some(range) = ArcRange::new(start_angle, sweep_angle) {
+        outline(center, radii, Some(&range), plot, rect);
+    }
+}
+
+/// Fills an antialiased circle, nothing is drawn if the radius is not positive.
+///
+/// See [`fill_ellipse`].
+pub fn fill_circle<C: LineSpanConsumer>(center: &Vec2f32, radius: f32, clip_rect: &Rect<usize>, consumer: &mut C) {
+    fill_ellipse(center, &Vec2f32::new(radius, radius), clip_rect, consumer);
+}
+
+/// Fills an antialiased ellipse, nothing is drawn if a radius is not positive.
+///
+/// The coverage of a pixel is estimated from the signed distance `d` between its center and the
+/// ellipse as `clamp(0.5 - d, 0, 1)`, the distance being approximated to the first order from the
+/// implicit equation of the ellipse. The estimate is exact for circles up to the curvature of the
+/// outline inside the pixels, but it overestimates the coverage across the ellipses thinner than two
+/// pixels: the radii smaller than one pixel are raised to one pixel and the coverage is multiplied by
+/// their ratio.
+///
+/// The indices passed to the consumer are the device coordinates of the pixels inside `clip_rect`
+/// (`max` is exclusive), the lines are emitted from top to bottom and only the covered pixels are
+/// passed to the consumer.
+pub fn fill_ellipse<C: LineSpanConsumer>(center: &Vec2f32, radii: &Vec2f32, clip_rect: &Rect<usize>, consumer: &mut C) {
+    let (center, radii): (Vec2f64, Vec2f64) = (center.cast(), radii.cast());
+    if !center.iter().chain(radii.iter()).all(|v| v.is_finite()) || radii.x <= 0.0 || radii.y <= 0.0 {
+        return;
+    }
+    let opacity = (radii.x.min(1.0) * radii.y.min(1.0)) as f32;
+    let (a, b) = (radii.x.max(1.0), radii.y.max(1.0));
+    let coverage = |x: usize, dy: f64| {
+        let dx = x as f64 + 0.5 - center.x;
+        // `d = k0 (k0 - 1) / k1`, with the norms of the gradients of the implicit equation
+        let k0 = (dx / a).hypot(dy / b);
+        let k1 = (dx / (a * a)).hypot(dy / (b * b));
+        let distance = if k1 > 0.0 { k0 * (k0 - 1.0) / k1 } else { -a.min(b) };
+        (0.5 - distance).clamp(0.0, 1.0) as f32
+    };
+
+    let first_row = (center.y - b - 1.0).floor().max(clip_rect.min.y as f64);
+    let last_row = (center.y + b + 1.0).ceil().min(clip_rect.max.y as f64);
+    if first_row >= last_row {
+        return;
+    }
+    for y in first_row as usize..last_row as usize {
+        let dy = y as f64 + 0.5 - center.y;
+        let coverage = |x: usize| coverage(x, dy);
+
+        // the pixels within one pixel of the ellipse: the half width of the nearest row of the ellipse,
+        // widened by one pixel
+        let nearest = (dy.abs() - 1.0).max(0.0);
+        if nearest > b {
+            continue;
+        }
+        let extent = a * (1.0 - (nearest / b).powi(2)).sqrt() + 1.0;
+        let mut start = (center.x - extent).floor().max(clip_rect.min.x as f64) as usize;
+        let mut end = (center.x + extent).ceil().min(clip_rect.max.x as f64).max(start as f64) as usize;
+        while start < end && coverage(start) == 0.0 {
+            start += 1;
+        }
+        while end > start && coverage(end - 1) == 0.0 {
+            end -= 1;
+        }
+        if start == end {
+            continue;
+        }
+
+        // the estimated distance decreases towards the middle of the line, so the fully covered pixels
+        // are between the partially covered ones
+        let mut solid_start = start;
+        while solid_start < end && coverage(solid_start) < 1.0 {
+            solid_start += 1;
+        }
+        let mut solid_end = end;
+        while solid_end > solid_start && coverage(solid_end - 1) < 1.0 {
+            solid_end -= 1;
+        }
+
+        consumer.start_line(y);
+        for x in start..solid_start {
+            put_weighted(consumer, x, coverage(x) * opacity);
+        }
+        if opacity < 1.0 {
+            for x in solid_start..solid_end {
+                put_weighted(consumer, x, opacity);
+            }
+        } else if solid_start < solid_end {
+            consumer.put_solid_span(solid_start, solid_end);
+        }
+        for x in solid_end..end {
+            put_weighted(consumer, x, coverage(x) * opacity);
+        }
+        consumer.end_line();
+    }
+}
+
+/// Draws the outline, or the part of it on `arc`.
+fn outline<F: FnMut(i32, i32, f32)>(
+    center: &Vec2f32,
+    radii: &Vec2f32,
+    arc: Option<&ArcRange>,
+    plot: &mut F,
+    rect: &Rect<i32>,
+) {
+    let (center, radii): (Vec2f64, Vec2f64) = (center.cast(), radii.cast());
+    if !center.iter().chain(radii.iter()).all(|v| v.is_finite()) {
+        return;
+    }
+    if radii.x < 0.0 || radii.y < 0.0 || radii == Vec2f64::zeros() {
+        return;
+    }
+    if rect.min.x >= rect.max.x || rect.min.y >= rect.max.y {
+        return;
+    }
+
+    // the offsets of the point of the quadrant where the slope is one
+    let norm = radii.x.hypot(radii.y);
+    let turn = (radii.x * radii.x / norm, radii.y * radii.y / norm);
+    let radii = (radii.x, radii.y);
+    let mut plot_crossing = |x: i32, y: i32, intensity: f32, dx: f64, dy: f64| {
+        if arc.is_none_or(|range| range.contains(dx, dy, radii)) {
+            plot(x, y, intensity);
+        }
+    };
+
+    // the columns whose center is at most `turn.0` from the center, then the rows whose center is less
+    // than `turn.1` from it, so that each crossing is in one of them
+    let columns = Columns { center: (center.x, center.y), radii, limit: turn.0, strict: false };
+    columns.plot((rect.min.x, rect.max.x), (rect.min.y, rect.max.y), &mut |u, v, intensity, du, dv| {
+        plot_crossing(u, v, intensity, du, dv)
+    });
+    let rows = Columns { center: (center.y, center.x), radii: (radii.1, radii.0), limit: turn.1, strict: true };
+    rows.plot((rect.min.y, rect.max.y), (rect.min.x, rect.max.x), &mut |u, v, intensity, du, dv| {
+        plot_crossing(v, u, intensity, dv, du)
+    });
+}
+
+/// The columns of an ellipse, in a frame where `u` is the axis along which the columns are ordered and
+/// `v` the axis along them.
+struct Columns {
+    center: (f64, f64),
+    radii: (f64, f64),
+    /// The maximum distance from the center to the center line of a column.
+    limit: f64,
+    /// Tells if the columns at `limit` are excluded.
+    strict: bool,
+}
+
+impl Columns {
+    /// Plots the two nearest pixels of the crossings of the center lines of the columns with the
+    /// ellipse, with the offsets of the crossings from the center.
+    fn plot<F: FnMut(i32, i32, f32, f64, f64)>(&self, u_range: (i32, i32), v_range: (i32, i32), plot: &mut F) {
+        let (cu, cv) = self.center;
+        let (first, last) = if self.strict {
+            ((cu - self.limit - 0.5).floor() + 1.0, (cu + self.limit - 0.5).ceil())
+        } else {
+            ((cu - self.limit - 0.5).ceil(), (cu + self.limit - 0.5).floor() + 1.0)
+        };
+        let first = first.max(u_range.0 as f64) as i32;
+        let last = last.min(u_range.1 as f64).max(first as f64) as i32;
+
+        for u in first..last {
+            let du = u as f64 + 0.5 - cu;
+            let half_height = if self.radii.0 > 0.0 {
+                self.radii.1 * (1.0 - (du / self.radii.0).powi(2)).max(0.0).sqrt()
+            } else {
+                self.radii.1
+            };
+            // a single crossing at the ends
+            let offsets: &[f64] = if half_height > 0.0 { &[-half_height, half_height] } else { &[0.0] };
+            for &dv in offsets {
+                let v = cv + dv - 0.5;
+                let v_floor = v.floor();
+                let fract = (v - v_floor) as f32;
+                for (v, intensity) in [(v_floor, 1.0 - fract), (v_floor + 1.0, fract)] {
+                    if intensity > 0.0 && v >= v_range.0 as f64 && v < v_range.1 as f64 {
+                        plot(u, v as i32, intensity, du, dv);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Passes a pixel to the consumer if it is covered.
+fn put_weighted<C: LineSpanConsumer>(consumer: &mut C, idx: usize, weight: f32) {
+    if weight > 0.0 {
+        consumer.put_weighted(idx, weight);
+    }
+}
@@ -0,0 +1,219 @@
+/*!
+Integer midpoint circles and ellipses.
+
+The center is a pixel and the radii are integers: the ellipse goes through the centers of the pixels
+at `center ± (radii.x, 0)` and `center ± (0, radii.y)`. The outline of a quadrant is computed with
+integer decision variables only, the midpoint between the two candidate pixels of each step telling
+which one is nearer to the ellipse, and the other quadrants are symmetric. A circle uses the eight-way
+symmetric version of the algorithm.
+
+The outline is 8-connected and each of its pixels is plotted once, the pixels are not plotted along
+the curve but in symmetric groups. The filled ellipse is made of the outline and the pixels between it,
+so that an outline drawn over it doesn't overflow. The cost is proportional to the radii, even when
+most of the ellipse is clipped.
+
+See <https://en.wikipedia.org/wiki/Midpoint_circle_algorithm>
+*/
+
+use crate::base::defs::{linalg::*, rect::Rect};
+use crate::base::rasterization::algorithms::polygon_filling::scanline::LineSpanConsumer;
+
+use super::ArcRange;
+
+/// Draws the outline of a circle, nothing is drawn if the radius is negative.
+///
+/// ## Arguments
+/// * `center` - the center pixel
+/// * `radius` - the radius in pixels
+/// * `plot` - a function that is called for each pixel of the outline
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn circle<F: FnMut(i32, i32)>(center: &Vec2i32, radius: i32, plot: &mut F, rect: &Rect<i32>) {
+    ellipse(center, &Vec2i32::new(radius, radius), plot, rect);
+}
+
+/// Draws the outline of an ellipse, nothing is drawn if a radius is negative.
+///
+/// ## Arguments
+/// * `center` - the center pixel
+/// * `radii` - the radii along the x and y axes in pixels
+/// * `plot` - a function that is called for each pixel of the outline
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn ellipse<F: FnMut(i32, i32)>(center: &Vec2i32, radii: &Vec2i32, plot: &mut F, rect: &Rect<i32>) {
+    if radii.x < 0 || radii.y < 0 {
+        return;
+    }
+    quadrant(radii, &mut |dx, dy| plot_symmetric(center, dx, dy, &mut |_, _| true, plot, rect));
+}
+
+/// Draws the pixels of the outline of an ellipse that are on an arc.
+///
+/// A pixel of the outline of [`ellipse`] is plotted if the parametric angle of its offset from the
+/// center is on the arc, so that an arc and the rest of the ellipse plot the whole outline.
+///
+/// ## Arguments
+/// * `center` - the center pixel
+/// * `radii` - the radii along the x and y axes in pixels
+/// * `start_angle` - the angle of the start of the arc, see the [conventions](super)
+/// * `sweep_angle` - the angle from the start to the end of the arc, the whole ellipse is drawn if it
+///   is larger than a full turn
+/// * `plot` - a function that is called for each pixel of the arc
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn arc<F: FnMut(i32, i32)>(
+    center: &Vec2i32,
+    radii: &Vec2i32,
+    start_angle: f64,
+    sweep_angle: f64,
+    plot: &mut F,
+    rect: &Rect<i32>,
+) {
+    if radii.x < 0 || radii.y < 0 {
+        return;
+    }
+    let Some(range) = ArcRange::new(start_angle, sweep_angle) else {
+        return;
+    };
+    let radii_f64 = (radii.x as f64, radii.y as f64);
+    let mut on_arc = |dx: i64, dy: i64| range.contains(dx as f64, dy as f64, radii_f64);
+    quadrant(radii, &mut |dx, dy| plot_symmetric(center, dx, dy, &mut on_arc, plot, rect));
+}
+
+/// Fills a circle, nothing is drawn if the radius is negative.
+///
+/// The indices passed to the consumer are the device coordinates of the pixels inside `clip_rect`
+/// (`max` is exclusive), each line is a single span.
+pub fn fill_circle<C: LineSpanConsumer>(center: &Vec2i32, radius: i32, clip_rect: &Rect<usize>, consumer: &mut C) {
+    fill_ellipse(center, &Vec2i32::new(radius, radius), clip_rect, consumer);
+}
+
+/// Fills an ellipse, nothing is drawn if a radius is negative.
+///
+/// The indices passed to the consumer are the device coordinates of the pixels inside `clip_rect`
+/// (`max` is exclusive), each line is a single span.
+pub fn fill_ellipse<C: LineSpanConsumer>(center: &Vec2i32, radii: &Vec2i32, clip_rect: &Rect<usize>, consumer: &mut C) {
+    if radii.x < 0 || radii.y < 0 {
+        return;
+    }
+    let (cx, cy) = (center.x as i64, center.y as i64);
+    let first_row = (cy - radii.y as i64).max(clip_rect.min.y as i64);
+    let last_row = (cy + radii.y as i64 + 1).min(clip_rect.max.y as i64);
+    if first_row >= last_row || clip_rect.min.x >= clip_rect.max.x {
+        return;
+    }
+
+    // the half widths of the rows inside the clip rectangle
+    let mut half_widths = vec![-1i64; (last_row - first_row) as usize];
+    quadrant(radii, &mut |dx, dy| {
+        for y in [cy - dy, cy + dy] {
+            if y >= first_row && y < last_row {
+                let half_width = &mut half_widths[(y - first_row) as usize];
+                *half_width = (*half_width).max(dx);
+            }
+        }
+    });
+
+    for (y, half_width) in (first_row..last_row).zip(half_widths) {
+        let start = (cx - half_width).max(clip_rect.min.x as i64);
+        let end = (cx + half_width + 1).min(clip_rect.max.x as i64);
+        if start < end {
+            consumer.start_line(y as usize);
+            consumer.put_solid_span(start as usize, end as usize);
+            consumer.end_line();
+        }
+    }
+}
+
+/// Calls `f` with the offsets of the pixels of the outline in the quadrant `dx >= 0, dy >= 0`, once
+/// each. The radii must not be negative.
+fn quadrant<F: FnMut(i64, i64)>(radii: &Vec2i32, f: &mut F) {
+    let (a, b) = (radii.x as i64, radii.y as i64);
+    if a == 0 || b == 0 {
+        // a segment
+        for i in 0..=a.max(b) {
+            if a == 0 { f(0, i) } else { f(i, 0) }
+        }
+    } else if a == b {
+        circle_quadrant(a, f);
+    } else {
+        ellipse_quadrant(a, b, f);
+    }
+}
+
+/// The quadrant of a circle: the octant from the top to the diagonal, and its mirror.
+fn circle_quadrant<F: FnMut(i64, i64)>(radius: i64, f: &mut F) {
+    let (mut x, mut y) = (0, radius);
+    // the decision variable is `x^2 + y^2 - r^2` at the midpoint `(x + 1, y - 1/2)`, shifted by `1/4`
+    let mut d = 1 - radius;
+    while x <= y {
+        f(x, y);
+        if x != y {
+            f(y, x);
+        }
+        if d < 0 {
+            d += 2 * x + 3;
+        } else {
+            d += 2 * (x - y) + 5;
+            y -= 1;
+        }
+        x += 1;
+    }
+}
+
+/// The quadrant of an ellipse, in two regions: from the top, stepping along `x` while the slope is
+/// below one, then along `y` down to the right end.
+fn ellipse_quadrant<F: FnMut(i64, i64)>(a: i64, b: i64, f: &mut F) {
+    // the decision variables are `b^2 x^2 + a^2 y^2 - a^2 b^2` at the midpoints, multiplied by 4
+    let (a2, b2) = (a as i128 * a as i128, b as i128 * b as i128);
+    let (mut x, mut y) = (0i64, b);
+    let (mut dx, mut dy) = (0i128, 2 * a2 * b as i128);
+
+    let mut d = 4 * b2 - 4 * a2 * b as i128 + a2;
+    while dx < dy {
+        f(x, y);
+        x += 1;
+        dx += 2 * b2;
+        if d < 0 {
+            d += 4 * (dx + b2);
+        } else {
+            y -= 1;
+            dy -= 2 * a2;
+            d += 4 * (dx - dy + b2);
+        }
+    }
+
+    let (xm, ym) = (2 * x as i128 + 1, y as i128 - 1);
+    let mut d = b2 * xm * xm + 4 * a2 * ym * ym - 4 * a2 * b2;
+    while y >= 0 {
+        f(x, y);
+        y -= 1;
+        dy -= 2 * a2;
+        if d > 0 {
+            d += 4 * (a2 - dy);
+        } else {
+            x += 1;
+            dx += 2 * b2;
+            d += 4 * (dx - dy + a2);
+        }
+    }
+}
+
+/// Plots the pixels at `center + (±dx, ±dy)` accepted by `filter`, once each.
+fn plot_symmetric<G: FnMut(i64, i64) -> bool, F: FnMut(i32, i32)>(
+    center: &Vec2i32,
+    dx: i64,
+    dy: i64,
+    filter: &mut G,
+    plot: &mut F,
+    rect: &Rect<i32>,
+) {
+    let x_offsets: &[i64] = if dx == 0 { &[0] } else { &[dx, -dx] };
+    let y_offsets: &[i64] = if dy == 0 { &[0] } else { &[dy, -dy] };
+    for &oy in y_offsets {
+        for &ox in x_offsets {
+            let (x, y) = (center.x as i64 + ox, center.y as i64 + oy);
+            let inside = x >= rect.min.x as i64 && x < rect.max.x as i64 && y >= rect.min.y as i64 && y < rect.max.y as i64;
+            if inside && filter(ox, oy) {
+                plot(x as i32, y as i32);
+            }
+        }
+    }
+}
@@ -0,0 +1,335 @@
+/*!
+Flood fill and boundary fill, for paint-bucket tools.
+
+Both fill the region of the pixels connected to a seed pixel, with 4- or 8-connectivity
+([`Connectivity`]):
+ - the flood fill ([`flood_region`], [`flood_fill`]) grows the region over the pixels whose color is
+   within the tolerance of the color of the seed;
+ - the boundary fill ([`boundary_region`], [`boundary_fill`]) grows the region until the pixels whose
+   color is within the tolerance of a boundary color.
+
+The colors are compared with [`color_distance`]. The region is grown span by span: the pixels of a row
+are filled from the seed to the left and to the right, and the runs of pixels to fill in the rows above
+and below the span become the next seeds. Each pixel is filled once, even if the fill color is also
+within the tolerance.
+
+With antialiasing, the pixels around the region get a partial coverage: the closer their color is to
+the colors of the region, the more they are covered. The edges antialiased in the image, like the
+outline of a drawing, are then filled smoothly instead of leaving a fringe of the old color.
+
+The pixels are read and written through [`PixelAccess`], that any buffer of colors can implement, like
+the memory of a CPU [`RasterPainter`](crate::painter::RasterPainter); [`PixelSlice`] implements it for
+a slice of colors.
+*/
+
+use nalgebra::Vector2;
+
+use crate::base::defs::colors::{default_color_types::RgbaFColor, ColorType};
+
+/// The pixels of a buffer, read and written by the flood fill.
+pub trait PixelAccess {
+    /// The color of the pixels.
+    type Color: ColorType;
+
+    /// Returns the width and the height of the buffer, in pixels.
+    fn size(&self) -> Vector2<usize>;
+
+    /// Returns the color of a pixel inside the buffer.
+    fn get_pixel(&self, pos: Vector2<usize>) -> Self::Color;
+
+    /// Sets the color of a pixel inside the buffer.
+    fn set_pixel(&mut self, pos: Vector2<usize>, color: Self::Color);
+}
+
+/// A slice of colors seen as rows of pixels, from top to bottom.
+#[derive(Debug)]
+pub struct PixelSlice<'a, C: ColorType> {
+    pixels: &'a mut [C],
+    width: usize,
+}
+
+impl<'a, C: ColorType> PixelSlice<'a, C> {
+    /// Creates the buffer of the rows of `width` pixels of the slice, a last incomplete row is ignored.
+    pub fn new(pixels: &'a mut [C], width: usize) -> Self {
+        Self { pixels, width }
+    }
+}
+
+impl<C: ColorType> PixelAccess for PixelSlice<'_, C> {
+    type Color = C;
+
+    fn size(&self) -> Vector2<usize> {
+        Vector2::new(self.width, self.pixels.len().checked_div(self.width).unwrap_or(0))
+    }
+
+    fn get_pixel(&self, pos: Vector2<usize>) -> C {
+        self.pixels[pos.y * self.width + pos.x]
+    }
+
+    fn set_pixel(&mut self, pos: Vector2<usize>, color: C) {
+        self.pixels[pos.y * self.width + pos.x] = color;
+    }
+}
+
+/// The neighbors through which a region grows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Connectivity {
+    /// The pixels sharing a side.
+    #[default]
+    Four,
+
+    /// The pixels sharing a side or a corner.
+    Eight,
+}
+
+/// The parameters of a flood fill or of a boundary fill.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FloodFillParams {
+    /// The largest [`color_distance`] of two colors considered the same, between `0.0` (the exact
+    /// color only) and `1.0` (all the colors).
+    pub tolerance: f32,
+
+    /// The neighbors through which the region grows.
+    pub connectivity: Connectivity,
+
+    /// Whether the pixels around the region are partially covered, see the [module](self)
+    /// documentation.
+    pub antialias: bool,
+}
+
+impl Default for FloodFillParams {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.0,
+            connectivity: Connectivity::Four,
+            antialias: false,
+        }
+    }
+}
+
+/// Returns the distance between two colors, between `0.0` and `1.0`: the largest difference of their
+/// premultiplied channels.
+///
+/// The colors of the transparent pixels don't matter: all of them are at a distance `0.0`.
+pub fn color_distance(a: &RgbaFColor, b: &RgbaFColor) -> f32 {
+    let premultiplied = |c: &RgbaFColor| {
+        let alpha = c.a().clamp(0.0, 1.0);
+        [c.r().clamp(0.0, 1.0) * alpha, c.g().clamp(0.0, 1.0) * alpha, c.b().clamp(0.0, 1.0) * alpha, alpha]
+    };
+    premultiplied(a)
+        .iter()
+        .zip(premultiplied(b))
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max)
+}
+
+/// Computes the region of a flood fill without changing the pixels, `plot` is called with each pixel
+/// of the region and its coverage.
+///
+/// The region holds the pixels connected to the seed whose color is within the tolerance of the color
+/// of the seed, they have the coverage `1.0`. With antialiasing, the neighbors of the region outside it
+/// have the coverage `(1 - distance) / (1 - tolerance)`, the pixels without coverage aren't plotted.
+/// The pixels are plotted from top to bottom and from left to right, nothing is plotted if the seed is
+/// outside the buffer.
+///
+/// ## Arguments
+/// * `pixels` - the buffer
+/// * `seed` - the pixel from which the region grows
+/// * `params` - the tolerance, the connectivity and the antialiasing
+/// * `plot` - a function that is called with each pixel of the region and its coverage
+pub fn flood_region<P: PixelAccess, F: FnMut(Vector2<usize>, f32)>(
+    pixels: &P,
+    seed: Vector2<usize>,
+    params: &FloodFillParams,
+    plot: &mut F,
+) {
+    let size = pixels.size();
+    if seed.x >= size.x || seed.y >= size.y {
+        return;
+    }
+    let target: RgbaFColor = pixels.get_pixel(seed).into();
+    let tolerance = params.tolerance.max(0.0);
+    region(
+        pixels,
+        seed,
+        params,
+        |color| color_distance(color, &target) <= tolerance,
+        |color| (1.0 - color_distance(color, &target)) / (1.0 - tolerance),
+        plot,
+    );
+}
+
+/// Computes the region of a boundary fill without changing the pixels, `plot` is called with each
+/// pixel of the region and its coverage.
+///
+/// The region holds the pixels connected to the seed whose color is not within the tolerance of the
+/// boundary color, they have the coverage `1.0`. With antialiasing, the neighbors of the region outside
+/// it have the coverage of their distance to the boundary color relative to the distance of the seed,
+/// the pixels without coverage aren't plotted. The pixels are plotted from top to bottom and from left
+/// to right, nothing is plotted if the seed is outside the buffer or on the boundary.
+///
+/// ## Arguments
+/// * `pixels` - the buffer
+/// * `seed` - the pixel from which the region grows
+/// * `boundary` - the color that stops the region
+/// * `params` - the tolerance, the connectivity and the antialiasing
+/// * `plot` - a function that is called with each pixel of the region and its coverage
+pub fn boundary_region<P: PixelAccess, F: FnMut(Vector2<usize>, f32)>(
+    pixels: &P,
+    seed: Vector2<usize>,
+    boundary: P::Color,
+    params: &FloodFillParams,
+    plot: &mut F,
+) {
+    let size = pixels.size();
+    if seed.x >= size.x || seed.y >= size.y {
+        return;
+    }
+    let boundary: RgbaFColor = boundary.into();
+    let seed_distance = color_distance(&pixels.get_pixel(seed).into(), &boundary);
+    let tolerance = params.tolerance.max(0.0);
+    region(
+        pixels,
+        seed,
+        params,
+        |color| color_distance(color, &boundary) > tolerance,
+        |color| color_distance(color, &boundary) / seed_distance,
+        plot,
+    );
+}
+
+/// Fills the region of a flood fill with a color, see [`flood_region`].
+///
+/// The pixels of the region are replaced by the color, the partially covered ones are interpolated
+/// between their color and the fill color with their coverage.
+pub fn flood_fill<P: PixelAccess>(pixels: &mut P, seed: Vector2<usize>, color: P::Color, params: &FloodFillParams) {
+    let mut covered = Vec::new();
+    flood_region(pixels, seed, params, &mut |pos, coverage| covered.push((pos, coverage)));
+    paint(pixels, &covered, color);
+}
+
+/// Fills the region of a boundary fill with a color, see [`boundary_region`].
+///
+/// The pixels of the region are replaced by the color, the partially covered ones are interpolated
+/// between their color and the fill color with their coverage.
+pub fn boundary_fill<P: PixelAccess>(
+    pixels: &mut P,
+    seed: Vector2<usize>,
+    color: P::Color,
+    boundary: P::Color,
+    params: &FloodFillParams,
+) {
+    let mut covered = Vec::new();
+    boundary_region(pixels, seed, boundary, params, &mut |pos, coverage| covered.push((pos, coverage)));
+    paint(pixels, &covered, color);
+}
+
+/// Grows the region from the seed span by span and plots it, the seed must be inside the buffer.
+fn region<P: PixelAccess, F: FnMut(Vector2<usize>, f32)>(
+    pixels: &P,
+    seed: Vector2<usize>,
+    params: &FloodFillParams,
+    inside: impl Fn(&RgbaFColor) -> bool,
+    edge_coverage: impl Fn(&RgbaFColor) -> f32,
+    plot: &mut F,
+) {
+    let size = pixels.size();
+    let is_inside = |x: usize, y: usize| inside(&pixels.get_pixel(Vector2::new(x, y)).into());
+    if !is_inside(seed.x, seed.y) {
+        return;
+    }
+
+    let mut mask = vec![false; size.x * size.y];
+    let (mut min, mut max) = (seed, seed);
+    let mut seeds = vec![seed];
+    while let Some(seed) = seeds.pop() {
+        let (x, y) = (seed.x, seed.y);
+        let row = y * size.x;
+        if mask[row + x] {
+            continue;
+        }
+        let mut left = x;
+        while left > 0 && !mask[row + left - 1] && is_inside(left - 1, y) {
+            left -= 1;
+        }
+        let mut right = x + 1;
+        while right < size.x && !mask[row + right] && is_inside(right, y) {
+            right += 1;
+        }
+        mask[row + left..row + right].fill(true);
+        min = min.inf(&Vector2::new(left, y));
+        max = max.sup(&Vector2::new(right - 1, y));
+
+        // the runs of pixels to fill next to the span, diagonally too with 8-connectivity
+        let (from, to) = match params.connectivity {
+            Connectivity::Four => (left, right),
+            Connectivity::Eight => (left.saturating_sub(1), (right + 1).min(size.x)),
+        };
+        for next_y in [y.checked_sub(1), Some(y + 1).filter(|next_y| *next_y < size.y)].into_iter().flatten() {
+            let mut in_run = false;
+            for next_x in from..to {
+                let fill = !mask[next_y * size.x + next_x] && is_inside(next_x, next_y);
+                if fill && !in_run {
+                    seeds.push(Vector2::new(next_x, next_y));
+                }
+                in_run = fill;
+            }
+        }
+    }
+
+    // the region and, with antialiasing, its neighbors
+    let margin = usize::from(params.antialias);
+    let in_mask = |x: usize, y: usize| mask[y * size.x + x];
+    for y in min.y.saturating_sub(margin)..(max.y + 1 + margin).min(size.y) {
+        for x in min.x.saturating_sub(margin)..(max.x + 1 + margin).min(size.x) {
+            if in_mask(x, y) {
+                plot(Vector2::new(x, y), 1.0);
+                continue;
+            }
+            if !params.antialias || !has_neighbor(x, y, size, params.connectivity, in_mask) {
+                continue;
+            }
+            let coverage = edge_coverage(&pixels.get_pixel(Vector2::new(x, y)).into());
+            if coverage > 0.0 {
+                plot(Vector2::new(x, y), coverage.min(1.0));
+            }
+        }
+    }
+}
+
+/// Tells whether a neighbor of a pixel is in the region.
+fn has_neighbor(x: usize, y: usize, size: Vector2<usize>, connectivity: Connectivity, in_mask: impl Fn(usize, usize) -> bool) -> bool {
+    let (x_min, x_max) = (x.saturating_sub(1), (x + 1).min(size.x - 1));
+    let (y_min, y_max) = (y.saturating_sub(1), (y + 1).min(size.y - 1));
+    match connectivity {
+        Connectivity::Four => {
+            (x_min < x && in_mask(x_min, y))
+                || (x_max > x && in_mask(x_max, y))
+                || (y_min < y && in_mask(x, y_min))
+                || (y_max > y && in_mask(x, y_max))
+        }
+        Connectivity::Eight => (y_min..=y_max).any(|ny| (x_min..=x_max).any(|nx| in_mask(nx, ny))),
+    }
+}
+
+/// Replaces the covered pixels by the color, interpolated with the premultiplied channels.
+fn paint<P: PixelAccess>(pixels: &mut P, covered: &[(Vector2<usize>, f32)], color: P::Color) {
+    let src: RgbaFColor = color.into();
+    for (pos, coverage) in covered {
+        if *coverage >= 1.0 {
+            pixels.set_pixel(*pos, color);
+            continue;
+        }
+        let dst: RgbaFColor = pixels.get_pixel(*pos).into();
+        let alpha = dst.a() + (src.a() - dst.a()) * coverage;
+        let channel = |s: f32, d: f32| {
+            if alpha > 0.0 {
+                (d * dst.a() + (s * src.a() - d * dst.a()) * coverage) / alpha
+            } else {
+                0.0
+            }
+        };
+        let blended = RgbaFColor::new(channel(src.r(), dst.r()), channel(src.g(), dst.g()), channel(src.b(), dst.b()), alpha);
+        pixels.set_pixel(*pos, blended.into());
+    }
+}
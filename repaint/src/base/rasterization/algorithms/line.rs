@@ -1,2 +1,166 @@
+/*!
+Line rasterization.
+
+All the algorithms follow the same conventions, so that they can be swapped and compared:
+ - the pixel `(x, y)` is the square `[x, x + 1) x [y, y + 1)`, its center is `(x + 0.5, y + 0.5)`,
+   the coordinates can be negative;
+ - the major axis of a segment is the axis along which it is longer, `x` if both are equal, a
+   *column* is a row or a column of pixels perpendicular to the major axis;
+ - the pixels are plotted from the start to the end of the segment, only the pixels inside the clip
+   rectangle (`max` is exclusive) are plotted, nothing is plotted if a coordinate is not finite or if
+   the segment is a point.
+
+The [`plain`] algorithms plot one pixel per column, following the *diamond-exit* rule simplified for
+thin lines: a column is drawn if the segment crosses its center line, the start being included and
+the end excluded (the last pixel is not plotted), and the pixel is the one containing the crossing,
+its lower boundary included. Segments joined end to end, like the edges of a polyline, don't plot the
+shared pixel twice when they have the same major axis.
+
+The [`antialiased`] algorithms plot the columns overlapped by the segment, the intensities of a column
+are multiplied by the length of the segment inside it along the major axis: the columns at sub-pixel
+endpoints get a partial intensity and segments joined end to end add up to a continuous line.
+
+The [`thick`] algorithms draw lines of any width with the caps of a [`Pen`](crate::base::pen::Pen),
+they plot each pixel once.
+*/
+
 pub mod antialiased;
 pub mod plain;
+pub mod thick;
+
+use crate::base::defs::{linalg::*, rect::Rect};
+
+/// A segment in the frame of its major axis: `u` along the major axis, `v` along the minor one.
+struct MajorFrame {
+    steep: bool,
+    start: Vec2f64,
+    end: Vec2f64,
+    slope: f64,
+    /// The clip rectangle in the frame, `max` is exclusive.
+    clip_min: Vec2i32,
+    clip_max: Vec2i32,
+}
+
+impl MajorFrame {
+    /// Returns `None` if nothing can be plotted.
+    fn new(start: &Vec2f32, end: &Vec2f32, clip: &Rect<i32>) -> Option<Self> {
+        if start == end {
+            return None;
+        }
+        Self::with_point(start, end, clip)
+    }
+
+    /// Like [`MajorFrame::new`], but `start` and `end` can be the same point.
+    fn with_point(start: &Vec2f32, end: &Vec2f32, clip: &Rect<i32>) -> Option<Self> {
+        let (start, end): (Vec2f64, Vec2f64) = (start.cast(), end.cast());
+        if !start.iter().chain(end.iter()).all(|v| v.is_finite()) {
+            return None;
+        }
+        if clip.min.x >= clip.max.x || clip.min.y >= clip.max.y {
+            return None;
+        }
+
+        let delta = end - start;
+        let steep = delta.y.abs() > delta.x.abs();
+        let to_frame = |v: Vec2f64| if steep { Vec2f64::new(v.y, v.x) } else { v };
+        let (start, end) = (to_frame(start), to_frame(end));
+        let (clip_min, clip_max) = if steep {
+            (Vec2i32::new(clip.min.y, clip.min.x), Vec2i32::new(clip.max.y, clip.max.x))
+        } else {
+            (clip.min, clip.max)
+        };
+        let slope = if start == end { 0.0 } else { (end.y - start.y) / (end.x - start.x) };
+        Some(Self { steep, start, end, slope, clip_min, clip_max })
+    }
+
+    /// The pixel along the minor axis containing the crossing of the center line of the column `u`,
+    /// and the distance from its lower boundary to the crossing multiplied by the length of the segment
+    /// along the major axis.
+    ///
+    /// The pixel is computed with the line equation multiplied by the length, so that there are no
+    /// rounding errors when the coordinates have few significant bits, e.g. for half-integers.
+    fn center_pixel(&self, u: i32) -> (i32, f64) {
+        let (du, dv) = self.scaled_slope();
+        let scaled_v = self.start.y * du + (u as f64 + 0.5 - self.start.x) * dv;
+        let mut v = (scaled_v / du).floor();
+        let mut remainder = scaled_v - v * du;
+        if remainder < 0.0 {
+            v -= 1.0;
+            remainder += du;
+        } else if remainder >= du {
+            v += 1.0;
+            remainder -= du;
+        }
+        (v as i32, remainder)
+    }
+
+    /// The length of the segment along the major axis and along the minor axis, with the sign of
+    /// the slope.
+    fn scaled_slope(&self) -> (f64, f64) {
+        let delta = self.end - self.start;
+        if self.is_reversed() {
+            (-delta.x, -delta.y)
+        } else {
+            (delta.x, delta.y)
+        }
+    }
+
+    /// The coordinate along the minor axis at `u`.
+    fn v_at(&self, u: f64) -> f64 {
+        self.start.y + (u - self.start.x) * self.slope
+    }
+
+    /// Tells if the segment goes towards the negative direction of the major axis.
+    fn is_reversed(&self) -> bool {
+        self.end.x < self.start.x
+    }
+
+    /// The pixel of the frame in device space, `None` if it is outside the clip rectangle.
+    fn pixel(&self, u: i32, v: i32) -> Option<(i32, i32)> {
+        if v < self.clip_min.y || v >= self.clip_max.y {
+            None
+        } else if self.steep {
+            Some((v, u))
+        } else {
+            Some((u, v))
+        }
+    }
+
+    /// The columns whose center line is crossed, the end excluded, in the drawing order.
+    fn center_columns(&self) -> impl Iterator<Item = i32> {
+        let (first, last) = if self.is_reversed() {
+            ((self.end.x - 0.5).floor() + 1.0, (self.start.x - 0.5).floor() + 1.0)
+        } else {
+            ((self.start.x - 0.5).ceil(), (self.end.x - 0.5).ceil())
+        };
+        self.columns(first, last, 2.0)
+    }
+
+    /// The columns overlapped by the segment with the covered range along the major axis,
+    /// in the drawing order.
+    fn covered_columns(&self) -> impl Iterator<Item = (i32, f64, f64)> {
+        let (min, max) = if self.is_reversed() { (self.end.x, self.start.x) } else { (self.start.x, self.end.x) };
+        self.columns(min.floor(), max.ceil(), 2.0).map(move |u| {
+            let a = (u as f64).max(min);
+            let b = (u as f64 + 1.0).min(max);
+            (u, a, b)
+        })
+    }
+
+    /// The columns between `first` and `last` (excluded) that can have pixels inside the clip,
+    /// the pixels of a column must be within `margin` pixels of the segment along the minor axis.
+    fn columns(&self, first: f64, last: f64, margin: f64) -> impl Iterator<Item = i32> {
+        let (mut first, mut last) = (first.max(self.clip_min.x as f64), last.min(self.clip_max.x as f64));
+        if self.slope != 0.0 {
+            // the columns where the segment is near the clip rectangle
+            let u_at = |v: f64| self.start.x + (v - self.start.y) / self.slope;
+            let (a, b) = (u_at(self.clip_min.y as f64 - margin), u_at(self.clip_max.y as f64 + margin));
+            first = first.max(a.min(b).floor());
+            last = last.min(a.max(b).ceil());
+        }
+        let (first, last) = (first as i32, (last as i32).max(first as i32));
+        let reversed = self.is_reversed();
+        let count = (last - first) as usize;
+        (0..count).map(move |i| if reversed { last - 1 - i as i32 } else { first + i as i32 })
+    }
+}
@@ -1,3 +1,12 @@
+/*!
+Antialiased lines, one pixel wide.
+
+See the conventions in [`line`](super). The algorithms differ in the filter:
+ - [`xiaolin_wu`]: the two pixels nearest to the line, weighted by their distance;
+ - [`gupta_sproll`]: three pixels weighted by a cone filter of the distance to the line;
+ - [`midpoint`]: the exact area of each pixel inside the line (unweighted area sampling).
+*/
+
 pub mod gupta_sproll;
 pub mod midpoint;
 pub mod xiaolin_wu;
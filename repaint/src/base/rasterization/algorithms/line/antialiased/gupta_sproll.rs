@@ -1,50 +1,51 @@
-
-use crate::base::defs::linalg::*;
-
-/// See <https://en.wikipedia.org/wiki/Line_drawing_algorithm>
-pub fn line<F: FnMut(u32, u32, f32) -> ()>(start: &Vec2f32, end: &Vec2f32, plot: &mut F) -> () {
-    // TODO non funziona bene
-    if start == end {
+use crate::base::defs::{linalg::*, rect::Rect};
+
+use super::super::MajorFrame;
+
+/// Draws an antialiased line with the Gupta–Sproull algorithm (weighted area sampling).
+///
+/// In each column, the pixel containing the line and its two neighbors get the intensity of a
+/// one pixel wide line seen through a cone filter of radius `1` centered on the pixel, which only
+/// depends on the distance between the center of the pixel and the line. The intensities are multiplied
+/// by the length of the line inside the column along the major axis, see the conventions in
+/// [`line`](super::super) and [`xiaolin_wu::line`](super::xiaolin_wu::line) for the arguments.
+///
+/// See <https://en.wikipedia.org/wiki/Line_drawing_algorithm> and
+/// <https://www.inf.ed.ac.uk/teaching/courses/cg/lectures/cg4_2012.pdf>
+pub fn line<F: FnMut(i32, i32, f32)>(start: &Vec2f32, end: &Vec2f32, plot: &mut F, rect: &Rect<i32>) {
+    let Some(frame) = MajorFrame::new(start, end, rect) else {
         return;
-    }
-
-    let (start, end) = if start.x > end.x {
-        (end, start)
-    } else {
-        (start, end)
     };
-
-    //let istart: Vec2i = start.map(|x| x.round() as i32);
-    //let iend: Vec2i = end.map(|x| x.round() as i32);
-
-    //let p: Vec2i = istart;
-    let mut p: Vec2f32 = start.clone();
-    //let d_vec: Vec2i = iend - istart;
-    let d_vec: Vec2f32 = end - start;
-    let mut discriminator = 2.0 * d_vec.y - d_vec.x;
-
-    // Euclidean distance of point (x, y) from line (signed)
-    let mut distance = 0.0;
-
-    // Euclidean distance between points (x1, y1) and (x2, y2)
-    let length = d_vec.norm();
-
-    let sin = d_vec.y / length;
-    let cos = d_vec.x / length;
-
-    while p.x <= end.x {
-        plot(p.x as u32, (p.y - 1.0) as u32, distance + cos);
-        plot(p.x as u32, p.y as u32, distance);
-        plot(p.x as u32, (p.y + 1.0) as u32, distance - cos);
-
-        p.x += 1.0;
-        if discriminator <= 0.0 {
-            distance += sin;
-            discriminator += 2.0 * d_vec.y;
-        } else {
-            distance += sin - cos;
-            discriminator += 2.0 * (d_vec.y - d_vec.x);
-            p.y += 1.0;
+    // converts the distance along the minor axis to the distance to the line
+    let cos = 1.0 / (1.0 + frame.slope * frame.slope).sqrt();
+    for (u, a, b) in frame.covered_columns() {
+        let v = frame.v_at((a + b) / 2.0);
+        let v_pixel = v.floor() as i32;
+        for v_neighbor in v_pixel - 1..=v_pixel + 1 {
+            let distance = ((v_neighbor as f64 + 0.5 - v).abs() * cos) as f32;
+            let intensity = cone_filter(distance) * (b - a) as f32;
+            if intensity > 0.0 {
+                if let Some((x, y)) = frame.pixel(u, v_neighbor) {
+                    plot(x, y, intensity);
+                }
+            }
         }
     }
 }
+
+/// The intensity of a one pixel wide line through a cone filter of radius `1` and volume `1`,
+/// at distances from `0` to `1.5` in steps of `1 / 16`.
+const CONE_FILTER: [f32; 25] = [
+    0.7799, 0.7750, 0.7603, 0.7363, 0.7032, 0.6619, 0.6133, 0.5587, 0.5000, 0.4407, 0.3829, 0.3277, 0.2757,
+    0.2276, 0.1838, 0.1445, 0.1101, 0.0805, 0.0559, 0.0361, 0.0210, 0.0104, 0.0039, 0.0007, 0.0000,
+];
+
+fn cone_filter(distance: f32) -> f32 {
+    let position = distance * 16.0;
+    if position >= 24.0 {
+        return 0.0;
+    }
+    let idx = position as usize;
+    let t = position - idx as f32;
+    CONE_FILTER[idx] * (1.0 - t) + CONE_FILTER[idx + 1] * t
+}
@@ -1,7 +1,54 @@
+use crate::base::defs::{linalg::*, rect::Rect};
 
-use crate::base::defs::linalg::*;
+use super::super::MajorFrame;
 
+/// Draws an antialiased line with unweighted area sampling.
+///
+/// The line is the one pixel wide band around the segment, cut perpendicularly to the major axis at
+/// its ends, and the intensity of a pixel is the exact area of its intersection with the band. See the
+/// conventions in [`line`](super::super) and [`xiaolin_wu::line`](super::xiaolin_wu::line) for the
+/// arguments.
+///
 /// See <https://www.inf.ed.ac.uk/teaching/courses/cg/lectures/cg4_2012.pdf>
-pub fn line<F: FnMut(u32, u32, f32) -> ()>(_start: &Vec2f32, _end: &Vec2f32, _plot: &mut F) -> () {
-    todo!();
+pub fn line<F: FnMut(i32, i32, f32)>(start: &Vec2f32, end: &Vec2f32, plot: &mut F, rect: &Rect<i32>) {
+    let Some(frame) = MajorFrame::new(start, end, rect) else {
+        return;
+    };
+    // the thickness of the band along the minor axis
+    let half_thickness = (1.0 + frame.slope * frame.slope).sqrt() / 2.0;
+    for (u, a, b) in frame.covered_columns() {
+        let (va, vb) = (frame.v_at(a), frame.v_at(b));
+        let first = (va.min(vb) - half_thickness).floor() as i32;
+        let last = (va.max(vb) + half_thickness).floor() as i32;
+        for v in first..=last {
+            // the area between the edges of the band inside the pixel
+            let (lo, hi) = (v as f64, v as f64 + 1.0);
+            let upper = mean_clamped(va + half_thickness, vb + half_thickness, lo, hi);
+            let lower = mean_clamped(va - half_thickness, vb - half_thickness, lo, hi);
+            let intensity = ((upper - lower) * (b - a)) as f32;
+            if intensity > 0.0 {
+                if let Some((x, y)) = frame.pixel(u, v) {
+                    plot(x, y, intensity);
+                }
+            }
+        }
+    }
+}
+
+/// The mean of `clamp(f, lo, hi)` where `f` goes linearly from `p` to `q`.
+fn mean_clamped(p: f64, q: f64, lo: f64, hi: f64) -> f64 {
+    if (q - p).abs() < 1e-9 {
+        return ((p + q) / 2.0).clamp(lo, hi);
+    }
+    // an antiderivative of the clamped function
+    let antiderivative = |x: f64| {
+        if x < lo {
+            lo * x
+        } else if x <= hi {
+            (x * x + lo * lo) / 2.0
+        } else {
+            hi * x + (lo * lo - hi * hi) / 2.0
+        }
+    };
+    (antiderivative(q) - antiderivative(p)) / (q - p)
 }
@@ -1,2 +1,6 @@
+/*!
+Aliased lines, one pixel per column, see the conventions in [`line`](super).
+*/
+
 pub mod naive;
 pub mod parametric;
@@ -1,18 +1,37 @@
-use crate::base::defs::linalg::*;
+use crate::base::defs::{linalg::*, rect::Rect};
 
-/// Originally written by copilot.
-/// Produces artifacts, maybe it is better to use [`super::naive::line`] instead.
-pub fn line<F: FnMut(u32, u32) -> ()>(start: &Vec2f32, end: &Vec2f32, plot: &mut F) -> () {
-    if start == end {
-        return;
-    }
-
-    let len = (end - start).norm();
-    let d: Vec2f32 = (end - start).normalize();
+use super::super::MajorFrame;
 
-    let mut p: Vec2f32 = start.clone();
-    for _t in 0..(len as u32) {
-        plot(p.x.round() as u32, p.y.round() as u32);
-        p += d;
+/// Draws a line from `start` to `end` with an incremental algorithm: the position of the line in the
+/// current pixel is incremented by the slope from a column to the next, as in Bresenham's algorithm.
+///
+/// The pixels follow the conventions of [`line`](super::super), see [`naive::line`](super::naive::line)
+/// for the arguments. The increments are exact when the coordinates have few significant bits, otherwise
+/// the rounding errors can only change a pixel when the line goes almost exactly through a pixel boundary.
+pub fn line<F: FnMut(i32, i32)>(start: &Vec2f32, end: &Vec2f32, plot: &mut F, rect: &Rect<i32>) {
+    let Some(frame) = MajorFrame::new(start, end, rect) else {
+        return;
+    };
+    let mut columns = frame.center_columns();
+    let Some(first) = columns.next() else {
+        return;
+    };
+    // Bresenham's algorithm with real coordinates: the remainder is the distance between the lower
+    // boundary of the pixel and the line, multiplied by the length along the major axis
+    let (du, dv) = frame.scaled_slope();
+    let step = if frame.is_reversed() { -dv } else { dv };
+    let (mut v, mut remainder) = frame.center_pixel(first);
+    for u in std::iter::once(first).chain(columns) {
+        if let Some((x, y)) = frame.pixel(u, v) {
+            plot(x, y);
+        }
+        remainder += step;
+        if remainder >= du {
+            v += 1;
+            remainder -= du;
+        } else if remainder < 0.0 {
+            v -= 1;
+            remainder += du;
+        }
     }
 }
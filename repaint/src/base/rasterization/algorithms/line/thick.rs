@@ -0,0 +1,182 @@
+/*!
+Thick lines.
+
+A thick line is the shape stroked by a [`Pen`] along a segment: the rectangle of the width of the pen
+around the segment, extended by half the width at both ends for [`PenCap::Square`], with half disks at
+both ends for [`PenCap::Round`]. A line of length zero is a square or a disk with these caps, and is
+not drawn with [`PenCap::Butt`].
+
+The algorithms draw each pixel at most once (see the conventions in [`line`](super)):
+ - [`span`]: aliased, the pixels whose center is inside the shape, with the perpendicular runs of
+   Murphy's modified Bresenham algorithm;
+ - [`distance_field`]: antialiased, column by column along the major axis, with the coverage estimated
+   from the distances to the outline.
+
+[`pen_line`] draws the line of a pen with the algorithm matching its
+[`AntialiasMode`](crate::base::defs::antialiasing::AntialiasMode), so that a CPU
+painter can implement [`BasicPainter::line`](crate::BasicPainter::line) without stroking a path.
+*/
+
+pub mod distance_field;
+pub mod span;
+
+use crate::base::defs::{linalg::*, rect::Rect};
+use crate::base::pen::{Pen, PenCap, StrokeWidth};
+
+use super::MajorFrame;
+
+/// Draws the line of a pen, `plot` is called with each pixel and its coverage.
+///
+/// The segment must be in device coordinates, only the pixels inside `rect` are plotted (`max` is
+/// exclusive). Normal widths are in user space and multiplied by `width_scale`, the scale of the
+/// transform to device space, hairlines are one pixel wide and cosmetic widths are in device pixels.
+pub fn pen_line<C, F: FnMut(i32, i32, f32)>(
+    start: &Vec2f64,
+    end: &Vec2f64,
+    pen: &Pen<C>,
+    width_scale: f32,
+    plot: &mut F,
+    rect: &Rect<i32>,
+) {
+    let width = match pen.stroke_width {
+        StrokeWidth::Hairline => 1.0,
+        StrokeWidth::Normal(width) => width * width_scale,
+        StrokeWidth::Cosmetic(width) => width,
+    };
+    let (start, end): (Vec2f32, Vec2f32) = (start.cast(), end.cast());
+    if pen.paint.antialias_mode.is_antialiased() {
+        distance_field::line(&start, &end, width, pen.cap, plot, rect);
+    } else {
+        span::line(&start, &end, width, pen.cap, &mut |x, y| plot(x, y, 1.0), rect);
+    }
+}
+
+/// A thick segment in the frame of its major axis, see [`MajorFrame`].
+struct ThickFrame {
+    frame: MajorFrame,
+    half_width: f64,
+    cap: PenCap,
+    /// The unit vector from the start to the end, `(1, 0)` if the segment is a point.
+    direction: Vec2f64,
+    length: f64,
+}
+
+impl ThickFrame {
+    /// Returns `None` if nothing can be plotted, the width is raised to one pixel.
+    fn new(start: &Vec2f32, end: &Vec2f32, width: f32, cap: PenCap, clip: &Rect<i32>) -> Option<Self> {
+        if !width.is_finite() || width <= 0.0 || (start == end && cap == PenCap::Butt) {
+            return None;
+        }
+        let frame = MajorFrame::with_point(start, end, clip)?;
+        let delta = frame.end - frame.start;
+        let length = delta.norm();
+        let direction = if length > 0.0 { delta / length } else { Vec2f64::new(1.0, 0.0) };
+        Some(Self { frame, half_width: width.max(1.0) as f64 / 2.0, cap, direction, length })
+    }
+
+    /// The range of the shape along the segment, from the start.
+    fn axial_range(&self) -> (f64, f64) {
+        match self.cap {
+            PenCap::Butt | PenCap::Round => (0.0, self.length),
+            PenCap::Square => (-self.half_width, self.length + self.half_width),
+        }
+    }
+
+    /// The coordinates of a point of the frame along and across the segment, from the start.
+    fn to_local(&self, pt: &Vec2f64) -> Vec2f64 {
+        let d = pt - self.frame.start;
+        Vec2f64::new(d.dot(&self.direction), self.direction.x * d.y - self.direction.y * d.x)
+    }
+
+    /// Tells if a point of the frame is inside the shape, the sides across the segment and the ends
+    /// of the butt and square caps being half-open.
+    fn contains(&self, pt: &Vec2f64) -> bool {
+        let local = self.to_local(pt);
+        let (min, max) = self.axial_range();
+        match self.cap {
+            PenCap::Round => (local.x - local.x.clamp(min, max)).hypot(local.y) < self.half_width,
+            PenCap::Butt | PenCap::Square => {
+                (min..max).contains(&local.x) && (-self.half_width..self.half_width).contains(&local.y)
+            }
+        }
+    }
+
+    /// An estimate of the coverage of the pixel centered on a point of the frame.
+    ///
+    /// With round caps, it is `clamp(0.5 - d, 0, 1)` where `d` is the signed distance from the center to
+    /// the outline. Otherwise, it is the product of the overlaps of the pixel with the shape along and
+    /// across the segment, so that the corners and the lines shorter than a pixel are not overestimated.
+    /// Both are exact for a pixel crossed by a side parallel to the pixel grid.
+    fn coverage(&self, pt: &Vec2f64) -> f64 {
+        let local = self.to_local(pt);
+        let (min, max) = self.axial_range();
+        let overlap = |c: f64, min: f64, max: f64| ((c + 0.5).min(max) - (c - 0.5).max(min)).clamp(0.0, 1.0);
+        match self.cap {
+            PenCap::Round => {
+                let along = local.x - local.x.clamp(min, max);
+                (0.5 + self.half_width - along.hypot(local.y)).clamp(0.0, 1.0)
+            }
+            PenCap::Butt | PenCap::Square => {
+                overlap(local.x, min, max) * overlap(local.y, -self.half_width, self.half_width)
+            }
+        }
+    }
+
+    /// The part of the center line of the column `u` within `margin` of the shape, along the minor
+    /// axis. It is exact for a margin of zero, a superset otherwise.
+    fn section(&self, u: i32, margin: f64) -> Option<(f64, f64)> {
+        let c = u as f64 + 0.5;
+        let half_width = self.half_width + margin;
+        let (min, max) = self.axial_range();
+        // the points (c, v) with `lo <= a + b * v <= hi`
+        let constrain = |range: Option<(f64, f64)>, a: f64, b: f64, lo: f64, hi: f64| {
+            let (v_min, v_max) = range?;
+            if b == 0.0 {
+                return (a >= lo && a <= hi).then_some((v_min, v_max));
+            }
+            let (v0, v1) = ((lo - a) / b, (hi - a) / b);
+            let (v0, v1) = (v0.min(v1).max(v_min), v0.max(v1).min(v_max));
+            (v0 <= v1).then_some((v0, v1))
+        };
+
+        // the rectangle, in local coordinates `x = origin.x + along_v * v` and `y = origin.y + across_v * v`
+        let origin = self.to_local(&Vec2f64::new(c, 0.0));
+        let (along_v, across_v) = (self.direction.y, self.direction.x);
+        let (axial_min, axial_max) = match self.cap {
+            PenCap::Round => (min, max),
+            PenCap::Butt | PenCap::Square => (min - margin, max + margin),
+        };
+        let all = Some((f64::NEG_INFINITY, f64::INFINITY));
+        let rect = constrain(all, origin.x, along_v, axial_min, axial_max);
+        let rect = constrain(rect, origin.y, across_v, -half_width, half_width);
+        if self.cap != PenCap::Round {
+            return rect;
+        }
+
+        // the disks at the ends, the section of the shape is convex so the union is an interval
+        let disk = |center: &Vec2f64| {
+            let dx = c - center.x;
+            let dv = (half_width * half_width - dx * dx).sqrt();
+            (dv >= 0.0).then_some((center.y - dv, center.y + dv))
+        };
+        [rect, disk(&self.frame.start), disk(&self.frame.end)]
+            .into_iter()
+            .flatten()
+            .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))
+    }
+
+    /// The columns that can have pixels within `margin` of the shape, in the drawing order.
+    fn columns(&self, margin: f64) -> impl Iterator<Item = i32> {
+        // the corners of the square caps are the farthest from the segment along both axes
+        let extent = std::f64::consts::SQRT_2 * (self.half_width + margin) + 1.0;
+        let (start, end) = (self.frame.start.x, self.frame.end.x);
+        self.frame.columns((start.min(end) - extent).floor(), (start.max(end) + extent).ceil(), extent)
+    }
+
+    /// The pixels of a column whose center is in the section, inside the clip.
+    fn pixels(&self, (lo, hi): (f64, f64)) -> std::ops::Range<i32> {
+        let first = (lo - 0.5).ceil().max(self.frame.clip_min.y as f64);
+        let last = (hi - 0.5).ceil().min(self.frame.clip_max.y as f64);
+        first as i32..(last as i32).max(first as i32)
+    }
+}
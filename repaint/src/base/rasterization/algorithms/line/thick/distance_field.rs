@@ -0,0 +1,47 @@
+use crate::base::defs::{linalg::*, rect::Rect};
+use crate::base::pen::PenCap;
+
+use super::ThickFrame;
+
+/// Draws an antialiased thick line, see [`thick`](super).
+///
+/// The coverage of a pixel is estimated from the distances between its center and the outline of the
+/// shape: `clamp(0.5 - d, 0, 1)` for the signed distance `d` to a round outline, the product of such
+/// estimates along and across the segment otherwise. It is exact for the pixels crossed by a side
+/// parallel to the pixel grid. Lines thinner than one pixel are drawn one pixel wide with their
+/// coverage multiplied by the width.
+///
+/// ## Arguments
+/// * `start` - the start point of the line
+/// * `end` - the end point of the line
+/// * `width` - the width of the line, nothing is drawn if it is not positive
+/// * `cap` - the shape of the ends of the line
+/// * `plot` - a function that is called with each pixel of the line and its coverage
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn line<F: FnMut(i32, i32, f32)>(
+    start: &Vec2f32,
+    end: &Vec2f32,
+    width: f32,
+    cap: PenCap,
+    plot: &mut F,
+    rect: &Rect<i32>,
+) {
+    let Some(frame) = ThickFrame::new(start, end, width, cap, rect) else {
+        return;
+    };
+    let opacity = width.min(1.0);
+    for u in frame.columns(0.5) {
+        let Some(section) = frame.section(u, 0.5) else {
+            continue;
+        };
+        for v in frame.pixels(section) {
+            let center = Vec2f64::new(u as f64 + 0.5, v as f64 + 0.5);
+            let coverage = frame.coverage(&center) as f32 * opacity;
+            if coverage > 0.0 {
+                if let Some((x, y)) = frame.frame.pixel(u, v) {
+                    plot(x, y, coverage);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::base::defs::{linalg::*, rect::Rect};
+use crate::base::pen::PenCap;
+
+use super::ThickFrame;
+
+/// Draws an aliased thick line with Murphy's modified Bresenham algorithm: the pixels whose center is
+/// inside the shape, see [`thick`](super).
+///
+/// The center line is followed with Bresenham's algorithm, one pixel per column, and each of its pixels
+/// starts a *perpendicular run*: the pixels of a Bresenham line perpendicular to the segment, with the
+/// same pattern from a run to the next. When the center line steps along the minor axis, two
+/// consecutive runs leave a pixel between them wherever the pattern steps along the major axis, these
+/// pixels are filled as in Murphy's algorithm. The runs and the filled pixels tile the plane, so each
+/// pixel is visited once, and the pixels are tested against the shape so that the sub-pixel endpoints
+/// and the caps are exact. Lines thinner than one pixel are drawn one pixel wide, so that they have no
+/// gaps.
+///
+/// The pixels are plotted run by run from the start to the end of the segment, only the runs crossing
+/// the clip rectangle are followed.
+///
+/// ## Arguments
+/// * `start` - the start point of the line
+/// * `end` - the end point of the line
+/// * `width` - the width of the line, nothing is drawn if it is not positive
+/// * `cap` - the shape of the ends of the line
+/// * `plot` - a function that is called for each pixel of the line
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn line<F: FnMut(i32, i32)>(start: &Vec2f32, end: &Vec2f32, width: f32, cap: PenCap, plot: &mut F, rect: &Rect<i32>) {
+    let Some(frame) = ThickFrame::new(start, end, width, cap, rect) else {
+        return;
+    };
+    let major = &frame.frame;
+    // the slope scaled by the length along the major axis, a point is followed as a horizontal segment
+    let (du, dv) = if frame.length > 0.0 { major.scaled_slope() } else { (1.0, 0.0) };
+    // the runs reach the pixels of the shape, the margin covers the rounding of the pattern
+    let reach = (frame.half_width.ceil() + 3.0).min(1e15) as i64;
+    let (clip_min, clip_max) = (major.clip_min.cast::<i64>(), major.clip_max.cast::<i64>());
+
+    let mut put = |u: i64, v: i64| {
+        if u >= clip_min.x && u < clip_max.x && frame.contains(&Vec2f64::new(u as f64 + 0.5, v as f64 + 0.5)) {
+            if let Some((x, y)) = major.pixel(u as i32, v as i32) {
+                plot(x, y);
+            }
+        }
+    };
+
+    let step = if major.is_reversed() { -dv } else { dv };
+    let mut center: Option<(i64, f64)> = None;
+    let mut pattern = Vec::new();
+    for u in run_columns(&frame, reach) {
+        // Bresenham's algorithm along the center line, as in `plain::parametric`
+        let previous = center.map(|(v, _)| v);
+        let (v, remainder) = match center {
+            None => center_pixel(u, du, dv, &major.start),
+            Some((mut v, mut remainder)) => {
+                remainder += step;
+                if remainder >= du {
+                    v += 1;
+                    remainder -= du;
+                } else if remainder < 0.0 {
+                    v -= 1;
+                    remainder += du;
+                }
+                (v, remainder)
+            }
+        };
+        center = Some((v, remainder));
+
+        // the steps of the run whose pixels are in the rows of the clip rectangle
+        let (first, last) = ((-reach).max(clip_min.y - v), reach.min(clip_max.y - 1 - v));
+        if first > last {
+            continue;
+        }
+        perpendicular(du, dv, first - 1, last + 1, &mut pattern);
+        let offset = |k: i64| pattern[(k - first + 1) as usize];
+
+        // the pixels left between this run and the previous one, from the run of the lower column
+        if let Some(previous) = previous.filter(|previous| *previous != v) {
+            for k in first..=last {
+                if major.is_reversed() {
+                    let d = previous - v;
+                    if offset(k - d) != offset(k) {
+                        put(u + 1 + offset(k), v + k);
+                    }
+                } else {
+                    let d = v - previous;
+                    if offset(k) != offset(k + d) {
+                        put(u + offset(k + d), v + k);
+                    }
+                }
+            }
+        }
+        for k in first..=last {
+            put(u + offset(k), v + k);
+        }
+    }
+}
+
+/// The columns whose runs can have pixels of the shape inside the clip rectangle, in the drawing order.
+///
+/// The pixels of a run are less than 1.5 pixels away along the segment from the crossing of the center
+/// line of its column, the filled pixels less than 2.5, and they are less than `reach + 2` columns away.
+fn run_columns(frame: &ThickFrame, reach: i64) -> impl Iterator<Item = i64> {
+    let major = &frame.frame;
+    let (min, max) = frame.axial_range();
+    let disks = if frame.cap == PenCap::Round { frame.half_width } else { 0.0 };
+    // the range of the clip rectangle along the segment
+    let (clip_min, clip_max): (Vec2f64, Vec2f64) = (major.clip_min.cast(), major.clip_max.cast());
+    let corners = [clip_min, Vec2f64::new(clip_max.x, clip_min.y), Vec2f64::new(clip_min.x, clip_max.y), clip_max];
+    let (clip_start, clip_end) = corners
+        .iter()
+        .map(|corner| frame.to_local(corner).x)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), along| (a.min(along), b.max(along)));
+    let (t0, t1) = ((min - disks).max(clip_start) - 4.0, (max + disks).min(clip_end) + 4.0);
+
+    // the center line of the column `u` is crossed at `t` along the segment for `u + 0.5 = start.x + t * direction.x`
+    let (a, b) = (major.start.x + t0 * frame.direction.x - 0.5, major.start.x + t1 * frame.direction.x - 0.5);
+    let margin = reach as f64 + 3.0;
+    let first = a.min(b).floor().max(clip_min.x - margin);
+    let last = a.max(b).ceil().min(clip_max.x + margin);
+    let (first, count) = if t0 <= t1 && first <= last { (first as i64, (last - first) as i64 + 1) } else { (0, 0) };
+    let reversed = major.is_reversed();
+    (0..count).map(move |i| if reversed { first + count - 1 - i } else { first + i })
+}
+
+/// The pixel of the center line in the column `u` and the distance from its lower boundary to the line
+/// multiplied by `du`, as in `MajorFrame::center_pixel`.
+fn center_pixel(u: i64, du: f64, dv: f64, start: &Vec2f64) -> (i64, f64) {
+    let scaled_v = start.y * du + (u as f64 + 0.5 - start.x) * dv;
+    let mut v = (scaled_v / du).floor();
+    let mut remainder = scaled_v - v * du;
+    if remainder < 0.0 {
+        v -= 1.0;
+        remainder += du;
+    } else if remainder >= du {
+        v += 1.0;
+        remainder -= du;
+    }
+    // the lines far away from the clip rectangle stay far away, without overflows
+    (v.clamp(-MAX_COORDINATE, MAX_COORDINATE) as i64, remainder)
+}
+
+/// The bound of the rows of the center line, far beyond the clip rectangle and the reach of the runs.
+const MAX_COORDINATE: f64 = (1u64 << 60) as f64;
+
+/// Fills `offsets` with the offsets along the major axis of the perpendicular run at the steps
+/// `first..=last` along the minor axis: Bresenham's algorithm for the perpendicular to the segment
+/// through the center of the pixel at the step `0`.
+fn perpendicular(du: f64, dv: f64, first: i64, last: i64, offsets: &mut Vec<i64>) {
+    offsets.clear();
+    // the remainder is the distance between the left boundary of the pixel and the perpendicular,
+    // multiplied by `du`
+    let scaled_u = du * 0.5 - dv * first as f64;
+    let mut offset = (scaled_u / du).floor();
+    let mut remainder = scaled_u - offset * du;
+    if remainder < 0.0 {
+        offset -= 1.0;
+        remainder += du;
+    } else if remainder >= du {
+        offset += 1.0;
+        remainder -= du;
+    }
+    for _ in first..=last {
+        offsets.push(offset as i64);
+        remainder -= dv;
+        if remainder < 0.0 {
+            offset -= 1.0;
+            remainder += du;
+        } else if remainder >= du {
+            offset += 1.0;
+            remainder -= du;
+        }
+    }
+}
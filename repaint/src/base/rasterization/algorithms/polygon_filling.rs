@@ -1 +1,165 @@
+/*!
+Polygon filling.
+
+The fillers take the contours of a broken polyline and pass the covered pixels to a
+[`LineSpanConsumer`], line by line:
+ - [`scanline`]: even-odd filling with horizontal-only antialiasing, the simplest and fastest;
+ - [`accumulation`]: exact-area coverage, for [`AntialiasMode::Exact`];
+ - [`sparse_strips`]: exact-area coverage accumulated in the tiles crossed by the edges only, with
+   the interior passed as solid spans, for many small paths or large ones;
+ - [`supersampling`]: sparse-scanline supersampling, for [`AntialiasMode::SubPixel`] and
+   [`AntialiasMode::None`] (a single sample at the center of each pixel).
+
+[`fill`] selects the filler matching an [`AntialiasMode`].
+*/
+
 pub mod scanline;
+pub mod accumulation;
+pub mod sparse_strips;
+pub mod supersampling;
+
+use crate::base::defs::{antialiasing::AntialiasMode, fill_rule::FillRule, linalg::*, rect::Rect};
+use crate::base::shapes::polyline::BrokenPolylineCommand;
+
+use scanline::LineSpanConsumer;
+
+/// Fills the implicitly closed contours with the filler matching the antialias mode.
+///
+/// The indices passed to the consumer are the device coordinates of the pixels inside `clip_rect`
+/// (`max` is exclusive), the points with non-finite coordinates are ignored.
+pub fn fill<C: LineSpanConsumer>(
+    contour_commands: &[BrokenPolylineCommand],
+    clip_rect: &Rect<usize>,
+    fill_rule: FillRule,
+    antialias: AntialiasMode,
+    consumer: &mut C,
+) {
+    match antialias {
+        AntialiasMode::None => supersampling::fill(contour_commands, clip_rect, fill_rule, (1, 1), consumer),
+        AntialiasMode::Exact => accumulation::fill(contour_commands, clip_rect, fill_rule, consumer),
+        AntialiasMode::SubPixel { nx, ny } => {
+            supersampling::fill(contour_commands, clip_rect, fill_rule, (nx, ny), consumer)
+        }
+    }
+}
+
+/// Calls `f` for each edge of the implicitly closed contours, skipping the non-finite points.
+///
+/// A contour starting with a non-finite point starts at its first finite point, a leading line
+/// without a move starts at the origin, as for [`split_polylines`](crate::base::shapes::polyline::split_polylines).
+pub(crate) fn for_each_edge(commands: &[BrokenPolylineCommand], mut f: impl FnMut(Vec2f64, Vec2f64)) {
+    let mut first: Option<Vec2f64> = None;
+    let mut last: Option<Vec2f64> = None;
+    let mut moved = false;
+    for command in commands {
+        if let BrokenPolylineCommand::MoveTo(_) = command {
+            if let (Some(first), Some(last)) = (first, last) {
+                f(last, first);
+            }
+            (first, last, moved) = (None, None, true);
+        }
+        let pt = command.point();
+        if !pt.x.is_finite() || !pt.y.is_finite() {
+            continue;
+        }
+        match last {
+            Some(last) => f(last, pt),
+            None if !moved => {
+                first = Some(Vec2f64::zeros());
+                f(Vec2f64::zeros(), pt);
+            }
+            None => first = Some(pt),
+        }
+        last = Some(pt);
+    }
+    if let (Some(first), Some(last)) = (first, last) {
+        f(last, first);
+    }
+}
+
+/// An edge going down, `winding` is `-1` if the original edge was going up.
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    top: f64,
+    bottom: f64,
+    x_top: f64,
+    dxdy: f64,
+    winding: i32,
+}
+
+impl Edge {
+    fn x_at(&self, y: f64) -> f64 {
+        self.x_top + (y - self.top) * self.dxdy
+    }
+}
+
+/// An active edge table: the edges are sorted by their top, and the edges crossing the current
+/// scanline are kept sorted by the abscissa of their crossing.
+///
+/// An edge crosses the scanline `y` if `top <= y < bottom`, so a vertex shared by two edges is
+/// counted once and the horizontal edges are ignored.
+struct ActiveEdgeTable {
+    edges: Vec<Edge>,
+    next: usize,
+    /// The crossings of the current scanline and the index of their edge.
+    active: Vec<(f64, usize)>,
+}
+
+impl ActiveEdgeTable {
+    /// Builds the table of the implicitly closed contours, keeping the edges that cross the
+    /// scanlines between `min_y` and `max_y`.
+    fn new(commands: &[BrokenPolylineCommand], min_y: f64, max_y: f64) -> Self {
+        let mut edges = Vec::new();
+        for_each_edge(commands, |start, end| {
+            if start.y == end.y {
+                return;
+            }
+            let (top, bottom, winding) = if start.y < end.y { (start, end, 1) } else { (end, start, -1) };
+            if bottom.y <= min_y || top.y >= max_y {
+                return;
+            }
+            let dxdy = (bottom.x - top.x) / (bottom.y - top.y);
+            // can only happen with huge coordinates
+            if !dxdy.is_finite() {
+                return;
+            }
+            edges.push(Edge { top: top.y, bottom: bottom.y, x_top: top.x, dxdy, winding });
+        });
+        edges.sort_by(|a, b| a.top.total_cmp(&b.top));
+        Self { edges, next: 0, active: Vec::new() }
+    }
+
+    /// The vertical extent of the edges, `None` if there are no edges.
+    fn y_range(&self) -> Option<(f64, f64)> {
+        let top = self.edges.first()?.top;
+        let bottom = self.edges.iter().map(|edge| edge.bottom).fold(f64::NEG_INFINITY, f64::max);
+        Some((top, bottom))
+    }
+
+    /// Moves to the scanline `y`, that must not be above the previous one, and returns the
+    /// crossings sorted from left to right, with the winding of their edge.
+    fn advance(&mut self, y: f64) -> impl Iterator<Item = (f64, i32)> + '_ {
+        let edges = &self.edges;
+        self.active.retain(|(_, idx)| edges[*idx].bottom > y);
+        while self.next < edges.len() && edges[self.next].top <= y {
+            if edges[self.next].bottom > y {
+                self.active.push((0.0, self.next));
+            }
+            self.next += 1;
+        }
+        for (x, idx) in self.active.iter_mut() {
+            *x = edges[*idx].x_at(y);
+        }
+
+        // the order changes little from a scanline to the next, an insertion sort is nearly linear
+        for i in 1..self.active.len() {
+            let mut j = i;
+            while j > 0 && self.active[j - 1].0.total_cmp(&self.active[j].0).is_gt() {
+                self.active.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+
+        self.active.iter().map(|(x, idx)| (*x, edges[*idx].winding))
+    }
+}
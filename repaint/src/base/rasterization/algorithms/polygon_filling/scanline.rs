@@ -6,6 +6,8 @@ use crate::{base::defs::rect::Rect, base::shapes::polyline::BrokenPolylineComman
 
 use crate::base::defs::linalg::*;
 
+use super::ActiveEdgeTable;
+
 /*struct LineSpan {
     start_index: usize,
     end_index: usize,
@@ -14,6 +16,12 @@ use crate::base::defs::linalg::*;
     end_weight: f32,
 }*/
 
+/// Receives the pixels covered by a filler, line by line.
+///
+/// The fillers passing spans take the clip as a `&Rect<usize>` (`max` is exclusive) and pass device
+/// indices: the row to [`start_line`](Self::start_line) and the columns to the other methods, all inside
+/// the clip rectangle. [`fill`] takes an arbitrary `&Rect<i32>` clip instead and passes the indices
+/// relative to its origin.
 pub trait LineSpanConsumer {
     fn start_line(&mut self, idx: usize);
     fn end_line(&mut self) {}
@@ -30,225 +38,82 @@ pub trait LineSpanConsumer {
     fn scan_line<C: LineSpanConsumer>(consumer: &mut C);
 }*/
 
-#[derive(Clone, Copy, Debug)]
-pub struct Segment {
-    pub start: Vector2<f32>,
-    pub end: Vector2<f32>,
-}
-
-impl Segment {
-    fn new(start: Vector2<f32>, end: Vector2<f32>) -> Self {
-        Self { start, end }
-    }
-}
-
-static mut LAST_VALUE: usize = 0;
-
+/// Fills the polygon with the even-odd rule, one scanline per row of pixels.
+///
+/// Each row is sampled by the horizontal line through the centers of its pixels, the contours are
+/// implicitly closed and the points with non-finite coordinates are ignored. Without antialiasing a
+/// pixel is covered if its center is inside a span, with antialiasing the pixels at the ends of the
+/// spans are weighted by their horizontal coverage.
+///
+/// The indices passed to the consumer are relative to `clip_rect.min`, only the pixels inside
+/// `clip_rect` are passed (`max` is exclusive) and the lines are emitted from top to bottom.
 pub fn fill<C: LineSpanConsumer>(
     contour_commands: &[BrokenPolylineCommand],
-    clip_rect: &Rect<usize>,
+    clip_rect: &Rect<i32>,
     antialiased: bool,
     consumer: &mut C,
 ) {
-    // check if positively oriented
-    //assert!(signed_polygon_area(contour) > 0.0);
-
-    let mut segments = {
-        let mut segments = Vec::<Segment>::new();
-        segments.reserve(contour_commands.len());
-
-        let mut pos = Vector2::new(0.0, 0.0);
-
-        for cmd in contour_commands {
-            match cmd {
-                BrokenPolylineCommand::MoveTo(pt) => {
-                    pos = *pt;
-                }
-                BrokenPolylineCommand::LineTo(pt) => {
-                    let mut segment = Segment::new(
-                        pos.cast(),
-                        pt.cast(),
-                    );
-
-                    // ingore degenerate segments
-                    if segment.start == segment.end {
-                        continue;
-                    }
-
-                    // make sure that start.y <= end.y
-                    if segment.start.y > segment.end.y {
-                        std::mem::swap(&mut segment.start, &mut segment.end);
-                    }
+    if clip_rect.min.x >= clip_rect.max.x || clip_rect.min.y >= clip_rect.max.y {
+        return;
+    }
+    let min: Vec2f64 = clip_rect.min.cast();
+    let max: Vec2f64 = clip_rect.max.cast();
 
-                    segments.push(segment);
-                    pos = *pt;
+    let mut table = ActiveEdgeTable::new(contour_commands, min.y, max.y);
+    let Some((top, bottom)) = table.y_range() else {
+        return;
+    };
+    let first_line = top.floor().max(min.y) as i32;
+    let last_line = bottom.ceil().min(max.y) as i32;
+
+    for y in first_line..last_line {
+        consumer.start_line((y - clip_rect.min.y) as usize);
+        let mut span_start: Option<f64> = None;
+        for (x, _) in table.advance(y as f64 + 0.5) {
+            match span_start.take() {
+                None => span_start = Some(x),
+                Some(start) => {
+                    // relative to the clip origin
+                    let start = start.clamp(min.x, max.x) - min.x;
+                    let end = x.clamp(min.x, max.x) - min.x;
+                    put_span(start, end, antialiased, consumer);
                 }
             }
         }
+        consumer.end_line();
+    }
+}
 
-        segments.sort_by(|a, b| a.start.y.partial_cmp(&b.start.y).unwrap());
-
-        segments
-    };
-
-    let (min, max): (Vec2f32, Vec2f32) = {
-        let mut min = Vec2f32::new(std::f32::MAX, std::f32::MAX);
-        let mut max = Vec2f32::new(std::f32::MIN, std::f32::MIN);
-        for segment in &segments {
-            min.x = min.x.min(segment.start.x).min(segment.end.x);
-            min.y = min.y.min(segment.start.y).min(segment.end.y);
-            max.x = max.x.max(segment.start.x).max(segment.end.x);
-            max.y = max.y.max(segment.start.y).max(segment.end.y);
+/// Passes the pixels of a span to the consumer, the ends must not be negative.
+fn put_span<C: LineSpanConsumer>(start: f64, end: f64, antialiased: bool, consumer: &mut C) {
+    if start >= end {
+        return;
+    }
+    if !antialiased {
+        // the pixels whose center is inside the span
+        let (first, last) = ((start - 0.5).ceil() as usize, (end - 0.5).ceil() as usize);
+        if first < last {
+            consumer.put_solid_span(first, last);
         }
-        (min, max)
-    };
-
-    // The active segments are the segments that are intersecting the current line
-    let mut active_segments = Vec::<Segment>::new();
-
-    // The intersections are the x coordinates of the intersections between the current line and the
-    // active segments. We need to sort the intersections to know where to start and end the spans.
-    // TODO maybe use a binary heap instead of a vector
-    let mut intersections = Vec::<f32>::new();
-    //let mut intersections = std::collections::BinaryHeap::<F32>::new();
+        return;
+    }
 
-    let (start_line, end_line): (usize, usize) = {
-        let start_line = (min.y.floor() as usize).clamp(0, clip_rect.height());
-        let end_line = (max.y.ceil() as usize).clamp(0, clip_rect.height());
-        (start_line, end_line)
+    let (first, last) = (start.floor(), end.floor());
+    if first == last {
+        consumer.put_weighted(first as usize, (end - start) as f32);
+        return;
+    }
+    let solid_start = if start > first {
+        consumer.put_weighted(first as usize, (first + 1.0 - start) as f32);
+        first as usize + 1
+    } else {
+        first as usize
     };
-
-    let y_offset: f32 = 0.0;
-    let x_offset: f32 = 0.0;
-
-    //println!("---------");
-
-    // scan the lines
-    for y_line in start_line..end_line {
-        let y = y_line as f32 + y_offset;
-
-        let active_segments: &Vec<Segment> = {
-            active_segments.clear();
-
-            // https://users.rust-lang.org/t/how-to-delete-element-when-iterating-a-vec/65862/6
-            {
-                let mut idx_wr = 0;
-                for idx_rd in 0..segments.len() {
-                    if segments[idx_rd].start.y <= y as f32 && segments[idx_rd].end.y > y as f32 {
-                        active_segments.push(segments[idx_rd]);
-                    } else {
-                        if segments[idx_rd].start.y > y as f32 {
-                            break;
-                        }
-                        if segments[idx_rd].end.y <= y as f32 {
-                            segments.swap(idx_wr, idx_rd);
-                            idx_wr += 1;
-                        }
-                    }
-                    //let segment = &segments[idx_rd];
-                }
-                segments.drain(..idx_wr);
-            }
-            unsafe {
-                if segments.len() != LAST_VALUE {
-                    //println!("at line {}: {} -> {}, dropped {}", y_line, LAST_VALUE, segments.len(), LAST_VALUE as isize - segments.len() as isize);
-                    LAST_VALUE = segments.len();
-                }
-            }
-
-            /*for i in active_segments_index_start..segments.len() {
-                let segment = &segments[i];
-
-                if segment.start.y <= y as f32 && segment.end.y > y as f32 {
-                    active_segments.push(*segment);
-                } else {
-                    if segment.end.y <= y as f32 {
-                        active_segments_index_start = i + 1;
-                    }
-                    if segment.start.y > y as f32 {
-                        break;
-                    }
-                }
-            }*/
-
-            &active_segments
-        };
-
-        let intersections = {
-            intersections.clear();
-
-            for segment in active_segments {
-                let x = segment.start.x
-                    + (segment.end.x - segment.start.x) * ((y as f32 + y_offset) - segment.start.y)
-                        / (segment.end.y - segment.start.y);
-                let min_x = segment.start.x.min(segment.end.x);
-                let max_x = segment.start.x.max(segment.end.x);
-                let intersection = (x + x_offset)
-                    .clamp(min_x, max_x)
-                    .clamp(0.0, clip_rect.width() as f32);
-                //intersections.push();
-                intersections.push(intersection);
-            }
-
-            intersections.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-            &intersections
-        };
-
-        consumer.start_line(y_line);
-
-        if !antialiased {
-            if intersections.len() % 2 == 0 {
-                for i in (0..intersections.len()).step_by(2) {
-                    if i + 1 >= intersections.len() {
-                        break;
-                    }
-
-                    let start = intersections[i];
-                    let end = intersections[i + 1];
-                    let start = start.floor() as u32; // ? floor???
-                    let end = end.ceil() as u32; // ? floor???
-                    consumer.put_solid_span(start as usize, end as usize);
-                }
-            }
-        } else {
-            for i in (0..intersections.len()).step_by(2) {
-                if i + 1 >= intersections.len() {
-                    break;
-                }
-
-                let start = intersections[i];
-                let end = intersections[i + 1];
-
-                // the first and last pixel are weighted by the distance to the next/previous pixel
-                let start_f = start;
-                let end_f = end;
-
-                let start = start.floor() as u32;
-                let end = end.ceil() as u32;
-
-                let start_weight = 1.0 - start_f.fract();
-                let end_weight = end_f.fract();
-
-                if start == end {
-                    let x = start;
-                    let intensity = start_weight * end_weight;
-                    consumer.put_weighted(x as usize, intensity);
-                } else {
-                    let x = start;
-                    let intensity = start_weight;
-                    consumer.put_weighted(x as usize, intensity);
-
-                    consumer.put_solid_span(start as usize + 1, end as usize);
-
-                    let x = end;
-                    let intensity = end_weight;
-                    consumer.put_weighted(x as usize, intensity);
-                }
-            }
-        }
-
-        consumer.end_line();
+    if solid_start < last as usize {
+        consumer.put_solid_span(solid_start, last as usize);
+    }
+    if end > last {
+        consumer.put_weighted(last as usize, (end - last) as f32);
     }
 }
 
@@ -262,16 +127,3 @@ pub fn signed_polygon_area(polygon: &Vec<Vector2<f32>>) -> f32 {
 
     area / 2.0
 }
-
-#[derive(PartialOrd, PartialEq)]
-struct F32 {
-    value: f32,
-}
-
-impl Eq for F32 {}
-
-/*impl Ord for F32 {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
-    }
-}*/
@@ -0,0 +1,145 @@
+/*!
+Sparse-scanline supersampling.
+
+Every row of pixels is sampled by `ny` sub-scanlines going through the centers of the cells of a
+`ny x nx` sub-pixel grid, each sub-scanline has `nx` samples per pixel. For each sub-scanline, the
+crossings of the active edges are sorted and walked from left to right, and the spans inside the shape
+are added to the row as sample counts: the pixels at the ends of a span get their exact count, the
+pixels in between are counted with a difference array, so the cost of a span doesn't depend on its
+length and only the pixels near the edges are touched.
+
+With a `1 x 1` grid, a pixel is covered if its center is inside the shape: that is aliased filling.
+*/
+
+use crate::base::defs::{fill_rule::FillRule, rect::Rect};
+use crate::base::shapes::polyline::BrokenPolylineCommand;
+
+use super::{scanline::LineSpanConsumer, ActiveEdgeTable};
+
+/// Fills the polygon with the proportion of the samples of a `grid.1 x grid.0` grid inside it.
+///
+/// The contours are implicitly closed and the points with non-finite coordinates are ignored, a grid
+/// dimension of `0` is considered to be `1`. The indices passed to the consumer are the device
+/// coordinates of the pixels inside `clip_rect` (`max` is exclusive), the lines are emitted from top
+/// to bottom and only the covered pixels are passed to the consumer.
+pub fn fill<C: LineSpanConsumer>(
+    contour_commands: &[BrokenPolylineCommand],
+    clip_rect: &Rect<usize>,
+    fill_rule: FillRule,
+    grid: (u16, u16),
+    consumer: &mut C,
+) {
+    if clip_rect.min.x >= clip_rect.max.x || clip_rect.min.y >= clip_rect.max.y {
+        return;
+    }
+    let (nx, ny) = (grid.0.max(1) as usize, grid.1.max(1) as usize);
+
+    let mut table = ActiveEdgeTable::new(contour_commands, clip_rect.min.y as f64, clip_rect.max.y as f64);
+    let Some((top, bottom)) = table.y_range() else {
+        return;
+    };
+    let first_row = top.floor().max(clip_rect.min.y as f64) as usize;
+    let last_row = bottom.ceil().min(clip_rect.max.y as f64) as usize;
+
+    let mut row = SampleRow::new(clip_rect.min.x, clip_rect.max.x - clip_rect.min.x, nx, nx * ny);
+    for y in first_row..last_row {
+        for j in 0..ny {
+            let sample_y = y as f64 + (j as f64 + 0.5) / ny as f64;
+            let mut winding = 0;
+            let mut span_start = 0.0;
+            for (x, edge_winding) in table.advance(sample_y) {
+                let was_inside = fill_rule.is_inside(winding);
+                winding += edge_winding;
+                match (was_inside, fill_rule.is_inside(winding)) {
+                    (false, true) => span_start = x,
+                    (true, false) => row.add_span(span_start, x),
+                    _ => {}
+                }
+            }
+        }
+        row.emit(y, consumer);
+    }
+}
+
+/// The sample counts of a row of pixels.
+struct SampleRow {
+    /// The device coordinate of the first pixel.
+    origin: usize,
+    /// The samples per pixel on a sub-scanline.
+    nx: usize,
+    /// The samples per pixel.
+    samples: usize,
+    /// The counts of the pixels at the ends of the spans.
+    counts: Vec<usize>,
+    /// The difference array of the counts of the pixels inside the spans.
+    deltas: Vec<isize>,
+    /// The range of the modified pixels.
+    dirty: Option<(usize, usize)>,
+}
+
+impl SampleRow {
+    fn new(origin: usize, width: usize, nx: usize, samples: usize) -> Self {
+        Self { origin, nx, samples, counts: vec![0; width], deltas: vec![0; width + 1], dirty: None }
+    }
+
+    /// Adds the samples between `start` and `end`, in device coordinates.
+    fn add_span(&mut self, start: f64, end: f64) {
+        // the sample `k` of the row is at `(k + 0.5) / nx`
+        let nx = self.nx as f64;
+        let limit = (self.counts.len() * self.nx) as f64;
+        let to_sample = |x: f64| ((x - self.origin as f64) * nx - 0.5).ceil().clamp(0.0, limit) as usize;
+        let (k0, k1) = (to_sample(start), to_sample(end));
+        if k0 >= k1 {
+            return;
+        }
+
+        let (p0, p1) = (k0 / self.nx, (k1 - 1) / self.nx);
+        if p0 == p1 {
+            self.counts[p0] += k1 - k0;
+        } else {
+            self.counts[p0] += (p0 + 1) * self.nx - k0;
+            self.counts[p1] += k1 - p1 * self.nx;
+            if p1 > p0 + 1 {
+                self.deltas[p0 + 1] += self.nx as isize;
+                self.deltas[p1] -= self.nx as isize;
+            }
+        }
+        self.dirty = Some(match self.dirty {
+            Some((min, max)) => (min.min(p0), max.max(p1 + 1)),
+            None => (p0, p1 + 1),
+        });
+    }
+
+    /// Passes the covered pixels to the consumer and clears the row.
+    fn emit<C: LineSpanConsumer>(&mut self, y: usize, consumer: &mut C) {
+        let Some((min, max)) = self.dirty.take() else {
+            return;
+        };
+        consumer.start_line(y);
+        let mut inner = 0isize;
+        let mut solid_start: Option<usize> = None;
+        for x in min..max {
+            inner += self.deltas[x];
+            let count = self.counts[x] + inner as usize;
+            self.counts[x] = 0;
+            self.deltas[x] = 0;
+
+            // runs of fully covered pixels are emitted as solid spans
+            if count >= self.samples {
+                solid_start.get_or_insert(x);
+                continue;
+            }
+            if let Some(start) = solid_start.take() {
+                consumer.put_solid_span(self.origin + start, self.origin + x);
+            }
+            if count > 0 {
+                consumer.put_weighted(self.origin + x, count as f32 / self.samples as f32);
+            }
+        }
+        self.deltas[max] = 0;
+        if let Some(start) = solid_start {
+            consumer.put_solid_span(self.origin + start, self.origin + max);
+        }
+        consumer.end_line();
+    }
+}
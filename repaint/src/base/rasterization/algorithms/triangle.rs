@@ -0,0 +1,203 @@
+/*!
+Triangle rasterization.
+
+[`fill`] plots the pixels whose center is inside a triangle, with the *top-left* rule for the centers
+exactly on an edge: they are inside if the edge is a top edge (horizontal, with the triangle below it)
+or a left edge (with the triangle on its right). The triangles sharing an edge, like the triangles of
+a mesh, plot the pixels along it once, without gaps. As in [`line`](super::line), the pixel `(x, y)`
+is the square `[x, x + 1) x [y, y + 1)` and only the pixels inside the clip rectangle (`max` is
+exclusive) are plotted.
+
+Each pixel is plotted with the barycentric weights of its center: the weights of the three vertices,
+adding up to one, that give the center as their weighted mean. They interpolate the values given at
+the vertices, like colors ([`interpolate_color`]) and texture coordinates ([`interpolate_uv`]), and
+[`fill_mesh`] draws the triangles of a mesh with the interpolated values of its vertices.
+
+See <https://learn.microsoft.com/en-us/windows/win32/direct3d11/d3d10-graphics-programming-guide-rasterizer-stage-rules>
+*/
+
+use crate::base::defs::{colors::default_color_types::RgbaFColor, linalg::*, rect::Rect};
+
+/// Draws a triangle with the top-left rule, `plot` is called with each pixel and the barycentric
+/// weights of its center.
+///
+/// The pixels are plotted from top to bottom and from left to right, the vertices can be in any order.
+/// Nothing is plotted if a coordinate is not finite or if the triangle is flat.
+///
+/// ## Arguments
+/// * `vertices` - the vertices of the triangle
+/// * `plot` - a function that is called with each pixel and the weights of the vertices at its center
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn fill<F: FnMut(i32, i32, [f32; 3])>(vertices: &[Vec2f32; 3], plot: &mut F, rect: &Rect<i32>) {
+    if !vertices.iter().all(|v| v.iter().all(|c| c.is_finite())) {
+        return;
+    }
+    if rect.min.x >= rect.max.x || rect.min.y >= rect.max.y {
+        return;
+    }
+    let [a, b, c] = vertices.map(|v| v.cast::<f64>());
+    // twice the signed area
+    let area = (b - a).perp(&(c - a));
+    if area == 0.0 {
+        return;
+    }
+    // the edge opposite to each vertex, its value is proportional to the weight of the vertex
+    let edges = [Edge::new(&b, &c, area), Edge::new(&c, &a, area), Edge::new(&a, &b, area)];
+    let inside = |x: f64, y: f64| edges.iter().all(|edge| edge.contains(edge.value(x, y)));
+
+    let (min, max) = (a.inf(&b).inf(&c), a.sup(&b).sup(&c));
+    let first_row = (min.y - 0.5).ceil().max(rect.min.y as f64) as i32;
+    let last_row = ((max.y - 0.5).floor() + 1.0).min(rect.max.y as f64).max(first_row as f64) as i32;
+    for y in first_row..last_row {
+        let center_y = y as f64 + 0.5;
+
+        // the columns between the crossings of the edges, widened by one column for the rounding errors
+        // and then shrunk to the exact span
+        let (mut first, mut last) = ((min.x - 0.5).ceil(), (max.x - 0.5).floor() + 1.0);
+        for edge in &edges {
+            let (from, to) = edge.columns(center_y);
+            first = first.max(from - 1.0);
+            last = last.min(to + 1.0);
+        }
+        let mut first = first.max(rect.min.x as f64) as i32;
+        let mut last = last.min(rect.max.x as f64).max(first as f64) as i32;
+        while first < last && !inside(first as f64 + 0.5, center_y) {
+            first += 1;
+        }
+        while last > first && !inside(last as f64 - 0.5, center_y) {
+            last -= 1;
+        }
+
+        for x in first..last {
+            let values = edges.each_ref().map(|edge| edge.value(x as f64 + 0.5, center_y));
+            let sum = values[0] + values[1] + values[2];
+            plot(x, y, values.map(|value| (value / sum) as f32));
+        }
+    }
+}
+
+/// The color at the barycentric weights, interpolated with premultiplied alpha so that the transparent
+/// vertices don't darken the others.
+pub fn interpolate_color(colors: &[RgbaFColor; 3], weights: &[f32; 3]) -> RgbaFColor {
+    let mut premultiplied = [0.0f32; 4];
+    for (color, weight) in colors.iter().zip(weights) {
+        let alpha = color.a() * weight;
+        premultiplied[0] += color.r() * alpha;
+        premultiplied[1] += color.g() * alpha;
+        premultiplied[2] += color.b() * alpha;
+        premultiplied[3] += alpha;
+    }
+    let [r, g, b, alpha] = premultiplied;
+    if alpha > 0.0 {
+        RgbaFColor::new(r / alpha, g / alpha, b / alpha, alpha)
+    } else {
+        RgbaFColor::new(0.0, 0.0, 0.0, 0.0)
+    }
+}
+
+/// The texture coordinates at the barycentric weights.
+pub fn interpolate_uv(uvs: &[Vec2f32; 3], weights: &[f32; 3]) -> Vec2f32 {
+    uvs[0] * weights[0] + uvs[1] * weights[1] + uvs[2] * weights[2]
+}
+
+/// The values of a pixel of a mesh, interpolated from its vertices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fragment {
+    /// The color, if the vertices have colors.
+    pub color: Option<RgbaFColor>,
+    /// The texture coordinates, if the vertices have texture coordinates.
+    pub uv: Option<Vec2f32>,
+}
+
+/// Draws the triangles of a mesh, `plot` is called with each pixel and its interpolated values.
+///
+/// The triangles are given by the indices of their vertices, see
+/// [`VertexMode::triangles`](crate::base::defs::vertex_mode::VertexMode::triangles), and the triangles
+/// with an index out of the positions are skipped. The colors and the texture coordinates are ignored
+/// if there isn't one per position. The pixels are plotted triangle by triangle, the pixels on the
+/// edges shared by two triangles once.
+///
+/// ## Arguments
+/// * `positions` - the positions of the vertices
+/// * `colors` - the colors of the vertices, if any
+/// * `uvs` - the texture coordinates of the vertices, if any
+/// * `triangles` - the indices of the vertices of each triangle
+/// * `plot` - a function that is called with each pixel and its interpolated values
+/// * `rect` - the clipping rectangle, `max` is exclusive
+pub fn fill_mesh<F: FnMut(i32, i32, &Fragment)>(
+    positions: &[Vec2f32],
+    colors: Option<&[RgbaFColor]>,
+    uvs: Option<&[Vec2f32]>,
+    triangles: impl IntoIterator<Item = [usize; 3]>,
+    plot: &mut F,
+    rect: &Rect<i32>,
+) {
+    let colors = colors.filter(|colors| colors.len() == positions.len());
+    let uvs = uvs.filter(|uvs| uvs.len() == positions.len());
+    for triangle in triangles {
+        if triangle.iter().any(|i| *i >= positions.len()) {
+            continue;
+        }
+        let triangle_colors = colors.map(|colors| triangle.map(|i| colors[i]));
+        let triangle_uvs = uvs.map(|uvs| triangle.map(|i| uvs[i]));
+        fill(
+            &triangle.map(|i| positions[i]),
+            &mut |x, y, weights| {
+                let fragment = Fragment {
+                    color: triangle_colors.as_ref().map(|colors| interpolate_color(colors, &weights)),
+                    uv: triangle_uvs.as_ref().map(|uvs| interpolate_uv(uvs, &weights)),
+                };
+                plot(x, y, &fragment);
+            },
+            rect,
+        );
+    }
+}
+
+/// An edge of a triangle, with the value of its line equation positive inside the triangle.
+///
+/// The equation is computed from the endpoints in a canonical order, so that the two triangles sharing
+/// the edge get opposite values without rounding differences.
+struct Edge {
+    origin: Vec2f64,
+    direction: Vec2f64,
+    /// The sign of the equation.
+    sign: f64,
+    /// Tells if the points on the edge are inside.
+    top_left: bool,
+}
+
+impl Edge {
+    /// The edge from `start` to `end` of a triangle of twice the signed area `area`.
+    fn new(start: &Vec2f64, end: &Vec2f64, area: f64) -> Self {
+        let (origin, direction, sign) = if (start.y, start.x) < (end.y, end.x) {
+            (*start, end - start, area.signum())
+        } else {
+            (*end, start - end, -area.signum())
+        };
+        // the value grows towards the right of a left edge, and below a top edge
+        let top_left = if direction.y != 0.0 { -sign * direction.y > 0.0 } else { sign * direction.x > 0.0 };
+        Self { origin, direction, sign, top_left }
+    }
+
+    fn value(&self, x: f64, y: f64) -> f64 {
+        self.sign * (self.direction.x * (y - self.origin.y) - self.direction.y * (x - self.origin.x))
+    }
+
+    fn contains(&self, value: f64) -> bool {
+        value > 0.0 || (value == 0.0 && self.top_left)
+    }
+
+    /// An approximation of the columns whose center is inside on the row at `y`, `max` is exclusive.
+    fn columns(&self, y: f64) -> (f64, f64) {
+        if self.direction.y == 0.0 {
+            return if self.contains(self.value(0.0, y)) { (f64::NEG_INFINITY, f64::INFINITY) } else { (0.0, 0.0) };
+        }
+        let crossing = self.origin.x + self.direction.x * (y - self.origin.y) / self.direction.y;
+        if -self.sign * self.direction.y > 0.0 {
+            ((crossing - 0.5).ceil(), f64::INFINITY)
+        } else {
+            (f64::NEG_INFINITY, (crossing - 0.5).floor() + 1.0)
+        }
+    }
+}
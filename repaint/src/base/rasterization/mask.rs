@@ -0,0 +1,338 @@
+/*!
+Coverage masks.
+
+An [`AlphaMask`] stores the coverage of the pixels of a device-space rectangle, as `f32` values or as
+8-bit values ([`A8Mask`]). It is a [`LineSpanConsumer`], so the polygon fillers can rasterize into it,
+and masks can be combined to build clip masks. The pixels outside the bounds of a mask have no coverage.
+
+A mask can then be used to draw through it: the callback receives the color of each covered pixel along
+with its coverage, to be blended into the destination.
+*/
+
+use std::error::Error;
+use std::fmt::Debug;
+
+use nalgebra::Vector2;
+use strum::Display;
+
+use crate::base::defs::{antialiasing::AntialiasMode, fill_rule::FillRule, linalg::*, rect::{F64Rect, Rect}};
+use crate::base::paint::Ink;
+use crate::base::shapes::polyline::BrokenPolylineCommand;
+
+use super::algorithms::polygon_filling::{self, scanline::LineSpanConsumer};
+
+/// The storage of the coverage of a pixel.
+pub trait MaskValue: Copy + Default + PartialEq + Debug {
+    /// Converts to a coverage between `0.0` and `1.0`.
+    fn to_coverage(self) -> f32;
+
+    /// Converts from a coverage, clamped between `0.0` and `1.0`.
+    fn from_coverage(coverage: f32) -> Self;
+}
+
+impl MaskValue for f32 {
+    fn to_coverage(self) -> f32 {
+        self
+    }
+
+    fn from_coverage(coverage: f32) -> Self {
+        coverage.clamp(0.0, 1.0)
+    }
+}
+
+impl MaskValue for u8 {
+    fn to_coverage(self) -> f32 {
+        self as f32 / 255.0
+    }
+
+    fn from_coverage(coverage: f32) -> Self {
+        (coverage.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+/// Error returned when an ink can't be drawn through a mask.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum InkError {
+    /// The shaders can't be evaluated yet, [`AlphaMask::draw_with`] takes the color of each pixel.
+    UnsupportedShader,
+}
+
+impl Error for InkError {}
+
+/// A coverage mask over a device-space rectangle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlphaMask<T: MaskValue = f32> {
+    bounds: Rect<i32>,
+    data: Vec<T>,
+    /// The current line of the [`LineSpanConsumer`].
+    line: i32,
+    /// The coverage of the current line while it is filled, so that it is only quantized once,
+    /// empty between the lines.
+    line_coverage: Vec<f32>,
+}
+
+/// A mask with 8-bit coverage.
+pub type A8Mask = AlphaMask<u8>;
+
+impl<T: MaskValue> AlphaMask<T> {
+    /// Creates a mask without coverage, `bounds.max` is exclusive.
+    pub fn new(bounds: Rect<i32>) -> Self {
+        Self::new_filled(bounds, 0.0)
+    }
+
+    /// Creates a mask with the same coverage everywhere.
+    pub fn new_filled(bounds: Rect<i32>, coverage: f32) -> Self {
+        let bounds = Rect::new(bounds.min, bounds.max.sup(&bounds.min));
+        let size = bounds.max - bounds.min;
+        Self {
+            bounds,
+            data: vec![T::from_coverage(coverage); size.x as usize * size.y as usize],
+            line: bounds.min.y,
+            line_coverage: Vec::new(),
+        }
+    }
+
+    /// Creates the mask of a rectangle, the pixels on its edges are partially covered.
+    pub fn from_rect(bounds: Rect<i32>, rect: &F64Rect) -> Self {
+        let mut mask = Self::new(bounds);
+        for y in mask.bounds.min.y..mask.bounds.max.y {
+            for x in mask.bounds.min.x..mask.bounds.max.x {
+                mask.set(x, y, rect_coverage(rect, x, y));
+            }
+        }
+        mask
+    }
+
+    /// The pixels of the mask, `max` is exclusive.
+    pub fn bounds(&self) -> Rect<i32> {
+        self.bounds
+    }
+
+    pub fn width(&self) -> usize {
+        (self.bounds.max.x - self.bounds.min.x) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.bounds.max.y - self.bounds.min.y) as usize
+    }
+
+    /// The coverage of a pixel, `0.0` outside the bounds.
+    pub fn get(&self, x: i32, y: i32) -> f32 {
+        self.index(x, y).map_or(0.0, |idx| self.data[idx].to_coverage())
+    }
+
+    /// Sets the coverage of a pixel, pixels outside the bounds are ignored.
+    pub fn set(&mut self, x: i32, y: i32, coverage: f32) {
+        if let Some(idx) = self.index(x, y) {
+            self.data[idx] = T::from_coverage(coverage);
+        }
+    }
+
+    /// The stored values of a row, `y` must be inside the bounds.
+    pub fn row(&self, y: i32) -> &[T] {
+        let start = (y - self.bounds.min.y) as usize * self.width();
+        &self.data[start..start + self.width()]
+    }
+
+    /// The smallest rectangle containing the covered pixels, `None` if nothing is covered.
+    pub fn coverage_bounds(&self) -> Option<Rect<i32>> {
+        let zero = T::default();
+        let mut result: Option<Rect<i32>> = None;
+        for y in self.bounds.min.y..self.bounds.max.y {
+            let row = self.row(y);
+            if let Some(first) = row.iter().position(|value| *value != zero) {
+                let last = row.iter().rposition(|value| *value != zero).unwrap_or(first);
+                let min = Vector2::new(self.bounds.min.x + first as i32, y);
+                let max = Vector2::new(self.bounds.min.x + last as i32 + 1, y + 1);
+                result = Some(match result {
+                    Some(rect) => Rect::new(rect.min.inf(&min), rect.max.sup(&max)),
+                    None => Rect::new(min, max),
+                });
+            }
+        }
+        result
+    }
+
+    /// Returns true if no pixel is covered.
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|value| *value == T::default())
+    }
+
+    /// Adds the exact coverage of a filled polyline, the contours are implicitly closed.
+    pub fn fill_polyline(&mut self, commands: &[BrokenPolylineCommand], fill_rule: FillRule) {
+        self.fill_polyline_with(commands, fill_rule, AntialiasMode::Exact);
+    }
+
+    /// Adds the coverage of a filled polyline computed with the given antialias mode.
+    pub fn fill_polyline_with(
+        &mut self,
+        commands: &[BrokenPolylineCommand],
+        fill_rule: FillRule,
+        antialias: AntialiasMode,
+    ) {
+        // the fillers only handle non-negative coordinates, the polyline is moved to the origin of the mask
+        let origin: Vec2f64 = self.bounds.min.cast();
+        let commands: Vec<_> = commands
+            .iter()
+            .map(|command| match command {
+                BrokenPolylineCommand::MoveTo(pt) => BrokenPolylineCommand::MoveTo(pt - origin),
+                BrokenPolylineCommand::LineTo(pt) => BrokenPolylineCommand::LineTo(pt - origin),
+            })
+            .collect();
+
+        let clip_rect = Rect::new(Vector2::zeros(), Vector2::new(self.width(), self.height()));
+        polygon_filling::fill(&commands, &clip_rect, fill_rule, antialias, &mut LocalFiller(self));
+    }
+
+    /// Intersects with another mask: the coverages are multiplied.
+    pub fn intersect<U: MaskValue>(&mut self, other: &AlphaMask<U>) {
+        self.combine(other, |a, b| a * b);
+    }
+
+    /// Unites with another mask, as if the coverages were independent probabilities.
+    pub fn union<U: MaskValue>(&mut self, other: &AlphaMask<U>) {
+        self.combine(other, |a, b| a + b - a * b);
+    }
+
+    /// Removes the coverage of another mask.
+    pub fn subtract<U: MaskValue>(&mut self, other: &AlphaMask<U>) {
+        self.combine(other, |a, b| a * (1.0 - b));
+    }
+
+    /// Calls `put(x, y, color, coverage)` for each covered pixel, with the color of the ink.
+    ///
+    /// Nothing is drawn with [`Ink::None`]. [`Ink::Shader`] doesn't carry the shader yet, it returns
+    /// [`InkError::UnsupportedShader`] without drawing, see [`draw_with`](Self::draw_with).
+    pub fn draw_ink<C: Clone>(&self, ink: &Ink<C>, mut put: impl FnMut(i32, i32, C, f32)) -> Result<(), InkError> {
+        match ink {
+            Ink::None => Ok(()),
+            Ink::Color(color) => {
+                self.draw_with(|_| color.clone(), &mut put);
+                Ok(())
+            }
+            Ink::Shader => Err(InkError::UnsupportedShader),
+        }
+    }
+
+    /// Calls `put(x, y, color, coverage)` for each covered pixel, the color is given by `shader`
+    /// evaluated at the center of the pixel.
+    pub fn draw_with<C>(&self, mut shader: impl FnMut(Vec2f64) -> C, mut put: impl FnMut(i32, i32, C, f32)) {
+        for y in self.bounds.min.y..self.bounds.max.y {
+            for (x, value) in (self.bounds.min.x..).zip(self.row(y)) {
+                let coverage = value.to_coverage();
+                if coverage > 0.0 {
+                    put(x, y, shader(Vec2f64::new(x as f64 + 0.5, y as f64 + 0.5)), coverage);
+                }
+            }
+        }
+    }
+
+    fn combine<U: MaskValue>(&mut self, other: &AlphaMask<U>, f: impl Fn(f32, f32) -> f32) {
+        for y in self.bounds.min.y..self.bounds.max.y {
+            for x in self.bounds.min.x..self.bounds.max.x {
+                let value = f(self.get(x, y), other.get(x, y));
+                self.set(x, y, value);
+            }
+        }
+    }
+
+    /// Loads the coverage of the row `y` to accumulate into it, nothing is accumulated outside of the
+    /// bounds.
+    fn begin_row(&mut self, y: i64) {
+        self.line_coverage.clear();
+        if y >= self.bounds.min.y as i64 && y < self.bounds.max.y as i64 {
+            self.line = y as i32;
+            let coverage = self.row(self.line).iter().map(|value| value.to_coverage()).collect::<Vec<_>>();
+            self.line_coverage.extend(coverage);
+        }
+    }
+
+    /// Stores the accumulated coverage of the current row.
+    fn finish_row(&mut self) {
+        if !self.line_coverage.is_empty() {
+            let start = (self.line - self.bounds.min.y) as usize * self.width();
+            for (value, coverage) in self.data[start..].iter_mut().zip(self.line_coverage.drain(..)) {
+                *value = T::from_coverage(coverage);
+            }
+        }
+    }
+
+    fn add_coverage(&mut self, x: i64, weight: f32) {
+        let x = x - self.bounds.min.x as i64;
+        if let Some(coverage) = usize::try_from(x).ok().and_then(|x| self.line_coverage.get_mut(x)) {
+            *coverage += weight;
+        }
+    }
+
+    fn cover_span(&mut self, start: i64, end: i64) {
+        if self.line_coverage.is_empty() {
+            return;
+        }
+        let start = start.max(self.bounds.min.x as i64);
+        let end = end.min(self.bounds.max.x as i64);
+        if start < end {
+            let offset = |x: i64| (x - self.bounds.min.x as i64) as usize;
+            self.line_coverage[offset(start)..offset(end)].fill(1.0);
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= self.bounds.min.x && x < self.bounds.max.x && y >= self.bounds.min.y && y < self.bounds.max.y {
+            Some((y - self.bounds.min.y) as usize * self.width() + (x - self.bounds.min.x) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// The indices are device coordinates, the coverage is added to the current one.
+///
+/// The coverage of a line is accumulated as `f32` and stored by [`end_line`](LineSpanConsumer::end_line),
+/// so the 8-bit masks round each pixel once.
+impl<T: MaskValue> LineSpanConsumer for AlphaMask<T> {
+    fn start_line(&mut self, idx: usize) {
+        self.begin_row(idx as i64);
+    }
+
+    fn end_line(&mut self) {
+        self.finish_row();
+    }
+
+    fn put_weighted(&mut self, idx: usize, weight: f32) {
+        self.add_coverage(idx as i64, weight);
+    }
+
+    fn put_solid_span(&mut self, start_idx: usize, end_idx: usize) {
+        self.cover_span(start_idx as i64, end_idx as i64);
+    }
+}
+
+/// Adds coverage to a mask, the indices are relative to the origin of the mask.
+struct LocalFiller<'a, T: MaskValue>(&'a mut AlphaMask<T>);
+
+impl<'a, T: MaskValue> LineSpanConsumer for LocalFiller<'a, T> {
+    fn start_line(&mut self, idx: usize) {
+        self.0.begin_row(idx as i64 + self.0.bounds.min.y as i64);
+    }
+
+    fn end_line(&mut self) {
+        self.0.finish_row();
+    }
+
+    fn put_weighted(&mut self, idx: usize, weight: f32) {
+        self.0.add_coverage(idx as i64 + self.0.bounds.min.x as i64, weight);
+    }
+
+    fn put_solid_span(&mut self, start_idx: usize, end_idx: usize) {
+        let min = self.0.bounds.min.x as i64;
+        self.0.cover_span(start_idx as i64 + min, end_idx as i64 + min);
+    }
+}
+
+/// The area of the pixel `(x, y)` inside the rectangle.
+fn rect_coverage(rect: &F64Rect, x: i32, y: i32) -> f32 {
+    let (x, y) = (x as f64, y as f64);
+    let width = (rect.max.x.min(x + 1.0) - rect.min.x.max(x)).max(0.0);
+    let height = (rect.max.y.min(y + 1.0) - rect.min.y.max(y)).max(0.0);
+    (width * height) as f32
+}
@@ -0,0 +1,657 @@
+/*!
+Tiled rasterization, to render large images on several threads.
+
+A [`TiledRasterizer`] records the draws of an image and bins them into square tiles, 64 × 64 pixels
+by default: each tile keeps the list of the draws whose bounds overlap it. The edges of the filled
+polylines are clipped to the image when they are recorded, and each tile also keeps the edges crossing
+it. Rendering then replays the draws of each tile in the recorded order: the coverage of a draw is
+computed into a [`TileCoverage`], and its color is blended into the covered pixels with the
+[blend mode](crate::base::blending) of its paint. The rows of tiles are shared among the threads, each
+row is rendered from left to right.
+
+A fill only walks the edges crossing the tile. As in the
+[sparse strips](super::algorithms::polygon_filling::sparse_strips) filler, the edges on the left of the
+tile are summed up by a state carried from the tile on its left: the areas accumulated along each row
+of pixels with [`AntialiasMode::Exact`](crate::base::defs::antialiasing::AntialiasMode::Exact), and the
+winding number of each row of samples with the aliased and the supersampling modes. The other draws,
+see [`TiledRasterizer::record`], are rasterized with the tile as clip rectangle.
+
+Each pixel is only written by its tile, with the draws in the recorded order, and the coverage of a
+fill is computed with the same operations whatever the tiles: the output depends neither on the number
+of threads nor on the tile size, the image is the one rendered on one thread with a single tile covering
+it, bit for bit. The recorded rasterizers keep this as long as they give the same coverage to a pixel
+whatever the clip rectangle.
+
+This module requires the `tiled` feature.
+*/
+
+use std::mem;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+use nalgebra::Vector2;
+
+use crate::base::defs::{
+    antialiasing::AntialiasMode, colors::default_color_types::RgbaFColor, fill_rule::FillRule, linalg::*, rect::Rect,
+};
+use crate::base::paint::{Ink, Paint};
+use crate::base::shapes::polyline::BrokenPolylineCommand;
+
+use super::algorithms::polygon_filling::{
+    accumulation::{add_row_areas, area_coverage, clip_edge},
+    for_each_edge,
+    scanline::LineSpanConsumer,
+};
+
+/// The default width and height of the tiles, in pixels.
+pub const DEFAULT_TILE_SIZE: usize = 64;
+
+/// The rasterizer of a recorded draw, called with the clip rectangle of a tile.
+type Rasterize = dyn Fn(&Rect<usize>, &mut TileCoverage) + Send + Sync;
+
+/// The implementation of a blend mode.
+type Blend = fn(&RgbaFColor, &mut RgbaFColor);
+
+/// A recorded draw.
+struct Draw {
+    coverage: DrawCoverage,
+    color: RgbaFColor,
+    blend: Blend,
+}
+
+/// How the coverage of a recorded draw is computed.
+enum DrawCoverage {
+    Fill(Fill),
+    Rasterize(Box<Rasterize>),
+}
+
+/// A recorded polyline fill.
+struct Fill {
+    /// The edges clipped to the image, in the order of the polyline.
+    edges: Vec<Edge>,
+    fill_rule: FillRule,
+    /// The samples per pixel along `x` and `y`, `None` for the exact area coverage.
+    samples: Option<(usize, usize)>,
+    /// The pixels that the edges can cover, `max` is exclusive.
+    bounds: Rect<usize>,
+}
+
+/// An edge of a fill, from top to bottom.
+struct Edge {
+    top: Vec2f64,
+    bottom: Vec2f64,
+    dxdy: f64,
+    /// `1` if the edge goes down, `-1` if it goes up.
+    winding: i32,
+}
+
+impl Edge {
+    fn new(start: Vec2f64, end: Vec2f64) -> Self {
+        let (winding, top, bottom) = if start.y < end.y { (1, start, end) } else { (-1, end, start) };
+        Self { top, bottom, dxdy: (bottom.x - top.x) / (bottom.y - top.y), winding }
+    }
+
+    /// The abscissa of the edge at `y`, which doesn't depend on the tile.
+    fn x_at(&self, y: f64) -> f64 {
+        if y <= self.top.y {
+            self.top.x
+        } else if y >= self.bottom.y {
+            self.bottom.x
+        } else {
+            // the rounding errors must not move the edge out of its bounds
+            let x = self.top.x + (y - self.top.y) * self.dxdy;
+            x.clamp(self.top.x.min(self.bottom.x), self.top.x.max(self.bottom.x))
+        }
+    }
+}
+
+/// The draws overlapping a tile.
+#[derive(Clone, Default)]
+struct Bin {
+    draws: Vec<BinnedDraw>,
+    /// The indices of the edges of the fills crossing the tile, draw by draw.
+    edges: Vec<usize>,
+}
+
+/// A draw overlapping a tile.
+#[derive(Clone)]
+struct BinnedDraw {
+    index: usize,
+    /// The offset of the state of the fill carried along the row of tiles.
+    carry: usize,
+    /// The range of the edges of the fill crossing the tile in [`Bin::edges`].
+    edges: Range<usize>,
+}
+
+/// Records draws binned into tiles and renders them in parallel, see the [module](self) documentation.
+///
+/// The image depends neither on the number of threads nor on the tile size.
+pub struct TiledRasterizer {
+    width: usize,
+    height: usize,
+    tile_size: usize,
+    /// The number of columns and rows of tiles.
+    tiles: Vector2<usize>,
+    draws: Vec<Draw>,
+    /// The draws overlapping each tile, row by row.
+    bins: Vec<Bin>,
+    /// The length of the states of the fills carried along each row of tiles.
+    carries: Vec<usize>,
+}
+
+impl TiledRasterizer {
+    /// Creates a rasterizer for an image of `width` × `height` pixels, with the default tile size.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::with_tile_size(width, height, DEFAULT_TILE_SIZE)
+    }
+
+    /// Creates a rasterizer for an image of `width` × `height` pixels, with square tiles of
+    /// `tile_size` pixels (at least one).
+    pub fn with_tile_size(width: usize, height: usize, tile_size: usize) -> Self {
+        let tile_size = tile_size.max(1);
+        let tiles = Vector2::new(width.div_ceil(tile_size), height.div_ceil(tile_size));
+        Self {
+            width,
+            height,
+            tile_size,
+            tiles,
+            draws: Vec::new(),
+            bins: vec![Bin::default(); tiles.x * tiles.y],
+            carries: vec![0; tiles.y],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    /// The number of recorded draws.
+    pub fn draw_count(&self) -> usize {
+        self.draws.len()
+    }
+
+    /// Removes the recorded draws.
+    pub fn clear(&mut self) {
+        self.draws.clear();
+        for bin in &mut self.bins {
+            bin.draws.clear();
+            bin.edges.clear();
+        }
+        self.carries.fill(0);
+    }
+
+    /// Records the fill of a polyline in device coordinates, the contours are implicitly closed.
+    ///
+    /// The polyline is filled with the antialias mode, the color and the blend mode of the paint.
+    /// Nothing is recorded with [`Ink::None`] and [`Ink::Shader`], that is not supported yet, or with a
+    /// blend mode without [implementation](crate::base::blending::BlendMode::implementation).
+    ///
+    /// The edges are clipped to the image and binned into the tiles they cross, the coverage doesn't
+    /// depend on the tile size.
+    pub fn fill_polyline(&mut self, commands: &[BrokenPolylineCommand], fill_rule: FillRule, paint: &Paint<RgbaFColor>) {
+        let Some((color, blend)) = color_and_blend(paint) else {
+            return;
+        };
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        // the parts of the edges on the left or on the right of the image are moved onto its sides
+        let (min, max) = (Vec2f64::zeros(), Vec2f64::new(self.width as f64, self.height as f64));
+        let mut pieces = Vec::new();
+        for_each_edge(commands, |start, end| clip_edge(start, end, &min, &max, &mut pieces));
+        let edges: Vec<Edge> = pieces
+            .into_iter()
+            .filter(|(start, end)| start.y != end.y)
+            .map(|(start, end)| Edge::new(start, end))
+            .collect();
+        let (low, high) = edges
+            .iter()
+            .fold((max, min), |(low, high), edge| (low.inf(&edge.top).inf(&edge.bottom), high.sup(&edge.top).sup(&edge.bottom)));
+        let bounds = Rect::new(
+            Vector2::new(low.x.floor() as usize, low.y.floor() as usize),
+            Vector2::new(high.x.ceil() as usize, high.y.ceil() as usize),
+        );
+        let columns = self.tile_range(bounds.min.x, bounds.max.x, self.tiles.x);
+        let rows = self.tile_range(bounds.min.y, bounds.max.y, self.tiles.y);
+        if columns.is_empty() || rows.is_empty() {
+            return;
+        }
+
+        let samples = match paint.antialias_mode {
+            AntialiasMode::None => Some((1, 1)),
+            AntialiasMode::Exact => None,
+            AntialiasMode::SubPixel { nx, ny } => Some((nx.max(1) as usize, ny.max(1) as usize)),
+        };
+        let index = self.draws.len();
+        for row in rows.clone() {
+            // a row of pixels per row of the tile, or a row of samples per row of samples
+            let carry = self.carries[row];
+            self.carries[row] += self.tile_size * samples.map_or(1, |(_, ny)| ny);
+            for column in columns.clone() {
+                let bin = &mut self.bins[row * self.tiles.x + column];
+                let start = bin.edges.len();
+                bin.draws.push(BinnedDraw { index, carry, edges: start..start });
+            }
+        }
+
+        let tile_size = self.tile_size;
+        for (edge_index, edge) in edges.iter().enumerate() {
+            let first_row = edge.top.y as usize / tile_size;
+            let last_row = (edge.bottom.y.ceil() as usize).div_ceil(tile_size).min(rows.end);
+            for row in first_row..last_row {
+                let top = edge.top.y.max((row * tile_size) as f64);
+                let bottom = edge.bottom.y.min(((row + 1) * tile_size) as f64);
+                let (x0, x1) = (edge.x_at(top), edge.x_at(bottom));
+                // the pixels crossed by the edge in the row of tiles, the areas are also added to the
+                // pixel on their right, with a pixel of margin for the rounding
+                let first_column = ((x0.min(x1).floor() as usize).saturating_sub(1) / tile_size).max(columns.start);
+                let last_column = (x0.max(x1).ceil() as usize + 2).div_ceil(tile_size).min(columns.end);
+                for column in first_column..last_column {
+                    let bin = &mut self.bins[row * self.tiles.x + column];
+                    bin.edges.push(edge_index);
+                    if let Some(draw) = bin.draws.last_mut() {
+                        draw.edges.end = bin.edges.len();
+                    }
+                }
+            }
+        }
+
+        self.draws.push(Draw { coverage: DrawCoverage::Fill(Fill { edges, fill_rule, samples, bounds }), color, blend });
+    }
+
+    /// Records a draw rasterized by `rasterize`, with the color and the blend mode of the paint.
+    ///
+    /// `rasterize` is called for each tile overlapping `bounds` (`max` is exclusive), with the pixels of
+    /// the tile as clip rectangle (`max` is exclusive). It passes their coverage to the consumer in
+    /// device coordinates, the coverage of the pixels outside the clip rectangle is ignored. Nothing is
+    /// recorded with the inks and the blend modes that [`fill_polyline`](Self::fill_polyline) ignores.
+    pub fn record(
+        &mut self,
+        bounds: Rect<i32>,
+        paint: &Paint<RgbaFColor>,
+        rasterize: impl Fn(&Rect<usize>, &mut TileCoverage) + Send + Sync + 'static,
+    ) {
+        let Some((color, blend)) = color_and_blend(paint) else {
+            return;
+        };
+
+        // the tiles overlapping the bounds, inside the image
+        let inside = |min: i32, max: i32, size: usize| ((min.max(0) as usize).min(size), (max.max(0) as usize).min(size));
+        let (left, right) = inside(bounds.min.x, bounds.max.x, self.width);
+        let (top, bottom) = inside(bounds.min.y, bounds.max.y, self.height);
+        let columns = self.tile_range(left, right, self.tiles.x);
+        let rows = self.tile_range(top, bottom, self.tiles.y);
+        if columns.is_empty() || rows.is_empty() {
+            return;
+        }
+
+        let index = self.draws.len();
+        self.draws.push(Draw { coverage: DrawCoverage::Rasterize(Box::new(rasterize)), color, blend });
+        for row in rows {
+            for column in columns.clone() {
+                self.bins[row * self.tiles.x + column].draws.push(BinnedDraw { index, carry: 0, edges: 0..0 });
+            }
+        }
+    }
+
+    /// The tiles overlapping the pixels `min..max` along an axis with `count` tiles.
+    fn tile_range(&self, min: usize, max: usize, count: usize) -> Range<usize> {
+        if min >= max {
+            return 0..0;
+        }
+        min / self.tile_size..max.div_ceil(self.tile_size).min(count)
+    }
+
+    /// Renders the recorded draws over `target`, the pixels of the image row by row, on as many
+    /// threads as available.
+    ///
+    /// # Panics
+    /// Panics if the length of `target` is not the number of pixels of the image.
+    pub fn render(&self, target: &mut [RgbaFColor]) {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        self.render_with_threads(target, threads);
+    }
+
+    /// Renders the recorded draws over `target` on at most `threads` threads, on the calling thread if
+    /// `threads` is at most one.
+    ///
+    /// # Panics
+    /// Panics if the length of `target` is not the number of pixels of the image.
+    pub fn render_with_threads(&self, target: &mut [RgbaFColor], threads: usize) {
+        assert_eq!(target.len(), self.width * self.height, "the target doesn't have the size of the image");
+        if target.is_empty() {
+            return;
+        }
+
+        // the rows of tiles are contiguous in the target
+        let mut bands = target.chunks_mut(self.width * self.tile_size).enumerate();
+        let threads = threads.min(self.tiles.y);
+        if threads <= 1 {
+            let mut coverage = TileCoverage::new(self.tile_size);
+            for (row, band) in bands {
+                self.render_band(row, band, &mut coverage);
+            }
+            return;
+        }
+
+        let bands = Mutex::new(&mut bands);
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    let mut coverage = TileCoverage::new(self.tile_size);
+                    loop {
+                        let next = bands.lock().unwrap_or_else(PoisonError::into_inner).next();
+                        let Some((row, band)) = next else {
+                            break;
+                        };
+                        self.render_band(row, band, &mut coverage);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Renders a row of tiles, `band` are its pixels.
+    fn render_band(&self, row: usize, band: &mut [RgbaFColor], coverage: &mut TileCoverage) {
+        let top = row * self.tile_size;
+        coverage.carries.clear();
+        coverage.carries.resize(self.carries[row], 0.0);
+        for column in 0..self.tiles.x {
+            let left = column * self.tile_size;
+            let tile = Rect::new(
+                Vector2::new(left, top),
+                Vector2::new((left + self.tile_size).min(self.width), (top + self.tile_size).min(self.height)),
+            );
+            let bin = &self.bins[row * self.tiles.x + column];
+            for binned in &bin.draws {
+                let draw = &self.draws[binned.index];
+                coverage.reset(tile);
+                match &draw.coverage {
+                    DrawCoverage::Fill(fill) => coverage.fill(fill, &bin.edges[binned.edges.clone()], binned.carry),
+                    DrawCoverage::Rasterize(rasterize) => rasterize(&tile, coverage),
+                }
+                coverage.composite(draw, band, self.width, top);
+            }
+        }
+    }
+}
+
+/// The coverage of the pixels of a tile, passed to the rasterizers of the recorded draws.
+///
+/// The indices are device coordinates, the weights add up to the coverage of the pixels and the solid
+/// spans cover them entirely. The pixels outside the tile are ignored.
+pub struct TileCoverage {
+    tile: Rect<usize>,
+    /// The coverage of the pixels, with a stride of the tile size.
+    data: Vec<f32>,
+    stride: usize,
+    /// The current line, in device coordinates.
+    line: usize,
+    /// The range of lines with coverage, relative to the tile.
+    dirty: (usize, usize),
+    /// The states of the fills carried along the current row of tiles.
+    carries: Vec<f32>,
+    /// The accumulated areas of the exact fills, row by row.
+    cells: Vec<f32>,
+    /// The abscissas and the windings of the edges crossing a row of samples.
+    crossings: Vec<(f64, i32)>,
+    /// The number of covered samples of the pixels of a row.
+    counts: Vec<usize>,
+}
+
+impl TileCoverage {
+    fn new(tile_size: usize) -> Self {
+        Self {
+            tile: Rect::new(Vector2::zeros(), Vector2::zeros()),
+            data: vec![0.0; tile_size * tile_size],
+            stride: tile_size,
+            line: 0,
+            dirty: (0, 0),
+            carries: Vec::new(),
+            cells: Vec::new(),
+            crossings: Vec::new(),
+            counts: Vec::new(),
+        }
+    }
+
+    /// The pixels of the tile, `max` is exclusive.
+    pub fn tile(&self) -> Rect<usize> {
+        self.tile
+    }
+
+    /// Prepares the coverage of another draw, the buffer is already clear.
+    fn reset(&mut self, tile: Rect<usize>) {
+        self.tile = tile;
+        self.line = tile.min.y;
+        self.dirty = (usize::MAX, 0);
+    }
+
+    /// The offset of the current line in the buffer, if it is inside the tile.
+    fn line_offset(&mut self) -> Option<usize> {
+        if self.line < self.tile.min.y || self.line >= self.tile.max.y {
+            return None;
+        }
+        let line = self.line - self.tile.min.y;
+        self.dirty = (self.dirty.0.min(line), self.dirty.1.max(line + 1));
+        Some(line * self.stride)
+    }
+
+    /// Computes the coverage of a fill in the tile from the `edges` crossing it, `carry` is the offset of
+    /// the state carried from the tile on the left, which is updated for the tile on the right.
+    fn fill(&mut self, fill: &Fill, edges: &[usize], carry: usize) {
+        let columns = self.tile.min.x.max(fill.bounds.min.x)..self.tile.max.x.min(fill.bounds.max.x);
+        let rows = self.tile.min.y.max(fill.bounds.min.y)..self.tile.max.y.min(fill.bounds.max.y);
+        if columns.is_empty() || rows.is_empty() {
+            return;
+        }
+        let mut carries = mem::take(&mut self.carries);
+        match fill.samples {
+            None => self.fill_areas(fill, edges, &mut carries[carry..], columns, rows),
+            Some(samples) => self.fill_samples(fill, edges, &mut carries[carry..], samples, columns, rows),
+        }
+        self.carries = carries;
+    }
+
+    /// The exact area coverage, as in the [accumulation](super::algorithms::polygon_filling::accumulation) filler: the areas accumulated
+    /// along each row of pixels are carried.
+    fn fill_areas(&mut self, fill: &Fill, edges: &[usize], carries: &mut [f32], columns: Range<usize>, rows: Range<usize>) {
+        let width = columns.len();
+        let mut cells = mem::take(&mut self.cells);
+        cells.clear();
+        cells.resize(width * rows.len(), 0.0);
+        for edge in edges.iter().map(|&index| &fill.edges[index]) {
+            let first_row = (edge.top.y.floor() as usize).max(rows.start);
+            let last_row = (edge.bottom.y.ceil() as usize).min(rows.end);
+            for y in first_row..last_row {
+                let line = &mut cells[(y - rows.start) * width..][..width];
+                let (top, bottom) = ((y as f64).max(edge.top.y), ((y + 1) as f64).min(edge.bottom.y));
+                let d = ((bottom - top) * edge.winding as f64) as f32;
+                // the areas of the other tiles are added by them
+                add_row_areas(edge.x_at(top), edge.x_at(bottom), d, |x, area| {
+                    if let Some(cell) = x.checked_sub(columns.start).and_then(|x| line.get_mut(x)) {
+                        *cell += area;
+                    }
+                });
+            }
+        }
+
+        for (y, line) in rows.zip(cells.chunks_exact(width)) {
+            self.start_line(y);
+            let accumulated = &mut carries[y - self.tile.min.y];
+            let mut solid_start: Option<usize> = None;
+            for (x, cell) in columns.clone().zip(line) {
+                *accumulated += cell;
+                let coverage = area_coverage(*accumulated, fill.fill_rule);
+                if coverage >= 1.0 - 1e-6 {
+                    solid_start.get_or_insert(x);
+                    continue;
+                }
+                if let Some(start) = solid_start.take() {
+                    self.put_solid_span(start, x);
+                }
+                if coverage > 1e-6 {
+                    self.put_weighted(x, coverage);
+                }
+            }
+            if let Some(start) = solid_start {
+                self.put_solid_span(start, columns.end);
+            }
+        }
+        self.cells = cells;
+    }
+
+    /// The coverage of `nx` × `ny` samples per pixel, as in the [supersampling](super::algorithms::polygon_filling::supersampling) filler:
+    /// the winding numbers of the rows of samples are carried.
+    fn fill_samples(
+        &mut self,
+        fill: &Fill,
+        edges: &[usize],
+        carries: &mut [f32],
+        (nx, ny): (usize, usize),
+        columns: Range<usize>,
+        rows: Range<usize>,
+    ) {
+        let mut counts = mem::take(&mut self.counts);
+        let mut crossings = mem::take(&mut self.crossings);
+        let (left, right) = (columns.start as f64, columns.end as f64);
+        // the sample `k` of a row is at `(k + 0.5) / nx`, the crossings on the left are before it
+        let to_sample = |x: f64| ((x * nx as f64 - 0.5).ceil().max(0.0) as usize).clamp(columns.start * nx, columns.end * nx);
+
+        for y in rows {
+            counts.clear();
+            counts.resize(columns.len(), 0);
+            for row in 0..ny {
+                let sample_y = y as f64 + (row as f64 + 0.5) / ny as f64;
+                // each crossing is counted by the tile it is in
+                crossings.clear();
+                crossings.extend(
+                    edges
+                        .iter()
+                        .map(|&index| &fill.edges[index])
+                        .filter(|edge| edge.top.y <= sample_y && sample_y < edge.bottom.y)
+                        .map(|edge| (edge.x_at(sample_y), edge.winding))
+                        .filter(|(x, _)| *x >= left && *x < right),
+                );
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let winding = &mut carries[(y - self.tile.min.y) * ny + row];
+                let mut from = left;
+                for &(x, crossing) in crossings.iter().chain([(right, 0)].iter()) {
+                    if fill.fill_rule.is_inside(*winding as i32) {
+                        let (mut sample, end) = (to_sample(from), to_sample(x));
+                        while sample < end {
+                            let next = ((sample / nx + 1) * nx).min(end);
+                            counts[sample / nx - columns.start] += next - sample;
+                            sample = next;
+                        }
+                    }
+                    *winding += crossing as f32;
+                    from = x;
+                }
+            }
+
+            self.start_line(y);
+            let mut solid_start: Option<usize> = None;
+            for (x, &count) in columns.clone().zip(&counts) {
+                if count == nx * ny {
+                    solid_start.get_or_insert(x);
+                    continue;
+                }
+                if let Some(start) = solid_start.take() {
+                    self.put_solid_span(start, x);
+                }
+                if count > 0 {
+                    self.put_weighted(x, count as f32 / (nx * ny) as f32);
+                }
+            }
+            if let Some(start) = solid_start {
+                self.put_solid_span(start, columns.end);
+            }
+        }
+        self.counts = counts;
+        self.crossings = crossings;
+    }
+
+    /// Blends the color of the draw into the covered pixels of `band`, the rows of pixels from `top`,
+    /// and clears the coverage.
+    fn composite(&mut self, draw: &Draw, band: &mut [RgbaFColor], width: usize, top: usize) {
+        let columns = self.tile.min.x..self.tile.max.x;
+        for line in self.dirty.0..self.dirty.1 {
+            let coverage = &mut self.data[line * self.stride..][..columns.len()];
+            let pixels = &mut band[(self.tile.min.y + line - top) * width..][columns.clone()];
+            for (pixel, coverage) in pixels.iter_mut().zip(coverage) {
+                if *coverage > 0.0 {
+                    blend_with_coverage(draw, *coverage, pixel);
+                    *coverage = 0.0;
+                }
+            }
+        }
+    }
+}
+
+impl LineSpanConsumer for TileCoverage {
+    fn start_line(&mut self, idx: usize) {
+        self.line = idx;
+    }
+
+    fn put_weighted(&mut self, idx: usize, weight: f32) {
+        if idx < self.tile.min.x || idx >= self.tile.max.x {
+            return;
+        }
+        if let Some(offset) = self.line_offset() {
+            let value = &mut self.data[offset + idx - self.tile.min.x];
+            *value = (*value + weight).clamp(0.0, 1.0);
+        }
+    }
+
+    fn put_solid_span(&mut self, start_idx: usize, end_idx: usize) {
+        let start = start_idx.max(self.tile.min.x);
+        let end = end_idx.min(self.tile.max.x);
+        if start >= end {
+            return;
+        }
+        if let Some(offset) = self.line_offset() {
+            self.data[offset + start - self.tile.min.x..offset + end - self.tile.min.x].fill(1.0);
+        }
+    }
+}
+
+/// The color and the blend function of a paint, if it can be drawn.
+fn color_and_blend(paint: &Paint<RgbaFColor>) -> Option<(RgbaFColor, Blend)> {
+    let Ink::Color(color) = paint.ink else {
+        return None;
+    };
+    Some((color, paint.blend_mode.implementation()?))
+}
+
+/// Blends the color of a draw into a pixel, the result is interpolated with the premultiplied colors
+/// between the pixel and the fully covered result.
+fn blend_with_coverage(draw: &Draw, coverage: f32, pixel: &mut RgbaFColor) {
+    let mut blended = *pixel;
+    (draw.blend)(&draw.color, &mut blended);
+    if coverage >= 1.0 {
+        *pixel = blended;
+        return;
+    }
+    let alpha = pixel.a() + (blended.a() - pixel.a()) * coverage;
+    let mix = |pixel_channel: f32, blended_channel: f32| {
+        let premultiplied = pixel_channel * pixel.a();
+        (premultiplied + (blended_channel * blended.a() - premultiplied) * coverage) / alpha
+    };
+    *pixel = if alpha > 0.0 {
+        RgbaFColor::new(mix(pixel.r(), blended.r()), mix(pixel.g(), blended.g()), mix(pixel.b(), blended.b()), alpha)
+    } else {
+        RgbaFColor::TRANSPARENT
+    };
+}
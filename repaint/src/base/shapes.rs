@@ -1,6 +1,7 @@
 use self::{polyline::BrokenPolylineCommand, path::PathCommand};
 
-use super::defs::{rect::F64Rect, linalg::Vec2f64};
+use super::defs::{rect::{F64Rect, TlTrBlBr}, linalg::Vec2f64};
+use crate::painter::methods::rect_to_path;
 
 pub mod path;
 pub mod polyline;
@@ -47,7 +48,7 @@ pub trait Shape: Clone + std::fmt::Debug + Send + Sync + 'static {
 }
 
 impl Shape for F64Rect {
-    type Iter = std::array::IntoIter<PathCommand, 4>;
+    type Iter = std::array::IntoIter<PathCommand, 5>;
 
     fn bounding_box(&self) -> F64Rect {
         *self
@@ -58,18 +59,39 @@ impl Shape for F64Rect {
     }
 
     fn to_path_iter(&self) -> Self::Iter {
-        todo!()
+        rect_to_path(*self).into_iter()
     }
 }
 
 
+/// The basic shapes, that backends might be able to draw natively.
+///
+/// Angles are in radians and grow from the positive x axis towards the positive y axis,
+/// i.e. clockwise on screen when the y axis points down.
 #[derive(Clone, Debug, PartialEq)]
 pub enum BasicShape {
     Rect(F64Rect),
     Path(Vec<path::PathCommand>),
     Polyline(Vec<BrokenPolylineCommand>),
     Circle{ center: Vec2f64, radius: f64 },
-    // TODO ellipse, rounded rect, ...
+
+    /// An axis-aligned ellipse.
+    Ellipse{ center: Vec2f64, radii: Vec2f64 },
+
+    /// A rectangle with elliptical corners.
+    ///
+    /// If the radii of two adjacent corners do not fit in a side, all the radii are scaled down
+    /// as in CSS. A corner with a zero radius component is square.
+    RoundedRect{ rect: F64Rect, radii: TlTrBlBr<Vec2f64> },
+
+    /// An open elliptical arc, starting at `start_angle` and spanning `sweep_angle`.
+    Arc{ center: Vec2f64, radii: Vec2f64, start_angle: f64, sweep_angle: f64 },
+
+    /// An elliptical arc closed through the center, i.e. a pie slice.
+    Pie{ center: Vec2f64, radii: Vec2f64, start_angle: f64, sweep_angle: f64 },
+
+    /// An elliptical arc closed by the segment between its endpoints.
+    Chord{ center: Vec2f64, radii: Vec2f64, start_angle: f64, sweep_angle: f64 },
     // TODO ...
 }
 
@@ -80,8 +102,22 @@ impl Shape for BasicShape {
         match self {
             BasicShape::Rect(rect) => *rect,
             BasicShape::Path(_path) => todo!(),
-            BasicShape::Polyline(_polyline) => todo!(),
-            BasicShape::Circle{ center: _, radius: _ } => todo!(),
+            BasicShape::Polyline(polyline) => {
+                polyline::polyline_bounds(polyline).unwrap_or_else(|| F64Rect::new(Vec2f64::zeros(), Vec2f64::zeros()))
+            }
+            BasicShape::Circle{ center, radius } => ellipse_bounds(center, &Vec2f64::new(*radius, *radius)),
+            BasicShape::Ellipse{ center, radii } => ellipse_bounds(center, radii),
+            BasicShape::RoundedRect{ rect, radii: _ } => *rect,
+            BasicShape::Arc{ center, radii, start_angle, sweep_angle }
+            | BasicShape::Chord{ center, radii, start_angle, sweep_angle } => {
+                arc_bounds(center, radii, *start_angle, *sweep_angle)
+            }
+            BasicShape::Pie{ center, radii, start_angle, sweep_angle } => {
+                let mut bounds = arc_bounds(center, radii, *start_angle, *sweep_angle);
+                bounds.min = bounds.min.inf(center);
+                bounds.max = bounds.max.sup(center);
+                bounds
+            }
         }
     }
 
@@ -89,8 +125,15 @@ impl Shape for BasicShape {
         match self {
             BasicShape::Rect(rect) => *rect,
             BasicShape::Path(_path) => todo!(),
-            BasicShape::Polyline(_polyline) => todo!(),
-            BasicShape::Circle{ center: _, radius: _ } => todo!()
+            BasicShape::Polyline(polyline) => {
+                polyline::polyline_bounds(polyline).unwrap_or_else(|| F64Rect::new(Vec2f64::zeros(), Vec2f64::zeros()))
+            }
+            BasicShape::Circle{ center, radius } => ellipse_bounds(center, &Vec2f64::new(*radius, *radius)),
+            BasicShape::Ellipse{ center, radii }
+            | BasicShape::Arc{ center, radii, .. }
+            | BasicShape::Pie{ center, radii, .. }
+            | BasicShape::Chord{ center, radii, .. } => ellipse_bounds(center, radii),
+            BasicShape::RoundedRect{ rect, radii: _ } => *rect,
         }
     }
 
@@ -104,30 +147,167 @@ impl Shape for BasicShape {
 
     fn to_path_iter(&self) -> Self::Iter {
         match self {
-            BasicShape::Rect(rect) => {
-                let mut path = Vec::with_capacity(4);
-                path.push(PathCommand::MoveTo(rect.min));
-                path.push(PathCommand::LineTo(Vec2f64::new(rect.max.x, rect.min.y)));
-                path.push(PathCommand::LineTo(rect.max));
-                path.push(PathCommand::LineTo(Vec2f64::new(rect.min.x, rect.max.y)));
-                path.push(PathCommand::ClosePath);
-                path.into_iter()
-            }
+            BasicShape::Rect(rect) => rect_to_path(*rect).to_vec().into_iter(),
             BasicShape::Path(path) => path.clone().into_iter(),
-            BasicShape::Polyline(_polyline) => todo!(), // TODO
+            BasicShape::Polyline(polyline) => {
+                polyline.iter().map(|command| PathCommand::from(*command)).collect::<Vec<_>>().into_iter()
+            }
             BasicShape::Circle{ center, radius } => {
-                let path = vec![
-                    PathCommand::MoveTo(Vec2f64::new(center.x - radius, center.y)),
-                    PathCommand::EllipticalArcTo {
-                        radii: Vec2f64::new(*radius, *radius),
-                        x_axis_rotation: 0.0,
-                        large_arc_flag: false,
-                        sweep_flag: true,
-                        end_pt: Vec2f64::new(center.x + radius, center.y),
-                    },
+                ellipse_path(center, &Vec2f64::new(*radius, *radius)).into_iter()
+            }
+            BasicShape::Ellipse{ center, radii } => ellipse_path(center, radii).into_iter(),
+            BasicShape::RoundedRect{ rect, radii } => rounded_rect_path(rect, radii).into_iter(),
+            BasicShape::Arc{ center, radii, start_angle, sweep_angle } => {
+                let mut path = vec![PathCommand::MoveTo(ellipse_point(center, radii, *start_angle))];
+                push_arc(&mut path, center, radii, *start_angle, *sweep_angle);
+                path.into_iter()
+            }
+            BasicShape::Pie{ center, radii, start_angle, sweep_angle } => {
+                let mut path = vec![
+                    PathCommand::MoveTo(*center),
+                    PathCommand::LineTo(ellipse_point(center, radii, *start_angle)),
                 ];
+                push_arc(&mut path, center, radii, *start_angle, *sweep_angle);
+                path.push(PathCommand::ClosePath);
                 path.into_iter()
             }
+            BasicShape::Chord{ center, radii, start_angle, sweep_angle } => {
+                let mut path = vec![PathCommand::MoveTo(ellipse_point(center, radii, *start_angle))];
+                push_arc(&mut path, center, radii, *start_angle, *sweep_angle);
+                path.push(PathCommand::ClosePath);
+                path.into_iter()
+            }
+        }
+    }
+}
+
+/// The point of an axis-aligned ellipse at the given angle.
+fn ellipse_point(center: &Vec2f64, radii: &Vec2f64, angle: f64) -> Vec2f64 {
+    Vec2f64::new(center.x + radii.x * angle.cos(), center.y + radii.y * angle.sin())
+}
+
+/// The bounding box of an axis-aligned ellipse.
+fn ellipse_bounds(center: &Vec2f64, radii: &Vec2f64) -> F64Rect {
+    let radii = radii.abs();
+    F64Rect::new(center - radii, center + radii)
+}
+
+/// The exact bounding box of an elliptical arc.
+///
+/// The sweep is clamped to a full turn like the path of the arc, the bounds are NaN if the start
+/// angle is not finite or the sweep angle is NaN.
+fn arc_bounds(center: &Vec2f64, radii: &Vec2f64, start_angle: f64, sweep_angle: f64) -> F64Rect {
+    use std::f64::consts::{FRAC_PI_2, TAU};
+
+    if !start_angle.is_finite() || sweep_angle.is_nan() {
+        return F64Rect::new(Vec2f64::repeat(f64::NAN), Vec2f64::repeat(f64::NAN));
+    }
+    if sweep_angle.abs() >= TAU {
+        return ellipse_bounds(center, radii);
+    }
+    let start = ellipse_point(center, radii, start_angle);
+    let end = ellipse_point(center, radii, start_angle + sweep_angle);
+    let mut bounds = F64Rect::new(start.inf(&end), start.sup(&end));
+
+    // the extremes are at the multiples of 90 degrees inside the arc
+    let (from, to) = if sweep_angle >= 0.0 {
+        (start_angle, start_angle + sweep_angle)
+    } else {
+        (start_angle + sweep_angle, start_angle)
+    };
+    // an arc shorter than a full turn contains at most 4 of them, the bound keeps the loop short
+    // for the angles so large that `k + 1` is `k`
+    let first = (from / FRAC_PI_2).ceil();
+    for k in 0..4 {
+        let angle = (first + k as f64) * FRAC_PI_2;
+        if angle > to {
+            break;
         }
+        let pt = ellipse_point(center, radii, angle);
+        bounds.min = bounds.min.inf(&pt);
+        bounds.max = bounds.max.sup(&pt);
+    }
+    bounds
+}
+
+/// Appends the commands of an elliptical arc starting at the current point.
+///
+/// The arc is split in pieces spanning at most 90 degrees, so that the endpoint
+/// parameterization of each piece is well conditioned.
+fn push_arc(path: &mut Vec<PathCommand>, center: &Vec2f64, radii: &Vec2f64, start_angle: f64, sweep_angle: f64) {
+    use std::f64::consts::{FRAC_PI_2, TAU};
+
+    let sweep_angle = sweep_angle.clamp(-TAU, TAU);
+    if !sweep_angle.is_finite() || sweep_angle == 0.0 {
+        return;
+    }
+    let pieces = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+    for i in 1..=pieces {
+        let angle = start_angle + sweep_angle * i as f64 / pieces as f64;
+        path.push(PathCommand::EllipticalArcTo {
+            radii: *radii,
+            x_axis_rotation: 0.0,
+            large_arc_flag: false,
+            sweep_flag: sweep_angle > 0.0,
+            end_pt: ellipse_point(center, radii, angle),
+        });
     }
-}
\ No newline at end of file
+}
+
+/// The closed path of an axis-aligned ellipse, starting at angle zero.
+fn ellipse_path(center: &Vec2f64, radii: &Vec2f64) -> Vec<PathCommand> {
+    let mut path = Vec::with_capacity(6);
+    path.push(PathCommand::MoveTo(ellipse_point(center, radii, 0.0)));
+    push_arc(&mut path, center, radii, 0.0, std::f64::consts::TAU);
+    path.push(PathCommand::ClosePath);
+    path
+}
+
+/// Scales the radii of a rounded rectangle so that they fit in the rectangle.
+pub(crate) fn fit_rounded_rect_radii(rect: &F64Rect, radii: &TlTrBlBr<Vec2f64>) -> TlTrBlBr<Vec2f64> {
+    // a corner is square if one of its radii is zero
+    let fix = |r: &Vec2f64| {
+        let r = r.abs();
+        if r.x > 0.0 && r.y > 0.0 { r } else { Vec2f64::zeros() }
+    };
+    let (tl, tr, bl, br) = (fix(&radii.top_left), fix(&radii.top_right), fix(&radii.bottom_left), fix(&radii.bottom_right));
+
+    let width = rect.width().abs();
+    let height = rect.height().abs();
+    let ratio = |side: f64, sum: f64| if sum > side { side / sum } else { 1.0 };
+    let scale = ratio(width, tl.x + tr.x)
+        .min(ratio(width, bl.x + br.x))
+        .min(ratio(height, tl.y + bl.y))
+        .min(ratio(height, tr.y + br.y));
+    TlTrBlBr::new(tl * scale, tr * scale, bl * scale, br * scale)
+}
+
+/// The closed path of a rounded rectangle, clockwise on screen starting from the top side.
+fn rounded_rect_path(rect: &F64Rect, radii: &TlTrBlBr<Vec2f64>) -> Vec<PathCommand> {
+    let TlTrBlBr { top_left: tl, top_right: tr, bottom_left: bl, bottom_right: br } = fit_rounded_rect_radii(rect, radii);
+    let (min, max) = (rect.min.inf(&rect.max), rect.min.sup(&rect.max));
+
+    let mut path = Vec::with_capacity(10);
+    let corner = |path: &mut Vec<PathCommand>, radii: Vec2f64, end_pt: Vec2f64| {
+        if radii != Vec2f64::zeros() {
+            path.push(PathCommand::EllipticalArcTo {
+                radii,
+                x_axis_rotation: 0.0,
+                large_arc_flag: false,
+                sweep_flag: true,
+                end_pt,
+            });
+        }
+    };
+    path.push(PathCommand::MoveTo(Vec2f64::new(min.x + tl.x, min.y)));
+    path.push(PathCommand::LineTo(Vec2f64::new(max.x - tr.x, min.y)));
+    corner(&mut path, tr, Vec2f64::new(max.x, min.y + tr.y));
+    path.push(PathCommand::LineTo(Vec2f64::new(max.x, max.y - br.y)));
+    corner(&mut path, br, Vec2f64::new(max.x - br.x, max.y));
+    path.push(PathCommand::LineTo(Vec2f64::new(min.x + bl.x, max.y)));
+    corner(&mut path, bl, Vec2f64::new(min.x, max.y - bl.y));
+    path.push(PathCommand::LineTo(Vec2f64::new(min.x, min.y + tl.y)));
+    corner(&mut path, tl, Vec2f64::new(min.x + tl.x, min.y));
+    path.push(PathCommand::ClosePath);
+    path
+}
@@ -1,23 +1,14 @@
 
 use crate::{base::defs::linalg::*};
 
-pub struct PathBuilder {
-    pub commands: Vec<PathCommand>,
-}
-
-impl PathBuilder {
-    pub fn new() -> Self {
-        Self {
-            commands: Vec::new(),
-        }
-    }
-
-    pub fn push(&mut self, command: PathCommand) {
-        self.commands.push(command);
-    }
+mod builder;
+pub mod flattening;
+mod measure;
+mod offset;
 
-    // TODO skia-like methods e.g. move_to, line_to, etc.
-}
+pub use builder::PathBuilder;
+pub use measure::{ContourMeasure, PathMeasure};
+pub use offset::{offset_contours, offset_path, OffsetJoin};
 
 /// A command for a path.
 ///
@@ -454,15 +445,138 @@ impl SvgStylePathCommand {
     }
 }
 
+/// Tracks the current point of a path and resolves relative and smooth commands.
+///
+/// Feeding the commands of a path to [`PathCursor::resolve`] in order produces an equivalent path
+/// made only of absolute commands: [`PathCommand::MoveTo`], [`PathCommand::LineTo`],
+/// [`PathCommand::QuadraticBezierCurveTo`], [`PathCommand::CubicBezierTo`],
+/// [`PathCommand::EllipticalArcTo`] and [`PathCommand::ClosePath`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathCursor {
+    /// The current point.
+    pub current_position: Vec2f64,
+    /// The start point of the current sub-path.
+    pub current_polyline_start: Vec2f64,
+    /// The last control point, used by the smooth commands.
+    pub current_control_point: Vec2f64,
+}
+
+impl Default for PathCursor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathCursor {
+    /// Creates a cursor at the origin.
+    pub fn new() -> Self {
+        Self {
+            current_position: Vec2f64::zeros(),
+            current_polyline_start: Vec2f64::zeros(),
+            current_control_point: Vec2f64::zeros(),
+        }
+    }
+
+    /// Resolves a command to its absolute form and advances the cursor.
+    pub fn resolve(&mut self, command: &PathCommand) -> PathCommand {
+        let pos = self.current_position;
+        let reflected = pos + (pos - self.current_control_point);
+        match command {
+            PathCommand::MoveTo(pt) => self.move_to(*pt),
+            PathCommand::MoveToOffset(offset) => self.move_to(pos + offset),
+            PathCommand::LineTo(pt) => self.line_to(*pt),
+            PathCommand::LineToOffset(offset) => self.line_to(pos + offset),
+            PathCommand::HorizontalLineTo(x) => self.line_to(Vec2f64::new(*x, pos.y)),
+            PathCommand::HorizontalLineToOffset(dx) => self.line_to(Vec2f64::new(pos.x + dx, pos.y)),
+            PathCommand::VerticalLineTo(y) => self.line_to(Vec2f64::new(pos.x, *y)),
+            PathCommand::VerticalLineToOffset(dy) => self.line_to(Vec2f64::new(pos.x, pos.y + dy)),
+            PathCommand::ClosePath => {
+                let start = self.current_polyline_start;
+                self.current_position = start;
+                self.current_control_point = start;
+                PathCommand::ClosePath
+            }
+            PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt } => {
+                self.cubic_to(*control_pt_1, *control_pt_2, *end_pt)
+            }
+            PathCommand::CubicBezierToOffset { control_pt_1_offset, control_pt_2_offset, end_pt_offset } => {
+                self.cubic_to(pos + control_pt_1_offset, pos + control_pt_2_offset, pos + end_pt_offset)
+            }
+            PathCommand::SmoothCubicBezierCurveTo { control_pt_2, end_pt } => {
+                self.cubic_to(reflected, *control_pt_2, *end_pt)
+            }
+            PathCommand::SmoothCubicBezierCurveToOffset { control_pt_2_offset, end_pt_offset } => {
+                self.cubic_to(reflected, pos + control_pt_2_offset, pos + end_pt_offset)
+            }
+            PathCommand::QuadraticBezierCurveTo { control_pt, end_pt } => {
+                self.quad_to(*control_pt, *end_pt)
+            }
+            PathCommand::QuadraticBezierCurveToOffset { control_pt_offset, end_pt_offset } => {
+                self.quad_to(pos + control_pt_offset, pos + end_pt_offset)
+            }
+            PathCommand::SmoothQuadraticBezierCurveTo(end_pt) => self.quad_to(reflected, *end_pt),
+            PathCommand::SmoothQuadraticBezierCurveToOffset(end_pt_offset) => {
+                self.quad_to(reflected, pos + end_pt_offset)
+            }
+            PathCommand::EllipticalArcTo { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt } => {
+                self.arc_to(*radii, *x_axis_rotation, *large_arc_flag, *sweep_flag, *end_pt)
+            }
+            PathCommand::EllipticalArcToOffset { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt_offset } => {
+                self.arc_to(*radii, *x_axis_rotation, *large_arc_flag, *sweep_flag, pos + end_pt_offset)
+            }
+        }
+    }
+
+    fn move_to(&mut self, pt: Vec2f64) -> PathCommand {
+        self.current_position = pt;
+        self.current_control_point = pt;
+        self.current_polyline_start = pt;
+        PathCommand::MoveTo(pt)
+    }
+
+    fn line_to(&mut self, pt: Vec2f64) -> PathCommand {
+        self.current_position = pt;
+        self.current_control_point = pt;
+        PathCommand::LineTo(pt)
+    }
+
+    fn quad_to(&mut self, control_pt: Vec2f64, end_pt: Vec2f64) -> PathCommand {
+        self.current_position = end_pt;
+        self.current_control_point = control_pt;
+        PathCommand::QuadraticBezierCurveTo { control_pt, end_pt }
+    }
+
+    fn cubic_to(&mut self, control_pt_1: Vec2f64, control_pt_2: Vec2f64, end_pt: Vec2f64) -> PathCommand {
+        self.current_position = end_pt;
+        self.current_control_point = control_pt_2;
+        PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt }
+    }
+
+    fn arc_to(
+        &mut self,
+        radii: Vec2f64,
+        x_axis_rotation: f64,
+        large_arc_flag: bool,
+        sweep_flag: bool,
+        end_pt: Vec2f64,
+    ) -> PathCommand {
+        self.current_position = end_pt;
+        self.current_control_point = end_pt;
+        PathCommand::EllipticalArcTo { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt }
+    }
+}
+
 /// Some basic discretization utilities.
 ///
 /// This module contains some very simple algorithms for discretizing paths and polylines.
 pub mod discretization {
-    use super::{Mat2f64, Vec2f64};
+    use super::{Mat2f64, PathCursor, Vec2f64};
+    pub use super::flattening::FlatteningMethod;
     use crate::base::defs::rect::F64Rect;
     use crate::base::shapes::{path::PathCommand, polyline::BrokenPolylineCommand};
     use crate::base::transform::Transform2d;
 
+    use std::borrow::Borrow;
     use std::ops::{
         Add,
         //Div
@@ -475,7 +589,13 @@ pub mod discretization {
     /// The discretization algorithm will try to approximate the curve with a polyline
     /// that has a maximum distance between the curve and the polyline of `tolerance` and
     /// a maximum angle between the curve and the polyline of `max_angle`.
+    ///
+    /// Bézier curves can also be flattened analytically, see `flattening`.
+    ///
+    /// The struct may get new fields, start from [`DiscretizationParams::default`] and set the
+    /// fields or use the `with_*` methods.
     #[derive(Clone, Copy, Debug)]
+    #[non_exhaustive]
     pub struct DiscretizationParams {
         /// maximum distance between the curve and the polyline
         pub tolerance: f64,
@@ -488,6 +608,12 @@ pub mod discretization {
         ///
         /// If you don't want to use this feature, set it to `None`.
         pub aoi: Option<F64Rect>,
+
+        /// The algorithm used for quadratic and cubic Bézier curves.
+        ///
+        /// The analytic methods only use `tolerance` and ignore `aoi`, they are used
+        /// only with affine transforms.
+        pub flattening: FlatteningMethod,
     }
 
     impl Default for DiscretizationParams {
@@ -496,39 +622,77 @@ pub mod discretization {
                 tolerance: 0.1,
                 max_angle: 0.1,
                 aoi: None,
+                flattening: FlatteningMethod::Adaptive,
             }
         }
     }
 
+    impl DiscretizationParams {
+        /// Sets the maximum distance between the curve and the polyline.
+        pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+            self.tolerance = tolerance;
+            self
+        }
+
+        /// Sets the maximum angle between the curve and the polyline.
+        pub fn with_max_angle(mut self, max_angle: f64) -> Self {
+            self.max_angle = max_angle;
+            self
+        }
+
+        /// Sets the area of interest, see [`DiscretizationParams::aoi`].
+        pub fn with_aoi(mut self, aoi: Option<F64Rect>) -> Self {
+            self.aoi = aoi;
+            self
+        }
+
+        /// Sets the algorithm used for quadratic and cubic Bézier curves.
+        pub fn with_flattening(mut self, flattening: FlatteningMethod) -> Self {
+            self.flattening = flattening;
+            self
+        }
+    }
+
     
 
     /// This is used to perform the discretization of a path.
     ///
+    /// The discretization does not allocate: the path is consumed lazily and each curve is
+    /// flattened by a small state machine, so it can also be used on targets without a heap.
+    ///
+    /// Every point goes through the transform, including the ones of [`PathCommand::MoveTo`], and
+    /// [`PathCommand::ClosePath`] is discretized like a line back to the start of the polyline.
+    ///
     /// ## Example
     /// ```rust
     /// let path_commands = [
     ///    PathCommand::MoveTo(Vec2f64::new(0.0, 0.0)),
     ///    PathCommand::LineTo(Vec2f64::new(1.0, 0.0)),
     /// ];
-    /// let discretizer = PathDiscretizer::new(
-    ///     DiscretizationParams::default(),
-    ///     DiscretizationTransform::Identity
-    /// );
+    /// let transform = Transform2d::Identity;
+    /// let discretizer = PathDiscretizer::new(DiscretizationParams::default(), &transform);
     /// let mut it = path.iter();
     /// for command in discretizer.discretize(&mut it) {
     ///    // do something ...
     /// }
+    /// // or, with any iterator of owned or borrowed commands
+    /// for command in discretizer.discretize_iter(path_commands) {
+    ///    // do something ...
+    /// }
     /// ```
-    pub struct PathDiscretizer {
+    pub struct PathDiscretizer<'t> {
         /// The discretization parameters.
         params: DiscretizationParams,
         /// transform to apply to the curve
-        transform: Transform2d,
+        transform: &'t Transform2d,
     }
 
-    impl PathDiscretizer {
+    impl<'t> PathDiscretizer<'t> {
         /// Creates a new path discretizer for the given parameters and transform.
-        pub fn new(params: DiscretizationParams, transform: Transform2d) -> Self {
+        ///
+        /// The curves are flattened after the transform, so that the tolerance holds in the
+        /// transformed space even for the non-affine transforms.
+        pub fn new(params: DiscretizationParams, transform: &'t Transform2d) -> Self {
             Self { params, transform }
         }
 
@@ -536,384 +700,111 @@ pub mod discretization {
         pub fn discretize<'i, 'a>(
             &'a self,
             path: &'i mut dyn Iterator<Item = &'i PathCommand>,
-        ) -> PathDiscretizerIterator<'i, 'a> {
+        ) -> PathDiscretizerIterator<'a, &'i mut dyn Iterator<Item = &'i PathCommand>> {
+            self.discretize_iter(path)
+        }
+
+        /// Discretizes a path given as any iterator of commands, either owned or borrowed.
+        pub fn discretize_iter<'a, I>(&'a self, path: I) -> PathDiscretizerIterator<'a, I::IntoIter>
+        where
+            I: IntoIterator,
+            I::Item: Borrow<PathCommand>,
+        {
             PathDiscretizerIterator {
                 discretizer: self,
-                path,
-                state: PathDiscretizationState::new(),
+                path: path.into_iter(),
+                cursor: PathCursor::new(),
+                points: None,
             }
         }
     }
 
     /// Iterates through a path and discretizes it.
     ///
-    /// This is not intended to be used directly, use [`PathDiscretizer::discretize`] instead.
-    pub struct PathDiscretizerIterator<'i: 'a, 'a> {
+    /// This is not intended to be used directly, use [`PathDiscretizer::discretize`] or
+    /// [`PathDiscretizer::discretize_iter`] instead.
+    pub struct PathDiscretizerIterator<'a, I> {
         /// The discretizer that created this iterator.
-        discretizer: &'a PathDiscretizer,
+        discretizer: &'a PathDiscretizer<'a>,
         /// The path to discretize.
-        path: &'i mut dyn Iterator<Item = &'i PathCommand>,
-        /// The current state of the discretization.
-        state: PathDiscretizationState<'a>,
+        path: I,
+        /// The current position in the path.
+        cursor: PathCursor,
+        /// The points of the curve that is being discretized, if any.
+        points: Option<curves::CurvePointsIterator<'a>>,
     }
 
-    impl<'i, 'here> Iterator for PathDiscretizerIterator<'i, 'here> {
+    impl<'a, I> Iterator for PathDiscretizerIterator<'a, I>
+    where
+        I: Iterator,
+        I::Item: Borrow<PathCommand>,
+    {
         type Item = BrokenPolylineCommand;
 
         fn next(&mut self) -> Option<Self::Item> {
-            // TODO instead of recursion, use next_impl and that retuns Some<Option<Self::Item>> or
-            // Retry, here we should loop while Retry to avoid stack overflow caused by recursion
-
-            // If we are currently in the middle of a subpath, continue iterating over it.
-            if let Some(ref mut sub_iter) = self.state.subpath_iterator {
-                if let Some(pt) = sub_iter.next() {
-                    return Some(BrokenPolylineCommand::LineTo(pt));
-                } else {
-                    self.state.subpath_iterator = None;
+            let params = &self.discretizer.params;
+            let transform = self.discretizer.transform;
+
+            // Commands that do not produce any output (e.g. degenerate curves or closing an already
+            // closed polyline) just move on to the next one, hence the loop.
+            loop {
+                // If we are currently in the middle of a curve, continue iterating over it.
+                if let Some(ref mut points) = self.points {
+                    if let Some(pt) = points.next() {
+                        return Some(BrokenPolylineCommand::LineTo(pt));
+                    }
+                    self.points = None;
                 }
-            }
 
-            // Otherwise, try to pick up the next path command and start a new subpath.
-            if let Some(command) = self.path.next() {
-                match command {
+                // Otherwise, try to pick up the next path command and start a new curve.
+                let start = self.cursor.current_position;
+                let command = self.cursor.resolve(self.path.next()?.borrow());
+                let points = match command {
                     PathCommand::MoveTo(pt) => {
-                        self.state.move_to(pt);
-                        return Some(BrokenPolylineCommand::MoveTo(self.state.current_position));
-                    }
-                    PathCommand::MoveToOffset(offset) => {
-                        let pt = self.state.current_position + offset;
-                        self.state.move_to(&pt);
-                        return Some(BrokenPolylineCommand::MoveTo(pt));
-                    }
-                    PathCommand::LineTo(pt) => {
-                        let it = curves::discretize_segment(
-                            self.state.current_position,
-                            *pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&pt);
-                        self.state.subpath_iterator = Some(it);
-                        return self.next();
-                    }
-                    PathCommand::LineToOffset(offset) => {
-                        let pt = self.state.current_position + offset;
-                        let it = curves::discretize_segment(
-                            self.state.current_position,
-                            pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&pt);
-                        self.state.subpath_iterator = Some(it);
-                        return self.next();
-                    }
-                    PathCommand::HorizontalLineTo(x) => {
-                        let pt = Vec2f64::new(*x, self.state.current_position.y);
-                        let it = curves::discretize_segment(
-                            self.state.current_position,
-                            pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&pt);
-                        self.state.subpath_iterator = Some(it);
-                        return self.next();
-                    }
-                    PathCommand::HorizontalLineToOffset(offset) => {
-                        let pt = Vec2f64::new(
-                            self.state.current_position.x + offset,
-                            self.state.current_position.y,
-                        );
-                        let it = curves::discretize_segment(
-                            self.state.current_position,
-                            pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&pt);
-                        self.state.subpath_iterator = Some(it);
-                        return self.next();
-                    }
-                    PathCommand::VerticalLineTo(y) => {
-                        let pt = Vec2f64::new(self.state.current_position.x, *y);
-                        let it = curves::discretize_segment(
-                            self.state.current_position,
-                            pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&pt);
-                        self.state.subpath_iterator = Some(it);
-                        return self.next();
-                    }
-                    PathCommand::VerticalLineToOffset(offset) => {
-                        let pt = Vec2f64::new(
-                            self.state.current_position.x,
-                            self.state.current_position.y + offset,
-                        );
-                        let it = curves::discretize_segment(
-                            self.state.current_position,
-                            pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&pt);
-                        self.state.subpath_iterator = Some(it);
-                        return self.next();
+                        return Some(BrokenPolylineCommand::MoveTo(transform.eval(pt)));
                     }
+                    PathCommand::LineTo(pt) => curves::discretize_segment(start, pt, true, params, transform),
                     PathCommand::ClosePath => {
-                        let start = self.state.current_polyline_start;
-                        if self.state.current_position != self.state.current_polyline_start {
-                            // ? maybe use discretize_segment here, or maybe drawing a straight line is fine
-                            // even if the transform is not line preserving?
-                            self.state.set_curr_pos_no_ctrl_pt(&start);
-                            return Some(BrokenPolylineCommand::LineTo(
-                                self.state.current_polyline_start,
-                            ));
-                        } else {
-                            self.state.set_curr_pos_no_ctrl_pt(&start);
-                            return self.next(); // TODO or maybe return MoveTo?
+                        // the cursor has already moved back to the start of the polyline
+                        let end = self.cursor.current_position;
+                        if start == end {
+                            continue;
                         }
+                        curves::discretize_segment(start, end, true, params, transform)
                     }
-                    PathCommand::CubicBezierTo {
-                        control_pt_1,
-                        control_pt_2,
-                        end_pt,
-                    } => {
-                        let discretizer = curves::discretize_cubic_bezier(
-                            self.state.current_position,
-                            *control_pt_1,
-                            *control_pt_2,
-                            *end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state
-                            .set_current_pos_and_ctrl_pt(&end_pt, &control_pt_2);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
-                    }
-                    PathCommand::CubicBezierToOffset {
-                        control_pt_1_offset,
-                        control_pt_2_offset,
-                        end_pt_offset,
-                    } => {
-                        let control_pt_1 = self.state.current_position + control_pt_1_offset;
-                        let control_pt_2 = self.state.current_position + control_pt_2_offset;
-                        let end_pt = self.state.current_position + end_pt_offset;
-                        let discretizer = curves::discretize_cubic_bezier(
-                            self.state.current_position,
-                            control_pt_1,
-                            control_pt_2,
-                            end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state
-                            .set_current_pos_and_ctrl_pt(&end_pt, &control_pt_2);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
-                    }
-                    PathCommand::SmoothCubicBezierCurveTo {
-                        control_pt_2,
-                        end_pt,
-                    } => {
-                        let control_pt_1 = self.state.current_position
-                            + (self.state.current_position - self.state.current_control_point);
-                        let discretizer = curves::discretize_cubic_bezier(
-                            self.state.current_position,
-                            control_pt_1,
-                            *control_pt_2,
-                            *end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state
-                            .set_current_pos_and_ctrl_pt(end_pt, control_pt_2);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
+                    PathCommand::QuadraticBezierCurveTo { control_pt, end_pt } => {
+                        curves::discretize_quadratic_bezier(start, control_pt, end_pt, true, params, transform)
                     }
-                    PathCommand::SmoothCubicBezierCurveToOffset {
-                        control_pt_2_offset,
-                        end_pt_offset,
-                    } => {
-                        let control_pt_1 = self.state.current_position
-                            + (self.state.current_position - self.state.current_control_point);
-                        let control_pt_2 = self.state.current_position + control_pt_2_offset;
-                        let end_pt = self.state.current_position + end_pt_offset;
-                        let discretizer = curves::discretize_cubic_bezier(
-                            self.state.current_position,
+                    PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt } => {
+                        curves::discretize_cubic_bezier(
+                            start,
                             control_pt_1,
                             control_pt_2,
                             end_pt,
                             true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state
-                            .set_current_pos_and_ctrl_pt(&end_pt, &control_pt_2);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
-                    }
-                    PathCommand::QuadraticBezierCurveTo { control_pt, end_pt } => {
-                        let discretizer = curves::discretize_quadratic_bezier(
-                            self.state.current_position,
-                            *control_pt,
-                            *end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_current_pos_and_ctrl_pt(&end_pt, &control_pt);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
-                    }
-                    PathCommand::QuadraticBezierCurveToOffset {
-                        control_pt_offset,
-                        end_pt_offset,
-                    } => {
-                        let control_pt = self.state.current_position + control_pt_offset;
-                        let end_pt = self.state.current_position + end_pt_offset;
-                        let discretizer = curves::discretize_quadratic_bezier(
-                            self.state.current_position,
-                            control_pt,
-                            end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_current_pos_and_ctrl_pt(&end_pt, &control_pt);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
-                    }
-                    PathCommand::SmoothQuadraticBezierCurveTo(end_pt) => {
-                        let control_pt = self.state.current_position
-                            + (self.state.current_position - self.state.current_control_point);
-                        let discretizer = curves::discretize_quadratic_bezier(
-                            self.state.current_position,
-                            control_pt,
-                            *end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_current_pos_and_ctrl_pt(end_pt, &control_pt);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
-                    }
-                    PathCommand::SmoothQuadraticBezierCurveToOffset(end_point_offset) => {
-                        let control_pt = self.state.current_position
-                            + (self.state.current_position - self.state.current_control_point);
-                        let end_pt = self.state.current_position + end_point_offset;
-                        let discretizer = curves::discretize_quadratic_bezier(
-                            self.state.current_position,
-                            control_pt,
-                            end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_current_pos_and_ctrl_pt(&end_pt, &control_pt);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
-                    }
-                    PathCommand::EllipticalArcTo {
-                        radii,
-                        x_axis_rotation,
-                        large_arc_flag,
-                        sweep_flag,
-                        end_pt,
-                    } => {
-                        let discretizer = curves::discretize_elliptical_arc(
-                            &radii,
-                            *x_axis_rotation,
-                            *large_arc_flag,
-                            *sweep_flag,
-                            self.state.current_position,
-                            *end_pt,
-                            true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&end_pt);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
+                            params,
+                            transform,
+                        )
                     }
-                    PathCommand::EllipticalArcToOffset {
-                        radii,
-                        x_axis_rotation,
-                        large_arc_flag,
-                        sweep_flag,
-                        end_pt_offset,
-                    } => {
-                        let end_pt = self.state.current_position + end_pt_offset;
-                        let discretizer = curves::discretize_elliptical_arc(
+                    PathCommand::EllipticalArcTo { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt } => {
+                        curves::discretize_elliptical_arc(
                             &radii,
-                            *x_axis_rotation,
-                            *large_arc_flag,
-                            *sweep_flag,
-                            self.state.current_position,
+                            x_axis_rotation,
+                            large_arc_flag,
+                            sweep_flag,
+                            start,
                             end_pt,
                             true,
-                            &self.discretizer.params,
-                            &self.discretizer.transform,
-                        );
-                        self.state.set_curr_pos_no_ctrl_pt(&end_pt);
-                        self.state.subpath_iterator = Some(discretizer);
-                        return self.next();
+                            params,
+                            transform,
+                        )
                     }
-                }
-            }
-
-            // If we have no more path commands, we are done.
-            None
-        }
-    }
-
-    struct PathDiscretizationState<'a> {
-        current_position: Vec2f64,
-        current_polyline_start: Vec2f64,
-        current_control_point: Vec2f64,
-        subpath_iterator: Option<Box<dyn Iterator<Item = Vec2f64> + 'a>>,
-    }
-
-    impl<'a> PathDiscretizationState<'a> {
-        fn new() -> Self {
-            Self {
-                current_position: Vec2f64::new(0.0, 0.0),
-                current_polyline_start: Vec2f64::new(0.0, 0.0),
-                current_control_point: Vec2f64::new(0.0, 0.0),
-                subpath_iterator: None,
+                    // the cursor only produces absolute commands
+                    _ => unreachable!(),
+                };
+                self.points = Some(points);
             }
         }
-
-        /// Sets the current position and control point to the given point.
-        fn set_curr_pos_no_ctrl_pt(&mut self, pt: &Vec2f64) {
-            self.current_position = *pt;
-            self.current_control_point = *pt;
-        }
-
-        /// Sets the current position and control point to the given point and sets the current polyline start to the given point.
-        fn move_to(&mut self, pt: &Vec2f64) {
-            self.set_curr_pos_no_ctrl_pt(pt);
-            self.current_polyline_start = *pt;
-        }
-
-        /// Sets the current position and control point to the given point.
-        fn set_current_pos_and_ctrl_pt(&mut self, pos: &Vec2f64, ctrl_pt: &Vec2f64) {
-            self.current_position = *pos;
-            self.current_control_point = *ctrl_pt;
-        }
     }
 
     /// A parametric curve is a curve that can be evaluated at any point in the interval `[start, end]`.
@@ -921,6 +812,16 @@ pub mod discretization {
         fn eval(&self, t: f64) -> Vec2f64;
         fn start(&self) -> f64;
         fn end(&self) -> f64;
+
+        /// The derivative of the curve with respect to `t`.
+        ///
+        /// The default implementation uses a central finite difference, curves that know
+        /// their exact derivative should override it.
+        fn derivative(&self, t: f64) -> Vec2f64 {
+            let h = f64::EPSILON.sqrt() * self.param_range_width().max(1.0);
+            (self.eval(t + h) - self.eval(t - h)) / (2.0 * h)
+        }
+
         fn param_range_width(&self) -> f64 {
             (self.end() - self.start()).abs()
         }
@@ -952,25 +853,43 @@ pub mod discretization {
         }
     }
 
+    impl<C: ParametricCurve + ?Sized> ParametricCurve for Box<C> {
+        fn eval(&self, t: f64) -> Vec2f64 {
+            (**self).eval(t)
+        }
+        fn start(&self) -> f64 {
+            (**self).start()
+        }
+        fn end(&self) -> f64 {
+            (**self).end()
+        }
+        fn derivative(&self, t: f64) -> Vec2f64 {
+            (**self).derivative(t)
+        }
+    }
+
     /// This is an iterator that discretizes a parametric curve into a series of points.
     /// The generated points are guaranteed to be within the given accuracy of the curve.
     ///
+    /// The curve is stored by value, use [`curves::PathCurve`] to discretize the curves of a path
+    /// without boxing them.
+    ///
     /// ## Notes
     /// At the moment, the generated points just lie on the curve. In the future, we may want to
     /// generate points that may not lie on the curve, but are within the given accuracy.
     /// to minimize the error across the intermediate points.
-    pub struct ParametricCurveDiscretizerIterator<'a> {
-        curve: Box<dyn ParametricCurve>,
-        params: &'a DiscretizationParams,           // TODO reference
-        transform: &'a Transform2d, // TODO reference
+    pub struct ParametricCurveDiscretizerIterator<'a, C: ParametricCurve> {
+        curve: C,
+        params: &'a DiscretizationParams,
+        transform: &'a Transform2d,
         skip_first: bool,
         initial_subs: u32,
         state: Option<ParametricCurveDiscretizerState>,
     }
 
-    impl<'a> ParametricCurveDiscretizerIterator<'a> {
+    impl<'a, C: ParametricCurve> ParametricCurveDiscretizerIterator<'a, C> {
         pub fn new(
-            curve: Box<dyn ParametricCurve>,
+            curve: C,
             params: &'a DiscretizationParams,
             transform: &'a Transform2d,
             skip_first: bool,
@@ -987,7 +906,7 @@ pub mod discretization {
         }
 
         /// check if the given next point satisfies the discretization accuracy conditions
-        fn is_ok<'b1, 'c1>(
+        fn is_ok(
             state: &ParametricCurveDiscretizerState,
             params: &DiscretizationParams,
             next_point: &Vec2f64,
@@ -1005,24 +924,11 @@ pub mod discretization {
 
             // check that the midpoint is close enough to the line between the current point and the next point
             // (this is an approximation of the distance between the curve and the line, i.e. the "error")
-            let dist = dist_to_segment(&mid_pt, (&state.pt, &next_point));
+            let dist = dist_to_segment(mid_pt, (&state.pt, next_point));
             if dist > params.tolerance {
                 return false;
             }
 
-            // compute the angle between the next_point and the line between the current point and the previous point
-            //let angle = if let Some(prev_pt) = prev_pt {
-            //    // TODO written by copilot, to check
-            //    let v = curr_point - prev_pt;
-            //    let w = next_point - prev_pt;
-            //    let v = v.normalize();
-            //    let w = w.normalize();
-            //    let cos_angle = v.dot(&w);
-            //    let angle = cos_angle.acos();
-            //    angle
-            //} else {
-            //    0.0
-            //};
             let angle = angle_between(&(mid_pt - state.pt), &(next_point - mid_pt));
             // TODO instead of "angle_between" that requires atan2, we could just use the cross product
             // or the dot product of the normalized vectors and cache sin or cos of the max angle
@@ -1033,6 +939,47 @@ pub mod discretization {
             // if we reached this point, the point respects the accuracy conditions
             true
         }
+
+        /// Initializes the state at the start of the curve and returns the (transformed) start point.
+        ///
+        /// Returns `None` if the curve cannot be discretized.
+        fn init(&mut self) -> Option<Vec2f64> {
+            // we do not want to discretize curves that have non-finite parameter endpoints
+            if !self.curve.start().is_finite() || !self.curve.end().is_finite() {
+                // this is a meaningless curve we cannot discretize
+                return None;
+            }
+
+            // TODO move this check to the constructor
+            // we do not want to discretize curves that have very small parameter range
+            if (self.curve.end() - self.curve.start()).abs() < std::f64::EPSILON.sqrt() {
+                // this is a degenerate curve
+                return None;
+            }
+
+            // the transformed start point
+            let pt = self.transform.eval(self.curve.eval(self.curve.start()));
+            if !pt.x.is_finite() || !pt.y.is_finite() {
+                // this is a meaningless curve we cannot discretize
+                return None;
+            }
+
+            // the number of subdivisions for the first step,
+            // we add because we want `initial_subs` to represent the number of
+            // intermediate points, not the number of subdivisions ?!?!?!?!
+            let initial_divisions = self.initial_subs + 1;
+
+            // the initial step size
+            let dt = (self.curve.end() - self.curve.start()) / (initial_divisions as f64);
+
+            self.state = Some(ParametricCurveDiscretizerState {
+                t: self.curve.start(),
+                dt,
+                pt,
+                finished: false,
+            });
+            Some(pt)
+        }
     }
 
     /// The state of the discretizer
@@ -1047,149 +994,99 @@ pub mod discretization {
         finished: bool, // TODO this is unnecessary, maybe we could set to a value that is past the end of the curve to indicate that it is finished?
     }
 
-    impl<'a> Iterator for ParametricCurveDiscretizerIterator<'a> {
+    impl<'a, C: ParametricCurve> Iterator for ParametricCurveDiscretizerIterator<'a, C> {
         type Item = Vec2f64;
 
         fn next(&mut self) -> Option<Self::Item> {
+            // if we are at the beginning of the iteration, we need to perform some checks
+            // and initialize the state
+            if self.state.is_none() {
+                let pt = self.init()?;
+                if !self.skip_first {
+                    return Some(pt);
+                }
+            }
+
             // the transformed curve
-            let s = |t: f64| {
-                return self.transform.eval(self.curve.eval(t));
-            };
+            let s = |t: f64| self.transform.eval(self.curve.eval(t));
 
-            if let Some(ref mut state) = self.state {
-                if state.finished {
-                    return None;
-                }
+            let state = self.state.as_mut()?;
+            if state.finished {
+                return None;
+            }
 
-                // pick the next point
-                let (next_t, next_is_end, next_pt) = {
-                    let (next_t, next_is_end, stepped) =
-                        self.curve.next_t_for_step(state.t, state.dt);
-                    let next_pt = s(next_t);
-                    let mid_pt = s(state.t + stepped / 2.0);
-                    let next_pt_is_ok = Self::is_ok(&state, &self.params, &next_pt, &mid_pt);
-                    // Now, if the next point is the end and it is ok, we can use it.
-                    // But if the next point is not the end and it is ok, we can try to double the step
-                    // until we reach the end or the point is not ok to avoid too many points.
-                    // If the next point is either the end or not, but it is not ok, we can halve the step
-                    // until we reach the end or the point is ok.
-                    // note that every time we halve the step, we have to check that the step is greater than MIN_DT
-                    if next_is_end && next_pt_is_ok {
-                        (next_t, next_is_end, next_pt)
-                    } else if !next_is_end && next_pt_is_ok {
-                        let mut step = stepped;
-                        let mut t = next_t;
-                        let mut pt = next_pt;
-                        let mut next_is_end = next_is_end;
-                        loop {
-                            let (next_t, next_is_end_2, stepped) =
-                                self.curve.next_t_for_step(state.t, step * 2.0);
-                            let next_pt = s(next_t);
-                            let mid_pt = s(state.t + stepped / 2.0);
-                            let next_pt_is_ok =
-                                Self::is_ok(&state, &self.params, &next_pt, &mid_pt);
-                            if !next_pt_is_ok {
-                                break;
-                            }
-                            step = stepped;
-                            t = next_t;
-                            pt = next_pt;
-                            next_is_end = next_is_end_2;
-                            if next_is_end {
-                                break;
-                            }
+            // pick the next point
+            let (next_t, next_is_end, next_pt) = {
+                let (next_t, next_is_end, stepped) = self.curve.next_t_for_step(state.t, state.dt);
+                let next_pt = s(next_t);
+                let mid_pt = s(state.t + stepped / 2.0);
+                let next_pt_is_ok = Self::is_ok(state, self.params, &next_pt, &mid_pt);
+                // Now, if the next point is the end and it is ok, we can use it.
+                // But if the next point is not the end and it is ok, we can try to double the step
+                // until we reach the end or the point is not ok to avoid too many points.
+                // If the next point is either the end or not, but it is not ok, we can halve the step
+                // until we reach the end or the point is ok.
+                // note that every time we halve the step, we have to check that the step is greater than MIN_DT
+                if next_is_end && next_pt_is_ok {
+                    (next_t, next_is_end, next_pt)
+                } else if !next_is_end && next_pt_is_ok {
+                    let mut step = stepped;
+                    let mut t = next_t;
+                    let mut pt = next_pt;
+                    let mut next_is_end = next_is_end;
+                    loop {
+                        let (next_t, next_is_end_2, stepped) = self.curve.next_t_for_step(state.t, step * 2.0);
+                        let next_pt = s(next_t);
+                        let mid_pt = s(state.t + stepped / 2.0);
+                        let next_pt_is_ok = Self::is_ok(state, self.params, &next_pt, &mid_pt);
+                        if !next_pt_is_ok {
+                            break;
                         }
-                        (t, next_is_end, pt)
-                    } else {
-                        // && !next_pt_is_ok
-                        let mut step = stepped;
-                        let mut t = next_t;
-                        let mut pt = next_pt;
-                        let mut next_is_end = next_is_end;
-                        loop {
-                            let (next_t, next_is_end_2, stepped) =
-                                self.curve.next_t_for_step(state.t, step / 2.0);
-                            let next_pt = s(next_t);
-                            let mid_pt = s(state.t + stepped / 2.0);
-                            let next_pt_is_ok =
-                                Self::is_ok(&state, &self.params, &next_pt, &mid_pt);
-                            if stepped.abs()
-                                < std::f64::EPSILON.sqrt() * self.curve.param_range_width()
-                            {
-                                break;
-                            }
-                            step = stepped;
-                            t = next_t;
-                            pt = next_pt;
-                            next_is_end = next_is_end_2;
-                            if next_is_end || next_pt_is_ok {
-                                break;
-                            }
+                        step = stepped;
+                        t = next_t;
+                        pt = next_pt;
+                        next_is_end = next_is_end_2;
+                        if next_is_end {
+                            break;
                         }
-                        (t, next_is_end, pt)
                     }
-                };
-
-                let result_pt = next_pt;
-
-                if next_is_end {
-                    // we are done with the curve
-                    //return None;
-                    state.finished = true;
-                }
-
-                state.dt = next_t - state.t;
-                //prev_pt = Some(curr_point);
-                state.pt = next_pt;
-                state.t = next_t;
-
-                return Some(result_pt);
-            } else {
-                // if we are here, self.state is None, i.e. we are at the beginning of the iteration
-                // we need to perform some checks and initialize the state
-
-                // we do not want to discretize curves that have non-finite parameter endpoints
-                if !self.curve.start().is_finite() || !self.curve.end().is_finite() {
-                    // this is a meaningless curve we cannot discretize
-                    return None;
-                }
-
-                // TODO move this check to the constructor
-                // we do not want to discretize curves that have very small parameter range
-                if (self.curve.end() - self.curve.start()).abs() < std::f64::EPSILON.sqrt() {
-                    // this is a degenerate curve
-                    return None;
-                }
-
-                // the transformed start point
-                let pt = s(self.curve.start());
-                if !pt.x.is_finite() || !pt.y.is_finite() {
-                    // this is a meaningless curve we cannot discretize
-                    return None;
+                    (t, next_is_end, pt)
+                } else {
+                    // && !next_pt_is_ok
+                    let mut step = stepped;
+                    let mut t = next_t;
+                    let mut pt = next_pt;
+                    let mut next_is_end = next_is_end;
+                    loop {
+                        let (next_t, next_is_end_2, stepped) = self.curve.next_t_for_step(state.t, step / 2.0);
+                        let next_pt = s(next_t);
+                        let mid_pt = s(state.t + stepped / 2.0);
+                        let next_pt_is_ok = Self::is_ok(state, self.params, &next_pt, &mid_pt);
+                        if stepped.abs() < std::f64::EPSILON.sqrt() * self.curve.param_range_width() {
+                            break;
+                        }
+                        step = stepped;
+                        t = next_t;
+                        pt = next_pt;
+                        next_is_end = next_is_end_2;
+                        if next_is_end || next_pt_is_ok {
+                            break;
+                        }
+                    }
+                    (t, next_is_end, pt)
                 }
+            };
 
-                // the number of subdivisions for the first step,
-                // we add because we want `initial_subs` to represent the number of
-                // intermediate points, not the number of subdivisions ?!?!?!?!
-                let initial_divisions = self.initial_subs + 1;
-
-                // the initial step size
-                let dt = (self.curve.end() - self.curve.start()) / (initial_divisions as f64);
+            if next_is_end {
+                // we are done with the curve
+                state.finished = true;
+            }
 
-                self.state = Some(ParametricCurveDiscretizerState {
-                    t: self.curve.start(),
-                    dt,
-                    pt,
-                    finished: false,
-                });
+            state.dt = next_t - state.t;
+            state.pt = next_pt;
+            state.t = next_t;
 
-                // evaluate the curve at the start point if needed
-                if self.skip_first {
-                    return self.next();
-                } else {
-                    return Some(pt);
-                }
-            }
+            Some(next_pt)
         }
     }
 
@@ -1197,17 +1094,52 @@ pub mod discretization {
     pub mod curves {
 
         use super::*;
+        use crate::base::shapes::path::flattening::{FlatteningCurve, FlattenedBezierIterator};
+
+        /// Flattens a Bézier curve analytically if the parameters and the transform allow it.
+        fn flatten_bezier(
+            curve: FlatteningCurve,
+            skip_first: bool,
+            params: &DiscretizationParams,
+            transform: &Transform2d,
+        ) -> Option<FlattenedBezierIterator> {
+            if params.flattening == FlatteningMethod::Adaptive {
+                return None;
+            }
+            // the control points of the transformed curve are the transformed control points
+            let (linear, translation) = transform.to_affine()?;
+            let tr = |pt: Vec2f64| linear * pt + translation;
+            let curve = match curve {
+                FlatteningCurve::Quadratic(c) => {
+                    FlatteningCurve::Quadratic(ParametricQuadratic2dBezier::new(tr(c.p0), tr(c.p1), tr(c.p2)))
+                }
+                FlatteningCurve::Cubic(c) => {
+                    FlatteningCurve::Cubic(ParametricCubic2dBezier::new(tr(c.p0), tr(c.p1), tr(c.p2), tr(c.p3)))
+                }
+            };
+            Some(FlattenedBezierIterator::new(curve, params.flattening, params.tolerance, skip_first))
+        }
 
         /// A segment described as a parametric curve
+        #[derive(Clone, Copy, Debug)]
         pub struct ParametricSegment {
-            p0: Vec2f64,
-            p1: Vec2f64,
+            pub p0: Vec2f64,
+            pub p1: Vec2f64,
+        }
+
+        impl ParametricSegment {
+            pub fn new(p0: Vec2f64, p1: Vec2f64) -> Self {
+                Self { p0, p1 }
+            }
         }
 
         impl ParametricCurve for ParametricSegment {
             fn eval(&self, t: f64) -> Vec2f64 {
                 self.p0 + (self.p1 - self.p0) * t
             }
+            fn derivative(&self, _t: f64) -> Vec2f64 {
+                self.p1 - self.p0
+            }
             fn start(&self) -> f64 {
                 0.0
             }
@@ -1249,6 +1181,77 @@ pub mod discretization {
             }
         }
 
+        /// Any of the curves that can appear in a path.
+        ///
+        /// This allows the discretization of the curves of a path without boxing them.
+        #[derive(Clone, Copy, Debug)]
+        pub enum PathCurve {
+            Segment(ParametricSegment),
+            Quadratic(ParametricQuadratic2dBezier),
+            Cubic(ParametricCubic2dBezier),
+            Arc(CenterParametricArc),
+        }
+
+        impl ParametricCurve for PathCurve {
+            fn eval(&self, t: f64) -> Vec2f64 {
+                match self {
+                    PathCurve::Segment(curve) => curve.eval(t),
+                    PathCurve::Quadratic(curve) => curve.eval(t),
+                    PathCurve::Cubic(curve) => curve.eval(t),
+                    PathCurve::Arc(curve) => curve.eval(t),
+                }
+            }
+
+            fn derivative(&self, t: f64) -> Vec2f64 {
+                match self {
+                    PathCurve::Segment(curve) => curve.derivative(t),
+                    PathCurve::Quadratic(curve) => curve.derivative(t),
+                    PathCurve::Cubic(curve) => curve.derivative(t),
+                    PathCurve::Arc(curve) => curve.derivative(t),
+                }
+            }
+
+            fn start(&self) -> f64 {
+                match self {
+                    PathCurve::Segment(curve) => curve.start(),
+                    PathCurve::Quadratic(curve) => curve.start(),
+                    PathCurve::Cubic(curve) => curve.start(),
+                    PathCurve::Arc(curve) => curve.start(),
+                }
+            }
+
+            fn end(&self) -> f64 {
+                match self {
+                    PathCurve::Segment(curve) => curve.end(),
+                    PathCurve::Quadratic(curve) => curve.end(),
+                    PathCurve::Cubic(curve) => curve.end(),
+                    PathCurve::Arc(curve) => curve.end(),
+                }
+            }
+        }
+
+        /// Iterates over the points of a discretized curve, see the `discretize_*` functions.
+        pub enum CurvePointsIterator<'a> {
+            /// The endpoints of a segment, when the transform preserves lines.
+            Segment(SegmentPointsIterator),
+            /// The points of an adaptively discretized curve.
+            Curve(ParametricCurveDiscretizerIterator<'a, PathCurve>),
+            /// The points of an analytically flattened Bézier curve.
+            Flattened(FlattenedBezierIterator),
+        }
+
+        impl<'a> Iterator for CurvePointsIterator<'a> {
+            type Item = Vec2f64;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                match self {
+                    CurvePointsIterator::Segment(it) => it.next(),
+                    CurvePointsIterator::Curve(it) => it.next(),
+                    CurvePointsIterator::Flattened(it) => it.next(),
+                }
+            }
+        }
+
         /// Discretizes a segment.
         ///
         /// If the transform is line-preserving, the segment is discretized into two points,
@@ -1259,40 +1262,40 @@ pub mod discretization {
             skip_first: bool,
             params: &'a DiscretizationParams,
             transform: &'a Transform2d,
-        ) -> Box<dyn Iterator<Item = Vec2f64> + 'a> {
+        ) -> CurvePointsIterator<'a> {
             if transform.is_line_preserving() {
                 let p0_primed = transform.eval(p0);
                 let p1_primed = transform.eval(p1);
                 let it = SegmentPointsIterator::new(&p0_primed, &p1_primed, skip_first);
-                Box::new(it)
+                CurvePointsIterator::Segment(it)
             } else {
                 let line_curve = ParametricSegment { p0, p1 };
                 let discretizer = ParametricCurveDiscretizerIterator::new(
-                    Box::new(line_curve),
+                    PathCurve::Segment(line_curve),
                     params,
                     transform,
                     skip_first,
                     1,
                 ); // TODO maybe zero initial subdivisions is enough?
-                Box::new(discretizer)
+                CurvePointsIterator::Curve(discretizer)
             }
         }
 
         /// A parametric cubic 2d bezier curve
         #[derive(Clone, Copy, Debug)]
-        struct ParametricCubic2dBezier {
+        pub struct ParametricCubic2dBezier {
             /// The first pass-through point
-            p0: Vec2f64,
+            pub p0: Vec2f64,
             /// The first control point
-            p1: Vec2f64,
+            pub p1: Vec2f64,
             /// The second control point
-            p2: Vec2f64,
+            pub p2: Vec2f64,
             /// The second pass-through point
-            p3: Vec2f64,
+            pub p3: Vec2f64,
         }
 
         impl ParametricCubic2dBezier {
-            fn new(p0: Vec2f64, p1: Vec2f64, p2: Vec2f64, p3: Vec2f64) -> Self {
+            pub fn new(p0: Vec2f64, p1: Vec2f64, p2: Vec2f64, p3: Vec2f64) -> Self {
                 Self { p0, p1, p2, p3 }
             }
         }
@@ -1307,6 +1310,13 @@ pub mod discretization {
                 self.p0 * mt3 + self.p1 * 3.0 * mt2 * t + self.p2 * 3.0 * mt * t2 + self.p3 * t3
             }
 
+            fn derivative(&self, t: f64) -> Vec2f64 {
+                let mt = 1.0 - t;
+                (self.p1 - self.p0) * 3.0 * mt * mt
+                    + (self.p2 - self.p1) * 6.0 * mt * t
+                    + (self.p3 - self.p2) * 3.0 * t * t
+            }
+
             fn start(&self) -> f64 {
                 0.0
             }
@@ -1325,31 +1335,35 @@ pub mod discretization {
             skip_first: bool,
             params: &'a DiscretizationParams,
             transform: &'a Transform2d,
-        ) -> Box<dyn Iterator<Item = Vec2f64> + 'a> {
+        ) -> CurvePointsIterator<'a> {
             const DEGREE: u32 = 3;
             let curve = ParametricCubic2dBezier::new(p0, p1, p2, p3);
+            if let Some(it) = flatten_bezier(FlatteningCurve::Cubic(curve), skip_first, params, transform) {
+                return CurvePointsIterator::Flattened(it);
+            }
             let discretizer = ParametricCurveDiscretizerIterator::new(
-                Box::new(curve),
+                PathCurve::Cubic(curve),
                 params,
                 transform,
                 skip_first,
                 DEGREE,
             );
-            Box::new(discretizer)
+            CurvePointsIterator::Curve(discretizer)
         }
 
         /// A parametric quadratic 2d bezier curve
-        struct ParametricQuadratic2dBezier {
+        #[derive(Clone, Copy, Debug)]
+        pub struct ParametricQuadratic2dBezier {
             /// The first pass-through point
-            p0: Vec2f64,
+            pub p0: Vec2f64,
             /// The control point
-            p1: Vec2f64,
+            pub p1: Vec2f64,
             /// The second pass-through point
-            p2: Vec2f64,
+            pub p2: Vec2f64,
         }
 
         impl ParametricQuadratic2dBezier {
-            fn new(p0: Vec2f64, p1: Vec2f64, p2: Vec2f64) -> Self {
+            pub fn new(p0: Vec2f64, p1: Vec2f64, p2: Vec2f64) -> Self {
                 Self { p0, p1, p2 }
             }
         }
@@ -1362,6 +1376,10 @@ pub mod discretization {
                 self.p0 * mt2 + self.p1 * 2.0 * mt * t + self.p2 * t2
             }
 
+            fn derivative(&self, t: f64) -> Vec2f64 {
+                (self.p1 - self.p0) * 2.0 * (1.0 - t) + (self.p2 - self.p1) * 2.0 * t
+            }
+
             fn start(&self) -> f64 {
                 0.0
             }
@@ -1379,32 +1397,35 @@ pub mod discretization {
             skip_first: bool,
             params: &'a DiscretizationParams,
             transform: &'a Transform2d,
-        ) -> Box<dyn Iterator<Item = Vec2f64> + 'a> {
+        ) -> CurvePointsIterator<'a> {
             const DEGREE: u32 = 2;
             let curve = ParametricQuadratic2dBezier::new(p0, p1, p2);
+            if let Some(it) = flatten_bezier(FlatteningCurve::Quadratic(curve), skip_first, params, transform) {
+                return CurvePointsIterator::Flattened(it);
+            }
             let discretizer = ParametricCurveDiscretizerIterator::new(
-                Box::new(curve),
+                PathCurve::Quadratic(curve),
                 params,
                 transform,
                 skip_first,
                 DEGREE,
             );
-            Box::new(discretizer)
+            CurvePointsIterator::Curve(discretizer)
         }
 
         /// A parametric arc function.
         #[derive(Clone, Copy, Debug)]
         pub struct CenterParametricArc {
-            center: Vec2f64,
-            radii: Vec2f64,
-            start_angle: f64,
+            pub center: Vec2f64,
+            pub radii: Vec2f64,
+            pub start_angle: f64,
             //end_angle: f64,
-            sweep: f64,
-            x_axis_rotation: f64,
+            pub sweep: f64,
+            pub x_axis_rotation: f64,
         }
 
         impl CenterParametricArc {
-            fn new(
+            pub fn new(
                 center: Vec2f64,
                 radii: Vec2f64,
                 start_angle: f64,
@@ -1436,6 +1457,17 @@ pub mod discretization {
                 rot_mat * (pt - self.center) + self.center
             }
 
+            fn derivative(&self, t: f64) -> Vec2f64 {
+                let angle = self.start_angle + t * self.sweep;
+                let d = Vec2f64::new(
+                    -self.radii.x * angle.sin() * self.sweep,
+                    self.radii.y * angle.cos() * self.sweep,
+                );
+                let cos = self.x_axis_rotation.cos();
+                let sin = self.x_axis_rotation.sin();
+                Mat2f64::new(cos, -sin, sin, cos) * d
+            }
+
             fn start(&self) -> f64 {
                 0.0
             }
@@ -1483,7 +1515,7 @@ pub mod discretization {
             let c_primed = (
                 (sqr(rx) * sqr(ry) - sqr(rx) * sqr(p0_primed.y) - sqr(ry) * sqr(p0_primed.x)) /
                 (sqr(rx) * sqr(p0_primed.y) + sqr(ry) * sqr(p0_primed.x))
-            ).sqrt() *
+            ).max(0.0).sqrt() * // rounding can make it slightly negative for half ellipses
             (if large_arc_flag != sweep_flag { 1.0 } else { -1.0 }) *
             //Vec2f64::new(
             //    p0_primed.y,
@@ -1542,7 +1574,7 @@ pub mod discretization {
             skip_first: bool,
             params: &'a DiscretizationParams,
             transform: &'a Transform2d,
-        ) -> Box<dyn Iterator<Item = Vec2f64> + 'a> {
+        ) -> CurvePointsIterator<'a> {
             let arc = endpoint_to_center_parametric_arc_function(
                 radii,
                 x_axis_rotation,
@@ -1552,13 +1584,13 @@ pub mod discretization {
                 p1,
             );
             let discretizer = ParametricCurveDiscretizerIterator::new(
-                Box::new(arc),
+                PathCurve::Arc(arc),
                 params,
                 transform,
                 skip_first,
                 1,
             );
-            Box::new(discretizer)
+            CurvePointsIterator::Curve(discretizer)
         }
     }
 
@@ -0,0 +1,274 @@
+use crate::base::defs::{linalg::*, rect::{F64Rect, TlTrBlBr}};
+use crate::base::shapes::{polyline::BrokenPolylineCommand, BasicShape, Shape};
+use crate::base::transform::Transform2d;
+use crate::painter::methods::{points_to_path, rect_to_path};
+
+use super::{
+    discretization::{DiscretizationParams, PathDiscretizer},
+    PathCommand, PathCursor,
+};
+
+/// Builds a path with a Skia-like API.
+///
+/// The builder tracks the current point, so relative methods (prefixed with `r_`) are resolved
+/// and stored as absolute commands. All the methods return `&mut Self` for chaining.
+///
+/// ## Example
+/// ```rust
+/// use repaint::base::defs::linalg::Vec2f64;
+/// use repaint::base::shapes::path::PathBuilder;
+///
+/// let shape = PathBuilder::new()
+///     .move_to(Vec2f64::new(0.0, 0.0))
+///     .line_to(Vec2f64::new(10.0, 0.0))
+///     .r_line_to(Vec2f64::new(0.0, 10.0))
+///     .close()
+///     .build();
+/// ```
+pub struct PathBuilder {
+    pub commands: Vec<PathCommand>,
+    cursor: PathCursor,
+}
+
+impl Default for PathBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathBuilder {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            cursor: PathCursor::new(),
+        }
+    }
+
+    /// Appends a command as it is, relative commands are kept relative.
+    pub fn push(&mut self, command: PathCommand) {
+        self.cursor.resolve(&command);
+        self.commands.push(command);
+    }
+
+    /// The current point, i.e. the end of the last command or the origin for an empty path.
+    pub fn current_point(&self) -> Vec2f64 {
+        self.cursor.current_position
+    }
+
+    /// Starts a new contour at the given point.
+    pub fn move_to(&mut self, pt: Vec2f64) -> &mut Self {
+        self.push(PathCommand::MoveTo(pt));
+        self
+    }
+
+    /// Starts a new contour relatively to the current point.
+    pub fn r_move_to(&mut self, offset: Vec2f64) -> &mut Self {
+        self.move_to(self.current_point() + offset)
+    }
+
+    /// Adds a line from the current point.
+    pub fn line_to(&mut self, pt: Vec2f64) -> &mut Self {
+        self.push(PathCommand::LineTo(pt));
+        self
+    }
+
+    /// Adds a line, the end point is relative to the current point.
+    pub fn r_line_to(&mut self, offset: Vec2f64) -> &mut Self {
+        self.line_to(self.current_point() + offset)
+    }
+
+    /// Adds a quadratic Bézier curve from the current point.
+    pub fn quad_to(&mut self, control_pt: Vec2f64, end_pt: Vec2f64) -> &mut Self {
+        self.push(PathCommand::QuadraticBezierCurveTo { control_pt, end_pt });
+        self
+    }
+
+    /// Adds a quadratic Bézier curve, the points are relative to the current point.
+    pub fn r_quad_to(&mut self, control_pt_offset: Vec2f64, end_pt_offset: Vec2f64) -> &mut Self {
+        let pos = self.current_point();
+        self.quad_to(pos + control_pt_offset, pos + end_pt_offset)
+    }
+
+    /// Adds a cubic Bézier curve from the current point.
+    pub fn cubic_to(&mut self, control_pt_1: Vec2f64, control_pt_2: Vec2f64, end_pt: Vec2f64) -> &mut Self {
+        self.push(PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt });
+        self
+    }
+
+    /// Adds a cubic Bézier curve, the points are relative to the current point.
+    pub fn r_cubic_to(
+        &mut self,
+        control_pt_1_offset: Vec2f64,
+        control_pt_2_offset: Vec2f64,
+        end_pt_offset: Vec2f64,
+    ) -> &mut Self {
+        let pos = self.current_point();
+        self.cubic_to(pos + control_pt_1_offset, pos + control_pt_2_offset, pos + end_pt_offset)
+    }
+
+    /// Adds an SVG-like elliptical arc from the current point, see [`PathCommand::EllipticalArcTo`].
+    ///
+    /// `x_axis_rotation` is in radians.
+    pub fn arc_to(
+        &mut self,
+        radii: Vec2f64,
+        x_axis_rotation: f64,
+        large_arc_flag: bool,
+        sweep_flag: bool,
+        end_pt: Vec2f64,
+    ) -> &mut Self {
+        self.push(PathCommand::EllipticalArcTo { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt });
+        self
+    }
+
+    /// Adds an SVG-like elliptical arc, the end point is relative to the current point.
+    pub fn r_arc_to(
+        &mut self,
+        radii: Vec2f64,
+        x_axis_rotation: f64,
+        large_arc_flag: bool,
+        sweep_flag: bool,
+        end_pt_offset: Vec2f64,
+    ) -> &mut Self {
+        let end_pt = self.current_point() + end_pt_offset;
+        self.arc_to(radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt)
+    }
+
+    /// Adds a circular arc tangent to the lines from the current point to `pt1` and from `pt1`
+    /// to `pt2`, like Skia's `arcTo` and the canvas `arcTo`.
+    ///
+    /// A line is added from the current point to the start of the arc. If the points are
+    /// collinear or `radius` is not positive, only a line to `pt1` is added.
+    pub fn arc_to_tangent(&mut self, pt1: Vec2f64, pt2: Vec2f64, radius: f64) -> &mut Self {
+        let pos = self.current_point();
+        let (before, after) = match ((pt1 - pos).try_normalize(0.0), (pt2 - pt1).try_normalize(0.0)) {
+            (Some(before), Some(after)) => (before, after),
+            _ => return self.line_to(pt1),
+        };
+        let cos = before.dot(&after);
+        let sin = before.x * after.y - before.y * after.x;
+        if radius.is_nan() || radius <= 0.0 || sin.abs() <= f64::EPSILON {
+            return self.line_to(pt1);
+        }
+
+        // distance between pt1 and the tangent points
+        let dist = (radius * (1.0 - cos) / sin).abs();
+        self.line_to(pt1 - before * dist);
+        self.arc_to(Vec2f64::new(radius, radius), 0.0, false, sin > 0.0, pt1 + after * dist)
+    }
+
+    /// Closes the current contour, the current point goes back to its start.
+    pub fn close(&mut self) -> &mut Self {
+        self.push(PathCommand::ClosePath);
+        self
+    }
+
+    /// Adds a closed rectangle contour, clockwise from the top left corner.
+    pub fn add_rect(&mut self, rect: F64Rect) -> &mut Self {
+        self.extend(rect_to_path(rect))
+    }
+
+    /// Adds a closed ellipse contour inscribed in the rectangle.
+    pub fn add_oval(&mut self, rect: F64Rect) -> &mut Self {
+        let rect = rect.fix();
+        let center = (rect.min + rect.max) / 2.0;
+        self.add_shape(&BasicShape::Ellipse { center, radii: rect.size() / 2.0 })
+    }
+
+    /// Adds a closed circle contour.
+    pub fn add_circle(&mut self, center: Vec2f64, radius: f64) -> &mut Self {
+        self.add_shape(&BasicShape::Circle { center, radius })
+    }
+
+    /// Adds a closed rounded rectangle contour, see [`BasicShape::RoundedRect`].
+    pub fn add_rrect(&mut self, rect: F64Rect, radii: TlTrBlBr<Vec2f64>) -> &mut Self {
+        self.add_shape(&BasicShape::RoundedRect { rect, radii })
+    }
+
+    /// Adds a contour through the given points, optionally closed.
+    pub fn add_polygon(&mut self, points: impl IntoIterator<Item = Vec2f64>, closed: bool) -> &mut Self {
+        self.extend(points_to_path(points, closed))
+    }
+
+    /// Adds the commands of another path, transformed.
+    ///
+    /// Affine transforms are applied exactly to the control points and the arcs, with other
+    /// transforms the path is discretized into lines under the transform, with the default
+    /// parameters.
+    pub fn add_path<'a>(
+        &mut self,
+        path: impl IntoIterator<Item = &'a PathCommand>,
+        transform: &Transform2d,
+    ) -> &mut Self {
+        if let Some((linear, translation)) = transform.to_affine() {
+            let apply = |pt: &Vec2f64| linear * pt + translation;
+            let mut cursor = PathCursor::new();
+            for command in path {
+                let command = match cursor.resolve(command) {
+                    PathCommand::MoveTo(pt) => PathCommand::MoveTo(apply(&pt)),
+                    PathCommand::LineTo(pt) => PathCommand::LineTo(apply(&pt)),
+                    PathCommand::QuadraticBezierCurveTo { control_pt, end_pt } => {
+                        PathCommand::QuadraticBezierCurveTo { control_pt: apply(&control_pt), end_pt: apply(&end_pt) }
+                    }
+                    PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt } => PathCommand::CubicBezierTo {
+                        control_pt_1: apply(&control_pt_1),
+                        control_pt_2: apply(&control_pt_2),
+                        end_pt: apply(&end_pt),
+                    },
+                    PathCommand::EllipticalArcTo { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt } => {
+                        let (radii, x_axis_rotation) = transform_ellipse(&linear, radii, x_axis_rotation);
+                        PathCommand::EllipticalArcTo {
+                            radii,
+                            x_axis_rotation,
+                            large_arc_flag,
+                            // a reflection reverses the direction
+                            sweep_flag: sweep_flag != (linear.determinant() < 0.0),
+                            end_pt: apply(&end_pt),
+                        }
+                    }
+                    command => command,
+                };
+                self.push(command);
+            }
+        } else {
+            // the curves are flattened under the transform, so that the tolerance is in its space
+            let discretizer = PathDiscretizer::new(DiscretizationParams::default(), transform);
+            for command in discretizer.discretize_iter(path) {
+                match command {
+                    BrokenPolylineCommand::MoveTo(pt) => self.move_to(pt),
+                    BrokenPolylineCommand::LineTo(pt) => self.line_to(pt),
+                };
+            }
+        }
+        self
+    }
+
+    /// Builds the shape, the builder can still be used afterwards.
+    pub fn build(&self) -> BasicShape {
+        BasicShape::Path(self.commands.clone())
+    }
+
+    fn add_shape(&mut self, shape: &BasicShape) -> &mut Self {
+        self.extend(shape.to_path_iter())
+    }
+
+    fn extend(&mut self, commands: impl IntoIterator<Item = PathCommand>) -> &mut Self {
+        for command in commands {
+            self.push(command);
+        }
+        self
+    }
+}
+
+/// Computes the radii and rotation of the image of an ellipse by a linear transform.
+///
+/// The image of the unit circle by `U Σ Vᵀ` is the image of the unit circle by `U Σ`.
+fn transform_ellipse(linear: &Mat2f64, radii: Vec2f64, x_axis_rotation: f64) -> (Vec2f64, f64) {
+    let (sin, cos) = x_axis_rotation.sin_cos();
+    let ellipse = linear * Mat2f64::new(cos * radii.x, -sin * radii.y, sin * radii.x, cos * radii.y);
+    let svd = ellipse.svd(true, false);
+    match svd.u {
+        Some(u) => (svd.singular_values, u[(1, 0)].atan2(u[(0, 0)])),
+        None => (radii, x_axis_rotation),
+    }
+}
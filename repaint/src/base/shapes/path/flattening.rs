@@ -0,0 +1,359 @@
+/*!
+Analytic flattening of Bézier curves.
+
+The adaptive discretization of [`ParametricCurveDiscretizerIterator`](super::discretization::ParametricCurveDiscretizerIterator)
+looks for each point by halving or doubling the parameter step, this module instead computes the
+number of segments needed to stay within the tolerance directly from the control points:
+ - [*Wang's formula*](https://www.cs.cmu.edu/afs/cs/academic/class/15462-f10/www/lec_slides/Lecture12.pdf)
+   bounds the second derivative of the curve and uses uniform parameter steps, it is very cheap
+   but overestimates the number of points on curves with uneven curvature;
+ - the *parabola integral* method by *Raph Levien* (<https://raphlinus.github.io/graphics/curves/2019/12/23/flatten-quadbez.html>)
+   maps each quadratic to a segment of the parabola `y = x²`, where the number of segments
+   needed is the integral of an approximately known function, and spaces the points so that
+   every segment has about the same error. Cubics are first approximated with quadratics.
+
+The generated points always lie on the curve, the first and last ones being exactly the endpoints.
+*/
+
+use crate::base::defs::linalg::*;
+
+use super::discretization::{
+    curves::{ParametricCubic2dBezier, ParametricQuadratic2dBezier},
+    ParametricCurve,
+};
+
+/// The algorithm used to flatten quadratic and cubic Bézier curves.
+///
+/// Segments and elliptical arcs always use the adaptive algorithm.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlatteningMethod {
+    /// Adaptive subdivision driven by both `tolerance` and `max_angle`.
+    #[default]
+    Adaptive,
+
+    /// Raph Levien's parabola integral method, only `tolerance` is used.
+    ///
+    /// It produces the fewest points and spaces them evenly along the curve.
+    ParabolaIntegral,
+
+    /// Wang's formula with uniform parameter steps, only `tolerance` is used.
+    Wang,
+}
+
+/// The maximum number of segments a single curve is flattened into.
+const MAX_SEGMENTS: u32 = 1 << 16;
+
+/// The fraction of the tolerance used to approximate a cubic with quadratics.
+const TO_QUAD_TOLERANCE: f64 = 0.1;
+
+/// A Bézier curve to flatten.
+#[derive(Clone, Copy, Debug)]
+pub enum FlatteningCurve {
+    Quadratic(ParametricQuadratic2dBezier),
+    Cubic(ParametricCubic2dBezier),
+}
+
+impl FlatteningCurve {
+    fn eval(&self, t: f64) -> Vec2f64 {
+        match self {
+            FlatteningCurve::Quadratic(curve) => curve.eval(t),
+            FlatteningCurve::Cubic(curve) => curve.eval(t),
+        }
+    }
+
+    fn start_point(&self) -> Vec2f64 {
+        match self {
+            FlatteningCurve::Quadratic(curve) => curve.p0,
+            FlatteningCurve::Cubic(curve) => curve.p0,
+        }
+    }
+
+    fn end_point(&self) -> Vec2f64 {
+        match self {
+            FlatteningCurve::Quadratic(curve) => curve.p2,
+            FlatteningCurve::Cubic(curve) => curve.p3,
+        }
+    }
+
+    fn is_finite(&self) -> bool {
+        let finite = |pt: &Vec2f64| pt.x.is_finite() && pt.y.is_finite();
+        match self {
+            FlatteningCurve::Quadratic(c) => [c.p0, c.p1, c.p2].iter().all(finite),
+            FlatteningCurve::Cubic(c) => [c.p0, c.p1, c.p2, c.p3].iter().all(finite),
+        }
+    }
+
+    /// The number of quadratics approximating the curve within `tolerance`.
+    fn quad_count(&self, tolerance: f64) -> u32 {
+        match self {
+            FlatteningCurve::Quadratic(_) => 1,
+            FlatteningCurve::Cubic(c) => {
+                // the error of the approximation is sqrt(3)/36 |p3 - 3 p2 + 3 p1 - p0|,
+                // it scales with the cube of the parameter range when subdividing
+                let err = 3f64.sqrt() / 36.0 * (c.p3 - c.p2 * 3.0 + c.p1 * 3.0 - c.p0).norm();
+                ((err / tolerance).cbrt().ceil() as u32).clamp(1, MAX_SEGMENTS)
+            }
+        }
+    }
+
+    /// The `index`-th of `count` quadratics approximating the curve.
+    fn quad(&self, index: u32, count: u32) -> ParametricQuadratic2dBezier {
+        match self {
+            FlatteningCurve::Quadratic(curve) => *curve,
+            FlatteningCurve::Cubic(curve) => {
+                let t0 = index as f64 / count as f64;
+                let t1 = (index + 1) as f64 / count as f64;
+                let q0 = blossom(curve, t0, t0, t0);
+                let q1 = blossom(curve, t0, t0, t1);
+                let q2 = blossom(curve, t0, t1, t1);
+                let q3 = blossom(curve, t1, t1, t1);
+                ParametricQuadratic2dBezier::new(q0, ((q1 + q2) * 3.0 - q0 - q3) * 0.25, q3)
+            }
+        }
+    }
+}
+
+/// Evaluates the blossom (polar form) of a cubic Bézier.
+fn blossom(c: &ParametricCubic2dBezier, u: f64, v: f64, w: f64) -> Vec2f64 {
+    let lerp = |a: Vec2f64, b: Vec2f64, t: f64| a + (b - a) * t;
+    let a = lerp(c.p0, c.p1, u);
+    let b = lerp(c.p1, c.p2, u);
+    let d = lerp(c.p2, c.p3, u);
+    let e = lerp(a, b, v);
+    let f = lerp(b, d, v);
+    lerp(e, f, w)
+}
+
+/// Approximation of the integral of `(1 + 4x²)^-0.25`.
+fn approx_parabola_integral(x: f64) -> f64 {
+    const D: f64 = 0.67;
+    x / (1.0 - D + (D.powi(4) + 0.25 * x * x).sqrt().sqrt())
+}
+
+/// Approximation of the inverse of [`approx_parabola_integral`].
+fn approx_parabola_inv_integral(x: f64) -> f64 {
+    const B: f64 = 0.39;
+    x * (1.0 - B + (B * B + 0.25 * x * x).sqrt())
+}
+
+/// The parameters mapping a quadratic to a segment of the parabola `y = x²`.
+#[derive(Clone, Copy, Debug)]
+struct ParabolaParams {
+    a0: f64,
+    a2: f64,
+    u0: f64,
+    u_scale: f64,
+    /// The (unscaled) number of segments needed for this quadratic.
+    val: f64,
+}
+
+impl ParabolaParams {
+    fn new(quad: &ParametricQuadratic2dBezier, sqrt_tolerance: f64) -> Self {
+        let cross = |a: Vec2f64, b: Vec2f64| a.x * b.y - a.y * b.x;
+        let d01 = quad.p1 - quad.p0;
+        let d12 = quad.p2 - quad.p1;
+        let dd = d01 - d12;
+        let cross_p = cross(quad.p2 - quad.p0, dd);
+        let x0 = d01.dot(&dd) / cross_p;
+        let x2 = d12.dot(&dd) / cross_p;
+        let scale = (cross_p / (dd.norm() * (x2 - x0))).abs();
+
+        let a0 = approx_parabola_integral(x0);
+        let a2 = approx_parabola_integral(x2);
+        let val = if scale.is_finite() {
+            let da = (a2 - a0).abs();
+            let sqrt_scale = scale.sqrt();
+            if x0.signum() == x2.signum() {
+                da * sqrt_scale
+            } else {
+                // the cusp of the parabola is inside the curve
+                let x_min = sqrt_tolerance / sqrt_scale;
+                sqrt_tolerance * da / approx_parabola_integral(x_min)
+            }
+        } else {
+            // a straight line
+            0.0
+        };
+        let u0 = approx_parabola_inv_integral(a0);
+        let u2 = approx_parabola_inv_integral(a2);
+        Self { a0, a2, u0, u_scale: 1.0 / (u2 - u0), val }
+    }
+
+    /// The number of segments needed to flatten the quadratic.
+    fn segments(&self, sqrt_tolerance: f64) -> u32 {
+        let count = (0.5 * self.val / sqrt_tolerance).ceil();
+        if count.is_finite() {
+            (count as u32).clamp(1, MAX_SEGMENTS)
+        } else {
+            1
+        }
+    }
+
+    /// The parameter on the quadratic corresponding to the fraction `x` of its segments.
+    fn subdivision_t(&self, x: f64) -> f64 {
+        let a = self.a0 + (self.a2 - self.a0) * x;
+        let u = approx_parabola_inv_integral(a);
+        let t = (u - self.u0) * self.u_scale;
+        if t.is_finite() {
+            t.clamp(0.0, 1.0)
+        } else {
+            x
+        }
+    }
+}
+
+/// How the points are distributed along the curve.
+#[derive(Clone, Copy, Debug)]
+enum Spacing {
+    /// Uniform parameter steps.
+    Uniform,
+
+    /// Even steps of the parabola integral within each of the approximating quadratics.
+    Parabola {
+        /// The number of approximating quadratics.
+        quad_count: u32,
+        /// The current quadratic.
+        quad_index: u32,
+        /// The index of the point at the start of the current quadratic.
+        quad_first: u32,
+        /// The number of segments of the current quadratic.
+        quad_segments: u32,
+        /// The parameters of the current quadratic.
+        params: ParabolaParams,
+        /// The square root of the tolerance used for the quadratics.
+        sqrt_tolerance: f64,
+        /// The factor applied to the segments of each quadratic, below one when the curve needs
+        /// more than [`MAX_SEGMENTS`] segments.
+        segment_scale: f64,
+    },
+}
+
+/// Iterates over the points of a Bézier curve flattened with an analytic [`FlatteningMethod`].
+///
+/// It does not allocate, the number of points is known upfront (see [`FlattenedBezierIterator::segment_count`]).
+#[derive(Clone, Copy, Debug)]
+pub struct FlattenedBezierIterator {
+    curve: FlatteningCurve,
+    spacing: Spacing,
+    /// The number of segments.
+    count: u32,
+    /// The index of the next point.
+    index: u32,
+}
+
+impl FlattenedBezierIterator {
+    /// Creates an iterator for the given curve.
+    ///
+    /// The [`FlatteningMethod::Adaptive`] method is not analytic, [`FlatteningMethod::ParabolaIntegral`]
+    /// is used instead.
+    pub fn new(curve: FlatteningCurve, method: FlatteningMethod, tolerance: f64, skip_first: bool) -> Self {
+        let index = if skip_first { 1 } else { 0 };
+        if !curve.is_finite() || tolerance.is_nan() || tolerance <= 0.0 {
+            return Self { curve, spacing: Spacing::Uniform, count: 0, index: 1 };
+        }
+
+        let (count, spacing) = match method {
+            FlatteningMethod::Wang => (wang_segment_count(&curve, tolerance), Spacing::Uniform),
+            FlatteningMethod::ParabolaIntegral | FlatteningMethod::Adaptive => {
+                let quad_count = curve.quad_count(tolerance * TO_QUAD_TOLERANCE);
+                let sqrt_tolerance = (tolerance * (1.0 - TO_QUAD_TOLERANCE)).sqrt();
+                // The quadratics are flattened separately: the estimate of the parabola integral
+                // does not hold across them, especially near cusps.
+                let quad_segments = |i| ParabolaParams::new(&curve.quad(i, quad_count), sqrt_tolerance).segments(sqrt_tolerance);
+                let total = (0..quad_count).map(|i| quad_segments(i) as u64).sum::<u64>();
+                // too many segments: each quadratic gets its share of the maximum, so that the
+                // points still cover the whole curve
+                let segment_scale = if total > MAX_SEGMENTS as u64 { MAX_SEGMENTS as f64 / total as f64 } else { 1.0 };
+                let count = (0..quad_count).map(|i| scale_segments(quad_segments(i), segment_scale)).sum();
+                let params = ParabolaParams::new(&curve.quad(0, quad_count), sqrt_tolerance);
+                let spacing = Spacing::Parabola {
+                    quad_count,
+                    quad_index: 0,
+                    quad_first: 0,
+                    quad_segments: scale_segments(params.segments(sqrt_tolerance), segment_scale),
+                    params,
+                    sqrt_tolerance,
+                    segment_scale,
+                };
+                (count, spacing)
+            }
+        };
+
+        Self { curve, spacing, count, index }
+    }
+
+    /// The number of segments the curve is flattened into.
+    pub fn segment_count(&self) -> u32 {
+        self.count
+    }
+
+    /// The curve parameter of the `index`-th point, with `0 < index < count`.
+    fn parameter(&mut self, index: u32) -> f64 {
+        match &mut self.spacing {
+            Spacing::Uniform => index as f64 / self.count as f64,
+            Spacing::Parabola { quad_count, quad_index, quad_first, quad_segments, params, sqrt_tolerance, segment_scale } => {
+                while *quad_index + 1 < *quad_count && index >= *quad_first + *quad_segments {
+                    *quad_first += *quad_segments;
+                    *quad_index += 1;
+                    *params = ParabolaParams::new(&self.curve.quad(*quad_index, *quad_count), *sqrt_tolerance);
+                    *quad_segments = scale_segments(params.segments(*sqrt_tolerance), *segment_scale);
+                }
+                let x = ((index - *quad_first) as f64 / *quad_segments as f64).min(1.0);
+                (*quad_index as f64 + params.subdivision_t(x)) / *quad_count as f64
+            }
+        }
+    }
+}
+
+impl Iterator for FlattenedBezierIterator {
+    type Item = Vec2f64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index > self.count {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+        if index == 0 {
+            Some(self.curve.start_point())
+        } else if index == self.count {
+            Some(self.curve.end_point())
+        } else {
+            let t = self.parameter(index);
+            Some(self.curve.eval(t))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count + 1).saturating_sub(self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// The number of segments of a quadratic scaled down by `scale`, at least one.
+fn scale_segments(segments: u32, scale: f64) -> u32 {
+    if scale < 1.0 {
+        ((segments as f64 * scale).floor() as u32).max(1)
+    } else {
+        segments
+    }
+}
+
+/// The number of uniform parameter steps needed to flatten the curve within `tolerance`,
+/// using Wang's formula.
+pub fn wang_segment_count(curve: &FlatteningCurve, tolerance: f64) -> u32 {
+    // sqrt(n (n - 1) / (8 tolerance) * max |p[i] - 2 p[i + 1] + p[i + 2]|), n being the degree
+    let (factor, second_difference) = match curve {
+        FlatteningCurve::Quadratic(c) => (2.0 / 8.0, (c.p0 - c.p1 * 2.0 + c.p2).norm()),
+        FlatteningCurve::Cubic(c) => (
+            6.0 / 8.0,
+            (c.p0 - c.p1 * 2.0 + c.p2).norm().max((c.p1 - c.p2 * 2.0 + c.p3).norm()),
+        ),
+    };
+    let count = (factor * second_difference / tolerance).sqrt().ceil();
+    if count.is_finite() {
+        (count as u32).clamp(1, MAX_SEGMENTS)
+    } else {
+        1
+    }
+}
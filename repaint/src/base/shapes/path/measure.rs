@@ -0,0 +1,431 @@
+use crate::base::defs::linalg::*;
+
+use super::{
+    discretization::{
+        curves::{
+            endpoint_to_center_parametric_arc_function, CenterParametricArc, ParametricCubic2dBezier,
+            ParametricQuadratic2dBezier, ParametricSegment,
+        },
+        lerp, ParametricCurve,
+    },
+    PathCommand, PathCursor,
+};
+
+/// Measures the length of a path and finds points and tangents along it.
+///
+/// The path is split into contours (sub-paths), each one is measured by a [`ContourMeasure`].
+/// Distances passed to the methods of [`PathMeasure`] are measured along the whole path, i.e.
+/// the contours are considered one after the other.
+///
+/// ## Example
+/// ```rust
+/// use repaint::base::defs::linalg::Vec2f64;
+/// use repaint::base::shapes::path::{PathCommand, PathMeasure};
+///
+/// let path = [
+///     PathCommand::MoveTo(Vec2f64::new(0.0, 0.0)),
+///     PathCommand::LineTo(Vec2f64::new(3.0, 0.0)),
+///     PathCommand::LineTo(Vec2f64::new(3.0, 4.0)),
+/// ];
+/// let measure = PathMeasure::new(&path, 1e-3);
+/// assert!((measure.length() - 7.0).abs() < 1e-9);
+///
+/// let (pos, tangent) = measure.pos_tan(5.0).unwrap();
+/// assert!((pos - Vec2f64::new(3.0, 2.0)).norm() < 1e-9);
+/// assert!((tangent - Vec2f64::new(0.0, 1.0)).norm() < 1e-9);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathMeasure {
+    contours: Vec<ContourMeasure>,
+    length: f64,
+}
+
+impl PathMeasure {
+    /// Measures the given path.
+    ///
+    /// `tolerance` is the maximum error allowed when approximating the length of curves.
+    pub fn new<'a>(path: impl IntoIterator<Item = &'a PathCommand>, tolerance: f64) -> Self {
+        let tolerance = if tolerance > 0.0 { tolerance } else { 1e-3 };
+
+        let mut contours = Vec::new();
+        let mut builder = ContourBuilder::new(tolerance);
+        let mut cursor = PathCursor::new();
+        for command in path {
+            let start = cursor.current_position;
+            match cursor.resolve(command) {
+                PathCommand::MoveTo(_) => {
+                    contours.extend(builder.finish(false));
+                }
+                PathCommand::ClosePath => {
+                    builder.push(MeasuredCurve::Line(ParametricSegment::new(start, cursor.current_position)));
+                    contours.extend(builder.finish(true));
+                }
+                PathCommand::LineTo(end_pt) => {
+                    builder.push(MeasuredCurve::Line(ParametricSegment::new(start, end_pt)));
+                }
+                PathCommand::QuadraticBezierCurveTo { control_pt, end_pt } => {
+                    builder.push(MeasuredCurve::Quadratic(ParametricQuadratic2dBezier::new(
+                        start, control_pt, end_pt,
+                    )));
+                }
+                PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt } => {
+                    builder.push(MeasuredCurve::Cubic(ParametricCubic2dBezier::new(
+                        start, control_pt_1, control_pt_2, end_pt,
+                    )));
+                }
+                PathCommand::EllipticalArcTo { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt } => {
+                    if radii.x == 0.0 || radii.y == 0.0 {
+                        // out of range radii, the arc is a straight line as in SVG
+                        builder.push(MeasuredCurve::Line(ParametricSegment::new(start, end_pt)));
+                    } else if start != end_pt {
+                        let arc = endpoint_to_center_parametric_arc_function(
+                            &radii.abs(),
+                            x_axis_rotation,
+                            large_arc_flag,
+                            sweep_flag,
+                            start,
+                            end_pt,
+                        );
+                        builder.push(MeasuredCurve::Arc(arc));
+                    }
+                }
+                _ => unreachable!("PathCursor::resolve only returns absolute commands"),
+            }
+        }
+        contours.extend(builder.finish(false));
+
+        let length = contours.iter().map(|c| c.length()).sum();
+        Self { contours, length }
+    }
+
+    /// The total length of the path.
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// The measured contours, contours with zero length are skipped.
+    pub fn contours(&self) -> &[ContourMeasure] {
+        &self.contours
+    }
+
+    /// Returns the position and the unit tangent at the given distance from the start of the path.
+    ///
+    /// The distance is clamped to `[0, length]`. Returns `None` if the path has zero length.
+    pub fn pos_tan(&self, distance: f64) -> Option<(Vec2f64, Vec2f64)> {
+        let (contour, distance) = self.locate(distance)?;
+        contour.pos_tan(distance)
+    }
+
+    /// Returns the position at the given distance from the start of the path.
+    pub fn position(&self, distance: f64) -> Option<Vec2f64> {
+        self.pos_tan(distance).map(|(pos, _)| pos)
+    }
+
+    /// Returns the unit tangent at the given distance from the start of the path.
+    pub fn tangent(&self, distance: f64) -> Option<Vec2f64> {
+        self.pos_tan(distance).map(|(_, tangent)| tangent)
+    }
+
+    /// Extracts the part of the path between two distances.
+    ///
+    /// Every piece of contour in the range starts with a [`PathCommand::MoveTo`].
+    /// The result is empty if `start_distance >= end_distance`.
+    pub fn segment(&self, start_distance: f64, end_distance: f64) -> Vec<PathCommand> {
+        let mut result = Vec::new();
+        let mut offset = 0.0;
+        for contour in &self.contours {
+            let start = (start_distance - offset).max(0.0);
+            let end = (end_distance - offset).min(contour.length());
+            if start < end {
+                contour.segment_into(start, end, true, &mut result);
+            }
+            offset += contour.length();
+        }
+        result
+    }
+
+    fn locate(&self, distance: f64) -> Option<(&ContourMeasure, f64)> {
+        let last = self.contours.last()?;
+        let mut distance = distance.clamp(0.0, self.length);
+        for contour in &self.contours {
+            if distance <= contour.length() {
+                return Some((contour, distance));
+            }
+            distance -= contour.length();
+        }
+        Some((last, last.length()))
+    }
+}
+
+/// The measure of a single contour of a path, see [`PathMeasure`].
+#[derive(Clone, Debug)]
+pub struct ContourMeasure {
+    segments: Vec<MeasuredSegment>,
+    length: f64,
+    closed: bool,
+}
+
+impl ContourMeasure {
+    /// The length of the contour.
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Tells if the contour ends with a [`PathCommand::ClosePath`].
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Returns the position and the unit tangent at the given distance from the start of the contour.
+    ///
+    /// The distance is clamped to `[0, length]`.
+    pub fn pos_tan(&self, distance: f64) -> Option<(Vec2f64, Vec2f64)> {
+        let (segment, t) = self.locate(distance)?;
+        let pos = segment.curve.eval(t);
+        let mut tangent = segment.curve.derivative(t);
+        if tangent.norm_squared() == 0.0 {
+            // degenerate control points, use the chord towards a close point instead
+            let other = if t < 0.5 { t + 1e-6 } else { t - 1e-6 };
+            tangent = (segment.curve.eval(other) - pos) * if t < 0.5 { 1.0 } else { -1.0 };
+        }
+        let tangent = tangent.try_normalize(0.0).unwrap_or_else(Vec2f64::zeros);
+        Some((pos, tangent))
+    }
+
+    /// Extracts the part of the contour between two distances.
+    ///
+    /// If `start_with_move_to` is `true`, the result starts with a [`PathCommand::MoveTo`].
+    pub fn segment(&self, start_distance: f64, end_distance: f64, start_with_move_to: bool) -> Vec<PathCommand> {
+        let mut result = Vec::new();
+        self.segment_into(start_distance, end_distance, start_with_move_to, &mut result);
+        result
+    }
+
+    fn segment_into(
+        &self,
+        start_distance: f64,
+        end_distance: f64,
+        start_with_move_to: bool,
+        result: &mut Vec<PathCommand>,
+    ) {
+        let start_distance = start_distance.max(0.0);
+        let end_distance = end_distance.min(self.length);
+        if start_distance >= end_distance {
+            return;
+        }
+
+        let first = self.segment_index(start_distance);
+        let last = self.segment_index(end_distance);
+        if start_with_move_to {
+            let (segment, t) = (&self.segments[first], self.segments[first].t_at(start_distance));
+            result.push(PathCommand::MoveTo(segment.curve.eval(t)));
+        }
+        for idx in first..=last {
+            let segment = &self.segments[idx];
+            let t0 = if idx == first { segment.t_at(start_distance) } else { 0.0 };
+            let t1 = if idx == last { segment.t_at(end_distance) } else { 1.0 };
+            if t0 < t1 {
+                result.push(segment.curve.sub_curve(t0, t1));
+            }
+        }
+    }
+
+    fn segment_index(&self, distance: f64) -> usize {
+        self.segments
+            .partition_point(|segment| segment.end_distance() < distance)
+            .min(self.segments.len() - 1)
+    }
+
+    fn locate(&self, distance: f64) -> Option<(&MeasuredSegment, f64)> {
+        if self.segments.is_empty() {
+            return None;
+        }
+        let distance = distance.clamp(0.0, self.length);
+        let segment = &self.segments[self.segment_index(distance)];
+        Some((segment, segment.t_at(distance)))
+    }
+}
+
+/// A curve of a contour with its arc length table.
+#[derive(Clone, Debug)]
+struct MeasuredSegment {
+    curve: MeasuredCurve,
+    /// `(t, distance from the start of the contour)` pairs, sorted by `t`
+    table: Vec<(f64, f64)>,
+}
+
+impl MeasuredSegment {
+    fn start_distance(&self) -> f64 {
+        self.table[0].1
+    }
+
+    fn end_distance(&self) -> f64 {
+        self.table[self.table.len() - 1].1
+    }
+
+    /// Finds the curve parameter at the given distance from the start of the contour.
+    fn t_at(&self, distance: f64) -> f64 {
+        if distance <= self.start_distance() {
+            return 0.0;
+        }
+        if distance >= self.end_distance() {
+            return 1.0;
+        }
+        let idx = self.table.partition_point(|(_, d)| *d < distance).max(1);
+        let (t0, d0) = self.table[idx - 1];
+        let (t1, d1) = self.table[idx];
+        if d1 > d0 {
+            t0 + (t1 - t0) * (distance - d0) / (d1 - d0)
+        } else {
+            t0
+        }
+    }
+}
+
+/// The curves that can be measured, all parameterized on `[0, 1]`.
+#[derive(Clone, Copy, Debug)]
+enum MeasuredCurve {
+    Line(ParametricSegment),
+    Quadratic(ParametricQuadratic2dBezier),
+    Cubic(ParametricCubic2dBezier),
+    Arc(CenterParametricArc),
+}
+
+impl MeasuredCurve {
+    fn as_curve(&self) -> &dyn ParametricCurve {
+        match self {
+            MeasuredCurve::Line(curve) => curve,
+            MeasuredCurve::Quadratic(curve) => curve,
+            MeasuredCurve::Cubic(curve) => curve,
+            MeasuredCurve::Arc(curve) => curve,
+        }
+    }
+
+    fn eval(&self, t: f64) -> Vec2f64 {
+        self.as_curve().eval(t)
+    }
+
+    fn derivative(&self, t: f64) -> Vec2f64 {
+        self.as_curve().derivative(t)
+    }
+
+    /// The part of the curve between `t0` and `t1` as an absolute path command.
+    fn sub_curve(&self, t0: f64, t1: f64) -> PathCommand {
+        match self {
+            MeasuredCurve::Line(line) => PathCommand::LineTo(line.eval(t1)),
+            MeasuredCurve::Quadratic(q) => {
+                // blossoming: the control point of the sub-curve is B(t0, t1)
+                let blossom = |u: f64, v: f64| lerp(lerp(q.p0, q.p1, u), lerp(q.p1, q.p2, u), v);
+                PathCommand::QuadraticBezierCurveTo {
+                    control_pt: blossom(t0, t1),
+                    end_pt: blossom(t1, t1),
+                }
+            }
+            MeasuredCurve::Cubic(c) => {
+                let blossom = |u: f64, v: f64, w: f64| {
+                    let a = [lerp(c.p0, c.p1, u), lerp(c.p1, c.p2, u), lerp(c.p2, c.p3, u)];
+                    let b = [lerp(a[0], a[1], v), lerp(a[1], a[2], v)];
+                    lerp(b[0], b[1], w)
+                };
+                PathCommand::CubicBezierTo {
+                    control_pt_1: blossom(t0, t0, t1),
+                    control_pt_2: blossom(t0, t1, t1),
+                    end_pt: blossom(t1, t1, t1),
+                }
+            }
+            MeasuredCurve::Arc(arc) => {
+                let sweep = arc.sweep * (t1 - t0);
+                PathCommand::EllipticalArcTo {
+                    radii: arc.radii,
+                    x_axis_rotation: arc.x_axis_rotation,
+                    large_arc_flag: sweep.abs() > std::f64::consts::PI,
+                    sweep_flag: sweep > 0.0,
+                    end_pt: arc.eval(t1),
+                }
+            }
+        }
+    }
+}
+
+/// Accumulates the segments of a contour.
+struct ContourBuilder {
+    tolerance: f64,
+    segments: Vec<MeasuredSegment>,
+    length: f64,
+}
+
+impl ContourBuilder {
+    fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            segments: Vec::new(),
+            length: 0.0,
+        }
+    }
+
+    fn push(&mut self, curve: MeasuredCurve) {
+        let mut table = vec![(0.0, self.length)];
+        let start = curve.eval(0.0);
+        let end = curve.eval(1.0);
+        if !start.x.is_finite() || !start.y.is_finite() || !end.x.is_finite() || !end.y.is_finite() {
+            return;
+        }
+        match curve {
+            MeasuredCurve::Line(_) => table.push((1.0, self.length + (end - start).norm())),
+            _ => measure_curve(curve.as_curve(), 0.0, 1.0, start, end, self.tolerance, 0, &mut table),
+        }
+
+        let length = table[table.len() - 1].1;
+        if length > self.length {
+            self.length = length;
+            self.segments.push(MeasuredSegment { curve, table });
+        }
+    }
+
+    /// Returns the contour, if it is not empty, and starts a new one.
+    fn finish(&mut self, closed: bool) -> Option<ContourMeasure> {
+        let segments = std::mem::take(&mut self.segments);
+        let length = std::mem::replace(&mut self.length, 0.0);
+        if segments.is_empty() {
+            None
+        } else {
+            Some(ContourMeasure { segments, length, closed })
+        }
+    }
+}
+
+/// Recursively approximates the arc length of a curve between `t0` and `t1`,
+/// appending the `(t, distance)` pairs to the table.
+///
+/// The interval is split until the polyline through the midpoint is within `tolerance` of the chord,
+/// the length of each piece is then estimated from the two.
+#[allow(clippy::too_many_arguments)]
+fn measure_curve(
+    curve: &dyn ParametricCurve,
+    t0: f64,
+    t1: f64,
+    p0: Vec2f64,
+    p1: Vec2f64,
+    tolerance: f64,
+    depth: u32,
+    table: &mut Vec<(f64, f64)>,
+) {
+    const MIN_DEPTH: u32 = 2;
+    const MAX_DEPTH: u32 = 16;
+
+    let tm = (t0 + t1) * 0.5;
+    let pm = curve.eval(tm);
+    let chord = (p1 - p0).norm();
+    let polyline = (pm - p0).norm() + (p1 - pm).norm();
+    if depth >= MAX_DEPTH || (depth >= MIN_DEPTH && polyline - chord <= tolerance) {
+        // Richardson extrapolation of the chord and polyline lengths
+        let length = polyline + (polyline - chord) / 3.0;
+        let scale = if polyline > 0.0 { length / polyline } else { 0.0 };
+        let distance = table[table.len() - 1].1;
+        table.push((tm, distance + (pm - p0).norm() * scale));
+        table.push((t1, distance + length));
+        return;
+    }
+    measure_curve(curve, t0, tm, p0, pm, tolerance, depth + 1, table);
+    measure_curve(curve, tm, t1, pm, p1, tolerance, depth + 1, table);
+}
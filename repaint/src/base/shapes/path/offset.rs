@@ -0,0 +1,152 @@
+use crate::base::clipping::boolean::{contours_to_path, contours_to_polyline, simplify_contours};
+use crate::base::defs::{fill_rule::FillRule, linalg::*};
+use crate::base::shapes::polyline::BrokenPolylineCommand;
+use crate::base::transform::Transform2d;
+
+use super::{
+    discretization::{DiscretizationParams, PathDiscretizer},
+    PathCommand,
+};
+
+/// How the corners of an offset contour are joined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OffsetJoin {
+    /// The offset edges are extended until they meet.
+    ///
+    /// If the ratio between the length of the miter and the offset distance exceeds `limit`,
+    /// a [`OffsetJoin::Bevel`] join is used instead.
+    Miter { limit: f64 },
+
+    /// The corner is rounded with an arc centered on the original vertex.
+    Round,
+
+    /// The ends of the offset edges are connected with a straight line.
+    Bevel,
+}
+
+impl Default for OffsetJoin {
+    fn default() -> Self {
+        OffsetJoin::Miter { limit: 4.0 }
+    }
+}
+
+/// Offsets a closed path by a signed distance.
+///
+/// A positive `distance` grows the filled area (outset), a negative one shrinks it (inset).
+/// The path is flattened using `params`, interpreted with the given fill rule and every
+/// contour is implicitly closed. The tolerance of `params` is also used for round joins.
+///
+/// This is different from stroking: the result is the boundary of the area within `distance`
+/// of the shape (or of its complement when insetting), so it never self-intersects and the
+/// features thinner than `2 * |distance|` disappear when insetting. The result is empty if
+/// `distance` is not finite, an infinite outset can't be represented by a path.
+pub fn offset_path(
+    path: &[PathCommand],
+    fill_rule: FillRule,
+    distance: f64,
+    join: OffsetJoin,
+    params: DiscretizationParams,
+) -> Vec<PathCommand> {
+    let discretizer = PathDiscretizer::new(params, &Transform2d::Identity);
+    let polyline: Vec<BrokenPolylineCommand> = discretizer.discretize(&mut path.iter()).collect();
+    contours_to_path(&offset_contours(&polyline, fill_rule, distance, join, params.tolerance))
+}
+
+/// Offsets a set of closed contours by a signed distance, see [`offset_path`].
+///
+/// `tolerance` is the maximum distance between a round join and the approximating polyline.
+/// The result follows the same orientation rules of [`boolean_operation`](crate::base::clipping::boolean::boolean_operation),
+/// it is empty if `distance` is not finite and it is the simplified input if `distance` is zero.
+pub fn offset_contours(
+    commands: &[BrokenPolylineCommand],
+    fill_rule: FillRule,
+    distance: f64,
+    join: OffsetJoin,
+    tolerance: f64,
+) -> Vec<Vec<Vec2f64>> {
+    if !distance.is_finite() {
+        return Vec::new();
+    }
+    // normalize the input: non-overlapping contours with the inside on their left
+    let contours = simplify_contours(commands, |winding| fill_rule.is_inside(winding));
+    if distance == 0.0 {
+        return contours;
+    }
+
+    let raw: Vec<Vec<Vec2f64>> = contours
+        .iter()
+        .map(|contour| offset_contour(contour, distance, join, tolerance))
+        .collect();
+
+    // The raw contours overlap themselves where the offset edges cross. Since the inside
+    // is on the left of every edge, the regions that collapsed (when insetting) or that were
+    // swallowed by the offset (when outsetting) have a winding number lower than one.
+    simplify_contours(&contours_to_polyline(&raw), |winding| winding > 0)
+}
+
+/// Computes the raw offset of a single contour whose inside is on the left.
+fn offset_contour(contour: &[Vec2f64], distance: f64, join: OffsetJoin, tolerance: f64) -> Vec<Vec2f64> {
+    let n = contour.len();
+    let mut result = Vec::with_capacity(n * 2);
+
+    // unit normals on the right of each edge, i.e. pointing outside
+    let normals: Vec<Vec2f64> = (0..n)
+        .map(|i| {
+            let d = contour[(i + 1) % n] - contour[i];
+            Vec2f64::new(d.y, -d.x).try_normalize(0.0).unwrap_or_else(Vec2f64::zeros)
+        })
+        .collect();
+
+    for i in 0..n {
+        let pt = contour[i];
+        let n1 = normals[(i + n - 1) % n];
+        let n2 = normals[i];
+        let e1 = pt - contour[(i + n - 1) % n];
+        let e2 = contour[(i + 1) % n] - pt;
+        let turn = e1.x * e2.y - e1.y * e2.x;
+
+        if turn * distance <= 0.0 {
+            // The offset edges overlap at this corner: connect them through the vertex itself,
+            // the small loop that is created has a negative winding and is removed later.
+            result.push(pt + n1 * distance);
+            if turn != 0.0 {
+                result.push(pt);
+            }
+            result.push(pt + n2 * distance);
+            continue;
+        }
+
+        match join {
+            OffsetJoin::Miter { limit } => {
+                let cos = n1.dot(&n2);
+                // the ratio between the miter length and the distance is 1 / cos(angle / 2)
+                if (1.0 + cos) * limit * limit >= 2.0 {
+                    result.push(pt + (n1 + n2) * (distance / (1.0 + cos)));
+                } else {
+                    result.push(pt + n1 * distance);
+                    result.push(pt + n2 * distance);
+                }
+            }
+            OffsetJoin::Bevel => {
+                result.push(pt + n1 * distance);
+                result.push(pt + n2 * distance);
+            }
+            OffsetJoin::Round => {
+                let radius = distance.abs();
+                let start = n1 * distance;
+                let angle = (n1.x * n2.y - n1.y * n2.x).atan2(n1.dot(&n2));
+                let max_step = if tolerance > 0.0 && tolerance < radius {
+                    2.0 * (1.0 - tolerance / radius).acos()
+                } else {
+                    std::f64::consts::FRAC_PI_2
+                };
+                let steps = ((angle.abs() / max_step).ceil() as usize).clamp(1, 1024);
+                let rotation = |a: f64| Mat2f64::new(a.cos(), -a.sin(), a.sin(), a.cos());
+                for step in 0..=steps {
+                    result.push(pt + rotation(angle * step as f64 / steps as f64) * start);
+                }
+            }
+        }
+    }
+    result
+}
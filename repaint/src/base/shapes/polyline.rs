@@ -1,4 +1,9 @@
-use crate::base::defs::linalg::*;
+use crate::base::defs::{linalg::*, rect::F64Rect};
+
+use super::{path::PathCommand, BasicShape, Shape};
+
+pub mod simplification;
+pub mod fitting;
 
 /// A command for a broken polyline, i.e. multiple detached polylines.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -9,3 +14,250 @@ pub enum BrokenPolylineCommand {
     /// Make a line to a point.
     LineTo(Vec2f64),
 }
+
+impl BrokenPolylineCommand {
+    /// The point of the command.
+    pub fn point(&self) -> Vec2f64 {
+        match self {
+            BrokenPolylineCommand::MoveTo(pt) | BrokenPolylineCommand::LineTo(pt) => *pt,
+        }
+    }
+}
+
+impl From<BrokenPolylineCommand> for PathCommand {
+    fn from(command: BrokenPolylineCommand) -> Self {
+        match command {
+            BrokenPolylineCommand::MoveTo(pt) => PathCommand::MoveTo(pt),
+            BrokenPolylineCommand::LineTo(pt) => PathCommand::LineTo(pt),
+        }
+    }
+}
+
+/// Splits a broken polyline into its connected polylines.
+///
+/// A [`BrokenPolylineCommand::LineTo`] at the beginning starts from the origin.
+/// Polylines made of a single point are kept.
+pub fn split_polylines(commands: &[BrokenPolylineCommand]) -> Vec<Vec<Vec2f64>> {
+    let mut polylines: Vec<Vec<Vec2f64>> = Vec::new();
+    for command in commands {
+        match command {
+            BrokenPolylineCommand::MoveTo(pt) => polylines.push(vec![*pt]),
+            BrokenPolylineCommand::LineTo(pt) => match polylines.last_mut() {
+                Some(polyline) => polyline.push(*pt),
+                None => polylines.push(vec![Vec2f64::zeros(), *pt]),
+            },
+        }
+    }
+    polylines
+}
+
+/// The bounding box of a broken polyline, `None` if it is empty.
+///
+/// A [`BrokenPolylineCommand::LineTo`] at the beginning starts from the origin, that is included.
+pub fn polyline_bounds(commands: &[BrokenPolylineCommand]) -> Option<F64Rect> {
+    let first = match commands.first()? {
+        BrokenPolylineCommand::MoveTo(pt) => *pt,
+        BrokenPolylineCommand::LineTo(_) => Vec2f64::zeros(),
+    };
+    let (min, max) = commands
+        .iter()
+        .map(BrokenPolylineCommand::point)
+        .fold((first, first), |(min, max), pt| (min.inf(&pt), max.sup(&pt)));
+    Some(F64Rect::new(min, max))
+}
+
+/// The total length of the lines of a broken polyline.
+pub fn polyline_length(commands: &[BrokenPolylineCommand]) -> f64 {
+    split_polylines(commands)
+        .iter()
+        .map(|points| points_length(points))
+        .sum()
+}
+
+/// Reverses a broken polyline: the polylines are in reverse order and each one is reversed.
+pub fn reverse_polyline(commands: &[BrokenPolylineCommand]) -> Vec<BrokenPolylineCommand> {
+    let mut result = Vec::with_capacity(commands.len() + 1);
+    for points in split_polylines(commands).iter().rev() {
+        let mut points = points.iter().rev();
+        if let Some(first) = points.next() {
+            result.push(BrokenPolylineCommand::MoveTo(*first));
+            result.extend(points.map(|pt| BrokenPolylineCommand::LineTo(*pt)));
+        }
+    }
+    result
+}
+
+/// Returns true if the polyline ends where it starts, within `tolerance`.
+///
+/// A polyline with less than 3 points is never closed.
+pub fn is_polyline_closed(points: &[Vec2f64], tolerance: f64) -> bool {
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) if points.len() >= 3 => (last - first).norm() <= tolerance,
+        _ => false,
+    }
+}
+
+/// The maximum number of points of [`resample_points`].
+pub const MAX_RESAMPLED_POINTS: usize = 1 << 20;
+
+/// Resamples a polyline with points at uniform distance along it.
+///
+/// The first and last points are kept, so the last interval can be shorter than `spacing`.
+/// Corners between the samples are cut.
+///
+/// ## Notes
+///  - the segments of infinite or NaN length are skipped, the samples start again after them
+///  - the spacing is widened if the polyline would need more than [`MAX_RESAMPLED_POINTS`]
+pub fn resample_points(points: &[Vec2f64], spacing: f64) -> Vec<Vec2f64> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Vec::new(),
+    };
+    if spacing.is_nan() || spacing <= 0.0 {
+        return points.to_vec();
+    }
+
+    let segment_length = |segment: &[Vec2f64]| (segment[1] - segment[0]).norm();
+    let total: f64 = points.windows(2).map(segment_length).filter(|length| length.is_finite()).sum();
+    let spacing = spacing.max(total / MAX_RESAMPLED_POINTS as f64);
+
+    let mut result = vec![first];
+    // distance along the polyline of the next sample, relative to the start of the current segment
+    let mut next = spacing;
+    for segment in points.windows(2) {
+        let length = segment_length(segment);
+        if !length.is_finite() {
+            next = spacing;
+            continue;
+        }
+        while next < length {
+            result.push(segment[0] + (segment[1] - segment[0]) * (next / length));
+            next += spacing;
+        }
+        next -= length;
+    }
+    // avoid a tiny last interval due to rounding
+    let gap = (last - result[result.len() - 1]).norm();
+    if result.len() == 1 || gap.is_nan() || gap > spacing * 1e-9 {
+        result.push(last);
+    } else {
+        let end = result.len() - 1;
+        result[end] = last;
+    }
+    result
+}
+
+fn points_length(points: &[Vec2f64]) -> f64 {
+    points.windows(2).map(|segment| (segment[1] - segment[0]).norm()).sum()
+}
+
+/// A broken polyline, e.g. the output of a [`PathDiscretizer`](super::path::discretization::PathDiscretizer).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polyline(pub Vec<BrokenPolylineCommand>);
+
+impl Polyline {
+    /// Creates an empty polyline.
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Creates a single polyline through the given points.
+    pub fn from_points(points: impl IntoIterator<Item = Vec2f64>) -> Self {
+        let mut commands = Vec::new();
+        for pt in points {
+            commands.push(if commands.is_empty() {
+                BrokenPolylineCommand::MoveTo(pt)
+            } else {
+                BrokenPolylineCommand::LineTo(pt)
+            });
+        }
+        Self(commands)
+    }
+
+    /// The commands of the polyline.
+    pub fn commands(&self) -> &[BrokenPolylineCommand] {
+        &self.0
+    }
+
+    /// The connected polylines, see [`split_polylines`].
+    pub fn polylines(&self) -> Vec<Vec<Vec2f64>> {
+        split_polylines(&self.0)
+    }
+
+    /// The bounding box, `None` if the polyline is empty.
+    pub fn bounds(&self) -> Option<F64Rect> {
+        polyline_bounds(&self.0)
+    }
+
+    /// The total length of the lines.
+    pub fn length(&self) -> f64 {
+        polyline_length(&self.0)
+    }
+
+    /// The reversed polyline, see [`reverse_polyline`].
+    pub fn reversed(&self) -> Self {
+        Self(reverse_polyline(&self.0))
+    }
+
+    /// Returns true if it is not empty and every connected polyline is closed, see [`is_polyline_closed`].
+    pub fn is_closed(&self, tolerance: f64) -> bool {
+        let polylines = self.polylines();
+        !polylines.is_empty() && polylines.iter().all(|points| is_polyline_closed(points, tolerance))
+    }
+
+    /// Resamples each connected polyline, see [`resample_points`].
+    pub fn resample(&self, spacing: f64) -> Self {
+        let mut commands = Vec::new();
+        for points in self.polylines() {
+            let mut points = resample_points(&points, spacing).into_iter();
+            if let Some(first) = points.next() {
+                commands.push(BrokenPolylineCommand::MoveTo(first));
+                commands.extend(points.map(BrokenPolylineCommand::LineTo));
+            }
+        }
+        Self(commands)
+    }
+
+    /// Converts to path commands, the connected polylines closed within `tolerance` get a
+    /// [`PathCommand::ClosePath`] so that they are stroked with a join instead of caps.
+    pub fn to_closed_path(&self, tolerance: f64) -> Vec<PathCommand> {
+        let mut path = Vec::with_capacity(self.0.len());
+        for points in self.polylines() {
+            let closed = is_polyline_closed(&points, tolerance);
+            let count = if closed { points.len() - 1 } else { points.len() };
+            path.extend(Polyline::from_points(points[..count].iter().copied()).0.into_iter().map(PathCommand::from));
+            if closed {
+                path.push(PathCommand::ClosePath);
+            }
+        }
+        path
+    }
+}
+
+impl From<Vec<BrokenPolylineCommand>> for Polyline {
+    fn from(commands: Vec<BrokenPolylineCommand>) -> Self {
+        Self(commands)
+    }
+}
+
+impl From<Polyline> for BasicShape {
+    fn from(polyline: Polyline) -> Self {
+        BasicShape::Polyline(polyline.0)
+    }
+}
+
+impl Shape for Polyline {
+    type Iter = std::iter::Map<std::vec::IntoIter<BrokenPolylineCommand>, fn(BrokenPolylineCommand) -> PathCommand>;
+
+    fn bounding_box(&self) -> F64Rect {
+        self.bounds().unwrap_or_else(|| F64Rect::new(Vec2f64::zeros(), Vec2f64::zeros()))
+    }
+
+    fn to_basic_shape(&self) -> Option<BasicShape> {
+        Some(BasicShape::Polyline(self.0.clone()))
+    }
+
+    fn to_path_iter(&self) -> Self::Iter {
+        self.0.clone().into_iter().map(PathCommand::from)
+    }
+}
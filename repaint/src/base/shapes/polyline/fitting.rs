@@ -0,0 +1,213 @@
+/*!
+Curve fitting.
+
+Turns polylines into smooth sequences of cubic Bézier curves using the least-squares algorithm by
+*Philip J. Schneider* ("An Algorithm for Automatically Fitting Digitized Curves", Graphics Gems, 1990):
+ 1. the points are parameterized by their chord length;
+ 2. a cubic Bézier with the estimated end tangents is fitted to the points with least-squares;
+ 3. if the error is slightly too large, the parameterization is improved with a few *Newton-Raphson* steps;
+ 4. otherwise the points are split at the point of maximum error and the two halves are fitted separately,
+    sharing the tangent at the split point so that the result is smooth.
+*/
+
+use crate::base::defs::linalg::*;
+use crate::base::shapes::path::{
+    discretization::{curves::ParametricCubic2dBezier, ParametricCurve},
+    PathCommand,
+};
+
+use super::{split_polylines, BrokenPolylineCommand};
+
+/// Fits each polyline of a broken polyline with cubic Bézier curves, see [`fit_cubic_beziers`].
+pub fn fit_polyline(commands: &[BrokenPolylineCommand], tolerance: f64) -> Vec<PathCommand> {
+    let mut result = Vec::new();
+    for polyline in split_polylines(commands) {
+        result.extend(fit_cubic_beziers(&polyline, tolerance));
+    }
+    result
+}
+
+/// Fits a polyline with a sequence of cubic Bézier curves.
+///
+/// Every point of the polyline is within `tolerance` of the resulting curves, that are smoothly
+/// connected. The result starts with a [`PathCommand::MoveTo`] followed by
+/// [`PathCommand::CubicBezierTo`] commands, it is empty if there are no points.
+///
+/// ## Notes
+///  - consecutive duplicate points are ignored
+///  - the distance is measured between each point and the curve at its (refined) parameter,
+///    so it is an upper bound of the real distance
+pub fn fit_cubic_beziers(points: &[Vec2f64], tolerance: f64) -> Vec<PathCommand> {
+    let mut pts: Vec<Vec2f64> = Vec::with_capacity(points.len());
+    for pt in points {
+        if pts.last() != Some(pt) && pt.x.is_finite() && pt.y.is_finite() {
+            pts.push(*pt);
+        }
+    }
+
+    let mut result = Vec::new();
+    match pts.len() {
+        0 => return result,
+        1 => {
+            result.push(PathCommand::MoveTo(pts[0]));
+            return result;
+        }
+        _ => result.push(PathCommand::MoveTo(pts[0])),
+    }
+
+    let last = pts.len() - 1;
+    let start_tangent = (pts[1] - pts[0]).normalize();
+    let end_tangent = (pts[last - 1] - pts[last]).normalize();
+
+    // explicit stack instead of recursion, the right half is pushed first so that
+    // the curves are produced in order
+    let mut stack = vec![(0, last, start_tangent, end_tangent)];
+    while let Some((first, last, t1, t2)) = stack.pop() {
+        match fit_cubic(&pts[first..=last], t1, t2, tolerance) {
+            Ok(curve) => result.push(PathCommand::CubicBezierTo {
+                control_pt_1: curve.p1,
+                control_pt_2: curve.p2,
+                end_pt: curve.p3,
+            }),
+            Err(split) => {
+                let split = first + split;
+                let center = (pts[split - 1] - pts[split + 1])
+                    .try_normalize(0.0)
+                    .unwrap_or_else(|| (pts[split - 1] - pts[split]).normalize());
+                stack.push((split, last, -center, t2));
+                stack.push((first, split, t1, center));
+            }
+        }
+    }
+    result
+}
+
+/// Fits a single cubic Bézier to the points with the given end tangents.
+///
+/// Returns the index of the point where the points should be split if the error is too large.
+fn fit_cubic(
+    points: &[Vec2f64],
+    t1: Vec2f64,
+    t2: Vec2f64,
+    tolerance: f64,
+) -> Result<ParametricCubic2dBezier, usize> {
+    const MAX_ITERATIONS: usize = 4;
+
+    let first = points[0];
+    let last = points[points.len() - 1];
+    if points.len() == 2 {
+        let dist = (last - first).norm() / 3.0;
+        return Ok(ParametricCubic2dBezier::new(first, first + t1 * dist, last + t2 * dist, last));
+    }
+
+    let tolerance_sqr = tolerance * tolerance;
+    let mut u = chord_length_parameterize(points);
+    let mut curve = generate_bezier(points, &u, t1, t2);
+    let (mut max_error, mut split) = max_error(points, &curve, &u);
+    if max_error < tolerance_sqr {
+        return Ok(curve);
+    }
+
+    // if the error is not too large, try to improve the parameterization
+    if max_error < tolerance_sqr * 4.0 {
+        for _ in 0..MAX_ITERATIONS {
+            reparameterize(points, &curve, &mut u);
+            curve = generate_bezier(points, &u, t1, t2);
+            (max_error, split) = self::max_error(points, &curve, &u);
+            if max_error < tolerance_sqr {
+                return Ok(curve);
+            }
+        }
+    }
+
+    Err(split)
+}
+
+/// Assigns a parameter in `[0, 1]` to each point, proportional to the length of the polyline.
+fn chord_length_parameterize(points: &[Vec2f64]) -> Vec<f64> {
+    let mut u = Vec::with_capacity(points.len());
+    u.push(0.0);
+    for i in 1..points.len() {
+        u.push(u[i - 1] + (points[i] - points[i - 1]).norm());
+    }
+    let total = u[u.len() - 1];
+    for value in u.iter_mut() {
+        *value /= total;
+    }
+    u
+}
+
+/// Finds the least-squares cubic Bézier with the given end points and tangents.
+fn generate_bezier(points: &[Vec2f64], u: &[f64], t1: Vec2f64, t2: Vec2f64) -> ParametricCubic2dBezier {
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    let mut c = [[0.0; 2]; 2];
+    let mut x = [0.0; 2];
+    for (pt, &u) in points.iter().zip(u) {
+        let mu = 1.0 - u;
+        let (b0, b1, b2, b3) = (mu * mu * mu, 3.0 * u * mu * mu, 3.0 * u * u * mu, u * u * u);
+        let a1 = t1 * b1;
+        let a2 = t2 * b2;
+        c[0][0] += a1.dot(&a1);
+        c[0][1] += a1.dot(&a2);
+        c[1][1] += a2.dot(&a2);
+        let tmp = pt - (first * (b0 + b1) + last * (b2 + b3));
+        x[0] += a1.dot(&tmp);
+        x[1] += a2.dot(&tmp);
+    }
+    c[1][0] = c[0][1];
+
+    let det_c0_c1 = c[0][0] * c[1][1] - c[1][0] * c[0][1];
+    let det_c0_x = c[0][0] * x[1] - c[1][0] * x[0];
+    let det_x_c1 = x[0] * c[1][1] - x[1] * c[0][1];
+    let (alpha_l, alpha_r) = if det_c0_c1 == 0.0 {
+        (0.0, 0.0)
+    } else {
+        (det_x_c1 / det_c0_c1, det_c0_x / det_c0_c1)
+    };
+
+    // If the solution is degenerate (or the control points would go backwards),
+    // fall back to the Wu/Barsky heuristic.
+    let segment_length = (last - first).norm();
+    let epsilon = 1e-6 * segment_length;
+    let (alpha_l, alpha_r) = if alpha_l < epsilon || alpha_r < epsilon {
+        (segment_length / 3.0, segment_length / 3.0)
+    } else {
+        (alpha_l, alpha_r)
+    };
+
+    ParametricCubic2dBezier::new(first, first + t1 * alpha_l, last + t2 * alpha_r, last)
+}
+
+/// Returns the maximum squared distance between the points and the curve, and the index of the
+/// farthest point, excluding the endpoints.
+fn max_error(points: &[Vec2f64], curve: &ParametricCubic2dBezier, u: &[f64]) -> (f64, usize) {
+    let mut max_dist = 0.0;
+    let mut split = points.len() / 2;
+    for i in 1..(points.len() - 1) {
+        let dist = (curve.eval(u[i]) - points[i]).norm_squared();
+        if dist >= max_dist {
+            max_dist = dist;
+            split = i;
+        }
+    }
+    (max_dist, split)
+}
+
+/// Improves the parameters with a Newton-Raphson step towards the closest points on the curve.
+fn reparameterize(points: &[Vec2f64], curve: &ParametricCubic2dBezier, u: &mut [f64]) {
+    let second_derivative = |t: f64| {
+        (curve.p2 - curve.p1 * 2.0 + curve.p0) * 6.0 * (1.0 - t)
+            + (curve.p3 - curve.p2 * 2.0 + curve.p1) * 6.0 * t
+    };
+    for (pt, u) in points.iter().zip(u.iter_mut()) {
+        let diff = curve.eval(*u) - pt;
+        let d1 = curve.derivative(*u);
+        let d2 = second_derivative(*u);
+        let denominator = d1.dot(&d1) + diff.dot(&d2);
+        if denominator != 0.0 {
+            *u = (*u - diff.dot(&d1) / denominator).clamp(0.0, 1.0);
+        }
+    }
+}
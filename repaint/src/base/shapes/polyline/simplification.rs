@@ -0,0 +1,194 @@
+/*!
+Polyline simplification.
+
+Dense polylines, for example the samples recorded by a freehand drawing tool, can be reduced to
+far fewer points with a small visual difference. Two classic algorithms are provided:
+ - [*Ramer–Douglas–Peucker*](https://en.wikipedia.org/wiki/Ramer%E2%80%93Douglas%E2%80%93Peucker_algorithm):
+   keeps the points farther than a tolerance from the simplified polyline, it preserves the shape
+   within a known distance;
+ - [*Visvalingam–Whyatt*](https://en.wikipedia.org/wiki/Visvalingam%E2%80%93Whyatt_algorithm):
+   repeatedly removes the point forming the smallest triangle with its neighbors, it tends to
+   produce smoother results.
+
+The first and last points of each polyline are always kept.
+*/
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::base::defs::linalg::*;
+
+use super::{split_polylines, BrokenPolylineCommand};
+
+/// The algorithm used to simplify a polyline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimplificationMethod {
+    /// Ramer–Douglas–Peucker, see [`ramer_douglas_peucker`].
+    RamerDouglasPeucker {
+        /// The maximum distance between the original points and the simplified polyline.
+        tolerance: f64,
+    },
+
+    /// Visvalingam–Whyatt, see [`visvalingam_whyatt`].
+    Visvalingam {
+        /// Points forming a triangle with a smaller area are removed.
+        min_area: f64,
+    },
+}
+
+impl SimplificationMethod {
+    /// Simplifies a single polyline.
+    pub fn simplify(&self, points: &[Vec2f64]) -> Vec<Vec2f64> {
+        match self {
+            SimplificationMethod::RamerDouglasPeucker { tolerance } => ramer_douglas_peucker(points, *tolerance),
+            SimplificationMethod::Visvalingam { min_area } => visvalingam_whyatt(points, *min_area),
+        }
+    }
+}
+
+/// Simplifies each polyline of a broken polyline with the given method.
+pub fn simplify_polyline(
+    commands: &[BrokenPolylineCommand],
+    method: SimplificationMethod,
+) -> Vec<BrokenPolylineCommand> {
+    let mut result = Vec::new();
+    for polyline in split_polylines(commands) {
+        let mut points = method.simplify(&polyline).into_iter();
+        if let Some(first) = points.next() {
+            result.push(BrokenPolylineCommand::MoveTo(first));
+            result.extend(points.map(BrokenPolylineCommand::LineTo));
+        }
+    }
+    result
+}
+
+/// Simplifies a polyline with the Ramer–Douglas–Peucker algorithm.
+///
+/// Every removed point is within `tolerance` of the resulting polyline.
+///
+/// ## Notes
+///  - the implementation is iterative, it does not recurse
+pub fn ramer_douglas_peucker(points: &[Vec2f64], tolerance: f64) -> Vec<Vec2f64> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let (mut max_dist, mut max_idx) = (0.0, first);
+        for idx in (first + 1)..last {
+            let dist = if points[first] == points[last] {
+                (points[idx] - points[first]).norm()
+            } else {
+                dist_to_line_segment(&points[idx], &points[first], &points[last])
+            };
+            if dist > max_dist {
+                max_dist = dist;
+                max_idx = idx;
+            }
+        }
+        if max_dist > tolerance {
+            keep[max_idx] = true;
+            stack.push((first, max_idx));
+            stack.push((max_idx, last));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(pt, keep)| if keep { Some(*pt) } else { None })
+        .collect()
+}
+
+/// Simplifies a polyline with the Visvalingam–Whyatt algorithm.
+///
+/// The point forming the triangle with the smallest area with its neighbors is removed until
+/// every remaining triangle has an area of at least `min_area`.
+pub fn visvalingam_whyatt(points: &[Vec2f64], min_area: f64) -> Vec<Vec2f64> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+
+    let n = points.len();
+    let mut prev: Vec<usize> = (0..n).map(|i| i.wrapping_sub(1)).collect();
+    let mut next: Vec<usize> = (1..=n).collect();
+    let mut areas = vec![f64::INFINITY; n];
+    let mut heap = BinaryHeap::new();
+
+    let triangle_area = |a: usize, b: usize, c: usize| {
+        let u = points[b] - points[a];
+        let v = points[c] - points[a];
+        (u.x * v.y - u.y * v.x).abs() * 0.5
+    };
+
+    for (i, area) in areas.iter_mut().enumerate().take(n - 1).skip(1) {
+        *area = triangle_area(i - 1, i, i + 1);
+        heap.push(Candidate { area: *area, idx: i });
+    }
+
+    let mut removed = vec![false; n];
+    while let Some(Candidate { area, idx }) = heap.pop() {
+        // skip stale entries
+        if removed[idx] || area != areas[idx] {
+            continue;
+        }
+        if area >= min_area {
+            break;
+        }
+        removed[idx] = true;
+
+        let (p, q) = (prev[idx], next[idx]);
+        next[p] = q;
+        prev[q] = p;
+
+        // The area of a neighbor cannot become smaller than the one just removed,
+        // otherwise it would be removed before points that were already visited.
+        for neighbor in [p, q] {
+            if neighbor != 0 && neighbor != n - 1 {
+                let new_area = triangle_area(prev[neighbor], neighbor, next[neighbor]).max(area);
+                areas[neighbor] = new_area;
+                heap.push(Candidate { area: new_area, idx: neighbor });
+            }
+        }
+    }
+
+    points
+        .iter()
+        .zip(removed)
+        .filter_map(|(pt, removed)| if removed { None } else { Some(*pt) })
+        .collect()
+}
+
+/// An entry of the Visvalingam min-heap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Candidate {
+    area: f64,
+    idx: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed to make the std max-heap a min-heap
+        other.area.total_cmp(&self.area).then(other.idx.cmp(&self.idx))
+    }
+}
+
+/// Distance between a point and a segment, the projection is clamped to the segment endpoints.
+fn dist_to_line_segment(pt: &Vec2f64, a: &Vec2f64, b: &Vec2f64) -> f64 {
+    let ab = b - a;
+    let t = ((pt - a).dot(&ab) / ab.dot(&ab)).clamp(0.0, 1.0);
+    (pt - (a + ab * t)).norm()
+}
@@ -14,7 +14,7 @@ use methods::*;
 pub use text::*;
 use strum::EnumIter;
 
-use crate::{Canvas, base::{shapes::{path::{PathCommand}, Shape}, transform::Transform2d, defs::{linalg::Vec2f64, rect::F64Rect, colors::{ColorType}}, paint::{Ink, Paint}, pen::Pen, blending::BlendMode}};
+use crate::{Canvas, base::{shapes::{path::{PathCommand}, Shape, BasicShape}, transform::Transform2d, defs::{linalg::{Vec2f32, Vec2f64}, rect::{F64Rect, Rect, TlTrBlBr}, colors::{ColorType, default_color_types::RgbaFColor}, vertex_mode::VertexMode}, paint::{Ink, Paint}, pen::Pen, blending::BlendMode, rasterization::algorithms::triangle}};
 
 // TODO move?
 #[derive(Debug, Clone)] // TODO see https://api.skia.org/classSkCanvas.html#a06bd76ce35082366bb6b8e6dfcb6f435
@@ -31,9 +31,17 @@ pub enum ClipOperation {
     Intersect,
 }
 
+/// How [`BasicPainter::points`] draws the points.
 pub enum PointMode {
+    /// Each point is drawn separately, shaped by the pen cap: a circle for
+    /// [`PenCap::Round`](crate::base::pen::PenCap::Round), a square otherwise,
+    /// both as large as the stroke width.
     Points,
+
+    /// Each pair of points is drawn as a line, the last point is ignored if the count is odd.
     Lines,
+
+    /// The points are drawn as an open polyline.
     Polygon,
 }
 
@@ -109,12 +117,34 @@ pub trait BasicPainter {
         style: PaintStyle<Self::NativeColor>,
     );
 
+    /// Draw a set of points, see [`PointMode`].
+    ///
+    /// The points are always drawn with a pen, a default one with the paint for fill styles.
     fn points(
         &mut self,
         points: impl IntoIterator<Item = Vec2f64>,
         style: PaintStyle<Self::NativeColor>,
         point_mode: PointMode,
-    );
+    ) {
+        let pen = style.to_pen();
+        match point_mode {
+            PointMode::Points => {
+                for pos in points {
+                    let shape = point_shape(pos, pen.cap, &pen.stroke_width);
+                    self.draw_path_iter(&mut shape.to_path_iter(), PaintStyle::Fill(pen.paint.clone()));
+                }
+            }
+            PointMode::Lines => {
+                let mut points = points.into_iter();
+                while let (Some(start), Some(end)) = (points.next(), points.next()) {
+                    self.line(start, end, pen.clone());
+                }
+            }
+            PointMode::Polygon => {
+                self.draw_path_iter(&mut points_to_path(points, false), PaintStyle::Stroke(pen));
+            }
+        }
+    }
 
     fn line(
         &mut self,
@@ -154,6 +184,123 @@ pub trait BasicPainter {
         );
     }
 
+    /// Draw a circle.
+    fn circle(
+        &mut self,
+        center: Vec2f64,
+        radius: f64,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.draw_path_iter(
+            &mut BasicShape::Circle { center, radius }.to_path_iter(),
+            style,
+        );
+    }
+
+    /// Draw an axis-aligned ellipse.
+    fn ellipse(
+        &mut self,
+        center: Vec2f64,
+        radii: Vec2f64,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.draw_path_iter(
+            &mut BasicShape::Ellipse { center, radii }.to_path_iter(),
+            style,
+        );
+    }
+
+    /// Draw a rectangle with elliptical corners, see [`BasicShape::RoundedRect`].
+    fn rounded_rect(
+        &mut self,
+        rect: F64Rect,
+        radii: TlTrBlBr<Vec2f64>,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.draw_path_iter(
+            &mut BasicShape::RoundedRect { rect, radii }.to_path_iter(),
+            style,
+        );
+    }
+
+    /// Draw an elliptical arc.
+    ///
+    /// If `use_center` is `true`, the arc is closed through the center (a pie slice),
+    /// otherwise it is left open and filling it closes it with a straight line (a chord).
+    /// The angles are in radians, see [`BasicShape`].
+    fn arc(
+        &mut self,
+        center: Vec2f64,
+        radii: Vec2f64,
+        start_angle: f64,
+        sweep_angle: f64,
+        use_center: bool,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        let shape = if use_center {
+            BasicShape::Pie { center, radii, start_angle, sweep_angle }
+        } else {
+            BasicShape::Arc { center, radii, start_angle, sweep_angle }
+        };
+        self.draw_path_iter(&mut shape.to_path_iter(), style);
+    }
+
+    /// Draw a closed polygon through the given points.
+    fn polygon(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2f64>,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.draw_path_iter(&mut points_to_path(points, true), style);
+    }
+
+    /// Draw an open polyline through the given points.
+    fn polyline(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2f64>,
+        style: PaintStyle<Self::NativeColor>,
+    ) {
+        self.draw_path_iter(&mut points_to_path(points, false), style);
+    }
+
+    /// Draw a mesh of triangles, see [`VertexMode`].
+    ///
+    /// The vertices are given by their positions and, optionally, by their colors and texture
+    /// coordinates, one per position, the triangles are made by the vertices in order or by `indices`.
+    /// The colors are interpolated across the triangles and replace the ink of the paint, the texture
+    /// coordinates map the ink of the paint onto the triangles. The triangles with an index out of the
+    /// positions are not drawn.
+    ///
+    /// The default implementation fills each triangle as a polygon with the mean of the colors of its
+    /// vertices, the colors are not interpolated and the texture coordinates are ignored. The
+    /// painters that can interpolate should override it: the CPU painters with
+    /// [`RasterPainter::raster_vertices`], which interpolates the colors, while the Skia painter honors
+    /// the colors and the texture coordinates natively.
+    fn vertices(
+        &mut self,
+        positions: &[Vec2f64],
+        colors: Option<&[Self::NativeColor]>,
+        uvs: Option<&[Vec2f64]>,
+        indices: Option<&[u16]>,
+        mode: VertexMode,
+        paint: &Paint<Self::NativeColor>,
+    ) {
+        let _ = uvs; // not honored by the polygon fallback, see above
+        let colors = colors.filter(|colors| colors.len() == positions.len());
+        for triangle in mode.triangles(positions.len(), indices) {
+            if triangle.iter().any(|i| *i >= positions.len()) {
+                continue;
+            }
+            let mut paint = paint.clone();
+            if let Some(colors) = colors {
+                let colors = triangle.map(|i| colors[i].into());
+                let mean = triangle::interpolate_color(&colors, &[1.0 / 3.0; 3]);
+                paint.ink = Ink::Color(mean.into());
+            }
+            self.polygon(triangle.map(|i| positions[i]), PaintStyle::Fill(paint));
+        }
+    }
+
     // TODO ...
 }
 
@@ -167,6 +314,42 @@ pub trait RasterPainter: BasicPainter {
         ink: Ink<Self::NativeColor>,
     );
 
+    /// Draw a mesh of triangles pixel by pixel, with the colors interpolated by the
+    /// [`triangle`] rasterizer: a CPU painter can implement [`BasicPainter::vertices`] with it.
+    ///
+    /// The positions are in pixels and only the pixels inside `clip` (`max` is exclusive) are drawn,
+    /// with the interpolated colors or, without colors, with the ink of the paint. The texture
+    /// coordinates are ignored until [`Ink::Shader`] carries a shader that can be evaluated at them.
+    #[allow(clippy::too_many_arguments)]
+    fn raster_vertices(
+        &mut self,
+        positions: &[Vec2f64],
+        colors: Option<&[Self::NativeColor]>,
+        uvs: Option<&[Vec2f64]>,
+        indices: Option<&[u16]>,
+        mode: VertexMode,
+        paint: &Paint<Self::NativeColor>,
+        clip: &Rect<i32>,
+    ) {
+        let positions: Vec<Vec2f32> = positions.iter().map(|pos| pos.cast()).collect();
+        let colors: Option<Vec<RgbaFColor>> = colors.map(|colors| colors.iter().map(|color| (*color).into()).collect());
+        let _ = uvs; // ignored, see above
+        triangle::fill_mesh(
+            &positions,
+            colors.as_deref(),
+            None,
+            mode.triangles(positions.len(), indices),
+            &mut |x, y, fragment| {
+                let ink = match fragment.color {
+                    Some(color) => Ink::Color(color.into()),
+                    None => paint.ink.clone(),
+                };
+                self.pixel(Vec2f64::new(x as f64, y as f64), ink);
+            },
+            clip,
+        );
+    }
+
     // TODO other methods to efficiently draw pixels
 }
 
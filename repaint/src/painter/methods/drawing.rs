@@ -23,6 +23,14 @@ impl<Color: ColorType> PaintStyle<Color> {
             PaintStyle::Fill(paint) => paint,
         }
     }
+
+    /// The pen of the style, a default pen with the fill paint for [`PaintStyle::Fill`].
+    pub fn to_pen(&self) -> Pen<Color> {
+        match self {
+            PaintStyle::Stroke(pen) | Self::StrokeAndFill(pen) => pen.clone(),
+            PaintStyle::Fill(paint) => paint.clone().into(),
+        }
+    }
 }
 
 impl<Color> From<Color> for PaintStyle<Color> {
@@ -1,4 +1,4 @@
-use crate::base::{defs::rect::{F64Rect, VideoRect}, shapes::path::PathCommand};
+use crate::base::{defs::{linalg::Vec2f64, rect::{F64Rect, VideoRect}}, pen::{PenCap, StrokeWidth}, shapes::{path::PathCommand, BasicShape}};
 
 
 
@@ -13,4 +13,34 @@ pub fn rect_to_path(rect: F64Rect) -> [PathCommand; 5] {
         PathCommand::LineTo(rect.bottom_left()),
         PathCommand::ClosePath,
     ]
-}
\ No newline at end of file
+}
+
+/// Convert a sequence of points to a path, optionally closed.
+pub fn points_to_path(
+    points: impl IntoIterator<Item = Vec2f64>,
+    closed: bool,
+) -> impl Iterator<Item = PathCommand> {
+    points
+        .into_iter()
+        .enumerate()
+        .map(|(i, pt)| if i == 0 { PathCommand::MoveTo(pt) } else { PathCommand::LineTo(pt) })
+        .chain(closed.then_some(PathCommand::ClosePath))
+}
+
+/// The shape of a point drawn with a pen: a circle for round caps, a square otherwise.
+///
+/// The size is the stroke width, hairlines are 1 unit wide.
+pub fn point_shape(pos: Vec2f64, cap: PenCap, width: &StrokeWidth) -> BasicShape {
+    let width = match width {
+        StrokeWidth::Hairline => 1.0,
+        // TODO cosmetic widths should not be affected by the transform
+        StrokeWidth::Normal(width) | StrokeWidth::Cosmetic(width) => *width as f64,
+    };
+    match cap {
+        PenCap::Round => BasicShape::Circle { center: pos, radius: width / 2.0 },
+        PenCap::Butt | PenCap::Square => {
+            let half = Vec2f64::new(width / 2.0, width / 2.0);
+            BasicShape::Rect(F64Rect::new(pos - half, pos + half))
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! The coverage masks: rectangles, combinations, accumulated coverage and drawing through them.
+
+mod common;
+
+use common::{random_rect, Lcg};
+use nalgebra::Vector2;
+use repaint::base::defs::{antialiasing::AntialiasMode, fill_rule::FillRule, linalg::*, rect::{F64Rect, Rect}};
+use repaint::base::paint::Ink;
+use repaint::base::rasterization::algorithms::polygon_filling::scanline::LineSpanConsumer;
+use repaint::base::rasterization::mask::{A8Mask, AlphaMask, InkError};
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+
+const ROUNDS: usize = 100;
+
+fn bounds(min: (i32, i32), max: (i32, i32)) -> Rect<i32> {
+    Rect::new(Vector2::new(min.0, min.1), Vector2::new(max.0, max.1))
+}
+
+fn pixels(bounds: &Rect<i32>) -> impl Iterator<Item = (i32, i32)> {
+    let (min, max) = (bounds.min, bounds.max);
+    (min.y..max.y).flat_map(move |y| (min.x..max.x).map(move |x| (x, y)))
+}
+
+#[test]
+fn rect_coverage() {
+    let mut rng = Lcg(1);
+    let mask_bounds = bounds((-3, -2), (24, 25));
+    for _ in 0..ROUNDS {
+        let rect = random_rect(&mut rng, -5.0..20.0, 0.0..15.0);
+        let mask = AlphaMask::<f32>::from_rect(mask_bounds, &rect);
+        // the total coverage is the area of the rectangle inside the bounds
+        let visible = F64Rect::new(rect.min.sup(&mask_bounds.min.cast()), rect.max.inf(&mask_bounds.max.cast()));
+        let area = (visible.max.x - visible.min.x).max(0.0) * (visible.max.y - visible.min.y).max(0.0);
+        let total: f64 = pixels(&mask_bounds).map(|(x, y)| mask.get(x, y) as f64).sum();
+        assert!((total - area).abs() < 1e-3, "coverage {} for the area {}", total, area);
+
+        for (x, y) in pixels(&mask_bounds) {
+            let center = Vec2f64::new(x as f64 + 0.5, y as f64 + 0.5);
+            let coverage = mask.get(x, y);
+            assert!((0.0..=1.0).contains(&coverage));
+            if rect.min.x <= x as f64 && x as f64 + 1.0 <= rect.max.x && rect.min.y <= y as f64 && y as f64 + 1.0 <= rect.max.y {
+                assert_eq!(coverage, 1.0, "pixel {:?} inside {:?}", (x, y), rect);
+            } else if !rect.contains(&center) && (rect.min.x >= x as f64 + 1.0 || rect.max.x <= x as f64 || rect.min.y >= y as f64 + 1.0 || rect.max.y <= y as f64) {
+                assert_eq!(coverage, 0.0, "pixel {:?} outside {:?}", (x, y), rect);
+            }
+        }
+        // outside of the bounds nothing is covered
+        assert_eq!(mask.get(mask_bounds.min.x - 1, 0), 0.0);
+        assert_eq!(mask.get(0, mask_bounds.max.y), 0.0);
+    }
+}
+
+#[test]
+fn combinations() {
+    let mut rng = Lcg(2);
+    for _ in 0..ROUNDS {
+        let a = AlphaMask::<f32>::from_rect(bounds((-3, -2), (20, 22)), &random_rect(&mut rng, -5.0..20.0, 0.0..15.0));
+        // the other mask has other bounds and 8-bit coverage
+        let b = A8Mask::from_rect(bounds((0, 1), (26, 18)), &random_rect(&mut rng, -5.0..20.0, 0.0..15.0));
+
+        let (mut union, mut intersection, mut difference) = (a.clone(), a.clone(), a.clone());
+        union.union(&b);
+        intersection.intersect(&b);
+        difference.subtract(&b);
+        for (x, y) in pixels(&bounds((-5, -5), (30, 30))) {
+            // the combined mask keeps the bounds of the first one
+            let inside = pixels(&a.bounds()).any(|pixel| pixel == (x, y));
+            let (p, q) = (a.get(x, y), if inside { b.get(x, y) } else { 0.0 });
+            assert!((union.get(x, y) - (p + q - p * q)).abs() < 1e-6, "union at {:?}", (x, y));
+            assert!((intersection.get(x, y) - p * q).abs() < 1e-6, "intersection at {:?}", (x, y));
+            assert!((difference.get(x, y) - p * (1.0 - q)).abs() < 1e-6, "difference at {:?}", (x, y));
+        }
+        assert_eq!(union.bounds(), a.bounds());
+    }
+
+    let mut empty = AlphaMask::<f32>::new(bounds((0, 0), (4, 4)));
+    assert!(empty.is_empty() && empty.coverage_bounds().is_none());
+    empty.union(&AlphaMask::<f32>::from_rect(bounds((0, 0), (4, 4)), &F64Rect::new(Vec2f64::new(1.0, 1.5), Vec2f64::new(2.5, 3.0))));
+    assert_eq!(empty.coverage_bounds(), Some(bounds((1, 1), (3, 3))));
+}
+
+#[test]
+fn accumulated_coverage_is_rounded_once() {
+    let mut f32_mask = AlphaMask::<f32>::new(bounds((0, 0), (8, 1)));
+    let mut a8_mask = A8Mask::new(bounds((0, 0), (8, 1)));
+    for mask in [&mut f32_mask as &mut dyn LineSpanConsumer, &mut a8_mask] {
+        mask.start_line(0);
+        for x in 0..8 {
+            for _ in 0..x + 1 {
+                mask.put_weighted(x, 0.01 * (x as f32 + 1.0));
+            }
+        }
+        mask.end_line();
+    }
+    for x in 0..8 {
+        let expected = (f32_mask.get(x, 0) * 255.0).round() / 255.0;
+        assert_eq!(a8_mask.get(x, 0), expected, "pixel {}", x);
+    }
+}
+
+#[test]
+fn filled_polylines() {
+    let mut rng = Lcg(3);
+    let mask_bounds = bounds((0, 0), (40, 40));
+    for _ in 0..ROUNDS / 5 {
+        // many edges cross each pixel of a small circle
+        let (center, radius) = (Vec2f64::new(rng.range(5.0, 35.0), rng.range(5.0, 35.0)), rng.range(0.5, 6.0));
+        let polyline: Vec<_> = (0..200)
+            .map(|i| {
+                let angle = i as f64 / 200.0 * std::f64::consts::TAU;
+                let pt = center + Vec2f64::new(angle.cos(), angle.sin()) * radius;
+                if i == 0 { BrokenPolylineCommand::MoveTo(pt) } else { BrokenPolylineCommand::LineTo(pt) }
+            })
+            .collect();
+        let mut f32_mask = AlphaMask::<f32>::new(mask_bounds);
+        let mut a8_mask = A8Mask::new(mask_bounds);
+        f32_mask.fill_polyline(&polyline, FillRule::NonZero);
+        a8_mask.fill_polyline(&polyline, FillRule::NonZero);
+        for (x, y) in pixels(&mask_bounds) {
+            assert!((a8_mask.get(x, y) - f32_mask.get(x, y)).abs() <= 0.5 / 255.0 + 1e-6, "pixel {:?}", (x, y));
+        }
+        // filling again adds the coverage
+        let once: f32 = pixels(&mask_bounds).map(|(x, y)| f32_mask.get(x, y)).sum();
+        f32_mask.fill_polyline(&polyline, FillRule::NonZero);
+        let twice: f32 = pixels(&mask_bounds).map(|(x, y)| f32_mask.get(x, y)).sum();
+        assert!(twice > once || once == 0.0);
+    }
+}
+
+#[test]
+fn negative_bounds() {
+    // a disk around the origin, most of it at negative coordinates
+    let radius = 6.0;
+    let polyline: Vec<_> = (0..400)
+        .map(|i| {
+            let angle = i as f64 / 400.0 * std::f64::consts::TAU;
+            let pt = Vec2f64::new(angle.cos(), angle.sin()) * radius;
+            if i == 0 { BrokenPolylineCommand::MoveTo(pt) } else { BrokenPolylineCommand::LineTo(pt) }
+        })
+        .collect();
+    let mask_bounds = bounds((-10, -10), (10, 10));
+    for antialias in [AntialiasMode::Exact, AntialiasMode::sub_pixel(4), AntialiasMode::None] {
+        let mut mask = AlphaMask::<f32>::new(mask_bounds);
+        mask.fill_polyline_with(&polyline, FillRule::NonZero, antialias);
+        // the coverage is symmetric around the origin
+        for (x, y) in pixels(&mask_bounds) {
+            assert!((mask.get(x, y) - mask.get(-1 - x, -1 - y)).abs() < 1e-3, "{:?}: pixel {:?}", antialias, (x, y));
+        }
+        let total: f64 = pixels(&mask_bounds).map(|(x, y)| mask.get(x, y) as f64).sum();
+        let area = 400.0 / 2.0 * (std::f64::consts::TAU / 400.0).sin() * radius * radius;
+        assert!((total - area).abs() < 2.0, "{:?}: coverage {} for the area {}", antialias, total, area);
+    }
+}
+
+#[test]
+fn drawing_inks() {
+    let rect = F64Rect::new(Vec2f64::new(1.0, 1.0), Vec2f64::new(3.5, 2.0));
+    let mask = AlphaMask::<f32>::from_rect(bounds((0, 0), (5, 5)), &rect);
+
+    let mut drawn = Vec::new();
+    mask.draw_ink(&Ink::Color('c'), |x, y, color, coverage| drawn.push((x, y, color, coverage))).unwrap();
+    assert_eq!(drawn, vec![(1, 1, 'c', 1.0), (2, 1, 'c', 1.0), (3, 1, 'c', 0.5)]);
+
+    assert_eq!(mask.draw_ink(&Ink::<char>::None, |_, _, _, _| panic!("no ink")), Ok(()));
+    assert_eq!(mask.draw_ink(&Ink::<char>::Shader, |_, _, _, _| panic!("no shader")), Err(InkError::UnsupportedShader));
+
+    // the shaders are evaluated at the centers of the pixels
+    let mut centers = Vec::new();
+    mask.draw_with(|pt| pt, |_, _, center, _| centers.push(center));
+    assert_eq!(centers, vec![Vec2f64::new(1.5, 1.5), Vec2f64::new(2.5, 1.5), Vec2f64::new(3.5, 1.5)]);
+}
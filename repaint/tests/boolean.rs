@@ -0,0 +1,155 @@
+//! Areas of the results of the boolean operations on overlapping, touching, collinear and
+//! self-intersecting polygons.
+
+mod common;
+
+use common::*;
+use repaint::base::clipping::boolean::{boolean_operation_contours, simplify_contours, BooleanOperation};
+use repaint::base::defs::fill_rule::FillRule;
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+
+const OPERATIONS: [BooleanOperation; 4] = [
+    BooleanOperation::Union,
+    BooleanOperation::Intersection,
+    BooleanOperation::Difference,
+    BooleanOperation::Xor,
+];
+
+fn area(subject: &[BrokenPolylineCommand], clip: &[BrokenPolylineCommand], operation: BooleanOperation) -> f64 {
+    contours_area(&boolean_operation_contours(subject, FillRule::NonZero, clip, FillRule::NonZero, operation))
+}
+
+/// The areas of the union, the intersection, the difference and the xor.
+fn areas(subject: &[BrokenPolylineCommand], clip: &[BrokenPolylineCommand]) -> [f64; 4] {
+    OPERATIONS.map(|operation| area(subject, clip, operation))
+}
+
+fn assert_areas(actual: [f64; 4], expected: [f64; 4]) {
+    for ((actual, expected), operation) in actual.iter().zip(expected).zip(OPERATIONS) {
+        assert!((actual - expected).abs() < 1e-9, "{:?}: area {} instead of {}", operation, actual, expected);
+    }
+}
+
+#[test]
+fn overlapping() {
+    let a = rectangle((0.0, 0.0), (2.0, 2.0));
+    let b = rectangle((1.0, 1.0), (3.0, 3.0));
+    assert_areas(areas(&a, &b), [7.0, 1.0, 3.0, 6.0]);
+    // a square rotated by 45 degrees, the intersections aren't on the grid
+    let diamond = polygon(&[(1.0, -0.5), (2.5, 1.0), (1.0, 2.5), (-0.5, 1.0)]);
+    let union = area(&a, &diamond, BooleanOperation::Union);
+    let intersection = area(&a, &diamond, BooleanOperation::Intersection);
+    assert!((union + intersection - 4.0 - 4.5).abs() < 1e-9);
+    assert!((intersection - (4.0 - 4.0 * 0.125)).abs() < 1e-9, "intersection {}", intersection);
+}
+
+#[test]
+fn touching() {
+    // sharing a side, a part of a side and a corner
+    let a = rectangle((0.0, 0.0), (2.0, 2.0));
+    assert_areas(areas(&a, &rectangle((2.0, 0.0), (4.0, 2.0))), [8.0, 0.0, 4.0, 8.0]);
+    assert_areas(areas(&a, &rectangle((2.0, 1.0), (4.0, 3.0))), [8.0, 0.0, 4.0, 8.0]);
+    assert_areas(areas(&a, &rectangle((2.0, 2.0), (4.0, 4.0))), [8.0, 0.0, 4.0, 8.0]);
+    let union = boolean_operation_contours(&a, FillRule::NonZero, &rectangle((2.0, 0.0), (4.0, 2.0)), FillRule::NonZero, BooleanOperation::Union);
+    assert_eq!(union.len(), 1, "the squares sharing a side are merged");
+    assert_eq!(union[0].len(), 4, "the collinear points are removed");
+}
+
+#[test]
+fn collinear() {
+    // identical operands, and a rectangle inside another one sharing parts of two sides
+    let a = rectangle((0.0, 0.0), (4.0, 2.0));
+    assert_areas(areas(&a, &a), [8.0, 8.0, 0.0, 0.0]);
+    assert_areas(areas(&a, &rectangle((0.0, 0.0), (1.0, 2.0))), [8.0, 2.0, 6.0, 6.0]);
+    assert_areas(areas(&a, &rectangle((1.0, 0.0), (3.0, 1.0))), [8.0, 2.0, 6.0, 6.0]);
+    // a contour going back on itself
+    let spike = polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 2.0), (5.0, 2.0), (4.0, 2.0), (0.0, 2.0)]);
+    assert_areas(areas(&spike, &rectangle((10.0, 10.0), (11.0, 11.0))), [9.0, 0.0, 8.0, 9.0]);
+}
+
+#[test]
+fn self_intersecting() {
+    // a bow tie, its triangles have opposite orientations
+    let bow_tie = polygon(&[(0.0, 0.0), (2.0, 2.0), (2.0, 0.0), (0.0, 2.0)]);
+    assert!((contours_area(&simplify_contours(&bow_tie, |winding| winding != 0)) - 2.0).abs() < 1e-9);
+    assert!((contours_area(&simplify_contours(&bow_tie, |winding| winding > 0)) - 1.0).abs() < 1e-9);
+    // a pentagram, its center is wound twice
+    let star: Vec<(f64, f64)> = (0..5)
+        .map(|i| {
+            let angle = i as f64 * 4.0 * std::f64::consts::PI / 5.0;
+            (angle.cos(), angle.sin())
+        })
+        .collect();
+    let star = polygon(&star);
+    let non_zero = contours_area(&simplify_contours(&star, |winding| winding != 0));
+    let even_odd = contours_area(&simplify_contours(&star, |winding| winding % 2 != 0));
+    let center = contours_area(&simplify_contours(&star, |winding| winding.abs() >= 2));
+    assert!(center > 0.0 && (non_zero - even_odd - center).abs() < 1e-9, "{} {} {}", non_zero, even_odd, center);
+    assert!((area(&star, &star, BooleanOperation::Union) - non_zero).abs() < 1e-9);
+}
+
+/// Random polygons with many rounded intersections: the areas must be consistent.
+#[test]
+fn random_polygons() {
+    let mut rng = Lcg(0xb001);
+    let random_polygon = |rng: &mut Lcg| {
+        let points: Vec<(f64, f64)> = (0..3 + rng.int(12)).map(|_| (rng.range(0.0, 10.0), rng.range(0.0, 10.0))).collect();
+        polygon(&points)
+    };
+    for round in 0..300 {
+        let (a, b) = (random_polygon(&mut rng), random_polygon(&mut rng));
+        let simplified = |commands: &[BrokenPolylineCommand]| contours_area(&simplify_contours(commands, |winding| winding != 0));
+        let (area_a, area_b) = (simplified(&a), simplified(&b));
+        let [union, intersection, difference, xor] = areas(&a, &b);
+        let tolerance = 1e-6;
+        assert!((union + intersection - area_a - area_b).abs() < tolerance, "round {}: union and intersection", round);
+        assert!((difference + intersection - area_a).abs() < tolerance, "round {}: difference", round);
+        assert!((xor + intersection - union).abs() < tolerance, "round {}: xor", round);
+        assert!(intersection >= -tolerance && intersection <= area_a.min(area_b) + tolerance, "round {}: intersection", round);
+    }
+}
+
+/// Star polygons with many edges crossing near the same points.
+#[test]
+fn crowded_intersections() {
+    let mut rng = Lcg(0xc0ffee);
+    for round in 0..50 {
+        let star = |rng: &mut Lcg| {
+            let count = 5 + rng.int(20);
+            let step = 1 + rng.int(count / 2);
+            let phase = rng.range(0.0, 1.0);
+            let points: Vec<(f64, f64)> = (0..count)
+                .map(|i| {
+                    let angle = ((i * step) % count) as f64 / count as f64 * std::f64::consts::TAU + phase;
+                    (1e3 + angle.cos() * 0.01, 1e3 + angle.sin() * 0.01)
+                })
+                .collect();
+            polygon(&points)
+        };
+        let (a, b) = (star(&mut rng), star(&mut rng));
+        let simplified = |commands: &[BrokenPolylineCommand]| contours_area(&simplify_contours(commands, |winding| winding != 0));
+        let (area_a, area_b) = (simplified(&a), simplified(&b));
+        let [union, intersection, difference, xor] = areas(&a, &b);
+        let tolerance = 1e-10;
+        assert!((union + intersection - area_a - area_b).abs() < tolerance, "round {}: union and intersection", round);
+        assert!((difference + intersection - area_a).abs() < tolerance, "round {}: difference", round);
+        assert!((xor + intersection - union).abs() < tolerance, "round {}: xor", round);
+    }
+}
+
+/// Polygons a millionth wide, where the rounded intersections split the pieces again.
+#[test]
+fn tiny_polygons() {
+    let mut rng = Lcg(0x7111);
+    for round in 0..100 {
+        let tiny_polygon = |rng: &mut Lcg| {
+            let points: Vec<(f64, f64)> = (0..12).map(|_| (0.3 + rng.range(0.0, 1e-6), 0.7 + rng.range(0.0, 1e-6))).collect();
+            polygon(&points)
+        };
+        let (a, b) = (tiny_polygon(&mut rng), tiny_polygon(&mut rng));
+        let simplified = |commands: &[BrokenPolylineCommand]| contours_area(&simplify_contours(commands, |winding| winding != 0));
+        let [union, intersection, ..] = areas(&a, &b);
+        let error = (union + intersection - simplified(&a) - simplified(&b)).abs() / 1e-12;
+        assert!(error < 1e-8, "round {}: relative error {}", round, error);
+    }
+}
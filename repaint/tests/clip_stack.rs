@@ -0,0 +1,59 @@
+//! The clip of the painters without native clipping.
+
+use std::f64::consts::PI;
+
+use repaint::base::clipping::ClipStack;
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::BasicShape;
+use repaint::base::transform::Transform2d;
+use repaint::ClipOperation;
+
+fn visible_area(clip: &ClipStack, size: i32) -> f64 {
+    (0..size).flat_map(|y| (0..size).map(move |x| (x, y))).map(|(x, y)| clip.coverage(x, y) as f64).sum()
+}
+
+#[test]
+fn scaled_curves() {
+    // a circle much smaller than the tolerance, it is flattened after the scale
+    let circle = BasicShape::Circle { center: Vec2f64::new(0.05, 0.05), radius: 0.04 };
+    let mut clip = ClipStack::new(100, 100);
+    clip.clip(&circle, &Transform2d::Scale(1000.0), ClipOperation::Intersect).unwrap();
+    let area = visible_area(&clip, 100);
+    let expected = PI * 40.0 * 40.0;
+    // the polygon is at most the default tolerance of 0.1 pixels from the circle
+    let max_error = 2.0 * PI * 40.0 * 0.1;
+    assert!((area - expected).abs() < max_error, "area {} instead of {}", area, expected);
+}
+
+#[test]
+fn general_transforms() {
+    // the same small circle as above, scaled by a general transform
+    let circle = BasicShape::Circle { center: Vec2f64::new(0.05, 0.05), radius: 0.04 };
+    let mut clip = ClipStack::new(100, 100);
+    let transform = Transform2d::General(Box::new(|pt| pt * 1000.0));
+    clip.clip(&circle, &transform, ClipOperation::Difference).unwrap();
+    let area = visible_area(&clip, 100);
+    let expected = 100.0 * 100.0 - PI * 40.0 * 40.0;
+    let max_error = 2.0 * PI * 40.0 * 0.1;
+    assert!((area - expected).abs() < max_error, "area {} instead of {}", area, expected);
+}
+
+#[test]
+fn warped_curves() {
+    // a horizontal shear growing with y maps the disk to a shape of the same area, the lines of
+    // the flattened path would not follow the warped circle
+    let circle = BasicShape::Circle { center: Vec2f64::new(0.0, 0.0), radius: 1.0 };
+    let mut clip = ClipStack::new(100, 100);
+    let transform = Transform2d::General(Box::new(|pt: Vec2f64| Vec2f64::new(50.0 + 30.0 * pt.x + 5.0 * pt.y * pt.y, 50.0 + 30.0 * pt.y)));
+    clip.clip(&circle, &transform, ClipOperation::Intersect).unwrap();
+    let area = visible_area(&clip, 100);
+    let expected = PI * 30.0 * 30.0;
+    let max_error = 2.0 * PI * 35.0 * 0.1;
+    assert!((area - expected).abs() < max_error, "area {} instead of {}", area, expected);
+}
+
+#[test]
+fn shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ClipStack>();
+}
@@ -0,0 +1,176 @@
+//! Helpers shared by the tests.
+
+// each test crate uses a part of the helpers only
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use repaint::base::defs::{linalg::*, rect::{F64Rect, Rect}};
+use repaint::base::rasterization::algorithms::polygon_filling::scanline::LineSpanConsumer;
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::nalgebra::Vector2;
+
+/// A small deterministic pseudo-random generator, so that runs are comparable.
+pub struct Lcg(pub u64);
+
+impl Lcg {
+    pub fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+
+    /// An integer in `0..max`.
+    pub fn int(&mut self, max: usize) -> usize {
+        (self.next() * max as f64) as usize
+    }
+
+    /// An integer in `min..max`.
+    pub fn int_range(&mut self, min: i32, max: i32) -> i32 {
+        self.range(min as f64, max as f64).floor() as i32
+    }
+}
+
+/// A coordinate in `range`, sometimes not finite or huge.
+pub fn random_coordinate(rng: &mut Lcg, range: Range<f64>) -> f64 {
+    match rng.int(40) {
+        0 => f64::NAN,
+        1 => f64::INFINITY,
+        2 => f64::NEG_INFINITY,
+        3 => rng.range(-1e12, 1e12),
+        _ => rng.range(range.start, range.end),
+    }
+}
+
+/// A clip rectangle whose corner has its coordinates in `min`, with sides shorter than `max_size`.
+pub fn random_clip(rng: &mut Lcg, min: Range<i32>, max_size: i32) -> Rect<i32> {
+    let min = Vector2::new(rng.int_range(min.start, min.end), rng.int_range(min.start, min.end));
+    Rect::new(min, min + Vector2::new(rng.int_range(0, max_size), rng.int_range(0, max_size)))
+}
+
+/// A rectangle whose corner has its coordinates in `min`, with sides in `size`.
+pub fn random_rect(rng: &mut Lcg, min: Range<f64>, size: Range<f64>) -> F64Rect {
+    let min = Vec2f64::new(rng.range(min.start, min.end), rng.range(min.start, min.end));
+    F64Rect::new(min, min + Vec2f64::new(rng.range(size.start, size.end), rng.range(size.start, size.end)))
+}
+
+/// A convex polygon of `count` vertices on an ellipse, at random angles, clockwise on screen.
+pub fn random_convex_polygon(rng: &mut Lcg, center: Vec2f64, radii: Vec2f64, count: usize) -> Vec<Vec2f64> {
+    let mut angles: Vec<f64> = (0..count).map(|_| rng.range(0.0, std::f64::consts::TAU)).collect();
+    angles.sort_by(f64::total_cmp);
+    angles.iter().map(|angle| center + Vec2f64::new(radii.x * angle.cos(), radii.y * angle.sin())).collect()
+}
+
+/// A clip rectangle large enough for all the shapes of the tests.
+pub fn everything() -> Rect<i32> {
+    Rect::new(Vector2::new(-1000, -1000), Vector2::new(1000, 1000))
+}
+
+/// The part of a clip rectangle at non-negative coordinates, for the fillers passing device indices.
+pub fn device_clip(clip: &Rect<i32>) -> Rect<usize> {
+    let min = clip.min.sup(&Vector2::zeros());
+    Rect::new(min.map(|v| v as usize), clip.max.sup(&min).map(|v| v as usize))
+}
+
+/// Records the coverage of the pixels passed by a filler, with the checks of the consumer contract:
+/// the pixels are inside the clip rectangle, the lines are emitted from top to bottom and the
+/// coverages are between `0` and `1`.
+pub struct Recorder {
+    pub clip: Rect<usize>,
+    /// Whether each pixel must be passed once per line, otherwise the coverages are added.
+    pub unique: bool,
+    pub pixels: BTreeMap<(usize, usize), f32>,
+    /// The number of pixels passed in solid spans.
+    pub solid_pixels: usize,
+    line: Option<usize>,
+    /// The pixels of the current line.
+    line_pixels: Vec<usize>,
+}
+
+impl Recorder {
+    /// A recorder adding the coverages of the pixels passed several times.
+    pub fn new(clip: &Rect<usize>) -> Self {
+        Self { clip: *clip, unique: false, pixels: BTreeMap::new(), solid_pixels: 0, line: None, line_pixels: Vec::new() }
+    }
+
+    /// A recorder checking that each pixel is passed once per line.
+    pub fn unique(clip: &Rect<usize>) -> Self {
+        Self { unique: true, ..Self::new(clip) }
+    }
+
+    /// The sum of the coverages.
+    pub fn area(&self) -> f64 {
+        self.pixels.values().map(|coverage| *coverage as f64).sum()
+    }
+
+    fn put(&mut self, idx: usize, weight: f32) {
+        assert!(idx >= self.clip.min.x && idx < self.clip.max.x, "pixel {idx} outside of the clip");
+        assert!((0.0..=1.0 + 1e-6).contains(&weight), "invalid coverage {weight}");
+        let line = self.line.expect("pixel outside of a line");
+        if self.unique {
+            assert!(weight > 0.0, "empty coverage of pixel {idx} on line {line}");
+            assert!(!self.line_pixels.contains(&idx), "pixel {idx} passed twice on line {line}");
+            self.line_pixels.push(idx);
+        }
+        *self.pixels.entry((idx, line)).or_default() += weight;
+    }
+}
+
+impl LineSpanConsumer for Recorder {
+    fn start_line(&mut self, idx: usize) {
+        assert!(idx >= self.clip.min.y && idx < self.clip.max.y, "line {idx} outside of the clip");
+        assert!(self.line.is_none_or(|line| line < idx), "lines not emitted from top to bottom");
+        self.line = Some(idx);
+        self.line_pixels.clear();
+    }
+
+    fn put_weighted(&mut self, idx: usize, weight: f32) {
+        self.put(idx, weight);
+    }
+
+    fn put_solid_span(&mut self, start_idx: usize, end_idx: usize) {
+        assert!(start_idx < end_idx, "empty span {start_idx}..{end_idx}");
+        for idx in start_idx..end_idx {
+            self.put(idx, 1.0);
+        }
+        self.solid_pixels += end_idx - start_idx;
+    }
+}
+
+/// The commands of a closed polygon.
+pub fn polygon(points: &[(f64, f64)]) -> Vec<BrokenPolylineCommand> {
+    contour(&points.iter().map(|(x, y)| Vec2f64::new(*x, *y)).collect::<Vec<_>>())
+}
+
+/// The commands of a closed contour through the points.
+pub fn contour(points: &[Vec2f64]) -> Vec<BrokenPolylineCommand> {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, pt)| if i == 0 { BrokenPolylineCommand::MoveTo(*pt) } else { BrokenPolylineCommand::LineTo(*pt) })
+        .collect()
+}
+
+/// The commands of an axis-aligned rectangle.
+pub fn rectangle(min: (f64, f64), max: (f64, f64)) -> Vec<BrokenPolylineCommand> {
+    polygon(&[min, (max.0, min.1), max, (min.0, max.1)])
+}
+
+/// The signed area of closed contours, positive for the counter-clockwise ones in a y-up frame.
+///
+/// The points are taken relative to the first one, for the precision of the small contours far from
+/// the origin.
+pub fn contours_area(contours: &[Vec<Vec2f64>]) -> f64 {
+    let origin = contours.first().and_then(|contour| contour.first()).copied().unwrap_or_default();
+    contours
+        .iter()
+        .map(|contour| {
+            let n = contour.len();
+            (0..n).map(|i| (contour[i] - origin).perp(&(contour[(i + 1) % n] - origin))).sum::<f64>() * 0.5
+        })
+        .sum()
+}
@@ -0,0 +1,200 @@
+//! The path discretizer against the curve discretizers it chains, under the identity, affine and
+//! general transforms.
+//!
+//! The discretizer used to emit the points of the `MoveTo` commands untransformed and to close the
+//! polylines with a straight untransformed line: both now go through the transform.
+
+use std::f64::consts::PI;
+
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::path::discretization::{curves, DiscretizationParams, PathDiscretizer};
+use repaint::base::shapes::path::PathCommand;
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::base::transform::Transform2d;
+
+fn transforms() -> Vec<Transform2d> {
+    vec![
+        Transform2d::Identity,
+        Transform2d::Affine { linear: Mat2f64::new(2.0, 0.5, -0.3, 1.5), translation: Vec2f64::new(7.0, -3.0) },
+        // a warp, the lines become curves
+        Transform2d::General(Box::new(|pt: Vec2f64| Vec2f64::new(pt.x + 5.0 * (pt.y / 20.0).sin(), pt.y * (1.0 + pt.x / 200.0)))),
+    ]
+}
+
+fn v(x: f64, y: f64) -> Vec2f64 {
+    Vec2f64::new(x, y)
+}
+
+/// A path with every kind of command, with its equivalent made of absolute commands.
+fn paths() -> (Vec<PathCommand>, Vec<PathCommand>) {
+    let relative = vec![
+        PathCommand::MoveTo(v(10.0, 10.0)),
+        PathCommand::LineTo(v(60.0, 20.0)),
+        PathCommand::HorizontalLineToOffset(10.0),
+        PathCommand::VerticalLineTo(40.0),
+        PathCommand::QuadraticBezierCurveTo { control_pt: v(90.0, 60.0), end_pt: v(70.0, 80.0) },
+        PathCommand::SmoothQuadraticBezierCurveToOffset(v(-20.0, 0.0)),
+        PathCommand::CubicBezierToOffset { control_pt_1_offset: v(-10.0, 10.0), control_pt_2_offset: v(-30.0, -20.0), end_pt_offset: v(-40.0, 0.0) },
+        PathCommand::SmoothCubicBezierCurveTo { control_pt_2: v(0.0, 60.0), end_pt: v(10.0, 50.0) },
+        PathCommand::EllipticalArcToOffset { radii: v(10.0, 10.0), x_axis_rotation: 0.0, large_arc_flag: false, sweep_flag: true, end_pt_offset: v(0.0, -20.0) },
+        PathCommand::ClosePath,
+        PathCommand::MoveToOffset(v(100.0, 0.0)),
+        PathCommand::EllipticalArcTo { radii: v(30.0, 15.0), x_axis_rotation: PI / 6.0, large_arc_flag: true, sweep_flag: false, end_pt: v(140.0, 30.0) },
+        PathCommand::LineToOffset(v(0.0, 0.0)),
+        PathCommand::ClosePath,
+        // a closed polyline closes without a segment
+        PathCommand::ClosePath,
+    ];
+    let absolute = vec![
+        PathCommand::MoveTo(v(10.0, 10.0)),
+        PathCommand::LineTo(v(60.0, 20.0)),
+        PathCommand::LineTo(v(70.0, 20.0)),
+        PathCommand::LineTo(v(70.0, 40.0)),
+        PathCommand::QuadraticBezierCurveTo { control_pt: v(90.0, 60.0), end_pt: v(70.0, 80.0) },
+        PathCommand::QuadraticBezierCurveTo { control_pt: v(50.0, 100.0), end_pt: v(50.0, 80.0) },
+        PathCommand::CubicBezierTo { control_pt_1: v(40.0, 90.0), control_pt_2: v(20.0, 60.0), end_pt: v(10.0, 80.0) },
+        PathCommand::CubicBezierTo { control_pt_1: v(0.0, 100.0), control_pt_2: v(0.0, 60.0), end_pt: v(10.0, 50.0) },
+        PathCommand::EllipticalArcTo { radii: v(10.0, 10.0), x_axis_rotation: 0.0, large_arc_flag: false, sweep_flag: true, end_pt: v(10.0, 30.0) },
+        PathCommand::ClosePath,
+        PathCommand::MoveTo(v(110.0, 10.0)),
+        PathCommand::EllipticalArcTo { radii: v(30.0, 15.0), x_axis_rotation: PI / 6.0, large_arc_flag: true, sweep_flag: false, end_pt: v(140.0, 30.0) },
+        PathCommand::LineTo(v(140.0, 30.0)),
+        PathCommand::ClosePath,
+        PathCommand::ClosePath,
+    ];
+    (relative, absolute)
+}
+
+/// The discretization of an absolute path, chaining the curve discretizers like the discretizer did
+/// before it was made iterative, except for the transformed `MoveTo` and `ClosePath`.
+fn reference(path: &[PathCommand], params: &DiscretizationParams, transform: &Transform2d) -> Vec<BrokenPolylineCommand> {
+    let mut result = Vec::new();
+    let (mut start, mut current) = (Vec2f64::zeros(), Vec2f64::zeros());
+    for command in path {
+        let points: Vec<Vec2f64> = match *command {
+            PathCommand::MoveTo(pt) => {
+                result.push(BrokenPolylineCommand::MoveTo(transform.eval(pt)));
+                (start, current) = (pt, pt);
+                continue;
+            }
+            PathCommand::LineTo(pt) => curves::discretize_segment(current, pt, true, params, transform).collect(),
+            PathCommand::ClosePath if current == start => Vec::new(),
+            PathCommand::ClosePath => curves::discretize_segment(current, start, true, params, transform).collect(),
+            PathCommand::QuadraticBezierCurveTo { control_pt, end_pt } => {
+                curves::discretize_quadratic_bezier(current, control_pt, end_pt, true, params, transform).collect()
+            }
+            PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt } => {
+                curves::discretize_cubic_bezier(current, control_pt_1, control_pt_2, end_pt, true, params, transform).collect()
+            }
+            PathCommand::EllipticalArcTo { radii, x_axis_rotation, large_arc_flag, sweep_flag, end_pt } => {
+                curves::discretize_elliptical_arc(&radii, x_axis_rotation, large_arc_flag, sweep_flag, current, end_pt, true, params, transform).collect()
+            }
+            _ => panic!("relative command {:?} in an absolute path", command),
+        };
+        result.extend(points.into_iter().map(BrokenPolylineCommand::LineTo));
+        current = match *command {
+            PathCommand::ClosePath => start,
+            PathCommand::LineTo(pt) => pt,
+            PathCommand::QuadraticBezierCurveTo { end_pt, .. }
+            | PathCommand::CubicBezierTo { end_pt, .. }
+            | PathCommand::EllipticalArcTo { end_pt, .. } => end_pt,
+            _ => unreachable!(),
+        };
+    }
+    result
+}
+
+/// The distance between a point and a polyline.
+fn distance_to_polyline(pt: &Vec2f64, polyline: &[Vec2f64]) -> f64 {
+    polyline
+        .windows(2)
+        .map(|segment| {
+            let ab = segment[1] - segment[0];
+            let t = if ab.norm_squared() > 0.0 { ((pt - segment[0]).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0) } else { 0.0 };
+            (pt - (segment[0] + ab * t)).norm()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[test]
+fn matches_the_curve_discretizers() {
+    let (relative, absolute) = paths();
+    for params in [DiscretizationParams::default(), DiscretizationParams::default().with_tolerance(0.01).with_max_angle(0.05)] {
+        for transform in transforms() {
+            let expected = reference(&absolute, &params, &transform);
+            let discretizer = PathDiscretizer::new(params, &transform);
+            let points: Vec<_> = discretizer.discretize(&mut relative.iter()).collect();
+            assert_eq!(points, expected);
+            let owned: Vec<_> = discretizer.discretize_iter(absolute.clone()).collect();
+            assert_eq!(owned, expected);
+        }
+    }
+}
+
+#[test]
+fn moves_and_closes_are_transformed() {
+    let path = [
+        PathCommand::MoveTo(v(1.0, 2.0)),
+        PathCommand::LineTo(v(5.0, 2.0)),
+        PathCommand::LineTo(v(5.0, 6.0)),
+        PathCommand::ClosePath,
+        PathCommand::MoveTo(v(-3.0, 4.0)),
+    ];
+    let transform = |pt: Vec2f64| v(2.0 * pt.x + 1.0, pt.y - pt.x);
+    let affine = Transform2d::Affine { linear: Mat2f64::new(2.0, 0.0, -1.0, 1.0), translation: v(1.0, 0.0) };
+    let discretizer = PathDiscretizer::new(DiscretizationParams::default(), &affine);
+    let points: Vec<_> = discretizer.discretize_iter(&path).collect();
+    assert_eq!(
+        points,
+        vec![
+            BrokenPolylineCommand::MoveTo(transform(v(1.0, 2.0))),
+            BrokenPolylineCommand::LineTo(transform(v(5.0, 2.0))),
+            BrokenPolylineCommand::LineTo(transform(v(5.0, 6.0))),
+            BrokenPolylineCommand::LineTo(transform(v(1.0, 2.0))),
+            BrokenPolylineCommand::MoveTo(transform(v(-3.0, 4.0))),
+        ]
+    );
+}
+
+#[test]
+fn close_to_the_transformed_curves() {
+    // the lines, a quadratic, a cubic and a half circle, with their exact points
+    let quad = |t: f64| v(70.0, 40.0) * ((1.0 - t) * (1.0 - t)) + v(90.0, 60.0) * (2.0 * t * (1.0 - t)) + v(70.0, 80.0) * (t * t);
+    let cubic = |t: f64| {
+        let s = 1.0 - t;
+        v(70.0, 80.0) * (s * s * s) + v(40.0, 90.0) * (3.0 * s * s * t) + v(20.0, 60.0) * (3.0 * s * t * t) + v(10.0, 80.0) * (t * t * t)
+    };
+    let half_circle = |t: f64| v(10.0, 60.0) + v((PI * t).sin(), -(PI * t).cos()) * 20.0;
+    let path = [
+        PathCommand::MoveTo(v(10.0, 10.0)),
+        PathCommand::LineTo(v(70.0, 40.0)),
+        PathCommand::QuadraticBezierCurveTo { control_pt: v(90.0, 60.0), end_pt: v(70.0, 80.0) },
+        PathCommand::CubicBezierTo { control_pt_1: v(40.0, 90.0), control_pt_2: v(20.0, 60.0), end_pt: v(10.0, 80.0) },
+        PathCommand::LineTo(v(10.0, 40.0)),
+        PathCommand::EllipticalArcTo { radii: v(20.0, 20.0), x_axis_rotation: 0.0, large_arc_flag: false, sweep_flag: true, end_pt: v(10.0, 80.0) },
+        PathCommand::ClosePath,
+    ];
+    let pieces: [&dyn Fn(f64) -> Vec2f64; 6] = [
+        &|t| v(10.0, 10.0) + v(60.0, 30.0) * t,
+        &quad,
+        &cubic,
+        &|t| v(10.0, 80.0) - v(0.0, 40.0) * t,
+        &half_circle,
+        &|t| v(10.0, 80.0) - v(0.0, 70.0) * t,
+    ];
+    for transform in transforms() {
+        let params = DiscretizationParams::default().with_tolerance(0.05);
+        let curve: Vec<_> = pieces.iter().flat_map(|piece| (0..=1000).map(|i| transform.eval(piece(i as f64 / 1000.0)))).collect();
+        let discretizer = PathDiscretizer::new(params, &transform);
+        let points: Vec<_> = discretizer.discretize_iter(&path).map(|command| command.point()).collect();
+        assert!(points.len() > 10);
+        // the points are on the curves, and the curves are within the tolerance of the polyline
+        for pt in &points {
+            assert!(distance_to_polyline(pt, &curve) < 1e-3, "{:?} is not on the curves", pt);
+        }
+        for pt in curve.iter().step_by(13) {
+            let distance = distance_to_polyline(pt, &points);
+            assert!(distance < params.tolerance * 1.5, "the curves are {} away from the polyline", distance);
+        }
+    }
+}
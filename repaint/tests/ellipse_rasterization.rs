@@ -0,0 +1,287 @@
+//! Properties of the circle and ellipse rasterizers on random ellipses.
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::f64::consts::{PI, TAU};
+
+use common::{device_clip, everything, random_clip, Lcg, Recorder};
+use repaint::base::defs::{linalg::*, rect::Rect};
+use repaint::base::rasterization::algorithms::ellipse::{antialiased, midpoint};
+
+const ROUNDS: usize = 200;
+
+/// A random ellipse with integer coordinates, a circle every third round.
+fn random_ellipse(rng: &mut Lcg, round: usize) -> (Vec2i32, Vec2i32) {
+    let center = Vec2i32::new(rng.int_range(40, 120), rng.int_range(40, 120));
+    let radii = if round.is_multiple_of(3) {
+        let radius = rng.int_range(0, 40);
+        Vec2i32::new(radius, radius)
+    } else {
+        Vec2i32::new(rng.int_range(0, 40), rng.int_range(0, 40))
+    };
+    (center, radii)
+}
+
+/// A random ellipse with sub-pixel coordinates, a circle every third round.
+fn random_aa_ellipse(rng: &mut Lcg, round: usize) -> (Vec2f32, Vec2f32) {
+    let center = Vec2f32::new(rng.range(40.0, 120.0) as f32, rng.range(40.0, 120.0) as f32);
+    let radii = if round.is_multiple_of(3) {
+        let radius = rng.range(0.0, 30.0) as f32;
+        Vec2f32::new(radius, radius)
+    } else {
+        Vec2f32::new(rng.range(0.0, 30.0) as f32, rng.range(0.0, 30.0) as f32)
+    };
+    (center, radii)
+}
+
+fn inside(rect: &Rect<i32>, (x, y): (i32, i32)) -> bool {
+    x >= rect.min.x && x < rect.max.x && y >= rect.min.y && y < rect.max.y
+}
+
+/// The recorded pixels, each passed once per line.
+fn recorded(recorder: Recorder) -> BTreeMap<(i32, i32), f32> {
+    recorder.pixels.into_iter().map(|((x, y), coverage)| ((x as i32, y as i32), coverage)).collect()
+}
+
+fn outline(center: &Vec2i32, radii: &Vec2i32, rect: &Rect<i32>) -> Vec<(i32, i32)> {
+    let mut pixels = Vec::new();
+    midpoint::ellipse(center, radii, &mut |x, y| pixels.push((x, y)), rect);
+    pixels
+}
+
+fn arc(center: &Vec2i32, radii: &Vec2i32, start: f64, sweep: f64, rect: &Rect<i32>) -> Vec<(i32, i32)> {
+    let mut pixels = Vec::new();
+    midpoint::arc(center, radii, start, sweep, &mut |x, y| pixels.push((x, y)), rect);
+    pixels
+}
+
+fn fill(center: &Vec2i32, radii: &Vec2i32, rect: &Rect<i32>) -> BTreeMap<(i32, i32), f32> {
+    let mut recorder = Recorder::unique(&device_clip(rect));
+    midpoint::fill_ellipse(center, radii, &device_clip(rect), &mut recorder);
+    recorded(recorder)
+}
+
+fn aa_outline(center: &Vec2f32, radii: &Vec2f32, rect: &Rect<i32>) -> BTreeMap<(i32, i32), f32> {
+    let mut pixels = BTreeMap::new();
+    antialiased::ellipse(center, radii, &mut |x, y, intensity| add_intensity(&mut pixels, x, y, intensity), rect);
+    pixels
+}
+
+fn aa_arc(center: &Vec2f32, radii: &Vec2f32, start: f64, sweep: f64, rect: &Rect<i32>) -> BTreeMap<(i32, i32), f32> {
+    let mut pixels = BTreeMap::new();
+    antialiased::arc(center, radii, start, sweep, &mut |x, y, intensity| add_intensity(&mut pixels, x, y, intensity), rect);
+    pixels
+}
+
+fn add_intensity(pixels: &mut BTreeMap<(i32, i32), f32>, x: i32, y: i32, intensity: f32) {
+    assert!(intensity > 0.0 && intensity <= 1.0, "invalid intensity {intensity}");
+    *pixels.entry((x, y)).or_default() += intensity;
+}
+
+fn aa_fill(center: &Vec2f32, radii: &Vec2f32, rect: &Rect<i32>) -> BTreeMap<(i32, i32), f32> {
+    let mut recorder = Recorder::unique(&device_clip(rect));
+    antialiased::fill_ellipse(center, radii, &device_clip(rect), &mut recorder);
+    recorded(recorder)
+}
+
+/// The distance from a point to an ellipse: the nearest of a few samples, refined by a ternary search
+/// between its neighbours.
+fn distance_to_ellipse(center: &Vec2f64, radii: &Vec2f64, pt: &Vec2f64) -> f64 {
+    const SAMPLES: usize = 64;
+    let distance = |t: f64| (center + Vec2f64::new(radii.x * t.cos(), radii.y * t.sin()) - pt).norm();
+    let step = TAU / SAMPLES as f64;
+    let nearest = (0..SAMPLES).map(|i| i as f64 * step).min_by(|a, b| distance(*a).total_cmp(&distance(*b))).unwrap();
+    let (mut lo, mut hi) = (nearest - step, nearest + step);
+    for _ in 0..20 {
+        let (a, b) = (lo + (hi - lo) / 3.0, hi - (hi - lo) / 3.0);
+        if distance(a) < distance(b) {
+            hi = b;
+        } else {
+            lo = a;
+        }
+    }
+    distance((lo + hi) / 2.0)
+}
+
+/// An approximation of the perimeter of an ellipse.
+fn perimeter(radii: &Vec2f64) -> f64 {
+    PI * (3.0 * (radii.x + radii.y) - ((3.0 * radii.x + radii.y) * (radii.x + 3.0 * radii.y)).sqrt())
+}
+
+#[test]
+fn midpoint_outlines() {
+    // each pixel once, symmetric, 8-connected and near the ellipse, the distances are slow to check
+    let mut rng = Lcg(11);
+    for round in 0..ROUNDS / 2 {
+        let (center, radii) = random_ellipse(&mut rng, round);
+        let pixels = outline(&center, &radii, &everything());
+        let mut sorted = pixels.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), pixels.len(), "pixels plotted twice for {radii:?}");
+        let contains = |pt: (i32, i32)| sorted.binary_search(&pt).is_ok();
+        for &(x, y) in &sorted {
+            let (dx, dy) = (x - center.x, y - center.y);
+            assert!(contains((center.x - dx, y)) && contains((x, center.y - dy)), "asymmetric outline for {radii:?}");
+            if radii.x > 0 && radii.y > 0 {
+                let neighbours = (-1..=1)
+                    .flat_map(|i| (-1..=1).map(move |j| (x + i, y + j)))
+                    .filter(|&pt| pt != (x, y) && contains(pt))
+                    .count();
+                assert!(neighbours >= 2, "the outline of {radii:?} is not closed at {dx}, {dy}");
+                let distance = distance_to_ellipse(&Vec2f64::zeros(), &radii.cast(), &Vec2f64::new(dx as f64, dy as f64));
+                assert!(distance <= 1.0, "pixel {dx}, {dy} is {distance} from the ellipse {radii:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn midpoint_fills() {
+    // the fill is one span per line, ending on the outline
+    let mut rng = Lcg(12);
+    for round in 0..ROUNDS {
+        let (center, radii) = random_ellipse(&mut rng, round);
+        let mut sorted = outline(&center, &radii, &everything());
+        sorted.sort();
+        let contains = |pt: (i32, i32)| sorted.binary_search(&pt).is_ok();
+        let filled = fill(&center, &radii, &everything());
+        let mut rows: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+        for &(x, y) in filled.keys() {
+            rows.entry(y).or_default().push(x);
+        }
+        for (y, xs) in &rows {
+            assert_eq!(xs.len() as i32, xs[xs.len() - 1] - xs[0] + 1, "line {y} of {radii:?} has several spans");
+            assert!(contains((xs[0], *y)) && contains((xs[xs.len() - 1], *y)), "line {y} of {radii:?} doesn't end on the outline");
+        }
+        assert!(sorted.iter().all(|pt| filled.contains_key(pt)), "the fill of {radii:?} doesn't contain the outline");
+    }
+}
+
+#[test]
+fn midpoint_arcs() {
+    // an arc and the rest of the ellipse make the whole outline
+    let mut rng = Lcg(13);
+    for round in 0..ROUNDS {
+        let (center, radii) = random_ellipse(&mut rng, round);
+        let mut sorted = outline(&center, &radii, &everything());
+        sorted.sort();
+        let (start, sweep) = (rng.range(-10.0, 10.0), rng.range(-TAU, TAU));
+        let mut both = arc(&center, &radii, start, sweep, &everything());
+        both.extend(arc(&center, &radii, start + sweep, sweep.signum() * TAU - sweep, &everything()));
+        both.sort();
+        both.dedup();
+        assert_eq!(both, sorted, "the arcs of {radii:?} at {start}, {sweep} don't make the outline");
+        let mut full = arc(&center, &radii, start, 7.0, &everything());
+        full.sort();
+        assert_eq!(full, sorted, "the full arc of {radii:?} is not the outline");
+    }
+}
+
+#[test]
+fn midpoint_clipping() {
+    let mut rng = Lcg(14);
+    for round in 0..ROUNDS {
+        let (center, radii) = random_ellipse(&mut rng, round);
+        let (start, sweep) = (rng.range(-10.0, 10.0), rng.range(-TAU, TAU));
+        let clip = random_clip(&mut rng, 0..150, 50);
+        let expected: Vec<_> = outline(&center, &radii, &everything()).into_iter().filter(|pt| inside(&clip, *pt)).collect();
+        assert_eq!(outline(&center, &radii, &clip), expected, "clipping differs for {radii:?}");
+        let expected: Vec<_> = arc(&center, &radii, start, sweep, &everything()).into_iter().filter(|pt| inside(&clip, *pt)).collect();
+        assert_eq!(arc(&center, &radii, start, sweep, &clip), expected, "clipping differs for {radii:?}");
+        let mut expected = fill(&center, &radii, &everything());
+        expected.retain(|pt, _| inside(&clip, *pt));
+        assert_eq!(fill(&center, &radii, &clip), expected, "clipping differs for {radii:?}");
+    }
+}
+
+#[test]
+fn antialiased_outlines() {
+    // the distances are slow to check
+    let mut rng = Lcg(15);
+    for round in 0..ROUNDS / 2 {
+        let (center, radii) = random_aa_ellipse(&mut rng, round);
+        let (center64, radii64): (Vec2f64, Vec2f64) = (center.cast(), radii.cast());
+        // the outline is near the ellipse
+        let pixels = aa_outline(&center, &radii, &everything());
+        for (x, y) in pixels.keys() {
+            let pixel_center = Vec2f64::new(*x as f64 + 0.5, *y as f64 + 0.5);
+            let distance = distance_to_ellipse(&center64, &radii64, &pixel_center);
+            assert!(distance < 1.5, "pixel {x}, {y} is {distance} from the ellipse {center:?}, {radii:?}");
+        }
+        // each crossing has a total intensity of one, there are about `2 * hypot(a, b)` crossings of the
+        // columns and as many of the rows
+        let total: f64 = pixels.values().map(|value| *value as f64).sum();
+        let crossings = 4.0 * radii64.x.hypot(radii64.y);
+        assert!((total - crossings).abs() <= 4.0, "total intensity {total} instead of {crossings} for {center:?}, {radii:?}");
+    }
+}
+
+#[test]
+fn antialiased_arcs() {
+    // an arc and the rest of the ellipse cover the outline, the crossings at the ends of the arcs can be
+    // in both
+    let mut rng = Lcg(16);
+    for round in 0..ROUNDS {
+        let (center, radii) = random_aa_ellipse(&mut rng, round);
+        let pixels = aa_outline(&center, &radii, &everything());
+        let (start, sweep) = (rng.range(-10.0, 10.0), rng.range(-TAU, TAU));
+        let mut both = aa_arc(&center, &radii, start, sweep, &everything());
+        for (pt, intensity) in aa_arc(&center, &radii, start + sweep, sweep.signum() * TAU - sweep, &everything()) {
+            *both.entry(pt).or_default() += intensity;
+        }
+        for (pt, intensity) in &pixels {
+            let value = both.get(pt).copied().unwrap_or(0.0);
+            assert!(value >= intensity - 1e-5, "the arcs of {radii:?} at {start}, {sweep} miss {pt:?}");
+        }
+    }
+}
+
+#[test]
+fn antialiased_fill_areas() {
+    let mut rng = Lcg(17);
+    for round in 0..ROUNDS {
+        let (center, radii) = random_aa_ellipse(&mut rng, round);
+        let radii64: Vec2f64 = radii.cast();
+        let area: f64 = aa_fill(&center, &radii, &everything()).values().map(|value| *value as f64).sum();
+        let expected = PI * radii64.x * radii64.y;
+        let error = (area - expected).abs() / (1.0 + perimeter(&radii64));
+        assert!(error < 0.06, "area {area} instead of {expected} for {center:?}, {radii:?}");
+    }
+}
+
+#[test]
+fn antialiased_clipping() {
+    let mut rng = Lcg(18);
+    for round in 0..ROUNDS {
+        let (center, radii) = random_aa_ellipse(&mut rng, round);
+        let clip = random_clip(&mut rng, 0..150, 50);
+        let mut expected = aa_outline(&center, &radii, &everything());
+        expected.retain(|pt, _| inside(&clip, *pt));
+        assert_eq!(aa_outline(&center, &radii, &clip), expected, "clipping differs for {center:?}, {radii:?}");
+        let mut expected = aa_fill(&center, &radii, &everything());
+        expected.retain(|pt, _| inside(&clip, *pt));
+        assert_eq!(aa_fill(&center, &radii, &clip), expected, "clipping differs for {center:?}, {radii:?}");
+    }
+}
+
+#[test]
+fn degenerate_ellipses() {
+    assert_eq!(outline(&Vec2i32::new(5, 5), &Vec2i32::zeros(), &everything()), vec![(5, 5)]);
+    assert_eq!(outline(&Vec2i32::new(5, 5), &Vec2i32::new(2, 0), &everything()).len(), 5);
+    assert!(outline(&Vec2i32::new(5, 5), &Vec2i32::new(-1, 3), &everything()).is_empty());
+    assert!(arc(&Vec2i32::new(5, 5), &Vec2i32::new(3, 3), 0.0, f64::NAN, &everything()).is_empty());
+    for (center, radii) in [
+        (Vec2f32::new(f32::NAN, 0.0), Vec2f32::new(3.0, 3.0)),
+        (Vec2f32::new(0.0, 0.0), Vec2f32::new(f32::INFINITY, 3.0)),
+        (Vec2f32::new(5.0, 5.0), Vec2f32::new(-1.0, 3.0)),
+        (Vec2f32::new(5.0, 5.0), Vec2f32::zeros()),
+    ] {
+        assert!(aa_outline(&center, &radii, &everything()).is_empty(), "{center:?}, {radii:?} plotted");
+        assert!(aa_fill(&center, &radii, &everything()).is_empty(), "{center:?}, {radii:?} filled");
+    }
+    let huge = Vec2f32::new(1e30, 1e30);
+    aa_outline(&-huge, &huge, &everything());
+    aa_fill(&-huge, &huge, &everything());
+}
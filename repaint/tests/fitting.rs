@@ -0,0 +1,116 @@
+//! The cubic Bézier curves fitted to polylines stay within the requested error.
+
+mod common;
+
+use std::f64::consts::TAU;
+
+use common::Lcg;
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::path::PathCommand;
+use repaint::base::shapes::polyline::fitting::fit_cubic_beziers;
+
+/// The control points of the fitted curves.
+fn curves(path: &[PathCommand]) -> Vec<[Vec2f64; 4]> {
+    let PathCommand::MoveTo(mut start) = path[0] else {
+        panic!("the fitted curves don't start with a move");
+    };
+    let mut curves = Vec::new();
+    for command in &path[1..] {
+        let PathCommand::CubicBezierTo { control_pt_1, control_pt_2, end_pt } = *command else {
+            panic!("unexpected command {:?}", command);
+        };
+        curves.push([start, control_pt_1, control_pt_2, end_pt]);
+        start = end_pt;
+    }
+    curves
+}
+
+fn cubic_point(p: &[Vec2f64; 4], t: f64) -> Vec2f64 {
+    let s = 1.0 - t;
+    p[0] * (s * s * s) + p[1] * (3.0 * s * s * t) + p[2] * (3.0 * s * t * t) + p[3] * (t * t * t)
+}
+
+/// The distance between a point and the curves: the closest of coarse samples, refined by a
+/// ternary search around it. The curves whose control points are all farther are skipped.
+fn distance(pt: &Vec2f64, curves: &[[Vec2f64; 4]]) -> f64 {
+    const SAMPLES: usize = 32;
+    let mut best = f64::INFINITY;
+    for curve in curves {
+        let (min, max) = curve.iter().fold((curve[0], curve[0]), |(min, max), p| (min.inf(p), max.sup(p)));
+        if (pt - pt.sup(&min).inf(&max)).norm() >= best {
+            continue;
+        }
+        let at = |t: f64| (cubic_point(curve, t) - pt).norm();
+        let closest = (0..=SAMPLES).min_by(|a, b| at(*a as f64 / SAMPLES as f64).total_cmp(&at(*b as f64 / SAMPLES as f64))).unwrap();
+        best = best.min(at(closest as f64 / SAMPLES as f64));
+        let (mut low, mut high) = ((closest as f64 - 1.0) / SAMPLES as f64, (closest as f64 + 1.0) / SAMPLES as f64);
+        (low, high) = (low.max(0.0), high.min(1.0));
+        for _ in 0..40 {
+            let (a, b) = (low + (high - low) / 3.0, high - (high - low) / 3.0);
+            if at(a) < at(b) {
+                high = b;
+            } else {
+                low = a;
+            }
+        }
+        best = best.min(at(low));
+    }
+    best
+}
+
+fn check_fit(points: &[Vec2f64], tolerance: f64) -> usize {
+    let path = fit_cubic_beziers(points, tolerance);
+    assert_eq!(path[0], PathCommand::MoveTo(points[0]));
+    let curves = curves(&path);
+    assert_eq!(curves.last().map(|curve| curve[3]), points.last().copied());
+    for pt in points {
+        let distance = distance(pt, &curves);
+        assert!(distance <= tolerance * (1.0 + 1e-9), "{:?} is {} away from the curves with a tolerance of {}", pt, distance, tolerance);
+    }
+
+    // the curves are smoothly connected
+    for pair in path[1..].windows(2) {
+        let (PathCommand::CubicBezierTo { control_pt_2, end_pt, .. }, PathCommand::CubicBezierTo { control_pt_1, .. }) = (&pair[0], &pair[1]) else {
+            unreachable!();
+        };
+        let (incoming, outgoing) = (end_pt - control_pt_2, control_pt_1 - end_pt);
+        assert!(incoming.perp(&outgoing).abs() <= 1e-6 * incoming.norm() * outgoing.norm() && incoming.dot(&outgoing) >= 0.0, "corner at {:?}", end_pt);
+    }
+    path.len() - 1
+}
+
+#[test]
+fn sampled_curves() {
+    let circle: Vec<_> = (0..=200).map(|i| Vec2f64::new((i as f64 / 200.0 * TAU).cos(), (i as f64 / 200.0 * TAU).sin()) * 50.0).collect();
+    let wave: Vec<_> = (0..=300).map(|i| Vec2f64::new(i as f64, 20.0 * (i as f64 / 15.0).sin())).collect();
+    for tolerance in [0.01, 0.5, 5.0] {
+        let circle_curves = check_fit(&circle, tolerance);
+        let wave_curves = check_fit(&wave, tolerance);
+        assert!(circle_curves < 50 && wave_curves < 100, "{} and {} curves for a tolerance of {}", circle_curves, wave_curves, tolerance);
+    }
+}
+
+#[test]
+fn random_walks() {
+    let mut rng = Lcg(1);
+    for _ in 0..30 {
+        let mut pt = Vec2f64::zeros();
+        let points: Vec<_> = (0..2 + rng.int(100))
+            .map(|_| {
+                pt += Vec2f64::new(rng.range(-5.0, 5.0), rng.range(-5.0, 5.0));
+                pt
+            })
+            .collect();
+        check_fit(&points, rng.range(0.05, 3.0));
+    }
+}
+
+#[test]
+fn degenerate() {
+    assert!(fit_cubic_beziers(&[], 1.0).is_empty());
+    let pt = Vec2f64::new(1.0, 2.0);
+    assert_eq!(fit_cubic_beziers(&[pt, pt, pt], 1.0), vec![PathCommand::MoveTo(pt)]);
+    let line = [Vec2f64::new(0.0, 0.0), Vec2f64::new(0.0, 0.0), Vec2f64::new(f64::NAN, 1.0), Vec2f64::new(3.0, 4.0)];
+    assert_eq!(check_fit(&[line[0], line[3]], 1e-9), 1);
+    assert_eq!(fit_cubic_beziers(&line, 1e-9).len(), 2);
+}
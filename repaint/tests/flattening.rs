@@ -0,0 +1,157 @@
+//! The error bounds of the analytic flattening methods, compared to the adaptive discretization of
+//! [`ParametricCurveDiscretizerIterator`].
+
+mod common;
+
+use common::Lcg;
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::path::discretization::{
+    curves::{ParametricCubic2dBezier, ParametricQuadratic2dBezier},
+    DiscretizationParams, FlatteningMethod, ParametricCurve, ParametricCurveDiscretizerIterator, PathDiscretizer,
+};
+use repaint::base::shapes::path::PathCommand;
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::base::transform::Transform2d;
+
+const CURVES: usize = 60;
+const ERROR_SAMPLES: usize = 200;
+const TOLERANCES: [f64; 3] = [1.0, 0.25, 0.05];
+
+enum Curve {
+    Quadratic(ParametricQuadratic2dBezier),
+    Cubic(ParametricCubic2dBezier),
+}
+
+impl Curve {
+    fn eval(&self, t: f64) -> Vec2f64 {
+        match self {
+            Curve::Quadratic(c) => c.eval(t),
+            Curve::Cubic(c) => c.eval(t),
+        }
+    }
+
+    fn to_path(&self) -> [PathCommand; 2] {
+        match self {
+            Curve::Quadratic(c) => [
+                PathCommand::MoveTo(c.p0),
+                PathCommand::QuadraticBezierCurveTo { control_pt: c.p1, end_pt: c.p2 },
+            ],
+            Curve::Cubic(c) => [
+                PathCommand::MoveTo(c.p0),
+                PathCommand::CubicBezierTo { control_pt_1: c.p1, control_pt_2: c.p2, end_pt: c.p3 },
+            ],
+        }
+    }
+
+    /// The points of the adaptive discretization, with as many initial subdivisions as the degree.
+    fn reference(&self, params: &DiscretizationParams) -> Vec<Vec2f64> {
+        let transform = Transform2d::Identity;
+        match self {
+            Curve::Quadratic(c) => ParametricCurveDiscretizerIterator::new(*c, params, &transform, false, 2).collect(),
+            Curve::Cubic(c) => ParametricCurveDiscretizerIterator::new(*c, params, &transform, false, 3).collect(),
+        }
+    }
+}
+
+fn random_curves() -> Vec<Curve> {
+    let mut rng = Lcg(42);
+    let mut point = |scale: f64| Vec2f64::new(rng.next() * scale, rng.next() * scale);
+    (0..CURVES)
+        .map(|i| {
+            let scale = [10.0, 100.0, 1000.0][i % 3];
+            if i % 2 == 0 {
+                Curve::Quadratic(ParametricQuadratic2dBezier::new(point(scale), point(scale), point(scale)))
+            } else {
+                Curve::Cubic(ParametricCubic2dBezier::new(point(scale), point(scale), point(scale), point(scale)))
+            }
+        })
+        .collect()
+}
+
+fn dist_to_segment(pt: &Vec2f64, a: &Vec2f64, b: &Vec2f64) -> f64 {
+    let ab = b - a;
+    let len2 = ab.dot(&ab);
+    let t = if len2 > 0.0 { ((pt - a).dot(&ab) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    (pt - (a + ab * t)).norm()
+}
+
+/// The maximum distance between the curve and the polyline approximating it, sampled.
+fn max_error(curve: &Curve, points: &[Vec2f64]) -> f64 {
+    (0..=ERROR_SAMPLES)
+        .map(|i| {
+            let pt = curve.eval(i as f64 / ERROR_SAMPLES as f64);
+            points.windows(2).map(|w| dist_to_segment(&pt, &w[0], &w[1])).fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+fn flatten(curve: &Curve, params: DiscretizationParams) -> Vec<Vec2f64> {
+    PathDiscretizer::new(params, &Transform2d::Identity)
+        .discretize_iter(curve.to_path())
+        .map(|command| match command {
+            BrokenPolylineCommand::MoveTo(pt) | BrokenPolylineCommand::LineTo(pt) => pt,
+        })
+        .collect()
+}
+
+fn analytic_params(tolerance: f64, flattening: FlatteningMethod) -> DiscretizationParams {
+    DiscretizationParams::default().with_tolerance(tolerance).with_flattening(flattening)
+}
+
+#[test]
+fn analytic_methods_within_tolerance() {
+    let curves = random_curves();
+    for tolerance in TOLERANCES {
+        for method in [FlatteningMethod::Wang, FlatteningMethod::ParabolaIntegral] {
+            for (idx, curve) in curves.iter().enumerate() {
+                let error = max_error(curve, &flatten(curve, analytic_params(tolerance, method)));
+                // a small margin for the sampling of the error
+                assert!(error <= tolerance * 1.01, "{:?}, curve {}: error {} over the tolerance {}", method, idx, error, tolerance);
+            }
+        }
+    }
+}
+
+#[test]
+fn analytic_methods_against_reference() {
+    let curves = random_curves();
+    for tolerance in TOLERANCES {
+        let reference_params = DiscretizationParams::default().with_tolerance(tolerance);
+        let (mut reference_error, mut errors) = (0.0f64, [0.0f64; 2]);
+        let mut points = [0; 2];
+        for curve in &curves {
+            let reference = curve.reference(&reference_params);
+            // the adaptive method of the path discretizer is the reference iterator
+            assert_eq!(flatten(curve, reference_params), reference);
+            reference_error = reference_error.max(max_error(curve, &reference));
+
+            for (idx, method) in [FlatteningMethod::Wang, FlatteningMethod::ParabolaIntegral].into_iter().enumerate() {
+                let flattened = flatten(curve, analytic_params(tolerance, method));
+                assert_eq!(flattened.first(), reference.first(), "{:?} starts elsewhere", method);
+                assert_eq!(flattened.last(), reference.last(), "{:?} ends elsewhere", method);
+                errors[idx] = errors[idx].max(max_error(curve, &flattened));
+                points[idx] += flattened.len();
+            }
+        }
+        // the analytic methods bound the error, the adaptive one only checks the midpoints
+        assert!(errors.iter().all(|error| *error <= reference_error.max(tolerance * 1.01)), "{:?} against {}", errors, reference_error);
+        // the parabola integral places the points where the curvature needs them
+        assert!(points[1] <= points[0], "{} points with the parabola integral, {} with Wang's formula", points[1], points[0]);
+    }
+}
+
+#[test]
+fn clamped_segment_counts_cover_the_curve() {
+    // far more segments than the maximum are needed, they are spread over the whole curve
+    let curve = Curve::Cubic(ParametricCubic2dBezier::new(
+        Vec2f64::new(0.0, 0.0),
+        Vec2f64::new(1e7, 2e7),
+        Vec2f64::new(-1e7, 2e7),
+        Vec2f64::new(1e6, 0.0),
+    ));
+    let points = flatten(&curve, analytic_params(1e-6, FlatteningMethod::ParabolaIntegral));
+    let length: f64 = points.windows(2).map(|w| (w[1] - w[0]).norm()).sum();
+    let longest = points.windows(2).map(|w| (w[1] - w[0]).norm()).fold(0.0, f64::max);
+    assert!(longest < length * 1e-3, "a chord of {} on a curve {} long", longest, length);
+    assert_eq!(points.last(), Some(&Vec2f64::new(1e6, 0.0)));
+}
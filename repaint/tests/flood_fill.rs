@@ -0,0 +1,220 @@
+//! Properties of the flood fill and the boundary fill, checked against a breadth-first search.
+
+mod common;
+
+use std::collections::VecDeque;
+
+use common::Lcg;
+use repaint::base::defs::colors::default_color_types::RgbaFColor;
+use repaint::base::rasterization::algorithms::flood_fill::*;
+use repaint::nalgebra::Vector2;
+
+const ROUNDS: usize = 3000;
+
+/// An image made of random rectangles of a few close colors.
+fn random_image(rng: &mut Lcg, width: usize, height: usize) -> Vec<RgbaFColor> {
+    let palette: Vec<RgbaFColor> = (0..2 + rng.int(4))
+        .map(|_| RgbaFColor::new(rng.next() as f32, 0.5, 0.5, 1.0))
+        .collect();
+    let mut image = vec![palette[0]; width * height];
+    for _ in 0..rng.int(40) {
+        let color = palette[rng.int(palette.len())];
+        let (x, y) = (rng.int(width), rng.int(height));
+        let (w, h) = (1 + rng.int(width / 2), 1 + rng.int(height / 2));
+        for row in y..(y + h).min(height) {
+            image[row * width + x..row * width + (x + w).min(width)].fill(color);
+        }
+    }
+    image
+}
+
+/// The region found pixel by pixel, with a breadth-first search.
+fn oracle(image: &[RgbaFColor], width: usize, seed: (usize, usize), connectivity: Connectivity, inside: impl Fn(&RgbaFColor) -> bool) -> Vec<bool> {
+    let height = image.len() / width;
+    let mut region = vec![false; image.len()];
+    if !inside(&image[seed.1 * width + seed.0]) {
+        return region;
+    }
+    let neighbors: &[(i64, i64)] = match connectivity {
+        Connectivity::Four => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+        Connectivity::Eight => &[(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)],
+    };
+    let mut queue = VecDeque::from([seed]);
+    region[seed.1 * width + seed.0] = true;
+    while let Some((x, y)) = queue.pop_front() {
+        for (dx, dy) in neighbors {
+            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+            if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                continue;
+            }
+            let idx = ny as usize * width + nx as usize;
+            if !region[idx] && inside(&image[idx]) {
+                region[idx] = true;
+                queue.push_back((nx as usize, ny as usize));
+            }
+        }
+    }
+    region
+}
+
+/// The coverage of each pixel, checking that each pixel is plotted once from top to bottom.
+fn plotted(width: usize, height: usize, region: impl FnOnce(&mut dyn FnMut(Vector2<usize>, f32))) -> Vec<f32> {
+    let mut coverage = vec![0.0; width * height];
+    let mut last: Option<(usize, usize)> = None;
+    region(&mut |pos, value| {
+        assert!(pos.x < width && pos.y < height, "pixel {:?} outside of the image", pos);
+        assert!(last.is_none_or(|last| last < (pos.y, pos.x)), "pixels not plotted in order");
+        assert!(value > 0.0 && value <= 1.0, "invalid coverage {}", value);
+        last = Some((pos.y, pos.x));
+        coverage[pos.y * width + pos.x] = value;
+    });
+    coverage
+}
+
+#[test]
+fn same_regions_as_the_oracle() {
+    let mut rng = Lcg(1);
+    for round in 0..ROUNDS {
+        let (width, height) = (1 + rng.int(40), 1 + rng.int(40));
+        let mut image = random_image(&mut rng, width, height);
+        let seed = (rng.int(width), rng.int(height));
+        let params = FloodFillParams {
+            tolerance: [0.0, 0.1, 0.3, 1.0][rng.int(4)],
+            connectivity: if round % 2 == 0 { Connectivity::Four } else { Connectivity::Eight },
+            antialias: false,
+        };
+        let buffer = PixelSlice::new(&mut image, width);
+
+        let target = buffer.get_pixel(Vector2::new(seed.0, seed.1));
+        let region = plotted(width, height, |plot| flood_region(&buffer, Vector2::new(seed.0, seed.1), &params, &mut |pos, c| plot(pos, c)));
+        let expected = oracle(&image, width, seed, params.connectivity, |color| color_distance(color, &target) <= params.tolerance);
+        assert_eq!(region.iter().map(|c| *c == 1.0).collect::<Vec<_>>(), expected, "flood region of round {}", round);
+
+        let buffer = PixelSlice::new(&mut image, width);
+        let boundary = buffer.get_pixel(Vector2::new(rng.int(width), rng.int(height)));
+        let region = plotted(width, height, |plot| boundary_region(&buffer, Vector2::new(seed.0, seed.1), boundary, &params, &mut |pos, c| plot(pos, c)));
+        let expected = oracle(&image, width, seed, params.connectivity, |color| color_distance(color, &boundary) > params.tolerance);
+        assert_eq!(region.iter().map(|c| *c == 1.0).collect::<Vec<_>>(), expected, "boundary region of round {}", round);
+
+        // the filled pixels are the ones of the region
+        let fill = RgbaFColor::new(0.0, 0.0, 1.0, 1.0);
+        let mut filled = image.clone();
+        flood_fill(&mut PixelSlice::new(&mut filled, width), Vector2::new(seed.0, seed.1), fill, &params);
+        let expected = oracle(&image, width, seed, params.connectivity, |color| color_distance(color, &target) <= params.tolerance);
+        for ((before, after), inside) in image.iter().zip(&filled).zip(&expected) {
+            assert_eq!(*after, if *inside { fill } else { *before });
+        }
+    }
+}
+
+#[test]
+fn connectivity() {
+    // a diagonal wall from the top right corner to the bottom left one
+    let size = 20;
+    let (white, black) = (RgbaFColor::new(1.0, 1.0, 1.0, 1.0), RgbaFColor::new(0.0, 0.0, 0.0, 1.0));
+    let mut image = vec![white; size * size];
+    for i in 0..size {
+        image[i * size + size - 1 - i] = black;
+    }
+    let count = |connectivity| {
+        let params = FloodFillParams { connectivity, ..FloodFillParams::default() };
+        let mut count = 0;
+        flood_region(&PixelSlice::new(&mut image.clone(), size), Vector2::new(0, 0), &params, &mut |_, _| count += 1);
+        count
+    };
+    assert_eq!(count(Connectivity::Four), size * (size - 1) / 2);
+    assert_eq!(count(Connectivity::Eight), size * (size - 1));
+}
+
+#[test]
+fn tolerance() {
+    // a gradient from black to white, each column a step of 1/64
+    let (width, height) = (65, 3);
+    let mut image: Vec<RgbaFColor> = (0..width * height)
+        .map(|idx| {
+            let value = (idx % width) as f32 / 64.0;
+            RgbaFColor::new(value, value, value, 1.0)
+        })
+        .collect();
+    for steps in [0, 1, 10, 64] {
+        let params = FloodFillParams { tolerance: steps as f32 / 64.0, ..FloodFillParams::default() };
+        let mut columns = Vec::new();
+        flood_region(&PixelSlice::new(&mut image, width), Vector2::new(0, 1), &params, &mut |pos, _| {
+            if pos.y == 1 {
+                columns.push(pos.x);
+            }
+        });
+        assert_eq!(columns, (0..=steps).collect::<Vec<_>>(), "tolerance of {} steps", steps);
+    }
+
+    // the fill color is within the tolerance: the fill terminates
+    let mut filled = image.clone();
+    let params = FloodFillParams { tolerance: 1.0, connectivity: Connectivity::Eight, antialias: true };
+    flood_fill(&mut PixelSlice::new(&mut filled, width), Vector2::new(30, 1), image[0], &params);
+    assert!(filled.iter().all(|pixel| *pixel == image[0]));
+}
+
+#[test]
+fn antialiasing() {
+    // a white square in a black image with a half covered gray column on its right side
+    let size = 10;
+    let (white, black, gray) = (RgbaFColor::new(1.0, 1.0, 1.0, 1.0), RgbaFColor::new(0.0, 0.0, 0.0, 1.0), RgbaFColor::new(0.5, 0.5, 0.5, 1.0));
+    let mut image = vec![black; size * size];
+    for y in 2..8 {
+        image[y * size + 2..y * size + 6].fill(white);
+        image[y * size + 6] = gray;
+    }
+    let red = RgbaFColor::new(1.0, 0.0, 0.0, 1.0);
+
+    let params = FloodFillParams { antialias: true, ..FloodFillParams::default() };
+    let mut flooded = image.clone();
+    flood_fill(&mut PixelSlice::new(&mut flooded, size), Vector2::new(3, 3), red, &params);
+    let mut bounded = image.clone();
+    boundary_fill(&mut PixelSlice::new(&mut bounded, size), Vector2::new(3, 3), red, black, &FloodFillParams { tolerance: 0.6, ..params });
+
+    let half = RgbaFColor::new(0.75, 0.25, 0.25, 1.0);
+    for filled in [&flooded, &bounded] {
+        for y in 0..size {
+            for x in 0..size {
+                let expected = match (x, y) {
+                    (2..=5, 2..=7) => red,
+                    (6, 2..=7) => half,
+                    _ => image[y * size + x],
+                };
+                let pixel = filled[y * size + x];
+                assert!(color_distance(&pixel, &expected) < 1e-6, "pixel ({}, {}) is {:?} instead of {:?}", x, y, pixel, expected);
+            }
+        }
+    }
+
+    // without antialiasing the gray column is left, the boundary fill covers it
+    let mut flooded = image.clone();
+    flood_fill(&mut PixelSlice::new(&mut flooded, size), Vector2::new(3, 3), red, &FloodFillParams::default());
+    assert_eq!(flooded[3 * size + 6], gray);
+    let mut bounded = image.clone();
+    boundary_fill(&mut PixelSlice::new(&mut bounded, size), Vector2::new(3, 3), red, black, &FloodFillParams::default());
+    assert_eq!(bounded[3 * size + 6], red);
+}
+
+#[test]
+fn degenerate() {
+    let red = RgbaFColor::new(1.0, 0.0, 0.0, 1.0);
+    let mut image = vec![RgbaFColor::TRANSPARENT; 12];
+    let params = FloodFillParams { antialias: true, connectivity: Connectivity::Eight, ..FloodFillParams::default() };
+    // outside of the buffer, empty buffers
+    flood_fill(&mut PixelSlice::new(&mut image, 4), Vector2::new(4, 0), red, &params);
+    flood_fill(&mut PixelSlice::new(&mut image, 4), Vector2::new(0, 3), red, &params);
+    flood_fill(&mut PixelSlice::new(&mut image, 0), Vector2::new(0, 0), red, &params);
+    flood_fill(&mut PixelSlice::new(&mut [], 4), Vector2::new(0, 0), red, &params);
+    assert!(image.iter().all(|pixel| *pixel == RgbaFColor::TRANSPARENT));
+    // the seed on the boundary
+    boundary_fill(&mut PixelSlice::new(&mut image, 4), Vector2::new(1, 1), red, RgbaFColor::TRANSPARENT, &params);
+    assert!(image.iter().all(|pixel| *pixel == RgbaFColor::TRANSPARENT));
+    // the transparent pixels are all the same, a single pixel and an incomplete last row
+    image[5] = RgbaFColor::new(0.3, 0.6, 0.9, 0.0);
+    flood_fill(&mut PixelSlice::new(&mut image, 5), Vector2::new(0, 0), red, &params);
+    assert!(image[..10].iter().all(|pixel| *pixel == red) && image[10..].iter().all(|pixel| *pixel == RgbaFColor::TRANSPARENT));
+    let mut single = [RgbaFColor::TRANSPARENT];
+    flood_fill(&mut PixelSlice::new(&mut single, 1), Vector2::new(0, 0), red, &params);
+    assert_eq!(single, [red]);
+}
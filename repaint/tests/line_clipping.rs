@@ -0,0 +1,216 @@
+//! The parametric line clipping, against sampled brute force and against each other.
+
+mod common;
+
+use std::f64::consts::TAU;
+
+use common::{random_convex_polygon, random_rect, Lcg};
+use repaint::base::clipping::line::{
+    clip_line_to_convex_polygon, clip_line_to_transformed_rect, cohen_sutherland, liang_barsky, ClippedLine, LineExtent,
+};
+use repaint::base::defs::{linalg::*, rect::{F64Rect, VideoRect}};
+use repaint::base::transform::Transform2d;
+
+const ROUNDS: usize = 300;
+const SAMPLES: usize = 2000;
+const EXTENTS: [LineExtent; 3] = [LineExtent::Segment, LineExtent::Ray, LineExtent::Line];
+
+fn random_point(rng: &mut Lcg) -> Vec2f64 {
+    Vec2f64::new(rng.range(-50.0, 150.0), rng.range(-50.0, 150.0))
+}
+
+/// A line long enough for the sampled parameters in `-10..10` to cover the whole area.
+fn random_line(rng: &mut Lcg) -> (Vec2f64, Vec2f64) {
+    let start = random_point(rng);
+    let angle = rng.range(0.0, TAU);
+    (start, start + Vec2f64::new(angle.cos(), angle.sin()) * rng.range(20.0, 100.0))
+}
+
+/// Checks the clipped line against the samples of the line inside the region.
+fn check_brute_force(
+    start: &Vec2f64,
+    end: &Vec2f64,
+    extent: LineExtent,
+    clipped: Option<ClippedLine>,
+    inside: impl Fn(&Vec2f64) -> bool,
+) {
+    let (min, max) = extent.range();
+    let (min, max) = (min.max(-10.0), max.min(10.0));
+    let step = (max - min) / SAMPLES as f64;
+    let delta = end - start;
+    let visible: Vec<f64> = (0..=SAMPLES).map(|i| min + step * i as f64).filter(|t| inside(&(start + delta * *t))).collect();
+
+    match (clipped, visible.first(), visible.last()) {
+        (Some(clipped), Some(first), Some(last)) => {
+            assert!(clipped.t0 <= clipped.t1);
+            assert!(min - 1e-12 <= clipped.t0 && clipped.t1 <= max + 1e-12, "{:?} outside {:?}", clipped, extent);
+            assert!((clipped.t0 - first).abs() <= step && (clipped.t1 - last).abs() <= step, "{:?}, the samples go from {} to {}", clipped, first, last);
+            assert!((clipped.start - (start + delta * clipped.t0)).norm() < 1e-9 * delta.norm().max(1.0) * clipped.t0.abs().max(1.0));
+            assert!((clipped.end - (start + delta * clipped.t1)).norm() < 1e-9 * delta.norm().max(1.0) * clipped.t1.abs().max(1.0));
+        }
+        (Some(clipped), _, _) => assert!(clipped.t1 - clipped.t0 <= step, "{:?} but no sample is inside", clipped),
+        (None, Some(first), Some(last)) => panic!("no line but the samples from {} to {} are inside", first, last),
+        (None, _, _) => {}
+    }
+}
+
+#[test]
+fn algorithms_agree() {
+    let mut rng = Lcg(1);
+    for _ in 0..20 * ROUNDS {
+        let rect = random_rect(&mut rng, 0.0..60.0, 1.0..40.0);
+        let (start, end) = (random_point(&mut rng), random_point(&mut rng));
+        let (a, b) = (liang_barsky(&start, &end, LineExtent::Segment, &rect), cohen_sutherland(&start, &end, &rect));
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                assert!((a.t0 - b.t0).abs() < 1e-9 && (a.t1 - b.t1).abs() < 1e-9, "{:?} and {:?}", a, b);
+                assert!((a.start - b.start).norm() < 1e-9 && (a.end - b.end).norm() < 1e-9, "{:?} and {:?}", a, b);
+                assert!(rect.contains(&a.start) && rect.contains(&a.end) && rect.contains(&b.start) && rect.contains(&b.end));
+            }
+            // the segment can touch a corner
+            (Some(line), None) | (None, Some(line)) => assert!((line.end - line.start).norm() < 1e-9, "{:?}", line),
+            (None, None) => {}
+        }
+    }
+}
+
+#[test]
+fn rays_and_lines() {
+    let mut rng = Lcg(2);
+    for _ in 0..ROUNDS {
+        let rect = random_rect(&mut rng, 0.0..60.0, 1.0..40.0);
+        let (start, end) = random_line(&mut rng);
+        let mut clipped = EXTENTS.map(|extent| liang_barsky(&start, &end, extent, &rect));
+        for (extent, clipped) in EXTENTS.iter().zip(clipped) {
+            check_brute_force(&start, &end, *extent, clipped, |pt| rect.contains(pt));
+        }
+
+        // the segment and the ray are the parts of the infinite line with the parameters in their range
+        let line = clipped[2];
+        for (extent, clipped) in EXTENTS[..2].iter().zip(&mut clipped) {
+            let (min, max) = extent.range();
+            let expected = line.map(|line| (line.t0.max(min), line.t1.min(max))).filter(|(t0, t1)| t0 <= t1);
+            assert_eq!(clipped.map(|clipped| (clipped.t0, clipped.t1)), expected, "{:?}", extent);
+        }
+    }
+
+    // parallel to the sides, on a side and outside
+    let rect = F64Rect::new(Vec2f64::new(0.0, 0.0), Vec2f64::new(10.0, 10.0));
+    let line = liang_barsky(&Vec2f64::new(5.0, 20.0), &Vec2f64::new(5.0, 30.0), LineExtent::Line, &rect).unwrap();
+    assert_eq!((line.t0, line.t1), (-2.0, -1.0));
+    assert_eq!((line.start, line.end), (Vec2f64::new(5.0, 0.0), Vec2f64::new(5.0, 10.0)));
+    assert!(liang_barsky(&Vec2f64::new(5.0, 20.0), &Vec2f64::new(5.0, 30.0), LineExtent::Ray, &rect).is_none());
+    assert!(liang_barsky(&Vec2f64::new(10.0, 20.0), &Vec2f64::new(10.0, 30.0), LineExtent::Line, &rect).is_some());
+    assert!(liang_barsky(&Vec2f64::new(11.0, 20.0), &Vec2f64::new(11.0, 30.0), LineExtent::Line, &rect).is_none());
+}
+
+#[test]
+fn reversed_lines() {
+    let mut rng = Lcg(3);
+    for _ in 0..ROUNDS {
+        let rect = random_rect(&mut rng, 0.0..60.0, 1.0..40.0);
+        let (start, end) = random_line(&mut rng);
+        for extent in [LineExtent::Segment, LineExtent::Line] {
+            let forward = liang_barsky(&start, &end, extent, &rect);
+            let backward = liang_barsky(&end, &start, extent, &rect);
+            let (Some(forward), Some(backward)) = (forward, backward) else {
+                assert_eq!(forward.is_some(), backward.is_some());
+                continue;
+            };
+            // the parameters of the reversed line are `1 - t`, it is visible in the other direction
+            assert!((backward.t0 - (1.0 - forward.t1)).abs() < 1e-9 && (backward.t1 - (1.0 - forward.t0)).abs() < 1e-9, "{:?} and {:?}", forward, backward);
+            assert!((backward.start - forward.end).norm() < 1e-9 && (backward.end - forward.start).norm() < 1e-9, "{:?} and {:?}", forward, backward);
+            assert!((forward.end - forward.start).dot(&(end - start)) >= 0.0, "{:?} is reversed", forward);
+        }
+
+        let (Some(forward), Some(backward)) = (cohen_sutherland(&start, &end, &rect), cohen_sutherland(&end, &start, &rect)) else {
+            continue;
+        };
+        assert!((backward.t0 - (1.0 - forward.t1)).abs() < 1e-9 && (backward.t1 - (1.0 - forward.t0)).abs() < 1e-9, "{:?} and {:?}", forward, backward);
+        assert!((forward.end - forward.start).dot(&(end - start)) >= 0.0, "{:?} is reversed", forward);
+    }
+}
+
+#[test]
+fn transformed_rects() {
+    let mut rng = Lcg(4);
+    for _ in 0..ROUNDS {
+        let rect = F64Rect::new(Vec2f64::new(-1.0, -1.0), Vec2f64::new(1.0, 1.0));
+        let angle = rng.range(0.0, TAU);
+        let (cos, sin) = (angle.cos(), angle.sin());
+        let (sx, sy) = (rng.range(5.0, 40.0), rng.range(5.0, 40.0));
+        let linear = Mat2f64::new(cos * sx, -sin * sy, sin * sx, cos * sy);
+        let translation = Vec2f64::new(rng.range(20.0, 80.0), rng.range(20.0, 80.0));
+        let transform = Transform2d::Affine { linear, translation };
+        let inverse = linear.try_inverse().unwrap();
+
+        let (start, end) = random_line(&mut rng);
+        for extent in EXTENTS {
+            let clipped = clip_line_to_transformed_rect(&start, &end, extent, &rect, &transform);
+            check_brute_force(&start, &end, extent, clipped, |pt| rect.contains(&(inverse * (pt - translation))));
+        }
+    }
+
+    let rect = F64Rect::new(Vec2f64::new(-1.0, -1.0), Vec2f64::new(1.0, 1.0));
+    let (start, end) = (Vec2f64::new(0.0, 0.0), Vec2f64::new(1.0, 0.0));
+    assert!(clip_line_to_transformed_rect(&start, &end, LineExtent::Line, &rect, &Transform2d::Scale(0.0)).is_none());
+    assert!(clip_line_to_transformed_rect(&start, &end, LineExtent::Line, &rect, &Transform2d::General(Box::new(|pt| pt))).is_none());
+}
+
+#[test]
+fn convex_polygons() {
+    let mut rng = Lcg(5);
+    for _ in 0..ROUNDS {
+        let (center, radius) = (Vec2f64::new(rng.range(20.0, 80.0), rng.range(20.0, 80.0)), rng.range(5.0, 40.0));
+        let count = 3 + rng.int(6);
+        let polygon = random_convex_polygon(&mut rng, center, Vec2f64::repeat(radius), count);
+        let reversed: Vec<_> = polygon.iter().rev().copied().collect();
+        // the polygon is counter-clockwise in a y-up frame
+        let inside = |pt: &Vec2f64| {
+            (0..polygon.len()).all(|i| (polygon[(i + 1) % polygon.len()] - polygon[i]).perp(&(pt - polygon[i])) >= 0.0)
+        };
+
+        let (start, end) = random_line(&mut rng);
+        for extent in EXTENTS {
+            let clipped = clip_line_to_convex_polygon(&start, &end, extent, &polygon);
+            check_brute_force(&start, &end, extent, clipped, inside);
+            let reversed = clip_line_to_convex_polygon(&start, &end, extent, &reversed);
+            assert_eq!(reversed.is_some(), clipped.is_some());
+            if let (Some(a), Some(b)) = (clipped, reversed) {
+                assert!((a.t0 - b.t0).abs() < 1e-9 && (a.t1 - b.t1).abs() < 1e-9, "{:?} and {:?}", a, b);
+            }
+        }
+
+        // a rectangle as a polygon
+        let rect = random_rect(&mut rng, 0.0..60.0, 1.0..40.0);
+        let corners = [rect.top_left(), rect.top_right(), rect.bottom_right(), rect.bottom_left()];
+        for extent in EXTENTS {
+            let a = clip_line_to_convex_polygon(&start, &end, extent, &corners);
+            let b = liang_barsky(&start, &end, extent, &rect);
+            assert_eq!(a.is_some(), b.is_some(), "{:?} and {:?}", a, b);
+            if let (Some(a), Some(b)) = (a, b) {
+                assert!((a.t0 - b.t0).abs() < 1e-9 && (a.t1 - b.t1).abs() < 1e-9, "{:?} and {:?}", a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn degenerate_inputs() {
+    let (start, end) = (Vec2f64::new(-10.0, 5.0), Vec2f64::new(20.0, 5.0));
+    let flat = [Vec2f64::new(0.0, 0.0), Vec2f64::new(5.0, 5.0), Vec2f64::new(10.0, 10.0)];
+    assert!(clip_line_to_convex_polygon(&start, &end, LineExtent::Line, &flat).is_none());
+    assert!(clip_line_to_convex_polygon(&start, &end, LineExtent::Line, &flat[..2]).is_none());
+
+    let rect = F64Rect::new(Vec2f64::new(0.0, 0.0), Vec2f64::new(10.0, 10.0));
+    let infinite = Vec2f64::new(f64::INFINITY, 5.0);
+    assert!(liang_barsky(&start, &infinite, LineExtent::Segment, &rect).is_none());
+    assert!(cohen_sutherland(&start, &infinite, &rect).is_none());
+    // a point is a line that is visible inside only
+    let point = Vec2f64::new(5.0, 5.0);
+    for extent in EXTENTS {
+        let clipped = liang_barsky(&point, &point, extent, &rect).unwrap();
+        assert_eq!((clipped.t0, clipped.t1, clipped.start), (0.0, 0.0, point));
+        assert!(liang_barsky(&start, &start, extent, &rect).is_none());
+    }
+}
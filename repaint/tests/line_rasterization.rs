@@ -0,0 +1,272 @@
+//! Pixel by pixel checks of the line rasterization algorithms on random segments.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::{everything, Lcg};
+use repaint::base::defs::{linalg::*, rect::Rect};
+use repaint::base::rasterization::algorithms::line::{antialiased, plain};
+use repaint::nalgebra::Vector2;
+
+const ROUNDS: usize = 1000;
+
+type PlainLine = fn(&Vec2f32, &Vec2f32, &mut dyn FnMut(i32, i32), &Rect<i32>);
+type AntialiasedLine = fn(&Vec2f32, &Vec2f32, &mut dyn FnMut(i32, i32, f32), &Rect<i32>);
+/// A transform of the points and the matching transform of the pixels.
+type Reflection = (fn(Vec2f32) -> Vec2f32, fn((i32, i32)) -> (i32, i32));
+
+const PLAIN: [(&str, PlainLine); 2] = [
+    ("naive", |start, end, plot, rect| plain::naive::line(start, end, &mut |x, y| plot(x, y), rect)),
+    ("parametric", |start, end, plot, rect| plain::parametric::line(start, end, &mut |x, y| plot(x, y), rect)),
+];
+
+const ANTIALIASED: [(&str, AntialiasedLine); 3] = [
+    ("xiaolin_wu", |start, end, plot, rect| antialiased::xiaolin_wu::line(start, end, &mut |x, y, intensity| plot(x, y, intensity), rect)),
+    ("gupta_sproll", |start, end, plot, rect| antialiased::gupta_sproll::line(start, end, &mut |x, y, intensity| plot(x, y, intensity), rect)),
+    ("midpoint", |start, end, plot, rect| antialiased::midpoint::line(start, end, &mut |x, y, intensity| plot(x, y, intensity), rect)),
+];
+
+/// A point with sub-pixel, integer or half-integer coordinates, possibly negative.
+fn point(rng: &mut Lcg) -> Vec2f32 {
+    let mut coordinate = || {
+        let value = rng.range(-30.0, 60.0);
+        match rng.int(4) {
+            0 => value.round(),
+            1 => value.round() + 0.5,
+            _ => value,
+        }
+    };
+    Vec2f32::new(coordinate() as f32, coordinate() as f32)
+}
+
+fn plain_pixels(line: PlainLine, start: &Vec2f32, end: &Vec2f32, rect: &Rect<i32>) -> Vec<(i32, i32)> {
+    let mut pixels = Vec::new();
+    line(start, end, &mut |x, y| pixels.push((x, y)), rect);
+    pixels
+}
+
+fn antialiased_pixels(line: AntialiasedLine, start: &Vec2f32, end: &Vec2f32, rect: &Rect<i32>) -> BTreeMap<(i32, i32), f32> {
+    let mut pixels = BTreeMap::new();
+    line(
+        start,
+        end,
+        &mut |x, y, intensity| {
+            assert!(intensity > 0.0 && intensity <= 1.0 + 1e-5, "invalid intensity {intensity}");
+            *pixels.entry((x, y)).or_default() += intensity;
+        },
+        rect,
+    );
+    pixels
+}
+
+/// Swaps the coordinates if the segment is steep, so that `x` is the major axis.
+fn major_frame(start: &Vec2f32, end: &Vec2f32) -> (bool, Vec2f64, Vec2f64) {
+    let delta = end - start;
+    let steep = delta.y.abs() > delta.x.abs();
+    let frame = |v: &Vec2f32| if steep { Vec2f64::new(v.y as f64, v.x as f64) } else { v.cast() };
+    (steep, frame(start), frame(end))
+}
+
+#[test]
+fn plain_diamond_exit_rule() {
+    // the columns whose center line is crossed from the start included to the end excluded, each with
+    // the pixel containing the crossing
+    let mut rng = Lcg(7);
+    for _ in 0..ROUNDS {
+        let (start, end) = (point(&mut rng), point(&mut rng));
+        let (steep, a, b) = major_frame(&start, &end);
+        let columns: Vec<i32> = if a.x < b.x {
+            ((a.x - 0.5).ceil() as i32..(b.x - 0.5).ceil() as i32).collect()
+        } else {
+            ((b.x - 0.5).floor() as i32 + 1..(a.x - 0.5).floor() as i32 + 1).rev().collect()
+        };
+        for (name, line) in PLAIN {
+            let pixels = plain_pixels(line, &start, &end, &everything());
+            assert_eq!(pixels.len(), columns.len(), "{name}: wrong columns for {start:?} -> {end:?}");
+            for (pixel, u) in pixels.iter().zip(&columns) {
+                let (pu, pv) = if steep { (pixel.1, pixel.0) } else { (pixel.0, pixel.1) };
+                assert_eq!(pu, *u, "{name}: wrong column order for {start:?} -> {end:?}");
+                let v = a.y + (*u as f64 + 0.5 - a.x) * (b.y - a.y) / (b.x - a.x);
+                // the rounding can move a crossing on a pixel boundary to the pixel below
+                let ambiguous = (v - v.round()).abs() < 1e-9 && pv == v.round() as i32 - 1;
+                assert!(pv == v.floor() as i32 || ambiguous, "{name}: pixel {pixel:?} for {start:?} -> {end:?}, crossing at {v}");
+            }
+        }
+    }
+}
+
+#[test]
+fn octants() {
+    // the reflections and the transposition of a segment plot the reflected pixels, the points are not
+    // on the grid so that no crossing is on a pixel boundary
+    let mut rng = Lcg(8);
+    for _ in 0..ROUNDS {
+        let start = Vec2f32::new(rng.range(-30.0, 60.0) as f32, rng.range(-30.0, 60.0) as f32);
+        let end = Vec2f32::new(rng.range(-30.0, 60.0) as f32, rng.range(-30.0, 60.0) as f32);
+        let transforms: [Reflection; 3] = [
+            (|v| Vec2f32::new(-v.x, v.y), |(x, y)| (-1 - x, y)),
+            (|v| Vec2f32::new(v.x, -v.y), |(x, y)| (x, -1 - y)),
+            (|v| Vec2f32::new(v.y, v.x), |(x, y)| (y, x)),
+        ];
+        for (name, line) in PLAIN {
+            let pixels = plain_pixels(line, &start, &end, &everything());
+            for (transform, pixel_transform) in transforms {
+                let expected: Vec<_> = pixels.iter().map(|pixel| pixel_transform(*pixel)).collect();
+                let transformed = plain_pixels(line, &transform(start), &transform(end), &everything());
+                assert_eq!(transformed, expected, "{name}: the reflection of {start:?} -> {end:?} differs");
+            }
+        }
+        for (name, line) in ANTIALIASED {
+            let pixels = antialiased_pixels(line, &start, &end, &everything());
+            for (transform, pixel_transform) in transforms {
+                let transformed = antialiased_pixels(line, &transform(start), &transform(end), &everything());
+                assert_eq!(transformed.len(), pixels.len(), "{name}: the reflection of {start:?} -> {end:?} differs");
+                for (pixel, intensity) in &pixels {
+                    let value = transformed.get(&pixel_transform(*pixel)).copied().unwrap_or(0.0);
+                    assert!((value - intensity).abs() < 1e-4, "{name}: the reflection of {start:?} -> {end:?} differs at {pixel:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn split_segments() {
+    // a segment split in two gives the same columns, the split point is plotted once
+    let mut rng = Lcg(9);
+    for _ in 0..ROUNDS {
+        let (start, end) = (point(&mut rng), point(&mut rng));
+        let delta = end - start;
+        let steep = delta.y.abs() > delta.x.abs();
+        let major = |pt: &(i32, i32)| if steep { pt.1 } else { pt.0 };
+        let middle = start + delta * rng.next() as f32;
+        let is_steep = |d: Vec2f32| d.y.abs() > d.x.abs();
+        let same_axis = is_steep(middle - start) == steep && is_steep(end - middle) == steep;
+        if !same_axis || middle == start || middle == end || (middle - start).dot(&delta) <= 0.0 || (end - middle).dot(&delta) <= 0.0 {
+            continue;
+        }
+        for (name, line) in PLAIN {
+            let columns: Vec<i32> = plain_pixels(line, &start, &end, &everything()).iter().map(major).collect();
+            let mut split = plain_pixels(line, &start, &middle, &everything());
+            split.extend(plain_pixels(line, &middle, &end, &everything()));
+            let split_columns: Vec<i32> = split.iter().map(major).collect();
+            assert_eq!(split_columns, columns, "{name}: {start:?} -> {middle:?} -> {end:?} plots other columns");
+        }
+    }
+}
+
+#[test]
+fn clipping() {
+    let mut rng = Lcg(10);
+    for _ in 0..ROUNDS {
+        let (start, end) = (point(&mut rng), point(&mut rng));
+        let min = Vector2::new(rng.int_range(-30, 40), rng.int_range(-30, 40));
+        let clip = Rect::new(min, min + Vector2::new(rng.int_range(0, 30), rng.int_range(0, 30)));
+        let inside = |pt: &(i32, i32)| pt.0 >= clip.min.x && pt.0 < clip.max.x && pt.1 >= clip.min.y && pt.1 < clip.max.y;
+        for (name, line) in PLAIN {
+            let expected: Vec<_> = plain_pixels(line, &start, &end, &everything()).into_iter().filter(inside).collect();
+            assert_eq!(plain_pixels(line, &start, &end, &clip), expected, "{name} clipping differs for {start:?} -> {end:?} in {clip:?}");
+        }
+        for (name, line) in ANTIALIASED {
+            let mut expected = antialiased_pixels(line, &start, &end, &everything());
+            expected.retain(|pt, _| inside(pt));
+            assert_eq!(antialiased_pixels(line, &start, &end, &clip), expected, "{name} clipping differs for {start:?} -> {end:?} in {clip:?}");
+        }
+    }
+}
+
+#[test]
+fn antialiased_covered_columns() {
+    // the intensities of a column add up to the length of the segment inside it along the major axis,
+    // times the length of the segment per unit of the major axis for the area sampling
+    let mut rng = Lcg(11);
+    for _ in 0..ROUNDS {
+        let (start, end) = (point(&mut rng), point(&mut rng));
+        if start == end {
+            continue;
+        }
+        let (steep, a, b) = major_frame(&start, &end);
+        let (min, max) = (a.x.min(b.x), a.x.max(b.x));
+        let stretch = (b - a).norm() / (max - min);
+        for (name, line) in ANTIALIASED {
+            let scale = match name {
+                "xiaolin_wu" => 1.0,
+                "midpoint" => stretch,
+                _ => continue,
+            };
+            let mut columns: BTreeMap<i32, f64> = BTreeMap::new();
+            for (pixel, intensity) in antialiased_pixels(line, &start, &end, &everything()) {
+                *columns.entry(if steep { pixel.1 } else { pixel.0 }).or_default() += intensity as f64;
+            }
+            let expected: BTreeMap<i32, f64> = (min.floor() as i32..max.ceil() as i32)
+                .map(|u| (u, ((u as f64 + 1.0).min(max) - (u as f64).max(min)) * scale))
+                .filter(|(_, length)| *length > 0.0)
+                .collect();
+            assert_eq!(columns.keys().collect::<Vec<_>>(), expected.keys().collect::<Vec<_>>(), "{name}: wrong columns for {start:?} -> {end:?}");
+            for (u, length) in expected {
+                assert!((columns[&u] - length).abs() < 1e-3 * scale, "{name}: column {u} of {start:?} -> {end:?} has {} instead of {length}", columns[&u]);
+            }
+        }
+    }
+}
+
+#[test]
+fn antialiased_brightest_on_the_line() {
+    // the antialiased algorithms are brightest on the plain pixels, far from the ends
+    let mut rng = Lcg(12);
+    for _ in 0..ROUNDS {
+        let (start, end) = (point(&mut rng), point(&mut rng));
+        let steep = (end - start).y.abs() > (end - start).x.abs();
+        let major = |pt: &(i32, i32)| if steep { pt.1 } else { pt.0 };
+        let minor = |pt: &(i32, i32)| if steep { pt.0 } else { pt.1 };
+        let columns: BTreeMap<i32, i32> = plain_pixels(PLAIN[0].1, &start, &end, &everything()).iter().map(|pt| (major(pt), minor(pt))).collect();
+        let (min, max) = if steep { (start.y.min(end.y), start.y.max(end.y)) } else { (start.x.min(end.x), start.x.max(end.x)) };
+        for (name, line) in ANTIALIASED {
+            let pixels = antialiased_pixels(line, &start, &end, &everything());
+            let mut brightest: BTreeMap<i32, ((i32, i32), f32)> = BTreeMap::new();
+            for (pt, intensity) in &pixels {
+                let entry = brightest.entry(major(pt)).or_insert((*pt, 0.0));
+                if *intensity > entry.1 {
+                    *entry = (*pt, *intensity);
+                }
+            }
+            for (column, (pt, intensity)) in brightest {
+                let inner = column as f32 > min + 1.0 && (column as f32) < max - 2.0;
+                // the nearest pixels are ambiguous when the line goes between two centers
+                let ambiguous = pixels
+                    .iter()
+                    .any(|(other, value)| major(other) == column && *other != pt && (*value - intensity).abs() < 1e-3);
+                if inner && !ambiguous {
+                    assert_eq!(Some(&minor(&pt)), columns.get(&column), "{name} is not brightest on the line {start:?} -> {end:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn degenerate_segments() {
+    let huge = Vec2f32::new(1e30, -1e30);
+    for (start, end) in [
+        (Vec2f32::new(f32::NAN, 0.0), Vec2f32::new(10.0, 10.0)),
+        (Vec2f32::new(0.0, 0.0), Vec2f32::new(f32::INFINITY, 10.0)),
+        (Vec2f32::new(5.5, 5.5), Vec2f32::new(5.5, 5.5)),
+        (-huge, huge),
+        (Vec2f32::new(-1e9, 3.5), Vec2f32::new(1e9, 4.5)),
+    ] {
+        let finite = start.iter().chain(end.iter()).all(|v| v.is_finite()) && start != end;
+        for (name, line) in PLAIN {
+            let pixels = plain_pixels(line, &start, &end, &everything());
+            assert!(finite || pixels.is_empty(), "{name} plotted {start:?} -> {end:?}");
+        }
+        for (name, line) in ANTIALIASED {
+            let pixels = antialiased_pixels(line, &start, &end, &everything());
+            assert!(finite || pixels.is_empty(), "{name} plotted {start:?} -> {end:?}");
+        }
+    }
+    let empty = Rect::new(Vector2::new(5, 5), Vector2::new(5, 50));
+    for (name, line) in PLAIN {
+        assert!(plain_pixels(line, &Vec2f32::new(0.0, 0.0), &Vec2f32::new(50.0, 40.0), &empty).is_empty(), "{name} plotted outside the clip");
+    }
+}
@@ -0,0 +1,66 @@
+//! Areas of offset contours, the insets larger than the shapes collapse.
+
+mod common;
+
+use common::*;
+use repaint::base::defs::fill_rule::FillRule;
+use repaint::base::shapes::path::{offset_contours, OffsetJoin};
+
+const TOLERANCE: f64 = 1e-3;
+
+#[test]
+fn square() {
+    let square = rectangle((0.0, 0.0), (4.0, 4.0));
+    let area = |distance: f64, join: OffsetJoin| contours_area(&offset_contours(&square, FillRule::NonZero, distance, join, TOLERANCE));
+    assert!((area(0.0, OffsetJoin::default()) - 16.0).abs() < 1e-9);
+    assert!((area(1.0, OffsetJoin::default()) - 36.0).abs() < 1e-9);
+    assert!((area(1.0, OffsetJoin::Bevel) - (36.0 - 2.0)).abs() < 1e-9);
+    let round = area(1.0, OffsetJoin::Round);
+    assert!(round < 16.0 + 16.0 + std::f64::consts::PI && round > 16.0 + 16.0 + std::f64::consts::PI - 0.01, "round outset {}", round);
+    for join in [OffsetJoin::default(), OffsetJoin::Bevel, OffsetJoin::Round] {
+        assert!((area(-1.0, join) - 4.0).abs() < 1e-9, "{:?} inset", join);
+    }
+}
+
+#[test]
+fn collapsing_insets() {
+    let square = rectangle((0.0, 0.0), (4.0, 4.0));
+    let triangle = polygon(&[(0.0, 0.0), (6.0, 0.0), (0.0, 3.0)]);
+    // the radius of the incircle is the area over the half perimeter
+    let inradius = 9.0 / ((9.0 + 45f64.sqrt()) / 2.0);
+    // an L shape with arms 1 wide, the bevel join of its inner corner leaves a triangle until 2/3
+    let l_shape = polygon(&[(0.0, 0.0), (5.0, 0.0), (5.0, 1.0), (1.0, 1.0), (1.0, 5.0), (0.0, 5.0)]);
+    for join in [OffsetJoin::default(), OffsetJoin::Bevel, OffsetJoin::Round] {
+        for (shape, half_width) in [(&square, 2.0), (&triangle, inradius), (&l_shape, 2.0 / 3.0)] {
+            for distance in [half_width + 1e-6, half_width * 2.0, 1e9] {
+                let contours = offset_contours(shape, FillRule::NonZero, -distance, join, TOLERANCE);
+                assert!(contours.is_empty(), "{:?} inset by {} of a shape {} wide: {:?}", join, distance, half_width * 2.0, contours);
+            }
+        }
+    }
+    // the inset of the L shape by a bit less than the half width of its arms leaves two strips 0.1 wide
+    let thin = offset_contours(&l_shape, FillRule::NonZero, -0.45, OffsetJoin::default(), TOLERANCE);
+    assert_eq!(thin.len(), 1);
+    assert!((contours_area(&thin) - (2.0 * 4.1 - 0.1) * 0.1).abs() < 1e-9, "area {}", contours_area(&thin));
+}
+
+#[test]
+fn holes() {
+    // a square with a square hole, the outset fills the hole
+    let mut frame = rectangle((0.0, 0.0), (6.0, 6.0));
+    frame.extend(rectangle((2.0, 2.0), (4.0, 4.0)));
+    let area = |distance: f64| contours_area(&offset_contours(&frame, FillRule::EvenOdd, distance, OffsetJoin::default(), TOLERANCE));
+    assert!((area(0.0) - 32.0).abs() < 1e-9);
+    assert!((area(0.5) - (49.0 - 1.0)).abs() < 1e-9);
+    assert!((area(1.0) - 64.0).abs() < 1e-9);
+    assert!((area(-0.5) - (25.0 - 9.0)).abs() < 1e-9);
+    assert!(area(-1.0).abs() < 1e-9);
+}
+
+#[test]
+fn non_finite_distances() {
+    let square = rectangle((0.0, 0.0), (4.0, 4.0));
+    for distance in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert!(offset_contours(&square, FillRule::NonZero, distance, OffsetJoin::Round, TOLERANCE).is_empty(), "offset by {}", distance);
+    }
+}
@@ -0,0 +1,51 @@
+//! Adding transformed paths to a [`PathBuilder`].
+
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::path::{PathBuilder, PathCommand};
+use repaint::base::shapes::{BasicShape, Shape};
+use repaint::base::transform::Transform2d;
+
+/// The default tolerance of the discretization.
+const TOLERANCE: f64 = 0.1;
+
+fn points(shape: &BasicShape) -> Vec<Vec2f64> {
+    let BasicShape::Path(commands) = shape else { panic!("{:?}", shape) };
+    commands
+        .iter()
+        .map(|command| match command {
+            PathCommand::MoveTo(pt) | PathCommand::LineTo(pt) => *pt,
+            command => panic!("{:?} under a general transform", command),
+        })
+        .collect()
+}
+
+#[test]
+fn general_transforms_flatten_in_their_space() {
+    let circle = BasicShape::Circle { center: Vec2f64::new(1.0, 2.0), radius: 1.0 };
+    for scale in [1.0, 100.0, 1000.0] {
+        let transform = Transform2d::General(Box::new(move |pt: Vec2f64| pt * scale));
+        let shape = PathBuilder::new().add_path(circle.to_path_iter().collect::<Vec<_>>().iter(), &transform).build();
+        let points = points(&shape);
+        let (center, radius) = (Vec2f64::new(1.0, 2.0) * scale, scale);
+        for pt in &points {
+            assert!(((pt - center).norm() - radius).abs() < 1e-9 * scale, "{:?} off the circle", pt);
+        }
+        // the chords are within the tolerance of the scaled circle, not of the original one
+        for w in points.windows(2) {
+            let half_chord = (w[1] - w[0]).norm() / 2.0;
+            let sagitta = radius - (radius * radius - half_chord * half_chord).sqrt();
+            assert!(sagitta <= TOLERANCE * 1.01, "a sagitta of {} at the scale {}", sagitta, scale);
+        }
+    }
+}
+
+#[test]
+fn affine_transforms_keep_the_curves() {
+    let circle = BasicShape::Circle { center: Vec2f64::new(1.0, 2.0), radius: 1.0 };
+    let path: Vec<_> = circle.to_path_iter().collect();
+    let transform = Transform2d::Scale(1000.0);
+    let shape = PathBuilder::new().add_path(path.iter(), &transform).build();
+    let BasicShape::Path(commands) = shape else { panic!() };
+    assert_eq!(commands.len(), path.len());
+    assert!(commands.iter().any(|command| matches!(command, PathCommand::EllipticalArcTo { .. })));
+}
@@ -0,0 +1,124 @@
+//! Lengths, positions and segments of measured paths, against polylines of many points.
+
+use std::f64::consts::{PI, TAU};
+
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::path::{PathCommand, PathMeasure};
+
+const TOLERANCE: f64 = 1e-4;
+
+/// A circle made of two half-circle arcs, starting at angle zero.
+fn circle(center: Vec2f64, radius: f64) -> Vec<PathCommand> {
+    let arc = |end_pt: Vec2f64| PathCommand::EllipticalArcTo {
+        radii: Vec2f64::new(radius, radius),
+        x_axis_rotation: 0.0,
+        large_arc_flag: false,
+        sweep_flag: true,
+        end_pt,
+    };
+    vec![
+        PathCommand::MoveTo(center + Vec2f64::new(radius, 0.0)),
+        arc(center - Vec2f64::new(radius, 0.0)),
+        arc(center + Vec2f64::new(radius, 0.0)),
+        PathCommand::ClosePath,
+    ]
+}
+
+fn cubic_point(p: &[Vec2f64; 4], t: f64) -> Vec2f64 {
+    let s = 1.0 - t;
+    p[0] * (s * s * s) + p[1] * (3.0 * s * s * t) + p[2] * (3.0 * s * t * t) + p[3] * (t * t * t)
+}
+
+/// The length of a polyline of `count` points of a curve.
+fn polyline_length(count: usize, point: impl Fn(f64) -> Vec2f64) -> f64 {
+    (1..=count).map(|i| (point(i as f64 / count as f64) - point((i - 1) as f64 / count as f64)).norm()).sum()
+}
+
+/// The end point of a path.
+fn last_point(path: &[PathCommand]) -> Vec2f64 {
+    match path.last().expect("empty path") {
+        PathCommand::MoveTo(pt) | PathCommand::LineTo(pt) => *pt,
+        PathCommand::CubicBezierTo { end_pt, .. }
+        | PathCommand::QuadraticBezierCurveTo { end_pt, .. }
+        | PathCommand::EllipticalArcTo { end_pt, .. } => *end_pt,
+        command => panic!("unexpected command {:?}", command),
+    }
+}
+
+#[test]
+fn circle_length() {
+    for radius in [0.5, 10.0, 1000.0] {
+        let center = Vec2f64::new(3.0, -7.0);
+        let measure = PathMeasure::new(&circle(center, radius), TOLERANCE);
+        assert!((measure.length() - TAU * radius).abs() < TOLERANCE * TAU, "radius {}: length {}", radius, measure.length());
+        assert_eq!(measure.contours().len(), 1);
+        assert!(measure.contours()[0].is_closed());
+
+        // the positions follow the angle, the tangents are perpendicular to the radius
+        for i in 0..=16 {
+            let distance = measure.length() * i as f64 / 16.0;
+            let (pos, tangent) = measure.pos_tan(distance).unwrap();
+            let angle = distance / radius;
+            let expected = center + Vec2f64::new(angle.cos(), angle.sin()) * radius;
+            assert!((pos - expected).norm() < 10.0 * TOLERANCE * radius.max(1.0), "radius {}: {:?} instead of {:?}", radius, pos, expected);
+            assert!((tangent.norm() - 1.0).abs() < 1e-9);
+            assert!(tangent.dot(&(pos - center)).abs() < 1e-3 * radius, "tangent {:?} at {:?}", tangent, pos);
+        }
+    }
+}
+
+#[test]
+fn cubic_length() {
+    let cubics = [
+        [Vec2f64::new(0.0, 0.0), Vec2f64::new(10.0, 30.0), Vec2f64::new(40.0, -30.0), Vec2f64::new(50.0, 0.0)],
+        // a cusp
+        [Vec2f64::new(0.0, 0.0), Vec2f64::new(60.0, 40.0), Vec2f64::new(-10.0, 40.0), Vec2f64::new(50.0, 0.0)],
+        // a loop
+        [Vec2f64::new(0.0, 0.0), Vec2f64::new(80.0, 60.0), Vec2f64::new(-30.0, 60.0), Vec2f64::new(50.0, 0.0)],
+        // collinear control points going back
+        [Vec2f64::new(0.0, 0.0), Vec2f64::new(30.0, 0.0), Vec2f64::new(-10.0, 0.0), Vec2f64::new(20.0, 0.0)],
+    ];
+    for p in cubics {
+        let path = [
+            PathCommand::MoveTo(p[0]),
+            PathCommand::CubicBezierTo { control_pt_1: p[1], control_pt_2: p[2], end_pt: p[3] },
+        ];
+        let measure = PathMeasure::new(&path, TOLERANCE);
+        let expected = polyline_length(100_000, |t| cubic_point(&p, t));
+        assert!((measure.length() - expected).abs() < 1e-3, "{:?}: length {} instead of {}", p, measure.length(), expected);
+        assert!((measure.position(0.0).unwrap() - p[0]).norm() < 1e-9);
+        assert!((measure.position(measure.length()).unwrap() - p[3]).norm() < 1e-9);
+    }
+}
+
+#[test]
+fn segment_endpoints() {
+    let mut path = circle(Vec2f64::new(0.0, 0.0), 10.0);
+    path.extend([
+        PathCommand::MoveTo(Vec2f64::new(30.0, 0.0)),
+        PathCommand::CubicBezierTo { control_pt_1: Vec2f64::new(40.0, 30.0), control_pt_2: Vec2f64::new(70.0, -30.0), end_pt: Vec2f64::new(80.0, 0.0) },
+        PathCommand::QuadraticBezierCurveTo { control_pt: Vec2f64::new(90.0, 20.0), end_pt: Vec2f64::new(100.0, 0.0) },
+    ]);
+    let measure = PathMeasure::new(&path, TOLERANCE);
+    assert_eq!(measure.contours().len(), 2);
+    let length = measure.length();
+    let steps = 23;
+    for i in 0..steps {
+        for j in i + 1..=steps {
+            let (start, end) = (length * i as f64 / steps as f64, length * j as f64 / steps as f64);
+            let segment = measure.segment(start, end);
+            let PathCommand::MoveTo(first) = segment[0] else {
+                panic!("the segment {}..{} doesn't start with a move", start, end);
+            };
+            assert!((first - measure.position(start).unwrap()).norm() < 1e-6, "start of {}..{}", start, end);
+            assert!((last_point(&segment) - measure.position(end).unwrap()).norm() < 1e-6, "end of {}..{}", start, end);
+            // the segments of a single contour have the length of the range
+            if end <= measure.contours()[0].length() || start >= measure.contours()[0].length() {
+                let sub_length = PathMeasure::new(&segment, TOLERANCE).length();
+                assert!((sub_length - (end - start)).abs() < 1e-2, "{}..{} is {} long", start, end, sub_length);
+            }
+        }
+    }
+    assert!(measure.segment(5.0, 5.0).is_empty());
+    assert!(measure.segment(PI, 1.0).is_empty());
+}
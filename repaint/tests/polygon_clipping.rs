@@ -0,0 +1,172 @@
+//! The Sutherland–Hodgman clipping of contours, against the boolean intersection with the clip
+//! region.
+
+mod common;
+
+use common::*;
+use repaint::base::clipping::boolean::{boolean_operation_contours, BooleanOperation};
+use repaint::base::clipping::polygon::{clip_contours_to_convex_polygon, clip_contours_to_rect, signed_area};
+use repaint::base::defs::{fill_rule::FillRule, linalg::*, rect::F64Rect};
+use repaint::base::shapes::polyline::{split_polylines, BrokenPolylineCommand};
+
+const ROUNDS: usize = 200;
+const FILL_RULES: [FillRule; 2] = [FillRule::NonZero, FillRule::EvenOdd];
+
+/// Random self-intersecting contours, partly outside of the `[0, 10]` square.
+fn random_contours(rng: &mut Lcg) -> Vec<BrokenPolylineCommand> {
+    (0..1 + rng.int(2))
+        .flat_map(|_| {
+            let points: Vec<(f64, f64)> = (0..3 + rng.int(8)).map(|_| (rng.range(-5.0, 15.0), rng.range(-5.0, 15.0))).collect();
+            polygon(&points)
+        })
+        .collect()
+}
+
+/// A random convex polygon around the center of the `[0, 10]` square, counter-clockwise on screen.
+fn random_convex_clip(rng: &mut Lcg) -> Vec<Vec2f64> {
+    let (count, radius) = (3 + rng.int(6), rng.range(2.0, 8.0));
+    let mut points = random_convex_polygon(rng, Vec2f64::new(5.0, 5.0), Vec2f64::repeat(radius), count);
+    points.reverse();
+    points
+}
+
+/// The filled area of contours, summing trapezoids between the heights of the vertices and of the
+/// crossings.
+///
+/// The boolean operations are not used here, the clipped contours have pieces going back and forth
+/// along the clip boundary which they don't always classify consistently.
+fn filled_area(commands: &[BrokenPolylineCommand], fill_rule: FillRule) -> f64 {
+    let edges: Vec<(Vec2f64, Vec2f64)> = split_polylines(commands)
+        .iter()
+        .flat_map(|contour| (0..contour.len()).map(|i| (contour[i], contour[(i + 1) % contour.len()])))
+        .filter(|(a, b)| a.y != b.y)
+        .collect();
+
+    let mut heights: Vec<f64> = edges.iter().flat_map(|(a, b)| [a.y, b.y]).collect();
+    for (i, (a, b)) in edges.iter().enumerate() {
+        for (c, d) in &edges[i + 1..] {
+            let denominator = (b - a).perp(&(d - c));
+            if denominator != 0.0 {
+                let (t, u) = ((c - a).perp(&(d - c)) / denominator, (c - a).perp(&(b - a)) / denominator);
+                if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                    heights.push(a.y + (b.y - a.y) * t);
+                }
+            }
+        }
+    }
+    heights.sort_by(f64::total_cmp);
+    heights.dedup();
+
+    let x_at = |(a, b): &(Vec2f64, Vec2f64), y: f64| a.x + (b.x - a.x) * (y - a.y) / (b.y - a.y);
+    let mut area = 0.0;
+    for slab in heights.windows(2) {
+        let (top, bottom) = (slab[0], slab[1]);
+        let middle = (top + bottom) / 2.0;
+        let mut crossings: Vec<(f64, f64, i32)> = edges
+            .iter()
+            .filter(|(a, b)| (a.y <= middle) != (b.y <= middle))
+            .map(|edge| (x_at(edge, top), x_at(edge, bottom), if edge.1.y > edge.0.y { 1 } else { -1 }))
+            .collect();
+        crossings.sort_by(|p, q| (p.0 + p.1).total_cmp(&(q.0 + q.1)));
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            winding += pair[0].2;
+            if fill_rule.is_inside(winding) {
+                area += ((pair[1].0 - pair[0].0) + (pair[1].1 - pair[0].1)) / 2.0 * (bottom - top);
+            }
+        }
+    }
+    area
+}
+
+/// The filled area of the intersection of contours with a clip region.
+fn intersection_area(commands: &[BrokenPolylineCommand], fill_rule: FillRule, clip: &[BrokenPolylineCommand]) -> f64 {
+    contours_area(&boolean_operation_contours(commands, fill_rule, clip, FillRule::NonZero, BooleanOperation::Intersection))
+}
+
+/// The winding number of the contours around a point.
+fn winding(commands: &[BrokenPolylineCommand], pt: &Vec2f64) -> i32 {
+    let mut winding = 0;
+    for contour in split_polylines(commands) {
+        for (i, a) in contour.iter().enumerate() {
+            let b = contour[(i + 1) % contour.len()];
+            let side = (b - a).perp(&(pt - a));
+            if a.y <= pt.y && b.y > pt.y && side > 0.0 {
+                winding += 1;
+            } else if b.y <= pt.y && a.y > pt.y && side < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+    winding
+}
+
+#[test]
+fn rect_clip_areas() {
+    let mut rng = Lcg(1);
+    for round in 0..ROUNDS {
+        let subject = random_contours(&mut rng);
+        let min = Vec2f64::new(rng.range(-2.0, 6.0), rng.range(-2.0, 6.0));
+        let rect = F64Rect::new(min, min + Vec2f64::new(rng.range(0.5, 8.0), rng.range(0.5, 8.0)));
+        let clipped = clip_contours_to_rect(&subject, &rect);
+        assert!(clipped.iter().all(|command| rect.contains(&command.point())), "round {}: outside the rect", round);
+
+        let clip = rectangle((rect.min.x, rect.min.y), (rect.max.x, rect.max.y));
+        for fill_rule in FILL_RULES {
+            let expected = intersection_area(&subject, fill_rule, &clip);
+            let area = filled_area(&clipped, fill_rule);
+            assert!((area - expected).abs() < 1e-6, "round {}, {:?}: area {} instead of {}", round, fill_rule, area, expected);
+        }
+    }
+}
+
+#[test]
+fn convex_clip_areas() {
+    let mut rng = Lcg(2);
+    for round in 0..ROUNDS {
+        let subject = random_contours(&mut rng);
+        let clip = random_convex_clip(&mut rng);
+        let reversed: Vec<_> = clip.iter().rev().copied().collect();
+        assert!(signed_area(&clip) * signed_area(&reversed) < 0.0);
+
+        let expected = FILL_RULES.map(|fill_rule| intersection_area(&subject, fill_rule, &contour(&clip)));
+        // the orientation of the clip polygon doesn't matter
+        for clip in [&clip, &reversed] {
+            let clipped = clip_contours_to_convex_polygon(&subject, clip);
+            for (fill_rule, expected) in FILL_RULES.into_iter().zip(expected) {
+                let area = filled_area(&clipped, fill_rule);
+                assert!((area - expected).abs() < 1e-6, "round {}, {:?}: area {} instead of {}", round, fill_rule, area, expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn windings_are_preserved() {
+    let mut rng = Lcg(3);
+    for round in 0..ROUNDS {
+        let subject = random_contours(&mut rng);
+        let clip = random_convex_clip(&mut rng);
+        let clipped = clip_contours_to_convex_polygon(&subject, &clip);
+        let clip_commands = contour(&clip);
+        for _ in 0..50 {
+            let pt = Vec2f64::new(rng.range(0.0, 10.0), rng.range(0.0, 10.0));
+            let expected = if winding(&clip_commands, &pt) != 0 { winding(&subject, &pt) } else { 0 };
+            assert_eq!(winding(&clipped, &pt), expected, "round {}: winding at {:?}", round, pt);
+        }
+    }
+}
+
+#[test]
+fn degenerate_clips() {
+    let subject = rectangle((0.0, 0.0), (10.0, 10.0));
+    let line = [Vec2f64::new(0.0, 0.0), Vec2f64::new(5.0, 5.0), Vec2f64::new(10.0, 10.0)];
+    assert!(clip_contours_to_convex_polygon(&subject, &line).is_empty());
+    assert!(clip_contours_to_convex_polygon(&subject, &line[..2]).is_empty());
+    // a contour wound twice keeps its winding number of 2 inside the clip
+    let twice: Vec<_> = subject.iter().chain(subject.iter()).copied().collect();
+    let clipped = clip_contours_to_rect(&twice, &F64Rect::new(Vec2f64::new(2.0, 2.0), Vec2f64::new(4.0, 4.0)));
+    assert_eq!(winding(&clipped, &Vec2f64::new(3.0, 3.0)).abs(), 2);
+    assert!((filled_area(&clipped, FillRule::EvenOdd)).abs() < 1e-9);
+    assert!((filled_area(&clipped, FillRule::NonZero) - 4.0).abs() < 1e-9);
+}
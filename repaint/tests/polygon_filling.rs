@@ -0,0 +1,146 @@
+//! Properties of the polygon fillers on random polygons.
+
+mod common;
+
+use common::{contour, device_clip, random_clip, random_convex_polygon, random_coordinate, rectangle, Lcg, Recorder};
+use repaint::base::clipping::polygon::{clip_polygon_to_rect, signed_area};
+use repaint::base::defs::{antialiasing::AntialiasMode, fill_rule::FillRule, linalg::*, rect::{F64Rect, Rect}};
+use repaint::base::rasterization::algorithms::polygon_filling::{self, scanline};
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::nalgebra::Vector2;
+use std::collections::BTreeMap;
+
+const ROUNDS: usize = 500;
+
+fn random_polygon(rng: &mut Lcg) -> Vec<Vec2f64> {
+    let count = 1 + rng.int(12);
+    (0..count).map(|_| Vec2f64::new(random_coordinate(rng, -40.0..140.0), random_coordinate(rng, -40.0..140.0))).collect()
+}
+
+/// Records the pixels relative to the origin of the clip, like the scanline filler passes them.
+fn scanline_fill(commands: &[BrokenPolylineCommand], clip: &Rect<i32>, antialiased: bool) -> Recorder {
+    let size = (clip.max - clip.min).sup(&Vector2::zeros()).map(|v| v as usize);
+    let mut recorder = Recorder::new(&Rect::new(Vector2::zeros(), size));
+    scanline::fill(commands, clip, antialiased, &mut recorder);
+    recorder
+}
+
+/// The recorded pixels at non-negative device coordinates.
+fn device_pixels(recorder: &Recorder, clip: &Rect<i32>) -> BTreeMap<(usize, usize), f32> {
+    recorder
+        .pixels
+        .iter()
+        .map(|((x, y), coverage)| ((*x as i64 + clip.min.x as i64, *y as i64 + clip.min.y as i64), *coverage))
+        .filter(|((x, y), _)| *x >= 0 && *y >= 0)
+        .map(|((x, y), coverage)| ((x as usize, y as usize), coverage))
+        .collect()
+}
+
+fn device_fill(commands: &[BrokenPolylineCommand], clip: &Rect<usize>, fill_rule: FillRule, antialias: AntialiasMode) -> Recorder {
+    let mut recorder = Recorder::new(clip);
+    polygon_filling::fill(commands, clip, fill_rule, antialias, &mut recorder);
+    recorder
+}
+
+#[test]
+fn degenerate_coordinates() {
+    // the recorder checks the consumer contract
+    let mut rng = Lcg(42);
+    let modes = [AntialiasMode::None, AntialiasMode::Exact, AntialiasMode::sub_pixel(4), AntialiasMode::SubPixel { nx: 5, ny: 2 }];
+    for _ in 0..ROUNDS {
+        let commands = contour(&random_polygon(&mut rng));
+        let clip = random_clip(&mut rng, -60..60, 80);
+        scanline_fill(&commands, &clip, false);
+        scanline_fill(&commands, &clip, true);
+        for fill_rule in [FillRule::NonZero, FillRule::EvenOdd] {
+            for antialias in modes {
+                device_fill(&commands, &device_clip(&clip), fill_rule, antialias);
+            }
+        }
+    }
+}
+
+#[test]
+fn outside_of_the_clip() {
+    let square = |min: f64, max: f64| rectangle((min, min), (max, max));
+    let clip = Rect::new(Vector2::new(10, 10), Vector2::new(50, 50));
+    for antialiased in [false, true] {
+        assert!(scanline_fill(&square(-30.0, -5.0), &clip, antialiased).pixels.is_empty());
+        assert!(scanline_fill(&square(60.0, 100.0), &clip, antialiased).pixels.is_empty());
+        assert_eq!(scanline_fill(&square(-1e12, 1e12), &clip, antialiased).area(), 40.0 * 40.0);
+    }
+
+    // the clip can be at negative coordinates, the pixels are relative to its origin
+    let clip = Rect::new(Vector2::new(-50, -50), Vector2::new(-10, -10));
+    for antialiased in [false, true] {
+        let recorder = scanline_fill(&square(-30.0, -20.0), &clip, antialiased);
+        assert_eq!(recorder.area(), 10.0 * 10.0);
+        assert_eq!(recorder.pixels.keys().next(), Some(&(20, 20)));
+        assert!(scanline_fill(&square(-5.0, 5.0), &clip, antialiased).pixels.is_empty());
+    }
+}
+
+#[test]
+fn translation_invariance() {
+    let mut rng = Lcg(43);
+    for _ in 0..ROUNDS {
+        let points = random_polygon(&mut rng);
+        let clip = random_clip(&mut rng, -60..60, 80);
+        let offset = Vector2::new(rng.int_range(-1000, 1000), rng.int_range(-1000, 1000));
+        let moved_points: Vec<Vec2f64> = points.iter().map(|pt| pt + offset.cast()).collect();
+        let moved_clip = Rect::new(clip.min + offset, clip.max + offset);
+        for antialiased in [false, true] {
+            let a = scanline_fill(&contour(&points), &clip, antialiased);
+            let b = scanline_fill(&contour(&moved_points), &moved_clip, antialiased);
+            // the moved coordinates are rounded differently
+            assert_eq!(a.pixels.len(), b.pixels.len(), "translation changed the output of {points:?}");
+            assert!((a.area() - b.area()).abs() < 1e-3, "translation changed the output of {points:?}");
+        }
+    }
+}
+
+#[test]
+fn aliased_fillers_agree() {
+    // the aliased scanline filler samples the pixel centers, like a 1x1 grid
+    let mut rng = Lcg(44);
+    for _ in 0..ROUNDS {
+        let commands = contour(&random_polygon(&mut rng));
+        let clip = random_clip(&mut rng, -60..60, 80);
+        let a = scanline_fill(&commands, &clip, false);
+        let b = device_fill(&commands, &device_clip(&clip), FillRule::EvenOdd, AntialiasMode::None);
+        assert_eq!(device_pixels(&a, &clip), b.pixels, "the aliased fillers disagree on {commands:?}");
+    }
+}
+
+#[test]
+fn convex_areas() {
+    let mut rng = Lcg(45);
+    for _ in 0..ROUNDS {
+        let center = Vec2f64::new(rng.range(10.0, 90.0), rng.range(10.0, 90.0));
+        let radii = Vec2f64::new(rng.range(0.5, 40.0), rng.range(0.5, 40.0));
+        let count = 3 + rng.int(20);
+        let points = random_convex_polygon(&mut rng, center, radii, count);
+        let commands = contour(&points);
+        let clip = random_clip(&mut rng, -60..60, 80);
+        let device = device_clip(&clip);
+        let area_in = |min: Vec2f64, max: Vec2f64| signed_area(&clip_polygon_to_rect(&points, &F64Rect::new(min, max))).abs();
+        // the device fillers only cover the non-negative part of the clip
+        let (area, device_area) = (area_in(clip.min.cast(), clip.max.cast()), area_in(device.min.cast(), device.max.cast()));
+        // the sampling errors are on the pixels crossed by the edges
+        let perimeter = (2.0 * (clip.width() + clip.height()) as f64).min(
+            points.iter().zip(points.iter().cycle().skip(1)).map(|(a, b)| (b - a).norm()).sum(),
+        );
+
+        let areas = [
+            (scanline_fill(&commands, &clip, true).area(), area),
+            (device_fill(&commands, &device, FillRule::NonZero, AntialiasMode::Exact).area(), device_area),
+            (device_fill(&commands, &device, FillRule::EvenOdd, AntialiasMode::Exact).area(), device_area),
+            (device_fill(&commands, &device, FillRule::NonZero, AntialiasMode::sub_pixel(16)).area(), device_area),
+            (device_fill(&commands, &device, FillRule::NonZero, AntialiasMode::None).area(), device_area),
+        ];
+        let tolerances = [perimeter + 4.0, 1e-3 * (1.0 + area), 1e-3 * (1.0 + area), perimeter / 8.0 + 4.0, perimeter + 4.0];
+        for ((value, area), tolerance) in areas.iter().zip(tolerances) {
+            assert!((value - area).abs() <= tolerance, "area {value} instead of {area} for {points:?} in {clip:?}");
+        }
+    }
+}
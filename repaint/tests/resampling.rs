@@ -0,0 +1,75 @@
+//! The resampling of polylines at uniform distance.
+
+mod common;
+
+use common::Lcg;
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::polyline::{resample_points, MAX_RESAMPLED_POINTS};
+
+const ROUNDS: usize = 100;
+
+fn length(points: &[Vec2f64]) -> f64 {
+    points.windows(2).map(|w| (w[1] - w[0]).norm()).sum()
+}
+
+/// The distance along the polyline of a point on it.
+fn arc_length(points: &[Vec2f64], pt: &Vec2f64) -> f64 {
+    let mut along = 0.0;
+    let (mut best, mut best_dist) = (0.0, f64::INFINITY);
+    for w in points.windows(2) {
+        let ab = w[1] - w[0];
+        let t = if ab.norm() > 0.0 { ((pt - w[0]).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0) } else { 0.0 };
+        let dist = (pt - (w[0] + ab * t)).norm();
+        if dist < best_dist {
+            (best, best_dist) = (along + t * ab.norm(), dist);
+        }
+        along += ab.norm();
+    }
+    best
+}
+
+#[test]
+fn uniform_spacing() {
+    let mut rng = Lcg(1);
+    for _ in 0..ROUNDS {
+        let points: Vec<_> = (0..2 + rng.int(8)).map(|_| Vec2f64::new(rng.range(0.0, 100.0), rng.range(0.0, 100.0))).collect();
+        let spacing = rng.range(0.5, 20.0);
+        let samples = resample_points(&points, spacing);
+        assert_eq!(samples.first(), points.first());
+        assert_eq!(samples.last(), points.last());
+
+        let total = length(&points);
+        assert!(samples.len() as f64 <= (total / spacing).ceil() + 1.0, "{} samples", samples.len());
+        // the samples are on the polyline, `spacing` apart along it
+        for (idx, pt) in samples.iter().enumerate().take(samples.len() - 1) {
+            let along = arc_length(&points, pt);
+            assert!((along - idx as f64 * spacing).abs() < 1e-6, "sample {} at {} along", idx, along);
+        }
+    }
+}
+
+#[test]
+fn non_finite_segments() {
+    let (a, b) = (Vec2f64::new(0.0, 0.0), Vec2f64::new(f64::INFINITY, 0.0));
+    assert_eq!(resample_points(&[a, b], 1.0), vec![a, b]);
+
+    let nan = Vec2f64::new(f64::NAN, 1.0);
+    let samples = resample_points(&[a, Vec2f64::new(3.0, 0.0), nan, Vec2f64::new(0.0, 5.0), Vec2f64::new(0.0, 7.0)], 1.0);
+    // the samples restart on the first finite segment after the skipped ones
+    let expected: Vec<_> = [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (0.0, 6.0), (0.0, 7.0)].iter().map(|(x, y)| Vec2f64::new(*x, *y)).collect();
+    assert_eq!(samples, expected);
+
+    let samples = resample_points(&[a, Vec2f64::new(2.0, 0.0), b], 0.5);
+    assert_eq!(samples.len(), 5);
+    assert_eq!(samples.last(), Some(&b));
+}
+
+#[test]
+fn bounded_number_of_samples() {
+    let points = [Vec2f64::new(0.0, 0.0), Vec2f64::new(1e6, 0.0), Vec2f64::new(1e6, 1e300)];
+    for spacing in [1e-300, f64::MIN_POSITIVE, 1e-3] {
+        let samples = resample_points(&points, spacing);
+        assert!(samples.len() <= MAX_RESAMPLED_POINTS + 2, "{} samples", samples.len());
+        assert_eq!(samples.last(), points.last());
+    }
+}
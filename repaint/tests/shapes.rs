@@ -0,0 +1,143 @@
+//! The bounding boxes of the basic shapes, against the discretization of their paths.
+
+mod common;
+
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use common::Lcg;
+use repaint::base::defs::{linalg::*, rect::{F64Rect, TlTrBlBr}};
+use repaint::base::pen::{PenCap, StrokeWidth};
+use repaint::base::shapes::path::discretization::{DiscretizationParams, PathDiscretizer};
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::base::shapes::{BasicShape, Shape};
+use repaint::base::transform::Transform2d;
+use repaint::painter::methods::point_shape;
+
+const ROUNDS: usize = 200;
+const TOLERANCE: f64 = 1e-3;
+
+fn discretize(shape: &impl Shape) -> Vec<Vec2f64> {
+    PathDiscretizer::new(DiscretizationParams::default().with_tolerance(TOLERANCE), &Transform2d::Identity)
+        .discretize_iter(shape.to_path_iter())
+        .map(|command| match command {
+            BrokenPolylineCommand::MoveTo(pt) | BrokenPolylineCommand::LineTo(pt) => pt,
+        })
+        .collect()
+}
+
+/// Checks that the bounds contain the discretized path and that each side is touched by it.
+fn check_bounds(shape: &impl Shape) {
+    let bounds = shape.bounding_box();
+    let points = discretize(shape);
+    assert!(!points.is_empty(), "{:?}", shape);
+
+    let mut min = Vec2f64::repeat(f64::INFINITY);
+    let mut max = Vec2f64::repeat(f64::NEG_INFINITY);
+    for pt in &points {
+        min = min.inf(pt);
+        max = max.sup(pt);
+    }
+    // the bounds are exact, the path is only within the tolerance of the curve
+    let margin = 10.0 * TOLERANCE;
+    for axis in 0..2 {
+        assert!(bounds.min[axis] <= min[axis] + 1e-9 && max[axis] <= bounds.max[axis] + 1e-9, "{:?}: {:?} outside {:?}", shape, (min, max), bounds);
+        assert!(min[axis] - bounds.min[axis] <= margin && bounds.max[axis] - max[axis] <= margin, "{:?}: {:?} loose around {:?}", shape, bounds, (min, max));
+    }
+}
+
+fn random_arc(rng: &mut Lcg) -> (Vec2f64, Vec2f64, f64, f64) {
+    let center = Vec2f64::new(rng.range(-100.0, 100.0), rng.range(-100.0, 100.0));
+    let radii = Vec2f64::new(rng.range(1.0, 50.0), rng.range(1.0, 50.0));
+    (center, radii, rng.range(-10.0, 10.0), rng.range(-1.5 * TAU, 1.5 * TAU))
+}
+
+#[test]
+fn ellipses_and_rounded_rects() {
+    let mut rng = Lcg(1);
+    for _ in 0..ROUNDS {
+        let center = Vec2f64::new(rng.range(-100.0, 100.0), rng.range(-100.0, 100.0));
+        let radii = Vec2f64::new(rng.range(0.1, 50.0), rng.range(0.1, 50.0));
+        check_bounds(&BasicShape::Ellipse { center, radii });
+        check_bounds(&BasicShape::Circle { center, radius: radii.x });
+
+        let rect = F64Rect::new(center - radii, center + radii);
+        let mut corner = || Vec2f64::new(rng.range(0.0, 40.0), rng.range(0.0, 40.0));
+        let radii = TlTrBlBr::new(corner(), corner(), corner(), corner());
+        check_bounds(&BasicShape::RoundedRect { rect, radii });
+    }
+}
+
+#[test]
+fn arcs_pies_and_chords() {
+    let mut rng = Lcg(2);
+    for _ in 0..ROUNDS {
+        let (center, radii, start_angle, sweep_angle) = random_arc(&mut rng);
+        check_bounds(&BasicShape::Arc { center, radii, start_angle, sweep_angle });
+        check_bounds(&BasicShape::Pie { center, radii, start_angle, sweep_angle });
+        check_bounds(&BasicShape::Chord { center, radii, start_angle, sweep_angle });
+    }
+}
+
+#[test]
+fn arcs_ending_at_the_quarter_turns() {
+    let (center, radii) = (Vec2f64::new(3.0, -2.0), Vec2f64::new(5.0, 2.0));
+    for start in -8..8 {
+        for quarters in [-5, -4, -3, -1, 1, 2, 3, 4, 5] {
+            let start_angle = start as f64 * FRAC_PI_2;
+            let sweep_angle = quarters as f64 * FRAC_PI_2;
+            check_bounds(&BasicShape::Arc { center, radii, start_angle, sweep_angle });
+            check_bounds(&BasicShape::Pie { center, radii, start_angle, sweep_angle });
+        }
+    }
+
+    // a quarter turn from the right to the bottom, in the y-down device space
+    let bounds = BasicShape::Arc { center, radii, start_angle: 0.0, sweep_angle: FRAC_PI_2 }.bounding_box();
+    assert!((bounds.min - Vec2f64::new(3.0, -2.0)).norm() < 1e-12, "{:?}", bounds);
+    assert!((bounds.max - Vec2f64::new(8.0, 0.0)).norm() < 1e-12, "{:?}", bounds);
+}
+
+#[test]
+fn huge_and_non_finite_angles() {
+    let (center, radii) = (Vec2f64::zeros(), Vec2f64::new(2.0, 1.0));
+    for start_angle in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+        let bounds = BasicShape::Arc { center, radii, start_angle, sweep_angle: 1.0 }.bounding_box();
+        assert!(bounds.min.x.is_nan() && bounds.max.y.is_nan(), "{}: {:?}", start_angle, bounds);
+    }
+    let bounds = BasicShape::Arc { center, radii, start_angle: 0.0, sweep_angle: f64::NAN }.bounding_box();
+    assert!(bounds.min.x.is_nan(), "{:?}", bounds);
+
+    // an infinite sweep is a full ellipse, like its path
+    let bounds = BasicShape::Arc { center, radii, start_angle: 0.0, sweep_angle: f64::INFINITY }.bounding_box();
+    assert_eq!((bounds.min, bounds.max), (-radii, radii));
+
+    // the quarter turns cannot be enumerated one by one at these angles
+    for start_angle in [1e20, -1e20, 1e300, f64::MAX] {
+        for sweep_angle in [1.0, -PI, 6.0] {
+            let bounds = BasicShape::Arc { center, radii, start_angle, sweep_angle }.bounding_box();
+            assert!(bounds.min.x >= -radii.x && bounds.max.x <= radii.x, "{}: {:?}", start_angle, bounds);
+            assert!(bounds.min.y >= -radii.y && bounds.max.y <= radii.y, "{}: {:?}", start_angle, bounds);
+        }
+    }
+}
+
+#[test]
+fn rects_and_points() {
+    let mut rng = Lcg(3);
+    for _ in 0..ROUNDS {
+        let min = Vec2f64::new(rng.range(-100.0, 100.0), rng.range(-100.0, 100.0));
+        let rect = F64Rect::new(min, min + Vec2f64::new(rng.range(0.0, 50.0), rng.range(0.0, 50.0)));
+        check_bounds(&rect);
+        check_bounds(&BasicShape::Rect(rect));
+
+        let width = rng.range(0.5, 20.0);
+        for cap in [PenCap::Butt, PenCap::Round, PenCap::Square] {
+            let shape = point_shape(min, cap, &StrokeWidth::Normal(width as f32));
+            check_bounds(&shape);
+            // the point is centered, as wide as the pen
+            let bounds = shape.bounding_box();
+            assert!(((bounds.min + bounds.max) / 2.0 - min).norm() < 1e-9, "{:?}", bounds);
+            assert!((bounds.max.x - bounds.min.x - width as f32 as f64).abs() < 1e-9, "{:?}", bounds);
+        }
+        assert_eq!(point_shape(min, PenCap::Square, &StrokeWidth::Hairline).bounding_box(), F64Rect::new(min - Vec2f64::repeat(0.5), min + Vec2f64::repeat(0.5)));
+    }
+}
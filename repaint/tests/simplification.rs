@@ -0,0 +1,140 @@
+//! Properties of the polyline simplifications on random walks and sampled curves.
+
+mod common;
+
+use std::f64::consts::TAU;
+
+use common::Lcg;
+use repaint::base::defs::linalg::*;
+use repaint::base::shapes::polyline::simplification::{ramer_douglas_peucker, simplify_polyline, visvalingam_whyatt, SimplificationMethod};
+use repaint::base::shapes::polyline::{split_polylines, BrokenPolylineCommand};
+
+const ROUNDS: usize = 200;
+
+/// A random walk, with some repeated and collinear points.
+fn random_walk(rng: &mut Lcg) -> Vec<Vec2f64> {
+    let mut pt = Vec2f64::new(rng.range(-50.0, 50.0), rng.range(-50.0, 50.0));
+    let mut points = vec![pt];
+    for _ in 0..rng.int(200) {
+        match rng.int(10) {
+            0 => {}
+            1 => pt += pt - points[points.len().saturating_sub(2)],
+            _ => pt += Vec2f64::new(rng.range(-3.0, 3.0), rng.range(-3.0, 3.0)),
+        }
+        points.push(pt);
+    }
+    points
+}
+
+fn distance_to_segment(pt: &Vec2f64, a: &Vec2f64, b: &Vec2f64) -> f64 {
+    let ab = b - a;
+    let t = if ab.norm_squared() > 0.0 { ((pt - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0) } else { 0.0 };
+    (pt - (a + ab * t)).norm()
+}
+
+/// Tells if the points of `part` are points of `points`, in the same order.
+fn is_subsequence(part: &[Vec2f64], points: &[Vec2f64]) -> bool {
+    let mut points = points.iter();
+    part.iter().all(|pt| points.any(|other| other == pt))
+}
+
+/// The indices of the points of `part` in `points`, which must be a subsequence.
+fn kept_indices(part: &[Vec2f64], points: &[Vec2f64]) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut idx = 0;
+    for pt in part {
+        while points[idx] != *pt {
+            idx += 1;
+        }
+        indices.push(idx);
+        idx += 1;
+    }
+    indices
+}
+
+#[test]
+fn ramer_douglas_peucker_tolerance() {
+    let mut rng = Lcg(1);
+    for _ in 0..ROUNDS {
+        let points = random_walk(&mut rng);
+        let tolerance = rng.range(0.0, 5.0);
+        let simplified = ramer_douglas_peucker(&points, tolerance);
+        assert_eq!(simplified.first(), points.first());
+        assert_eq!(simplified.last(), points.last());
+        assert!(is_subsequence(&simplified, &points));
+
+        // each removed point is within the tolerance of the segment replacing it
+        let kept = kept_indices(&simplified, &points);
+        for pair in kept.windows(2) {
+            let (a, b) = (&points[pair[0]], &points[pair[1]]);
+            for pt in &points[pair[0]..=pair[1]] {
+                let distance = if a == b { (pt - a).norm() } else { distance_to_segment(pt, a, b) };
+                assert!(distance <= tolerance, "a removed point is {} away with a tolerance of {}", distance, tolerance);
+            }
+        }
+    }
+}
+
+#[test]
+fn visvalingam_threshold() {
+    let mut rng = Lcg(2);
+    for _ in 0..ROUNDS {
+        let points = random_walk(&mut rng);
+        let min_area = rng.range(0.0, 10.0);
+        let simplified = visvalingam_whyatt(&points, min_area);
+        assert_eq!(simplified.first(), points.first());
+        assert_eq!(simplified.last(), points.last());
+        assert!(is_subsequence(&simplified, &points));
+        // the removal order doesn't depend on the threshold, a larger one removes more points
+        let coarser = visvalingam_whyatt(&points, min_area * 2.0);
+        assert!(is_subsequence(&coarser, &simplified), "a larger area keeps other points");
+        assert_eq!(visvalingam_whyatt(&points, 0.0), points);
+    }
+
+    // the collinear points have no area
+    let line: Vec<_> = (0..10).map(|i| Vec2f64::new(i as f64, 2.0 * i as f64)).collect();
+    assert_eq!(visvalingam_whyatt(&line, 1e-9), vec![line[0], line[9]]);
+}
+
+#[test]
+fn sampled_circle() {
+    // the removed points of an arc are within the sagitta of the chord replacing them
+    let radius = 100.0;
+    let points: Vec<_> = (0..=1000).map(|i| Vec2f64::new(radius, 0.0) + Vec2f64::new((i as f64 / 1000.0 * TAU).cos(), (i as f64 / 1000.0 * TAU).sin()) * radius).collect();
+    for min_area in [0.01, 1.0, 100.0] {
+        let simplified = visvalingam_whyatt(&points, min_area);
+        assert!(simplified.len() < points.len() && simplified.len() >= 3, "{} points kept", simplified.len());
+        let kept = kept_indices(&simplified, &points);
+        for pair in kept.windows(2) {
+            let (a, b) = (&points[pair[0]], &points[pair[1]]);
+            let chord = (b - a).norm();
+            let sagitta = radius - (radius * radius - chord * chord / 4.0).max(0.0).sqrt();
+            for pt in &points[pair[0]..=pair[1]] {
+                assert!(distance_to_segment(pt, a, b) <= sagitta + 1e-9);
+            }
+        }
+    }
+    for tolerance in [0.01, 1.0, 10.0] {
+        let simplified = ramer_douglas_peucker(&points, tolerance);
+        // the chords with a sagitta of the tolerance, up to the sampling
+        let expected = TAU / (2.0 * (1.0 - tolerance / radius).acos());
+        assert!((simplified.len() as f64) < expected * 2.0 + 2.0, "{} points kept for a tolerance of {}", simplified.len(), tolerance);
+    }
+}
+
+#[test]
+fn broken_polylines() {
+    let mut rng = Lcg(3);
+    let mut commands = Vec::new();
+    let mut walks = Vec::new();
+    for _ in 0..5 {
+        let walk = random_walk(&mut rng);
+        commands.push(BrokenPolylineCommand::MoveTo(walk[0]));
+        commands.extend(walk[1..].iter().map(|pt| BrokenPolylineCommand::LineTo(*pt)));
+        walks.push(walk);
+    }
+    let method = SimplificationMethod::RamerDouglasPeucker { tolerance: 1.0 };
+    let simplified = split_polylines(&simplify_polyline(&commands, method));
+    let expected: Vec<_> = walks.iter().map(|walk| method.simplify(walk)).collect();
+    assert_eq!(simplified, expected);
+}
@@ -0,0 +1,98 @@
+//! Properties of the sparse strip filler, checked against the accumulation filler on random polygons.
+
+mod common;
+
+use common::{contour, device_clip, random_clip, random_coordinate, rectangle, Lcg, Recorder};
+use repaint::base::defs::{fill_rule::FillRule, linalg::*, rect::Rect};
+use repaint::base::rasterization::algorithms::polygon_filling::{accumulation, sparse_strips::{self, SparseStripFiller}};
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::nalgebra::Vector2;
+
+const ROUNDS: usize = 600;
+
+/// Random contours, some of them with axis-aligned and integer points.
+fn random_contours(rng: &mut Lcg) -> Vec<BrokenPolylineCommand> {
+    let mut commands = Vec::new();
+    for _ in 0..1 + rng.int(3) {
+        let rounded = rng.next() < 0.3;
+        for i in 0..2 + rng.int(10) {
+            let mut pt = Vec2f64::new(random_coordinate(rng, -40.0..160.0), random_coordinate(rng, -40.0..160.0));
+            if rounded {
+                pt = pt.map(f64::round);
+            }
+            commands.push(if i == 0 { BrokenPolylineCommand::MoveTo(pt) } else { BrokenPolylineCommand::LineTo(pt) });
+        }
+    }
+    commands
+}
+
+/// The largest difference of coverage between two recordings.
+fn max_difference(a: &Recorder, b: &Recorder) -> f32 {
+    let mut keys: Vec<_> = a.pixels.keys().chain(b.pixels.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.iter()
+        .map(|key| (a.pixels.get(key).copied().unwrap_or(0.0) - b.pixels.get(key).copied().unwrap_or(0.0)).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn same_coverage_as_accumulation() {
+    let mut rng = Lcg(1);
+    let mut filler = SparseStripFiller::new();
+    for round in 0..ROUNDS {
+        let commands = random_contours(&mut rng);
+        let clip = device_clip(&random_clip(&mut rng, 0..60, 90));
+        let fill_rule = if round % 2 == 0 { FillRule::NonZero } else { FillRule::EvenOdd };
+
+        let mut expected = Recorder::unique(&clip);
+        accumulation::fill(&commands, &clip, fill_rule, &mut expected);
+        let mut sparse = Recorder::unique(&clip);
+        sparse_strips::fill(&commands, &clip, fill_rule, &mut sparse);
+        let mut reused = Recorder::unique(&clip);
+        filler.fill(&commands, &clip, fill_rule, &mut reused);
+
+        assert_eq!(sparse.pixels, reused.pixels, "a reused filler differs for {:?}", commands);
+        let error = max_difference(&expected, &sparse);
+        assert!(error < 1e-4, "coverage off by {} for {:?} in {:?}", error, commands, clip);
+    }
+}
+
+#[test]
+fn solid_spans() {
+    // a large disk: the interior is passed as spans, the edges pixel by pixel
+    let center = Vec2f64::new(250.3, 250.7);
+    let points: Vec<_> = (0..256)
+        .map(|i| {
+            let angle = i as f64 / 256.0 * std::f64::consts::TAU;
+            center + Vec2f64::new(angle.cos(), angle.sin()) * 200.0
+        })
+        .collect();
+    let commands = contour(&points);
+    let clip = Rect::new(Vector2::new(0, 0), Vector2::new(500, 500));
+    let mut recorder = Recorder::unique(&clip);
+    sparse_strips::fill(&commands, &clip, FillRule::NonZero, &mut recorder);
+    let expected = 256.0 / 2.0 * 200.0 * 200.0 * (std::f64::consts::TAU / 256.0).sin();
+    assert!((recorder.area() - expected).abs() < 1e-3 * expected, "area {} instead of {}", recorder.area(), expected);
+    let weighted = recorder.pixels.len() - recorder.solid_pixels;
+    assert!(weighted < 8 * 200 * 2, "{} pixels not in spans", weighted);
+}
+
+#[test]
+fn degenerate() {
+    let clip = Rect::new(Vector2::new(10, 10), Vector2::new(50, 50));
+    let empty_clip = Rect::new(Vector2::new(10, 10), Vector2::new(10, 50));
+    let square = |min: f64, max: f64| rectangle((min, min), (max, max));
+    let pixels = |commands: &[BrokenPolylineCommand], clip: &Rect<usize>| {
+        let mut recorder = Recorder::unique(clip);
+        sparse_strips::fill(commands, clip, FillRule::NonZero, &mut recorder);
+        recorder.pixels.len()
+    };
+    assert_eq!(pixels(&[], &clip), 0);
+    assert_eq!(pixels(&square(20.0, 20.0), &clip), 0);
+    assert_eq!(pixels(&square(20.0, 30.0), &empty_clip), 0);
+    // the squares covering the clip rectangle, outside and across its sides
+    assert_eq!(pixels(&square(0.0, 100.0), &clip), 40 * 40);
+    assert_eq!(pixels(&square(-1e12, 1e12), &clip), 40 * 40);
+    assert_eq!(pixels(&square(60.0, 100.0), &clip), 0);
+}
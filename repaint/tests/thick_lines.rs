@@ -0,0 +1,179 @@
+//! The thick line algorithms against a direct evaluation of the stroked shape.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::{everything, Lcg};
+use repaint::base::defs::{antialiasing::AntialiasMode, linalg::*, rect::Rect};
+use repaint::base::pen::{Pen, PenCap, StrokeWidth};
+use repaint::base::rasterization::algorithms::line::thick::{distance_field, pen_line, span};
+use repaint::nalgebra::Vector2;
+
+const ROUNDS: usize = 1000;
+const CAPS: [PenCap; 3] = [PenCap::Butt, PenCap::Square, PenCap::Round];
+
+fn point(rng: &mut Lcg) -> Vec2f32 {
+    Vec2f32::new(rng.range(-20.0, 60.0) as f32, rng.range(-20.0, 60.0) as f32)
+}
+
+/// A random segment, some of them points or shorter than a pixel, and a width.
+fn random_line(rng: &mut Lcg, round: usize) -> (Vec2f32, Vec2f32, f32) {
+    let start = point(rng);
+    let end = match round % 10 {
+        0 => start,
+        1 => start + Vec2f32::new(rng.range(-1.0, 1.0) as f32, rng.range(-1.0, 1.0) as f32),
+        _ => point(rng),
+    };
+    (start, end, rng.range(1.0, 8.0) as f32)
+}
+
+/// The signed distance from a point to the stroked shape, computed directly.
+fn shape_distance(start: &Vec2f64, end: &Vec2f64, width: f64, cap: PenCap, pt: &Vec2f64) -> f64 {
+    let delta = end - start;
+    let length = delta.norm();
+    let direction = if length > 0.0 { delta / length } else { Vec2f64::new(1.0, 0.0) };
+    let normal = Vec2f64::new(-direction.y, direction.x);
+    let along = (pt - start).dot(&direction);
+    let across = (pt - start).dot(&normal).abs();
+    let half = width / 2.0;
+    match cap {
+        PenCap::Round => (along - along.clamp(0.0, length)).hypot(across) - half,
+        PenCap::Butt => (along - length / 2.0).abs() - length / 2.0,
+        PenCap::Square => (along - length / 2.0).abs() - length / 2.0 - half,
+    }
+    .max(if cap == PenCap::Round { f64::NEG_INFINITY } else { across - half })
+}
+
+fn cap_area(width: f64, cap: PenCap) -> f64 {
+    match cap {
+        PenCap::Butt => 0.0,
+        PenCap::Square => width * width,
+        PenCap::Round => std::f64::consts::PI * width * width / 4.0,
+    }
+}
+
+fn aliased(start: &Vec2f32, end: &Vec2f32, width: f32, cap: PenCap, rect: &Rect<i32>) -> Vec<(i32, i32)> {
+    let mut pixels = Vec::new();
+    span::line(start, end, width, cap, &mut |x, y| pixels.push((x, y)), rect);
+    pixels
+}
+
+fn antialiased(start: &Vec2f32, end: &Vec2f32, width: f32, cap: PenCap, rect: &Rect<i32>) -> Vec<((i32, i32), f32)> {
+    let mut pixels = Vec::new();
+    distance_field::line(start, end, width, cap, &mut |x, y, coverage| pixels.push(((x, y), coverage)), rect);
+    pixels
+}
+
+#[test]
+fn aliased_pixels_are_inside_the_shape() {
+    let mut rng = Lcg(1);
+    // every pixel around the shape is checked
+    for round in 0..ROUNDS / 2 {
+        let (start, end, width) = random_line(&mut rng, round);
+        let (start64, end64): (Vec2f64, Vec2f64) = (start.cast(), end.cast());
+        for cap in CAPS {
+            // each pixel is plotted once
+            let pixels = aliased(&start, &end, width, cap, &everything());
+            let mut unique = pixels.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), pixels.len(), "{cap:?}: pixels plotted twice for {start:?} -> {end:?}");
+
+            // the pixels around the bounds of the shape
+            let (min, max) = (start.inf(&end).map(|v| v.floor() as i32 - 7), start.sup(&end).map(|v| v.ceil() as i32 + 7));
+            for (x, y) in (min.y..max.y).flat_map(|y| (min.x..max.x).map(move |x| (x, y))) {
+                let center = Vec2f64::new(x as f64 + 0.5, y as f64 + 0.5);
+                let distance = if start == end && cap == PenCap::Butt {
+                    f64::INFINITY
+                } else {
+                    shape_distance(&start64, &end64, width as f64, cap, &center)
+                };
+                // the pixels on the outline can go either way
+                if distance.abs() > 1e-6 {
+                    assert_eq!(
+                        unique.binary_search(&(x, y)).is_ok(),
+                        distance < 0.0,
+                        "{cap:?}: pixel {x}, {y} at {distance} from {start:?} -> {end:?} ({width})"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn antialiased_coverage_adds_up_to_the_area() {
+    let mut rng = Lcg(2);
+    for round in 0..ROUNDS {
+        let (start, end, width) = random_line(&mut rng, round);
+        let (start64, end64): (Vec2f64, Vec2f64) = (start.cast(), end.cast());
+        for cap in CAPS {
+            let pixels = antialiased(&start, &end, width, cap, &everything());
+            let coverage: BTreeMap<(i32, i32), f32> = pixels.iter().copied().collect();
+            assert_eq!(coverage.len(), pixels.len(), "{cap:?}: pixels plotted twice for {start:?} -> {end:?}");
+            let total: f64 = coverage.values().map(|value| *value as f64).sum();
+            let area = if start == end && cap == PenCap::Butt {
+                0.0
+            } else {
+                (end64 - start64).norm() * width as f64 + cap_area(width as f64, cap)
+            };
+            // the estimation errors are on the pixels crossed by the outline
+            let perimeter = 2.0 * ((end64 - start64).norm() + width as f64);
+            assert!((total - area).abs() < 0.25 * perimeter, "{cap:?}: coverage {total} instead of {area} for {start:?} -> {end:?} ({width})");
+        }
+    }
+}
+
+#[test]
+fn clipping_only_removes_pixels() {
+    let mut rng = Lcg(3);
+    for round in 0..ROUNDS {
+        let (start, end, width) = random_line(&mut rng, round);
+        for cap in CAPS {
+            let min = Vector2::new(rng.range(-20.0, 50.0) as i32, rng.range(-20.0, 50.0) as i32);
+            let clip = Rect::new(min, min + Vector2::new(rng.range(0.0, 30.0) as i32, rng.range(0.0, 30.0) as i32));
+            let inside = |pt: &(i32, i32)| pt.0 >= clip.min.x && pt.0 < clip.max.x && pt.1 >= clip.min.y && pt.1 < clip.max.y;
+
+            let expected: Vec<_> = aliased(&start, &end, width, cap, &everything()).into_iter().filter(inside).collect();
+            assert_eq!(aliased(&start, &end, width, cap, &clip), expected, "{cap:?}: clipping differs");
+            let expected: Vec<_> = antialiased(&start, &end, width, cap, &everything()).into_iter().filter(|(pt, _)| inside(pt)).collect();
+            assert_eq!(antialiased(&start, &end, width, cap, &clip), expected, "{cap:?}: clipping differs");
+        }
+    }
+}
+
+#[test]
+fn degenerate_lines() {
+    for width in [f32::NAN, 0.0, -1.0, f32::INFINITY] {
+        assert!(aliased(&Vec2f32::zeros(), &Vec2f32::new(10.0, 3.0), width, PenCap::Round, &everything()).is_empty());
+        assert!(antialiased(&Vec2f32::zeros(), &Vec2f32::new(10.0, 3.0), width, PenCap::Round, &everything()).is_empty());
+    }
+    let huge = Vec2f32::new(1e30, 1e30);
+    aliased(&-huge, &huge, 3.0, PenCap::Square, &everything());
+    antialiased(&-huge, &huge, 3.0, PenCap::Square, &everything());
+
+    // the lines wider than the clip rectangle only follow the runs crossing it
+    let clip = Rect::new(Vector2::new(0, 0), Vector2::new(10, 10));
+    assert_eq!(aliased(&Vec2f32::new(-1e6, 3.0), &Vec2f32::new(1e6, 5.0), 1e9, PenCap::Butt, &clip).len(), 100);
+}
+
+#[test]
+fn pen_widths() {
+    // a horizontal line along the centers of the pixels, whose rows are fully covered
+    let (start, end) = (Vec2f64::new(2.0, 10.5), Vec2f64::new(30.0, 10.5));
+    let rows = |stroke_width: StrokeWidth, antialias_mode: AntialiasMode| {
+        let mut pen = Pen::<()> { stroke_width, cap: PenCap::Butt, ..Pen::default() };
+        pen.paint.antialias_mode = antialias_mode;
+        let mut rows = BTreeMap::<i32, f32>::new();
+        pen_line(&start, &end, &pen, 2.0, &mut |_, y, coverage| *rows.entry(y).or_default() += coverage, &everything());
+        assert!(rows.values().all(|coverage| (coverage - 28.0).abs() < 1e-4), "{:?}", rows);
+        rows.keys().copied().collect::<Vec<_>>()
+    };
+    for antialias_mode in [AntialiasMode::None, AntialiasMode::Exact] {
+        // the normal widths are scaled to device pixels, not the cosmetic ones
+        assert_eq!(rows(StrokeWidth::Normal(1.5), antialias_mode), vec![9, 10, 11]);
+        assert_eq!(rows(StrokeWidth::Cosmetic(1.0), antialias_mode), vec![10]);
+        assert_eq!(rows(StrokeWidth::Hairline, antialias_mode), vec![10]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Properties of the tiled rasterizer: the image doesn't depend on the number of threads nor on the
+//! tile size, and the fills match the polygon fillers.
+
+#![cfg(feature = "tiled")]
+
+mod common;
+
+use common::{rectangle, Lcg, Recorder};
+use repaint::base::blending::BlendMode;
+use repaint::base::defs::{antialiasing::AntialiasMode, colors::default_color_types::RgbaFColor, fill_rule::FillRule, linalg::*, rect::Rect};
+use repaint::base::paint::{Ink, Paint};
+use repaint::base::rasterization::algorithms::{ellipse::antialiased, polygon_filling};
+use repaint::base::rasterization::tiled::TiledRasterizer;
+use repaint::base::shapes::polyline::BrokenPolylineCommand;
+use repaint::nalgebra::Vector2;
+
+fn random_color(rng: &mut Lcg) -> RgbaFColor {
+    RgbaFColor::new(rng.next() as f32, rng.next() as f32, rng.next() as f32, rng.range(0.2, 1.0) as f32)
+}
+
+/// A star polygon around a random center, partly outside the image.
+fn random_star(rng: &mut Lcg, width: f64, height: f64, size: f64) -> Vec<BrokenPolylineCommand> {
+    let center = Vec2f64::new(rng.range(-0.1, 1.1) * width, rng.range(-0.1, 1.1) * height);
+    let count = 3 + rng.int(12);
+    (0..count)
+        .map(|i| {
+            let angle = i as f64 / count as f64 * std::f64::consts::TAU * 2.0;
+            let radius = rng.range(0.2, 1.0) * size;
+            let pt = center + Vec2f64::new(angle.cos(), angle.sin()) * radius;
+            if i == 0 { BrokenPolylineCommand::MoveTo(pt) } else { BrokenPolylineCommand::LineTo(pt) }
+        })
+        .collect()
+}
+
+fn random_paint(rng: &mut Lcg) -> Paint<RgbaFColor> {
+    let modes = [AntialiasMode::None, AntialiasMode::Exact, AntialiasMode::sub_pixel(4)];
+    let blend_modes = [BlendMode::SrcOver, BlendMode::SrcOver, BlendMode::Src, BlendMode::Clear];
+    Paint {
+        ink: Ink::Color(random_color(rng)),
+        blend_mode: blend_modes[rng.int(4)],
+        antialias_mode: modes[rng.int(3)],
+    }
+}
+
+/// Records random stars and ellipses.
+fn record_scene(rasterizer: &mut TiledRasterizer, seed: u64, count: usize, size: f64) {
+    let mut rng = Lcg(seed);
+    let (width, height) = (rasterizer.width() as f64, rasterizer.height() as f64);
+    for i in 0..count {
+        let paint = random_paint(&mut rng);
+        if i % 5 == 4 {
+            let center = Vec2f32::new(rng.range(0.0, width) as f32, rng.range(0.0, height) as f32);
+            let radii = Vec2f32::new(rng.range(1.0, size) as f32, rng.range(1.0, size) as f32);
+            let bounds = Rect::new(
+                Vector2::new((center.x - radii.x) as i32 - 2, (center.y - radii.y) as i32 - 2),
+                Vector2::new((center.x + radii.x) as i32 + 2, (center.y + radii.y) as i32 + 2),
+            );
+            rasterizer.record(bounds, &paint, move |clip_rect, coverage| {
+                antialiased::fill_ellipse(&center, &radii, clip_rect, coverage);
+            });
+        } else {
+            let fill_rule = if rng.next() < 0.5 { FillRule::NonZero } else { FillRule::EvenOdd };
+            rasterizer.fill_polyline(&random_star(&mut rng, width, height, size), fill_rule, &paint);
+        }
+    }
+}
+
+fn render(width: usize, height: usize, tile_size: usize, threads: usize, seed: u64) -> Vec<RgbaFColor> {
+    let mut rasterizer = TiledRasterizer::with_tile_size(width, height, tile_size);
+    record_scene(&mut rasterizer, seed, 30, 60.0);
+    let mut image = vec![RgbaFColor::GRAY_SEMI; width * height];
+    rasterizer.render_with_threads(&mut image, threads);
+    image
+}
+
+fn same_bits(a: &[RgbaFColor], b: &[RgbaFColor]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.to_slice().iter().zip(b.to_slice()).all(|(a, b)| a.to_bits() == b.to_bits()))
+}
+
+#[test]
+fn independent_of_the_threads() {
+    let (width, height) = (151, 107);
+    for seed in 0..4 {
+        for tile_size in [64, 16, 7] {
+            let reference = render(width, height, tile_size, 1, seed);
+            assert!(reference.iter().any(|pixel| *pixel != RgbaFColor::GRAY_SEMI), "nothing drawn");
+            for threads in [2, 3, 1000] {
+                let image = render(width, height, tile_size, threads, seed);
+                assert!(same_bits(&image, &reference), "seed {}: {} pixel tiles differ on {} threads", seed, tile_size, threads);
+            }
+        }
+    }
+}
+
+#[test]
+fn independent_of_the_tile_size() {
+    let (width, height) = (151, 107);
+    for seed in 0..4 {
+        let untiled = render(width, height, width.max(height), 1, seed);
+        for tile_size in [64, 16, 7, 3] {
+            let image = render(width, height, tile_size, 1, seed);
+            assert!(same_bits(&image, &untiled), "seed {}: {} pixel tiles change the image", seed, tile_size);
+        }
+    }
+}
+
+#[test]
+fn same_coverage_as_the_polygon_fillers() {
+    let (width, height) = (151, 107);
+    let clip = Rect::new(Vector2::new(0, 0), Vector2::new(width, height));
+    let mut rng = Lcg(7);
+    for round in 0..60 {
+        let star = random_star(&mut rng, width as f64, height as f64, 60.0);
+        let fill_rule = if round % 2 == 0 { FillRule::NonZero } else { FillRule::EvenOdd };
+        let antialias_mode = [AntialiasMode::None, AntialiasMode::Exact, AntialiasMode::sub_pixel(4)][round % 3];
+
+        // the alpha of an opaque color copied over transparent pixels is the coverage
+        let mut rasterizer = TiledRasterizer::with_tile_size(width, height, 16);
+        let paint = Paint { ink: Ink::Color(RgbaFColor::WHITE), blend_mode: BlendMode::Src, antialias_mode };
+        rasterizer.fill_polyline(&star, fill_rule, &paint);
+        let mut image = vec![RgbaFColor::TRANSPARENT; width * height];
+        rasterizer.render_with_threads(&mut image, 1);
+
+        let mut recorder = Recorder::new(&clip);
+        polygon_filling::fill(&star, &clip, fill_rule, antialias_mode, &mut recorder);
+        for (index, pixel) in image.iter().enumerate() {
+            let expected = recorder.pixels.get(&(index % width, index / width)).copied().unwrap_or(0.0);
+            assert!((pixel.a() - expected).abs() < 1e-5, "{antialias_mode:?}: pixel {index} covered by {} instead of {expected}", pixel.a());
+        }
+    }
+}
+
+#[test]
+fn ignored_draws() {
+    let mut rasterizer = TiledRasterizer::new(100, 100);
+    let square = rectangle((10.0, 10.0), (50.0, 50.0));
+    rasterizer.fill_polyline(&square, FillRule::NonZero, &Paint::from(Ink::None));
+    rasterizer.fill_polyline(&square, FillRule::NonZero, &Paint { blend_mode: BlendMode::Multiply, ..Paint::from(RgbaFColor::RED) });
+    rasterizer.fill_polyline(&[], FillRule::NonZero, &Paint::from(RgbaFColor::RED));
+    rasterizer.record(Rect::new(Vector2::new(200, 0), Vector2::new(300, 100)), &Paint::from(RgbaFColor::RED), |_, _| {
+        panic!("a draw outside the image is rasterized")
+    });
+    assert_eq!(rasterizer.draw_count(), 0);
+
+    rasterizer.fill_polyline(&square, FillRule::NonZero, &Paint::from(RgbaFColor::RED));
+    let mut image = vec![RgbaFColor::TRANSPARENT; 100 * 100];
+    rasterizer.render(&mut image);
+    let covered = image.iter().filter(|pixel| **pixel == RgbaFColor::RED).count();
+    assert_eq!(covered, 40 * 40);
+
+    rasterizer.clear();
+    assert_eq!(rasterizer.draw_count(), 0);
+    let mut empty = TiledRasterizer::new(0, 50);
+    empty.fill_polyline(&square, FillRule::NonZero, &Paint::from(RgbaFColor::RED));
+    empty.render(&mut []);
+}
@@ -0,0 +1,242 @@
+//! Properties of the triangle rasterizer: the plotted pixels follow the top-left rule, the weights
+//! interpolate the centers of the pixels and the meshes plot each pixel once.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::Lcg;
+use repaint::base::defs::{colors::default_color_types::RgbaFColor, linalg::*, rect::Rect, vertex_mode::VertexMode};
+use repaint::base::rasterization::algorithms::triangle;
+use repaint::nalgebra::Vector2;
+
+const ROUNDS: usize = 300;
+
+/// A point on the grid of quarter pixels, where many pixel centers are on the edges.
+fn grid_point(rng: &mut Lcg) -> Vector2<i64> {
+    Vector2::new(rng.range(-40.0, 200.0) as i64, rng.range(-40.0, 200.0) as i64)
+}
+
+fn rect(min: (i32, i32), max: (i32, i32)) -> Rect<i32> {
+    Rect { min: Vector2::new(min.0, min.1), max: Vector2::new(max.0, max.1) }
+}
+
+fn fill(vertices: &[Vec2f32; 3], clip: &Rect<i32>) -> BTreeMap<(i32, i32), [f32; 3]> {
+    let mut pixels = BTreeMap::new();
+    triangle::fill(vertices, &mut |x, y, weights| {
+        assert!(pixels.insert((x, y), weights).is_none(), "pixel {:?} plotted twice", (x, y));
+    }, clip);
+    pixels
+}
+
+/// The pixels whose center is inside a triangle with vertices in quarter pixels, evaluated exactly.
+fn exact_fill(vertices: &[Vector2<i64>; 3], clip: &Rect<i32>) -> Vec<(i32, i32)> {
+    let [a, b, c] = vertices;
+    let cross = |u: Vector2<i64>, v: Vector2<i64>| u.x * v.y - u.y * v.x;
+    let area = cross(b - a, c - a);
+    if area == 0 {
+        return Vec::new();
+    }
+    let inside = |p: Vector2<i64>| {
+        [(b, c), (c, a), (a, b)].iter().all(|(start, end)| {
+            let direction = *end - *start;
+            let value = cross(direction, p - *start) * area.signum();
+            // the interior is on the right of a left edge, or below a top edge
+            let top_left = if direction.y != 0 { -direction.y * area.signum() > 0 } else { direction.x * area.signum() > 0 };
+            value > 0 || (value == 0 && top_left)
+        })
+    };
+    let mut pixels = Vec::new();
+    for y in clip.min.y..clip.max.y {
+        for x in clip.min.x..clip.max.x {
+            if inside(Vector2::new(4 * x as i64 + 2, 4 * y as i64 + 2)) {
+                pixels.push((x, y));
+            }
+        }
+    }
+    pixels
+}
+
+fn to_pixels(vertices: &[Vector2<i64>; 3]) -> [Vec2f32; 3] {
+    vertices.map(|v| Vec2f32::new(v.x as f32 / 4.0, v.y as f32 / 4.0))
+}
+
+#[test]
+fn exact_pixels() {
+    let mut rng = Lcg(0x5eed);
+    let clip = rect((-5, -5), (55, 55));
+    for _ in 0..ROUNDS {
+        let mut vertices = [grid_point(&mut rng), grid_point(&mut rng), grid_point(&mut rng)];
+        // axis-aligned edges, the ones that need the top part of the rule
+        if rng.next() < 0.3 {
+            vertices[1].y = vertices[0].y;
+        }
+        if rng.next() < 0.3 {
+            vertices[2].x = vertices[1].x;
+        }
+        let pixels: Vec<_> = fill(&to_pixels(&vertices), &clip).into_keys().collect();
+        let mut expected = exact_fill(&vertices, &clip);
+        expected.sort_by_key(|(x, y)| (*y, *x));
+        let mut sorted = pixels.clone();
+        sorted.sort_by_key(|(x, y)| (*y, *x));
+        assert_eq!(sorted, expected, "wrong pixels for {:?}", vertices);
+    }
+}
+
+#[test]
+fn weights() {
+    let mut rng = Lcg(0x5eed + 1);
+    let clip = rect((0, 0), (1000, 1000));
+    let mut max_error = 0.0f64;
+    for _ in 0..ROUNDS {
+        let vertices = [0; 3].map(|_| Vec2f32::new(rng.range(0.0, 80.0) as f32, rng.range(0.0, 80.0) as f32));
+        let pixels = fill(&vertices, &clip);
+        for ((x, y), weights) in &pixels {
+            assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-4, "weights {:?} don't add up to one", weights);
+            assert!(weights.iter().all(|w| *w >= -1e-5), "negative weight in {:?}", weights);
+            let position = triangle::interpolate_uv(&vertices, weights);
+            let error = (position.cast::<f64>() - Vec2f64::new(*x as f64 + 0.5, *y as f64 + 0.5)).norm();
+            max_error = max_error.max(error);
+        }
+
+        // the same pixels in any order, with the weights following the vertices
+        for permutation in [[1, 2, 0], [2, 1, 0], [0, 2, 1]] {
+            let permuted = fill(&permutation.map(|i| vertices[i]), &clip);
+            assert_eq!(permuted.len(), pixels.len(), "the order changes the pixels of {:?}", vertices);
+            for (pixel, weights) in &permuted {
+                let original = pixels.get(pixel).expect("the order changes the pixels");
+                for (k, i) in permutation.iter().enumerate() {
+                    assert!((weights[k] - original[*i]).abs() < 1e-4);
+                }
+            }
+        }
+    }
+    assert!(max_error < 1e-3, "interpolated centers off by {}", max_error);
+}
+
+#[test]
+fn mesh() {
+    let mut rng = Lcg(0x5eed + 2);
+    // a jittered grid covering [0, 64) x [0, 48), with the points of the border kept on it and the
+    // jitter small enough that no triangle folds over its neighbours
+    let (columns, rows, size) = (9usize, 7usize, 8.0f64);
+    for _ in 0..ROUNDS / 30 {
+        let mut positions = Vec::new();
+        for j in 0..rows {
+            for i in 0..columns {
+                let mut x = i as f64 * size;
+                let mut y = j as f64 * size;
+                if i > 0 && i < columns - 1 {
+                    x += rng.range(-1.5, 1.5);
+                }
+                if j > 0 && j < rows - 1 {
+                    y += rng.range(-1.5, 1.5);
+                }
+                positions.push(Vec2f32::new(x as f32, y as f32));
+            }
+        }
+        let colors: Vec<_> = (0..positions.len())
+            .map(|_| RgbaFColor::new(rng.next() as f32, rng.next() as f32, rng.next() as f32, rng.next() as f32))
+            .collect();
+
+        // the rows of the grid as strips, joined by indices
+        let mut triangles = Vec::new();
+        for j in 0..rows - 1 {
+            let strip: Vec<u16> = (0..columns).flat_map(|i| [(j * columns + i) as u16, ((j + 1) * columns + i) as u16]).collect();
+            triangles.extend(VertexMode::TriangleStrip.triangles(positions.len(), Some(&strip)));
+        }
+        // an index out of the positions skips its triangle
+        triangles.push([0, 1, positions.len()]);
+
+        let clip = rect((-10, -10), (100, 100));
+        let mut counts = BTreeMap::new();
+        triangle::fill_mesh(&positions, Some(&colors), None, triangles, &mut |x, y, fragment| {
+            *counts.entry((x, y)).or_insert(0) += 1;
+            let color = fragment.color.expect("no interpolated color");
+            assert!(fragment.uv.is_none());
+            assert!([color.r(), color.g(), color.b(), color.a()].iter().all(|c| (-1e-4..=1.0 + 1e-4).contains(c)));
+        }, &clip);
+        assert_eq!(counts.len(), 64 * 48, "the mesh doesn't cover its rectangle");
+        for ((x, y), count) in counts {
+            assert!((0..64).contains(&x) && (0..48).contains(&y), "pixel {:?} out of the mesh", (x, y));
+            assert_eq!(count, 1, "pixel {:?} plotted {} times", (x, y), count);
+        }
+    }
+}
+
+#[test]
+fn texture_coordinates() {
+    let mut rng = Lcg(0x5eed + 4);
+    for _ in 0..ROUNDS / 10 {
+        // an affine map of the positions, interpolated exactly at the centers of the pixels
+        let (scale, offset) = (rng.range(-2.0, 2.0) as f32, Vec2f32::new(rng.range(-5.0, 5.0) as f32, rng.range(-5.0, 5.0) as f32));
+        let positions: Vec<_> = (0..6).map(|_| Vec2f32::new(rng.range(0.0, 60.0) as f32, rng.range(0.0, 60.0) as f32)).collect();
+        let uvs: Vec<_> = positions.iter().map(|pos| pos * scale + offset).collect();
+        let mut plotted = 0;
+        triangle::fill_mesh(&positions, None, Some(&uvs), VertexMode::Triangles.triangles(positions.len(), None), &mut |x, y, fragment| {
+            plotted += 1;
+            assert!(fragment.color.is_none());
+            let uv = fragment.uv.expect("no interpolated texture coordinates");
+            let expected = Vec2f32::new(x as f32 + 0.5, y as f32 + 0.5) * scale + offset;
+            assert!((uv - expected).norm() < 1e-3, "texture coordinates {:?} instead of {:?}", uv, expected);
+        }, &rect((0, 0), (100, 100)));
+
+        // the texture coordinates of the wrong length are ignored
+        triangle::fill_mesh(&positions, None, Some(&uvs[1..]), VertexMode::Triangles.triangles(positions.len(), None), &mut |_, _, fragment| {
+            assert!(fragment.uv.is_none());
+            plotted -= 1;
+        }, &rect((0, 0), (100, 100)));
+        assert_eq!(plotted, 0);
+    }
+}
+
+#[test]
+fn clipping() {
+    let mut rng = Lcg(0x5eed + 3);
+    let full = rect((-100, -100), (300, 300));
+    for _ in 0..ROUNDS {
+        let vertices = [0; 3].map(|_| Vec2f32::new(rng.range(-20.0, 70.0) as f32, rng.range(-20.0, 70.0) as f32));
+        let (x0, y0) = (rng.range(-10.0, 40.0) as i32, rng.range(-10.0, 40.0) as i32);
+        let clip = rect((x0, y0), (x0 + rng.range(0.0, 40.0) as i32, y0 + rng.range(0.0, 40.0) as i32));
+        let clipped = fill(&vertices, &clip);
+        let filtered: BTreeMap<_, _> = fill(&vertices, &full)
+            .into_iter()
+            .filter(|((x, y), _)| *x >= clip.min.x && *x < clip.max.x && *y >= clip.min.y && *y < clip.max.y)
+            .collect();
+        assert_eq!(clipped, filtered, "clipping {:?} to {:?}", vertices, clip);
+    }
+}
+
+#[test]
+fn vertex_modes() {
+    let count = |mode: VertexMode, vertices: usize| mode.triangles(vertices, None).count();
+    assert_eq!((count(VertexMode::Triangles, 8), count(VertexMode::Triangles, 2)), (2, 0));
+    assert_eq!((count(VertexMode::TriangleStrip, 6), count(VertexMode::TriangleStrip, 1)), (4, 0));
+    assert_eq!((count(VertexMode::TriangleFan, 5), count(VertexMode::TriangleFan, 0)), (3, 0));
+    let fan: Vec<_> = VertexMode::TriangleFan.triangles(0, Some(&[7, 3, 5, 9])).collect();
+    assert_eq!(fan, vec![[7, 3, 5], [7, 5, 9]]);
+
+    // the triangles of a strip keep the winding of the first one
+    let positions: Vec<_> = (0..10).map(|i| Vec2f64::new((i / 2) as f64, (i % 2) as f64)).collect();
+    let areas: Vec<_> = VertexMode::TriangleStrip
+        .triangles(positions.len(), None)
+        .map(|[a, b, c]| (positions[b] - positions[a]).perp(&(positions[c] - positions[a])))
+        .collect();
+    assert!(areas.iter().all(|area| area.signum() == areas[0].signum()), "a strip triangle changes winding");
+}
+
+#[test]
+fn degenerate() {
+    let clip = rect((0, 0), (1000, 1000));
+    let plotted = |vertices: [Vec2f32; 3]| fill(&vertices, &clip).len();
+    let v = |x: f32, y: f32| Vec2f32::new(x, y);
+    assert_eq!(plotted([v(1.0, 1.0), v(5.0, 5.0), v(9.0, 9.0)]), 0);
+    assert_eq!(plotted([v(1.0, 1.0), v(1.0, 1.0), v(9.0, 3.0)]), 0);
+    assert_eq!(plotted([v(f32::NAN, 1.0), v(5.0, 5.0), v(9.0, 1.0)]), 0);
+    assert_eq!(plotted([v(f32::INFINITY, 1.0), v(5.0, 5.0), v(9.0, 1.0)]), 0);
+    assert_eq!(fill(&[v(0.0, 0.0), v(9.0, 0.0), v(0.0, 9.0)], &rect((5, 5), (5, 9))).len(), 0);
+    // huge triangles only cost their clipped part
+    assert_eq!(plotted([v(-1e30, -1e30), v(1e30, -1e30), v(0.0, 1e30)]), 1000 * 1000);
+    assert_eq!(plotted([v(-1e6, 0.25), v(1e6, 0.75), v(-1e6, 0.5)]), 0);
+    assert_eq!(triangle::interpolate_color(&[RgbaFColor::new(1.0, 0.0, 0.0, 0.0); 3], &[1.0 / 3.0; 3]).a(), 0.0);
+}